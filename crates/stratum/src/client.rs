@@ -19,16 +19,34 @@ use {
     tracing::{debug, error, warn},
 };
 
-pub use {error::ClientError, event::Event};
+pub use {
+    builder::{ClientBuilder, DEFAULT_TIMEOUT, USER_AGENT},
+    error::ClientError,
+    event::Event,
+    proxy::Proxy,
+};
 
 mod actor;
+mod builder;
 mod error;
 mod event;
+mod proxy;
 
 pub type Result<T = (), E = ClientError> = std::result::Result<T, E>;
 
 const CHANNEL_BUFFER_SIZE: usize = 256;
 
+/// Default number of events buffered for each [`EventReceiver`].
+pub const DEFAULT_EVENT_CAPACITY: usize = CHANNEL_BUFFER_SIZE;
+
+/// Receives connection events from a [`Client`].
+///
+/// Events are buffered per receiver up to the client's event capacity.
+/// Consumers must keep up: once a receiver falls further behind than that,
+/// the oldest events are dropped and the next call returns
+/// [`ClientError::EventsLagged`] with the number skipped. Dropped events can
+/// include `Notify`, so a miner that sees a lag should treat its current job
+/// as possibly stale until the next `Notify` arrives.
 #[derive(Debug)]
 pub struct EventReceiver {
     rx: broadcast::Receiver<Event>,
@@ -72,9 +90,18 @@ struct Config {
     password: Option<String>,
     user_agent: String,
     timeout: Duration,
+    proxy: Option<Proxy>,
 }
 
 impl Client {
+    /// Starts configuring a client with [`ClientBuilder`], which defaults
+    /// everything but the pool address and username.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Spawns a client with the default event capacity and no proxy. Unlike
+    /// [`ClientBuilder::build`], the address is not checked up front.
     #[must_use]
     pub fn new(
         address: String,
@@ -83,16 +110,14 @@ impl Client {
         user_agent: String,
         timeout: Duration,
     ) -> Self {
-        Self::with_cancel(
-            address,
-            username,
-            password,
-            user_agent,
-            timeout,
-            CancellationToken::new(),
-        )
+        Self::builder()
+            .with_password(password)
+            .with_user_agent(user_agent)
+            .with_timeout(timeout)
+            .spawn(address, username)
     }
 
+    /// Like [`Client::new`], stopping when `cancel` is cancelled.
     #[must_use]
     pub fn with_cancel(
         address: String,
@@ -102,16 +127,65 @@ impl Client {
         timeout: Duration,
         cancel: CancellationToken,
     ) -> Self {
+        Self::builder()
+            .with_password(password)
+            .with_user_agent(user_agent)
+            .with_timeout(timeout)
+            .with_cancel(cancel)
+            .spawn(address, username)
+    }
+
+    /// Like [`Client::with_cancel`], buffering up to `event_capacity` events
+    /// per [`EventReceiver`] before a slow consumer starts lagging.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `event_capacity` is zero.
+    #[must_use]
+    pub fn with_event_capacity(
+        address: String,
+        username: Username,
+        password: Option<String>,
+        user_agent: String,
+        timeout: Duration,
+        cancel: CancellationToken,
+        event_capacity: usize,
+    ) -> Self {
+        Self::builder()
+            .with_password(password)
+            .with_user_agent(user_agent)
+            .with_timeout(timeout)
+            .with_cancel(cancel)
+            .with_event_capacity(event_capacity)
+            .spawn(address, username)
+    }
+
+    /// Like [`Client::with_cancel`], dialing the pool through `proxy` when
+    /// one is given.
+    #[must_use]
+    pub fn with_proxy(
+        address: String,
+        username: Username,
+        password: Option<String>,
+        user_agent: String,
+        timeout: Duration,
+        cancel: CancellationToken,
+        proxy: Option<Proxy>,
+    ) -> Self {
+        Self::builder()
+            .with_password(password)
+            .with_user_agent(user_agent)
+            .with_timeout(timeout)
+            .with_cancel(cancel)
+            .with_proxy(proxy)
+            .spawn(address, username)
+    }
+
+    fn spawn(config: Config, cancel: CancellationToken, event_capacity: usize) -> Self {
         let (tx, rx) = mpsc::channel(CHANNEL_BUFFER_SIZE);
-        let (events, _) = broadcast::channel(CHANNEL_BUFFER_SIZE);
+        let (events, _) = broadcast::channel(event_capacity);
 
-        let config = Arc::new(Config {
-            address,
-            username,
-            password,
-            user_agent,
-            timeout,
-        });
+        let config = Arc::new(config);
 
         let actor = ClientActor::new(config.clone(), rx, events.clone(), cancel);
 
@@ -130,6 +204,10 @@ impl Client {
         &self.config.address
     }
 
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.config.proxy.as_ref()
+    }
+
     pub async fn connect(&self) -> Result<EventReceiver> {
         let (respond_to, rx) = oneshot::channel();
 
@@ -178,6 +256,17 @@ impl Client {
         Ok((rx, instant))
     }
 
+    async fn send_notification(&self, method: Method) -> Result {
+        let (respond_to, rx) = oneshot::channel();
+
+        self.tx
+            .send(ClientMessage::Notification { method, respond_to })
+            .await
+            .map_err(|_| ClientError::NotConnected)?;
+
+        rx.await.map_err(|_| ClientError::NotConnected)?
+    }
+
     async fn await_response(
         &self,
         rx: oneshot::Receiver<Result<(Message, usize)>>,
@@ -263,8 +352,13 @@ impl Client {
     }
 
     pub async fn authorize(&self) -> Result<(Duration, usize)> {
+        self.authorize_with_username(self.config.username.clone())
+            .await
+    }
+
+    pub async fn authorize_with_username(&self, username: Username) -> Result<(Duration, usize)> {
         let authorize = Method::Authorize(Authorize {
-            username: self.config.username.clone(),
+            username,
             password: self.config.password.clone().or(Some("x".to_string())),
         });
 
@@ -299,6 +393,17 @@ impl Client {
         }
     }
 
+    /// Sends `mining.suggest_difficulty` as a notification, returning once it
+    /// has been written. Pools that accept the suggestion answer with
+    /// `mining.set_difficulty`; use [`Client::suggest_difficulty`] to also
+    /// wait for the pool to acknowledge it.
+    pub async fn notify_suggest_difficulty(&self, difficulty: Difficulty) -> Result {
+        self.send_notification(Method::SuggestDifficulty(SuggestDifficulty::from(
+            difficulty,
+        )))
+        .await
+    }
+
     pub async fn submit(
         &self,
         job_id: JobId,
@@ -355,7 +460,7 @@ mod tests {
         super::*,
         std::net::SocketAddr,
         tokio::{
-            io::{AsyncReadExt, BufReader},
+            io::{AsyncBufReadExt, AsyncReadExt, BufReader},
             net::TcpListener,
         },
     };
@@ -385,6 +490,325 @@ mod tests {
         addr
     }
 
+    /// Answers the first request with `notifications` followed by a `true`
+    /// response, so they are all broadcast before the request returns.
+    async fn notifying_server(notifications: Vec<Method>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            let line = lines.next_line().await.unwrap().unwrap();
+            let Message::Request { id, .. } = serde_json::from_str(&line).unwrap() else {
+                panic!("expected request, got {line}");
+            };
+
+            let messages = notifications
+                .into_iter()
+                .map(|method| Message::Notification { method })
+                .chain([Message::Response {
+                    id,
+                    result: Some(serde_json::json!(true)),
+                    error: None,
+                    reject_reason: None,
+                }]);
+
+            for message in messages {
+                let frame = serde_json::to_string(&message).unwrap() + "\n";
+                writer.write_all(frame.as_bytes()).await.unwrap();
+            }
+
+            while let Ok(Some(_)) = lines.next_line().await {}
+        });
+
+        addr
+    }
+
+    /// Answers every request with `true` and forwards the raw request lines.
+    async fn recording_server() -> (SocketAddr, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).unwrap();
+
+                let response = serde_json::json!({
+                    "id": request["id"],
+                    "result": true,
+                    "error": null,
+                });
+
+                tx.send(request).await.unwrap();
+
+                let frame = response.to_string() + "\n";
+                writer.write_all(frame.as_bytes()).await.unwrap();
+            }
+        });
+
+        (addr, rx)
+    }
+
+    fn test_client(addr: String) -> Client {
+        Client::new(
+            addr,
+            "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.test"
+                .parse()
+                .unwrap(),
+            None,
+            "test".into(),
+            Duration::from_secs(5),
+        )
+    }
+
+    #[tokio::test]
+    async fn show_message_emits_message_event() {
+        let addr = notifying_server(vec![Method::ShowMessage(ShowMessage(
+            "maintenance at noon".into(),
+        ))])
+        .await;
+
+        let client = test_client(addr.to_string());
+        let mut events = client.connect().await.unwrap();
+
+        client
+            .suggest_difficulty(Difficulty::from(1))
+            .await
+            .unwrap();
+
+        let event = events.try_recv().unwrap().unwrap();
+        assert!(
+            matches!(&event, Event::Message(message) if message == "maintenance at noon"),
+            "Expected Message event, got: {event:?}",
+        );
+    }
+
+    #[tokio::test]
+    async fn new_prevhash_emits_new_block_once() {
+        fn notify(job_id: &str, prevhash: &str) -> Method {
+            Method::Notify(Notify {
+                job_id: job_id.parse().unwrap(),
+                prevhash: prevhash.repeat(32).parse().unwrap(),
+                coinb1: String::new(),
+                coinb2: String::new(),
+                merkle_branches: Vec::new(),
+                version: Version(bitcoin::block::Version::TWO),
+                nbits: "1c2ac4af".parse().unwrap(),
+                ntime: "504e86b9".parse().unwrap(),
+                clean_jobs: false,
+            })
+        }
+
+        let addr = notifying_server(vec![
+            notify("1", "aa"),
+            notify("2", "aa"),
+            notify("3", "bb"),
+        ])
+        .await;
+
+        let client = test_client(addr.to_string());
+        let mut events = client.connect().await.unwrap();
+
+        client
+            .suggest_difficulty(Difficulty::from(1))
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        while let Some(event) = events.try_recv() {
+            received.push(event.unwrap());
+        }
+
+        let new_blocks = received
+            .iter()
+            .filter_map(|event| match event {
+                Event::NewBlock(prevhash) => Some(prevhash.clone()),
+                _ => None,
+            })
+            .collect::<Vec<PrevHash>>();
+
+        assert_eq!(new_blocks, ["bb".repeat(32).parse::<PrevHash>().unwrap()]);
+        assert!(matches!(&received[2], Event::NewBlock(_)));
+        assert!(
+            matches!(&received[3], Event::Notify(notify) if notify.job_id == "3".parse().unwrap())
+        );
+        assert_eq!(received.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn reconnect_emits_event_and_redirects_next_connect() {
+        let redirect = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let redirect_port = redirect.local_addr().unwrap().port();
+
+        let addr = notifying_server(vec![Method::Reconnect(Reconnect {
+            hostname: Some("127.0.0.1".into()),
+            port: Some(redirect_port),
+            wait_time: Some(5),
+        })])
+        .await;
+
+        let client = test_client(addr.to_string());
+        let mut events = client.connect().await.unwrap();
+
+        client
+            .suggest_difficulty(Difficulty::from(1))
+            .await
+            .unwrap();
+
+        let event = events.try_recv().unwrap().unwrap();
+        let Event::Reconnect(reconnect) = event else {
+            panic!("Expected Reconnect event, got: {event:?}");
+        };
+        assert_eq!(reconnect.hostname.as_deref(), Some("127.0.0.1"));
+        assert_eq!(reconnect.port, Some(redirect_port));
+        assert_eq!(reconnect.wait_time, Some(5));
+
+        client.connect().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), redirect.accept())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(client.address(), addr.to_string());
+    }
+
+    #[tokio::test]
+    async fn slow_receiver_observes_lag() {
+        let addr = notifying_server(
+            (1..=4)
+                .map(|difficulty| {
+                    Method::SetDifficulty(SetDifficulty(Difficulty::from(difficulty)))
+                })
+                .chain([Method::ShowMessage(ShowMessage("latest".into()))])
+                .collect(),
+        )
+        .await;
+
+        let client = Client::with_event_capacity(
+            addr.to_string(),
+            "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.test"
+                .parse()
+                .unwrap(),
+            None,
+            "test".into(),
+            Duration::from_secs(5),
+            CancellationToken::new(),
+            2,
+        );
+        let mut events = client.connect().await.unwrap();
+
+        client
+            .suggest_difficulty(Difficulty::from(1))
+            .await
+            .unwrap();
+
+        let err = events.recv().await.unwrap_err();
+        assert!(
+            matches!(err, ClientError::EventsLagged { count: 3 }),
+            "Expected EventsLagged, got: {err:?}",
+        );
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            Event::SetDifficulty(difficulty) if difficulty == Difficulty::from(4)
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            Event::Message(message) if message == "latest"
+        ));
+    }
+
+    #[tokio::test]
+    async fn notify_suggest_difficulty_sends_notification() {
+        let (addr, mut requests) = recording_server().await;
+
+        let client = test_client(addr.to_string());
+        client.connect().await.unwrap();
+
+        client
+            .notify_suggest_difficulty(Difficulty::from(1000))
+            .await
+            .unwrap();
+
+        let notification = requests.recv().await.unwrap();
+        assert_eq!(
+            notification,
+            serde_json::json!({
+                "method": "mining.suggest_difficulty",
+                "params": [1000],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn notify_suggest_difficulty_requires_connection() {
+        let client = test_client("127.0.0.1:1".into());
+
+        assert!(matches!(
+            client
+                .notify_suggest_difficulty(Difficulty::from(1000))
+                .await
+                .unwrap_err(),
+            ClientError::NotConnected
+        ));
+    }
+
+    #[tokio::test]
+    async fn submit_sends_version_bits_only_when_provided() {
+        let (addr, mut requests) = recording_server().await;
+
+        let client = test_client(addr.to_string());
+        client.connect().await.unwrap();
+
+        for version_bits in [Some(Version::from(0x1fffe000)), None] {
+            client
+                .submit(
+                    JobId::new(7),
+                    "00000001".parse().unwrap(),
+                    "504e86ed".parse().unwrap(),
+                    "b2957c02".parse().unwrap(),
+                    version_bits,
+                )
+                .await
+                .unwrap();
+        }
+
+        let with_version_bits = requests.recv().await.unwrap();
+        assert_eq!(with_version_bits["method"], "mining.submit");
+        assert_eq!(
+            with_version_bits["params"],
+            serde_json::json!([
+                "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.test",
+                "7",
+                "00000001",
+                "504e86ed",
+                "b2957c02",
+                "1fffe000"
+            ])
+        );
+
+        let without_version_bits = requests.recv().await.unwrap();
+        assert_eq!(
+            without_version_bits["params"],
+            serde_json::json!([
+                "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.test",
+                "7",
+                "00000001",
+                "504e86ed",
+                "b2957c02"
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn request_timeout() {
         let addr = mock_server(false).await;
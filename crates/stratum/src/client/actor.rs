@@ -30,6 +30,10 @@ pub(super) enum ClientMessage {
         method: Method,
         respond_to: oneshot::Sender<Result<(Message, usize)>>,
     },
+    Notification {
+        method: Method,
+        respond_to: oneshot::Sender<Result>,
+    },
     Disconnect {
         respond_to: oneshot::Sender<()>,
     },
@@ -37,12 +41,14 @@ pub(super) enum ClientMessage {
 
 pub(super) struct ClientActor {
     inner: Arc<Config>,
+    address: String,
     rx: mpsc::Receiver<ClientMessage>,
     events: broadcast::Sender<Event>,
     cancel: CancellationToken,
     id_counter: u64,
     pending: HashMap<Id, PendingRequest>,
     connection: Option<ConnectionState>,
+    prevhash: Option<PrevHash>,
 }
 
 impl ClientActor {
@@ -53,6 +59,7 @@ impl ClientActor {
         cancel: CancellationToken,
     ) -> Self {
         Self {
+            address: inner.address.clone(),
             inner,
             rx,
             events,
@@ -60,6 +67,7 @@ impl ClientActor {
             id_counter: 0,
             pending: HashMap::new(),
             connection: None,
+            prevhash: None,
         }
     }
 
@@ -111,6 +119,12 @@ impl ClientActor {
                                 }
                             }
                         }
+                        ClientMessage::Notification { method, respond_to } => {
+                            let result = self.send_message(&Message::Notification { method }).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("Notification response dropped: caller gave up");
+                            }
+                        }
                         ClientMessage::Disconnect { respond_to } => {
                             self.handle_disconnect().await;
                             if respond_to.send(()).is_err() {
@@ -158,19 +172,29 @@ impl ClientActor {
             self.handle_disconnect().await;
         }
 
-        let stream =
-            tokio::time::timeout(self.inner.timeout, TcpStream::connect(&self.inner.address))
+        let stream = match &self.inner.proxy {
+            Some(proxy) => {
+                let stream = tokio::time::timeout(self.inner.timeout, proxy.connect(&self.address))
+                    .await
+                    .map_err(|source| ClientError::Timeout { source })??;
+
+                debug!("Connected to {} via proxy {proxy}", self.address);
+
+                stream
+            }
+            None => tokio::time::timeout(self.inner.timeout, TcpStream::connect(&self.address))
                 .await
                 .map_err(|source| ClientError::Timeout { source })?
-                .map_err(|source| ClientError::Io { source })?;
+                .map_err(|source| ClientError::Io { source })?,
+        };
 
         stream
             .set_nodelay(true)
             .map_err(|source| ClientError::Io { source })?;
 
         match stream.peer_addr() {
-            Ok(peer) => debug!("Connected to {} -> {peer}", self.inner.address),
-            Err(_) => debug!("Connected to {}", self.inner.address),
+            Ok(peer) => debug!("Connected to {} -> {peer}", self.address),
+            Err(_) => debug!("Connected to {}", self.address),
         }
 
         let (reader, writer) = stream.into_split();
@@ -252,6 +276,17 @@ impl ClientActor {
             }
             IncomingMessage::Notification { method } => match method {
                 Method::Notify(notify) => {
+                    let previous = self.prevhash.replace(notify.prevhash.clone());
+
+                    if previous.is_some_and(|previous| previous != notify.prevhash)
+                        && self
+                            .events
+                            .send(Event::NewBlock(notify.prevhash.clone()))
+                            .is_err()
+                    {
+                        debug!("NewBlock event dropped: no subscribers");
+                    }
+
                     if self.events.send(Event::Notify(notify)).is_err() {
                         debug!("Notify event dropped: no subscribers");
                     }
@@ -266,10 +301,20 @@ impl ClientActor {
                     }
                 }
                 Method::Reconnect(reconnect) => {
+                    if let Some(address) = reconnect_address(&self.address, &reconnect) {
+                        debug!("Redirected from {} to {address}", self.address);
+                        self.address = address;
+                    }
+
                     if self.events.send(Event::Reconnect(reconnect)).is_err() {
                         debug!("Reconnect event dropped: no subscribers");
                     }
                 }
+                Method::ShowMessage(message) => {
+                    if self.events.send(Event::Message(message.0)).is_err() {
+                        debug!("Message event dropped: no subscribers");
+                    }
+                }
                 _ => warn!("Unhandled notification: {}", method.method_name()),
             },
             IncomingMessage::Disconnected => {
@@ -354,3 +399,21 @@ impl ClientActor {
         }
     }
 }
+
+/// Address to use for the next connect after a `client.reconnect`, falling
+/// back to the current host or port for whichever the server left out.
+fn reconnect_address(current: &str, reconnect: &Reconnect) -> Option<String> {
+    if reconnect.hostname.is_none() && reconnect.port.is_none() {
+        return None;
+    }
+
+    let (host, port) = current.rsplit_once(':')?;
+
+    let host = reconnect.hostname.as_deref().unwrap_or(host);
+    let port = reconnect
+        .port
+        .map(|port| port.to_string())
+        .unwrap_or_else(|| port.into());
+
+    Some(format!("{host}:{port}"))
+}
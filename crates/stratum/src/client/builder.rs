@@ -0,0 +1,212 @@
+use super::*;
+
+/// User agent sent in `mining.subscribe` unless the caller sets one.
+pub const USER_AGENT: &str = concat!("stratum/", env!("CARGO_PKG_VERSION"));
+
+/// Timeout for connecting and for each request unless the caller sets one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configures a [`Client`]. The pool address and username are required;
+/// everything else has a default.
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    address: Option<String>,
+    username: Option<Username>,
+    password: Option<String>,
+    user_agent: String,
+    timeout: Duration,
+    cancel: CancellationToken,
+    event_capacity: usize,
+    proxy: Option<Proxy>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            address: None,
+            username: None,
+            password: None,
+            user_agent: USER_AGENT.into(),
+            timeout: DEFAULT_TIMEOUT,
+            cancel: CancellationToken::new(),
+            event_capacity: DEFAULT_EVENT_CAPACITY,
+            proxy: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    pub fn with_username(mut self, username: Username) -> Self {
+        self.username = Some(username);
+        self
+    }
+
+    pub fn with_password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Events buffered per [`EventReceiver`] before a slow consumer starts
+    /// lagging. Must be nonzero.
+    pub fn with_event_capacity(mut self, event_capacity: usize) -> Self {
+        self.event_capacity = event_capacity;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<Proxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Spawns the client, which must happen inside a Tokio runtime. Fails if
+    /// the address or username is missing, the address is empty, or the
+    /// event capacity is zero.
+    pub fn build(mut self) -> Result<Client> {
+        let address = self
+            .address
+            .take()
+            .filter(|address| !address.trim().is_empty())
+            .ok_or(ClientError::MissingConfig { field: "address" })?;
+
+        let username = self
+            .username
+            .take()
+            .ok_or(ClientError::MissingConfig { field: "username" })?;
+
+        if self.event_capacity == 0 {
+            return Err(ClientError::InvalidConfig {
+                field: "event_capacity",
+                reason: "must be greater than zero".into(),
+            });
+        }
+
+        Ok(self.spawn(address, username))
+    }
+
+    /// Spawns the client without the checks `build` makes, for the
+    /// positional constructors on [`Client`].
+    pub(super) fn spawn(self, address: String, username: Username) -> Client {
+        Client::spawn(
+            Config {
+                address,
+                username,
+                password: self.password,
+                user_agent: self.user_agent,
+                timeout: self.timeout,
+                proxy: self.proxy,
+            },
+            self.cancel,
+            self.event_capacity,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn username() -> Username {
+        "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.test"
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn defaults() {
+        let client = Client::builder()
+            .with_address("127.0.0.1:3333")
+            .with_username(username())
+            .build()
+            .unwrap();
+
+        assert_eq!(client.address(), "127.0.0.1:3333");
+        assert_eq!(client.username(), &username());
+        assert_eq!(client.proxy(), None);
+        assert_eq!(client.config.password, None);
+        assert_eq!(client.config.user_agent, USER_AGENT);
+        assert_eq!(client.config.timeout, DEFAULT_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn setters_override_defaults() {
+        let client = Client::builder()
+            .with_address("127.0.0.1:3333")
+            .with_username(username())
+            .with_password(Some("x".into()))
+            .with_user_agent("test")
+            .with_timeout(Duration::from_secs(1))
+            .with_proxy(Some("socks5://127.0.0.1:9050".parse().unwrap()))
+            .build()
+            .unwrap();
+
+        assert_eq!(client.config.password.as_deref(), Some("x"));
+        assert_eq!(client.config.user_agent, "test");
+        assert_eq!(client.config.timeout, Duration::from_secs(1));
+        assert_eq!(
+            client.proxy(),
+            Some(&Proxy::Socks5("127.0.0.1:9050".into()))
+        );
+    }
+
+    #[test]
+    fn address_required() {
+        for builder in [
+            Client::builder().with_username(username()),
+            Client::builder()
+                .with_address(" ")
+                .with_username(username()),
+        ] {
+            assert!(matches!(
+                builder.build().map(drop).unwrap_err(),
+                ClientError::MissingConfig { field: "address" }
+            ));
+        }
+    }
+
+    #[test]
+    fn username_required() {
+        assert!(matches!(
+            Client::builder()
+                .with_address("127.0.0.1:3333")
+                .build()
+                .map(drop)
+                .unwrap_err(),
+            ClientError::MissingConfig { field: "username" }
+        ));
+    }
+
+    #[test]
+    fn zero_event_capacity_rejected() {
+        assert_eq!(
+            Client::builder()
+                .with_address("127.0.0.1:3333")
+                .with_username(username())
+                .with_event_capacity(0)
+                .build()
+                .map(drop)
+                .unwrap_err()
+                .to_string(),
+            "Invalid client event_capacity: must be greater than zero"
+        );
+    }
+}
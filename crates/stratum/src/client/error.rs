@@ -43,4 +43,16 @@ pub enum ClientError {
 
     #[snafu(display("Event channel closed"))]
     EventChannelClosed,
+
+    #[snafu(display("Invalid proxy `{proxy}`: expected socks5://HOST:PORT or http://HOST:PORT"))]
+    InvalidProxy { proxy: String },
+
+    #[snafu(display("Client {field} is required"))]
+    MissingConfig { field: &'static str },
+
+    #[snafu(display("Invalid client {field}: {reason}"))]
+    InvalidConfig { field: &'static str, reason: String },
+
+    #[snafu(display("Proxy error: {message}"))]
+    Proxy { message: String },
 }
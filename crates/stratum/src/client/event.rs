@@ -3,7 +3,12 @@ use super::*;
 #[derive(Debug, Clone)]
 pub enum Event {
     Notify(Notify),
+    /// Sent ahead of the first notify building on a different prevhash than
+    /// the jobs before it, so a new block can be told apart from a template
+    /// refresh on the same tip.
+    NewBlock(PrevHash),
     SetDifficulty(Difficulty),
     Reconnect(Reconnect),
+    Message(String),
     Disconnected,
 }
@@ -0,0 +1,386 @@
+use {
+    super::*,
+    std::net::IpAddr,
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_CONNECT: u8 = 0x01;
+const SOCKS5_IPV4: u8 = 0x01;
+const SOCKS5_DOMAIN: u8 = 0x03;
+const SOCKS5_IPV6: u8 = 0x04;
+const SOCKS5_SUCCEEDED: u8 = 0x00;
+
+/// Longest HTTP CONNECT response header accepted from a proxy.
+const MAX_HTTP_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// Proxy used to reach the pool, written as `socks5://host:port` or
+/// `http://host:port`.
+///
+/// SOCKS5 proxies are asked to resolve the pool's host name themselves, so
+/// names such as Tor onion addresses work. Only proxies that require no
+/// authentication are supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proxy {
+    Socks5(String),
+    Http(String),
+}
+
+impl Proxy {
+    pub fn address(&self) -> &str {
+        match self {
+            Self::Socks5(address) | Self::Http(address) => address,
+        }
+    }
+
+    /// Opens a connection to `target` tunnelled through this proxy.
+    pub(super) async fn connect(&self, target: &str) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(self.address())
+            .await
+            .map_err(|source| ClientError::Io { source })?;
+
+        match self {
+            Self::Socks5(_) => socks5_handshake(&mut stream, target).await?,
+            Self::Http(_) => http_connect(&mut stream, target).await?,
+        }
+
+        Ok(stream)
+    }
+}
+
+impl FromStr for Proxy {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidProxy { proxy: s.into() };
+
+        let (scheme, address) = s.split_once("://").ok_or_else(invalid)?;
+
+        let address = address.strip_suffix('/').unwrap_or(address);
+
+        match address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => return Err(invalid()),
+        }
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "socks5" | "socks5h" => Ok(Self::Socks5(address.into())),
+            "http" => Ok(Self::Http(address.into())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for Proxy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Socks5(address) => write!(f, "socks5://{address}"),
+            Self::Http(address) => write!(f, "http://{address}"),
+        }
+    }
+}
+
+fn proxy_error(message: impl Into<String>) -> ClientError {
+    ClientError::Proxy {
+        message: message.into(),
+    }
+}
+
+async fn socks5_handshake(stream: &mut TcpStream, target: &str) -> Result {
+    let (host, port) = target
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+        .ok_or_else(|| proxy_error(format!("invalid target address {target}")))?;
+
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+
+    write_all(stream, &[SOCKS5_VERSION, 1, SOCKS5_NO_AUTH]).await?;
+
+    let mut choice = [0u8; 2];
+    read_exact(stream, &mut choice).await?;
+
+    if choice != [SOCKS5_VERSION, SOCKS5_NO_AUTH] {
+        return Err(proxy_error("SOCKS5 proxy requires authentication"));
+    }
+
+    let mut request = vec![SOCKS5_VERSION, SOCKS5_CONNECT, 0x00];
+
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS5_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS5_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let len = u8::try_from(host.len())
+                .map_err(|_| proxy_error(format!("host name too long: {host}")))?;
+            request.push(SOCKS5_DOMAIN);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+
+    request.extend_from_slice(&port.to_be_bytes());
+
+    write_all(stream, &request).await?;
+
+    let mut reply = [0u8; 4];
+    read_exact(stream, &mut reply).await?;
+
+    if reply[0] != SOCKS5_VERSION {
+        return Err(proxy_error("invalid SOCKS5 reply"));
+    }
+
+    if reply[1] != SOCKS5_SUCCEEDED {
+        return Err(proxy_error(format!(
+            "SOCKS5 proxy refused connection to {target} (reply {:#04x})",
+            reply[1]
+        )));
+    }
+
+    let bound_len = match reply[3] {
+        SOCKS5_IPV4 => 4,
+        SOCKS5_IPV6 => 16,
+        SOCKS5_DOMAIN => {
+            let mut len = [0u8; 1];
+            read_exact(stream, &mut len).await?;
+            usize::from(len[0])
+        }
+        _ => return Err(proxy_error("invalid SOCKS5 reply")),
+    };
+
+    let mut bound = vec![0u8; bound_len + 2];
+    read_exact(stream, &mut bound).await?;
+
+    Ok(())
+}
+
+async fn http_connect(stream: &mut TcpStream, target: &str) -> Result {
+    let request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n");
+
+    write_all(stream, request.as_bytes()).await?;
+
+    // Read a byte at a time so nothing past the header is consumed, since
+    // the pool may speak first once the tunnel is up.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_HTTP_RESPONSE_SIZE {
+            return Err(proxy_error("HTTP proxy response too large"));
+        }
+
+        let mut byte = [0u8; 1];
+        read_exact(stream, &mut byte).await?;
+        response.push(byte[0]);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(proxy_error(format!(
+            "HTTP proxy refused connection to {target}: {status_line}"
+        ))),
+    }
+}
+
+async fn write_all(stream: &mut TcpStream, buf: &[u8]) -> Result {
+    stream
+        .write_all(buf)
+        .await
+        .map_err(|source| ClientError::Io { source })
+}
+
+async fn read_exact<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result {
+    reader
+        .read_exact(buf)
+        .await
+        .map(|_| ())
+        .map_err(|source| ClientError::Io { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tokio::net::TcpListener};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "socks5://127.0.0.1:9050".parse::<Proxy>().unwrap(),
+            Proxy::Socks5("127.0.0.1:9050".into())
+        );
+        assert_eq!(
+            "socks5h://localhost:9050/".parse::<Proxy>().unwrap(),
+            Proxy::Socks5("localhost:9050".into())
+        );
+        assert_eq!(
+            "HTTP://proxy.example.com:3128".parse::<Proxy>().unwrap(),
+            Proxy::Http("proxy.example.com:3128".into())
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for proxy in [
+            "127.0.0.1:9050",
+            "socks4://127.0.0.1:9050",
+            "socks5://127.0.0.1",
+            "socks5://:9050",
+            "http://proxy:port",
+        ] {
+            assert!(
+                matches!(
+                    proxy.parse::<Proxy>(),
+                    Err(ClientError::InvalidProxy { .. })
+                ),
+                "{proxy}"
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for proxy in ["socks5://127.0.0.1:9050", "http://proxy:3128"] {
+            assert_eq!(proxy.parse::<Proxy>().unwrap().to_string(), proxy);
+        }
+    }
+
+    #[tokio::test]
+    async fn socks5_requests_domain_target() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Proxy::Socks5(listener.local_addr().unwrap().to_string());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [SOCKS5_VERSION, 1, SOCKS5_NO_AUTH]);
+            socket
+                .write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH])
+                .await
+                .unwrap();
+
+            let mut request = [0u8; 5];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(
+                request,
+                [SOCKS5_VERSION, SOCKS5_CONNECT, 0, SOCKS5_DOMAIN, 11]
+            );
+
+            let mut target = [0u8; 13];
+            socket.read_exact(&mut target).await.unwrap();
+            assert_eq!(&target[..11], b"example.com");
+            assert_eq!(u16::from_be_bytes([target[11], target[12]]), 3333);
+
+            socket
+                .write_all(&[
+                    SOCKS5_VERSION,
+                    SOCKS5_SUCCEEDED,
+                    0,
+                    SOCKS5_IPV4,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                ])
+                .await
+                .unwrap();
+            socket.write_all(b"hello").await.unwrap();
+        });
+
+        let mut stream = proxy.connect("example.com:3333").await.unwrap();
+
+        let mut greeting = [0u8; 5];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(&greeting, b"hello");
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn socks5_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Proxy::Socks5(listener.local_addr().unwrap().to_string());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH])
+                .await
+                .unwrap();
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(&[SOCKS5_VERSION, 0x05, 0, SOCKS5_IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+
+        assert!(matches!(
+            proxy.connect("127.0.0.1:3333").await,
+            Err(ClientError::Proxy { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn http_connect_tunnels() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Proxy::Http(listener.local_addr().unwrap().to_string());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut byte = [0u8; 1];
+                socket.read_exact(&mut byte).await.unwrap();
+                request.push(byte[0]);
+            }
+
+            assert!(request.starts_with(b"CONNECT pool.example.com:3333 HTTP/1.1\r\n"));
+
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nhello")
+                .await
+                .unwrap();
+        });
+
+        let mut stream = proxy.connect("pool.example.com:3333").await.unwrap();
+
+        let mut greeting = [0u8; 5];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(&greeting, b"hello");
+    }
+
+    #[tokio::test]
+    async fn http_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Proxy::Http(listener.local_addr().unwrap().to_string());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        assert!(matches!(
+            proxy.connect("pool.example.com:3333").await,
+            Err(ClientError::Proxy { .. })
+        ));
+    }
+}
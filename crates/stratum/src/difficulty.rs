@@ -24,6 +24,25 @@ impl Difficulty {
     pub fn as_f64(self) -> f64 {
         Target::from_compact(self.0).difficulty_float()
     }
+
+    /// The difficulty of a share with `hash`, measured against the
+    /// difficulty-1 target `0x00000000ffff0000...` without the rounding of
+    /// the compact form, so it matches what ckpool and miners report.
+    pub fn of_hash(hash: BlockHash) -> f64 {
+        Target::from_le_bytes(hash.to_byte_array()).difficulty_float()
+    }
+
+    /// Whether `hash` meets the target this difficulty stands for.
+    pub fn is_met_by(self, hash: BlockHash) -> bool {
+        self.to_target().is_met_by(hash)
+    }
+
+    /// Whether `hash` meets this difficulty, or falls short of it by no more
+    /// than the fraction `tolerance`. A tolerance of zero is `is_met_by`.
+    pub fn is_nearly_met_by(self, hash: BlockHash, tolerance: f64) -> bool {
+        self.is_met_by(hash)
+            || (tolerance > 0.0 && Self::of_hash(hash) >= self.as_f64() * (1.0 - tolerance))
+    }
 }
 
 impl From<BlockHash> for Difficulty {
@@ -198,9 +217,18 @@ impl FromStr for Difficulty {
             return Ok(Difficulty::from(x));
         }
 
-        Err(InternalError::Parse {
-            message: "difficulty must be an integer or float".to_string(),
-        })
+        // Accept the SI-prefixed form difficulties are displayed in, e.g. `1K`
+        let x = parse_si(difficulty, &[]).map_err(|_| InternalError::Parse {
+            message: "difficulty must be an integer, float, or SI-prefixed number".to_string(),
+        })?;
+
+        if x <= 0.0 {
+            return Err(InternalError::InvalidValue {
+                reason: "difficulty must be > 0".to_string(),
+            });
+        }
+
+        Ok(Difficulty::from(x))
     }
 }
 
@@ -232,6 +260,39 @@ mod tests {
         assert!(relative_error(got, want) < 1e-6);
     }
 
+    #[test]
+    fn is_met_by() {
+        let difficulty = Difficulty::from(1);
+        let target = difficulty.to_target();
+
+        assert!(difficulty.is_met_by(BlockHash::from_byte_array(target.to_le_bytes())));
+        assert!(Difficulty::from(0.5).is_met_by(BlockHash::from_byte_array(target.to_le_bytes())));
+        assert!(!Difficulty::from(2).is_met_by(BlockHash::from_byte_array(target.to_le_bytes())));
+    }
+
+    #[test]
+    fn is_nearly_met_by() {
+        let difficulty = Difficulty::from(1);
+        let target = U256::from_big_endian(&difficulty.to_target().to_be_bytes());
+
+        let hash = |target: U256| {
+            BlockHash::from_byte_array(Target::from_be_bytes(target.to_big_endian()).to_le_bytes())
+        };
+
+        let at_target = hash(target);
+        let just_under = hash(target + target / U256::from(10_000));
+        let well_under = hash(target * U256::from(2));
+
+        assert!(difficulty.is_nearly_met_by(at_target, 0.0));
+        assert!(difficulty.is_nearly_met_by(at_target, 0.001));
+
+        assert!(!difficulty.is_nearly_met_by(just_under, 0.0));
+        assert!(difficulty.is_nearly_met_by(just_under, 0.001));
+
+        assert!(!difficulty.is_nearly_met_by(well_under, 0.0));
+        assert!(!difficulty.is_nearly_met_by(well_under, 0.001));
+    }
+
     #[test]
     fn ordering() {
         let a = Difficulty::from(0.5);
@@ -385,6 +446,23 @@ mod tests {
         case_from_str("1000", 1000.0);
     }
 
+    #[test]
+    fn from_str_si_prefixed() {
+        case_from_str("1K", 1_000.0);
+        case_from_str("10k", 10_000.0);
+
+        assert_eq!(
+            Difficulty::from_str("2.5M").unwrap(),
+            Difficulty::from(2_500_000)
+        );
+
+        let difficulty = Difficulty::from(150_000_000_000_000u64);
+        assert_eq!(
+            Difficulty::from_str(&difficulty.to_string()).unwrap(),
+            difficulty
+        );
+    }
+
     #[test]
     fn from_str_rejects_bad() {
         for s in [
@@ -397,6 +475,9 @@ mod tests {
             "Infinity",
             "-Infinity",
             "nope",
+            "0K",
+            "-1K",
+            "K",
         ] {
             assert!(Difficulty::from_str(s).is_err(), "should reject {s}");
         }
@@ -414,6 +495,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn of_hash_matches_reference() {
+        #[track_caller]
+        fn case(header: &str, hash: &str, reference: f64) {
+            let header: block::Header =
+                bitcoin::consensus::encode::deserialize_hex(header).unwrap();
+            assert_eq!(header.block_hash().to_string(), hash);
+
+            // 0xffff * 2^208 / hash, computed with arbitrary precision integers.
+            let difficulty = Difficulty::of_hash(header.block_hash());
+            assert!(
+                relative_error(difficulty, reference) < 1e-12,
+                "{difficulty} != {reference}"
+            );
+        }
+
+        case(
+            "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c",
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            2536.4262984453103,
+        );
+
+        case(
+            "0100000081cd02ab7e569e8bcd9317e2fe99f2de44d49ab2b8851ba4a308000000000000e320b6c2fffc8d750423db8b1eb942ae710e951ed797f7affc8892b0f1fc122bc7f5d74df2b9441a42a14695",
+            "00000000000000001e8d6829a8a21adc5d38d0a473b144b6765798e61f98bd1d",
+            35987218905.508736,
+        );
+    }
+
+    #[test]
+    fn of_hash_max_target_is_difficulty_one() {
+        let hash = BlockHash::from_byte_array(Target::MAX.to_le_bytes());
+        assert_eq!(Difficulty::of_hash(hash), 1.0);
+    }
+
     #[test]
     fn from_blockhash_lower_hash_means_higher_difficulty() {
         let easy_target = Target::MAX;
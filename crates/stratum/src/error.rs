@@ -2,9 +2,11 @@ use super::*;
 
 pub type Result<T, E = InternalError> = std::result::Result<T, E>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[repr(i32)]
 pub enum StratumError {
+    InvalidParams = -13,
     PoolFull = -12,
     UnsupportedExtension = -11,
     MethodNotAllowed = -10,
@@ -23,11 +25,15 @@ pub enum StratumError {
     Duplicate = 4,
     AboveTarget = 5,
     InvalidVersionMask = 6,
+    StaleDifficulty = 7,
+    Maintenance = 8,
+    ClockSkew = 9,
 }
 
 impl fmt::Display for StratumError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let message = match self {
+            Self::InvalidParams => "Invalid params",
             Self::PoolFull => "Pool full",
             Self::UnsupportedExtension => "Unsupported extension",
             Self::MethodNotAllowed => "Method not allowed",
@@ -46,6 +52,9 @@ impl fmt::Display for StratumError {
             Self::Duplicate => "Duplicate",
             Self::AboveTarget => "Above target",
             Self::InvalidVersionMask => "Invalid version mask",
+            Self::StaleDifficulty => "Stale difficulty",
+            Self::Maintenance => "Pool under maintenance",
+            Self::ClockSkew => "Ntime too far from pool time",
         };
         write!(f, "{}", message)
     }
@@ -225,6 +234,14 @@ mod tests {
             StratumError::InvalidVersionMask.to_string(),
             "Invalid version mask"
         );
+        assert_eq!(
+            StratumError::StaleDifficulty.to_string(),
+            "Stale difficulty"
+        );
+        assert_eq!(
+            StratumError::ClockSkew.to_string(),
+            "Ntime too far from pool time"
+        );
     }
 
     #[test]
@@ -281,6 +298,8 @@ mod tests {
         assert_eq!(StratumError::Duplicate as i32, 4);
         assert_eq!(StratumError::AboveTarget as i32, 5);
         assert_eq!(StratumError::InvalidVersionMask as i32, 6);
+        assert_eq!(StratumError::StaleDifficulty as i32, 7);
+        assert_eq!(StratumError::ClockSkew as i32, 9);
     }
 
     #[test]
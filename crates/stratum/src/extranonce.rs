@@ -19,6 +19,18 @@ impl Extranonce {
         Self(SmallVec::from_slice(bytes))
     }
 
+    /// Increments in place, returning `false` and leaving the value
+    /// unchanged if every byte is already `0xff`.
+    pub fn increment(&mut self) -> bool {
+        if self.0.iter().all(|b| *b == u8::MAX) {
+            return false;
+        }
+
+        self.increment_wrapping();
+
+        true
+    }
+
     pub fn increment_wrapping(&mut self) {
         for b in self.0.iter_mut().rev() {
             let (next, carry) = b.overflowing_add(1);
@@ -153,6 +165,21 @@ mod tests {
         assert_eq!(enonce.len(), 2);
     }
 
+    #[test]
+    fn checked_increment() {
+        let mut enonce = "00fe".parse::<Extranonce>().unwrap();
+        assert!(enonce.increment());
+        assert_eq!(enonce.to_hex(), "00ff");
+        assert!(enonce.increment());
+        assert_eq!(enonce.to_hex(), "0100");
+
+        let mut enonce = "ffff".parse::<Extranonce>().unwrap();
+        assert!(!enonce.increment());
+        assert_eq!(enonce.to_hex(), "ffff");
+
+        assert!(!Extranonce::zeros(0).increment());
+    }
+
     #[test]
     fn increment_sequence() {
         let mut enonce = "00fe".parse::<Extranonce>().unwrap();
@@ -32,11 +32,11 @@ pub use {
     error::{InternalError, Result, StratumError, StratumErrorResponse},
     extranonce::Extranonce,
     job_id::JobId,
-    merkle::{MerkleNode, merkle_branches, merkle_root},
+    merkle::{MerkleNode, coinbase_merkle_root, merkle_branches, merkle_root},
     message::{Id, Message},
     method::{
-        Authorize, Configure, ConfigureResponse, Method, Notify, Reconnect, SetDifficulty, Submit,
-        Subscribe, SubscribeResponse, SuggestDifficulty,
+        Authorize, Configure, ConfigureResponse, Method, Notify, Reconnect, Resume, SetDifficulty,
+        ShowMessage, Submit, Subscribe, SubscribeResponse, SuggestDifficulty,
     },
     nbits::Nbits,
     nonce::Nonce,
@@ -90,9 +90,13 @@ pub fn merkle_root(
 ) -> Result<MerkleNode, InternalError> {
     let coinbase_bin =
         hex::decode(format!("{coinb1}{enonce1}{enonce2}{coinb2}")).context(error::HexParseSnafu)?;
-    let coinbase_hash = sha256d::Hash::hash(&coinbase_bin);
 
-    let mut merkle_root = coinbase_hash;
+    Ok(coinbase_merkle_root(&coinbase_bin, merkle_branches))
+}
+
+/// Calculates the merkle root from an already assembled coinbase transaction.
+pub fn coinbase_merkle_root(coinbase: &[u8], merkle_branches: &[MerkleNode]) -> MerkleNode {
+    let mut merkle_root = sha256d::Hash::hash(coinbase);
     for branch in merkle_branches {
         let mut concat = Vec::with_capacity(64);
         concat.extend_from_slice(&merkle_root[..]);
@@ -100,7 +104,7 @@ pub fn merkle_root(
         merkle_root = sha256d::Hash::hash(&concat);
     }
 
-    Ok(MerkleNode::from_raw_hash(merkle_root))
+    MerkleNode::from_raw_hash(merkle_root)
 }
 
 /// Constructs the merkle branches from all non-coinbase transactions that should be included in
@@ -4,7 +4,9 @@ mod authorize;
 mod configure;
 mod notify;
 mod reconnect;
+mod resume;
 mod set_difficulty;
+mod show_message;
 mod submit;
 mod subscribe;
 mod suggest_difficulty;
@@ -14,7 +16,9 @@ pub use {
     configure::{Configure, ConfigureResponse},
     notify::Notify,
     reconnect::Reconnect,
+    resume::Resume,
     set_difficulty::SetDifficulty,
+    show_message::ShowMessage,
     submit::Submit,
     subscribe::{Subscribe, SubscribeResponse},
     suggest_difficulty::SuggestDifficulty,
@@ -30,6 +34,8 @@ pub enum Method {
     SetDifficulty(SetDifficulty),
     SuggestDifficulty(SuggestDifficulty),
     Reconnect(Reconnect),
+    Resume(Resume),
+    ShowMessage(ShowMessage),
     Unknown { method: String, params: Value },
 }
 
@@ -44,6 +50,8 @@ impl Method {
             Self::SetDifficulty(_) => "mining.set_difficulty",
             Self::SuggestDifficulty(_) => "mining.suggest_difficulty",
             Self::Reconnect(_) => "client.reconnect",
+            Self::Resume(_) => "mining.resume",
+            Self::ShowMessage(_) => "client.show_message",
             Self::Unknown { method, .. } => method,
         }
     }
@@ -58,6 +66,8 @@ impl Method {
             Self::SetDifficulty(v) => v.serialize(serializer),
             Self::SuggestDifficulty(v) => v.serialize(serializer),
             Self::Reconnect(v) => v.serialize(serializer),
+            Self::Resume(v) => v.serialize(serializer),
+            Self::ShowMessage(v) => v.serialize(serializer),
             Self::Unknown { params, .. } => params.serialize(serializer),
         }
     }
@@ -74,6 +84,8 @@ impl Method {
                 serde_json::from_str(raw_params).map(Self::SuggestDifficulty)
             }
             "client.reconnect" => serde_json::from_str(raw_params).map(Self::Reconnect),
+            "mining.resume" => serde_json::from_str(raw_params).map(Self::Resume),
+            "client.show_message" => serde_json::from_str(raw_params).map(Self::ShowMessage),
             _ => Ok(Self::Unknown {
                 method: method.to_owned(),
                 params: serde_json::from_str(raw_params)?,
@@ -94,6 +106,8 @@ impl Method {
             Self::SetDifficulty(v) => serde_json::to_value(v),
             Self::SuggestDifficulty(v) => serde_json::to_value(v),
             Self::Reconnect(v) => serde_json::to_value(v),
+            Self::Resume(v) => serde_json::to_value(v),
+            Self::ShowMessage(v) => serde_json::to_value(v),
             Self::Unknown { params, .. } => Ok(params.clone()),
         }
     }
@@ -125,6 +139,8 @@ mod tests {
             "mining.suggest_difficulty",
         );
         case("client.reconnect", "[]", "client.reconnect");
+        case("mining.resume", r#"["foo", "ab"]"#, "mining.resume");
+        case("client.show_message", r#"["hello"]"#, "client.show_message");
         case(
             "mining.notify",
             r#"["bf","4d16b6f85af6e2198f44ae2a6de67f78487ae5611b77c6c0440b921e00000000","aa","bb",[],"00000002","1c2ac4af","504e86b9",false]"#,
@@ -217,6 +233,11 @@ mod tests {
             version_rolling_min_bit_count: None,
         }));
         case(Method::Reconnect(Reconnect::default()));
+        case(Method::Resume(Resume {
+            user_agent: "foo".into(),
+            token: "ab".into(),
+        }));
+        case(Method::ShowMessage(ShowMessage("hello".into())));
         case(Method::Unknown {
             method: "mining.foo".into(),
             params: serde_json::json!([1, "bar"]),
@@ -0,0 +1,59 @@
+use super::*;
+
+/// mining.resume, a para extension. Stands in for `mining.subscribe` on a
+/// connection picking up a session that dropped moments ago, using the token
+/// handed out as the `mining.resume` subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resume {
+    pub user_agent: String,
+    pub token: String,
+}
+
+impl Serialize for Resume {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(2))?;
+        seq.serialize_element(&self.user_agent)?;
+        seq.serialize_element(&self.token)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Resume {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (user_agent, token) = <(String, String)>::deserialize(deserializer)?;
+        Ok(Resume { user_agent, token })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_roundtrip() {
+        let expected = Resume {
+            user_agent: "para/0.1".into(),
+            token: "00112233445566778899aabbccddeeff".into(),
+        };
+
+        let parsed: Resume =
+            serde_json::from_str(r#"["para/0.1","00112233445566778899aabbccddeeff"]"#).unwrap();
+        assert_eq!(parsed, expected);
+
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::json!(["para/0.1", "00112233445566778899aabbccddeeff"])
+        );
+    }
+
+    #[test]
+    fn resume_requires_token() {
+        assert!(serde_json::from_str::<Resume>(r#"["para/0.1"]"#).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+/// client.show_message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShowMessage(pub String);
+
+impl ShowMessage {
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Serialize for ShowMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(1))?;
+        seq.serialize_element(&self.0)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ShowMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (message,): (String,) = Deserialize::deserialize(deserializer)?;
+        Ok(ShowMessage(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn show_message_roundtrip() {
+        let expected = ShowMessage("maintenance at noon".into());
+        let parsed: ShowMessage = serde_json::from_str(r#"["maintenance at noon"]"#).unwrap();
+        assert_eq!(parsed, expected);
+
+        let ser = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(ser, serde_json::json!(["maintenance at noon"]));
+    }
+
+    #[test]
+    fn show_message_reject_bad_arity() {
+        assert!(serde_json::from_str::<ShowMessage>("[]").is_err());
+        assert!(serde_json::from_str::<ShowMessage>(r#"["a", "b"]"#).is_err());
+    }
+}
@@ -8,6 +8,16 @@ impl Nbits {
     pub fn to_compact(&self) -> CompactTarget {
         self.0
     }
+
+    /// The network target a block hash has to meet.
+    pub fn to_target(self) -> Target {
+        Target::from_compact(self.0)
+    }
+
+    /// Whether `hash` is low enough to be a valid block.
+    pub fn is_met_by(self, hash: BlockHash) -> bool {
+        self.to_target().is_met_by(hash)
+    }
 }
 
 impl FromStr for Nbits {
@@ -38,3 +48,69 @@ impl From<CompactTarget> for Nbits {
         Nbits(n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(target: Target) -> BlockHash {
+        BlockHash::from_byte_array(target.to_le_bytes())
+    }
+
+    #[test]
+    fn known_nbits_to_target() {
+        assert_eq!(
+            "1d00ffff".parse::<Nbits>().unwrap().to_target(),
+            Target::MAX
+        );
+
+        assert_eq!(
+            "1b0404cb".parse::<Nbits>().unwrap().to_target(),
+            Target::from_be_bytes(
+                <[u8; 32]>::from_hex(
+                    "00000000000404cb000000000000000000000000000000000000000000000000"
+                )
+                .unwrap()
+            )
+        );
+
+        assert_eq!(
+            "207fffff".parse::<Nbits>().unwrap().to_target(),
+            Target::MAX_ATTAINABLE_REGTEST
+        );
+    }
+
+    #[test]
+    fn genesis_hash_meets_genesis_nbits() {
+        let nbits = "1d00ffff".parse::<Nbits>().unwrap();
+
+        assert!(
+            nbits.is_met_by(
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+                    .parse()
+                    .unwrap()
+            )
+        );
+
+        assert!(
+            !nbits.is_met_by(
+                "00000001009d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f0"
+                    .parse()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn hash_at_target_meets_it() {
+        let nbits = "1b0404cb".parse::<Nbits>().unwrap();
+        let target = nbits.to_target();
+
+        assert!(nbits.is_met_by(hash(target)));
+
+        let mut above = target.to_le_bytes();
+        above[0] += 1;
+
+        assert!(!nbits.is_met_by(BlockHash::from_byte_array(above)));
+    }
+}
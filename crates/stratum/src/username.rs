@@ -1,7 +1,7 @@
 use super::*;
 
 /// Worker identity with Bitcoin address parsing
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "String")]
 pub struct Username {
     raw: String,
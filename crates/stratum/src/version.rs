@@ -4,6 +4,17 @@ use super::*;
 #[derive(Debug, Clone, PartialEq, Eq, DeserializeFromStr, SerializeDisplay, Copy)]
 pub struct Version(pub block::Version);
 
+impl Version {
+    /// The nVersion bits BIP320 sets aside for general purpose use, and so the
+    /// only bits miners may roll.
+    pub const BIP320_MASK: Self = Self(block::Version::from_consensus(0x1fffe000));
+
+    /// Whether every bit set in this mask lies within [`Self::BIP320_MASK`].
+    pub fn is_within_bip320(self) -> bool {
+        self & !Self::BIP320_MASK == Self::from(0)
+    }
+}
+
 impl FromStr for Version {
     type Err = InternalError;
 
@@ -149,5 +160,16 @@ mod tests {
         let default_version = Version::default();
         assert_eq!(default_version.to_string(), "1fffe000");
         assert_eq!(default_version.0.to_consensus(), 0x1fffe000);
+        assert_eq!(default_version, Version::BIP320_MASK);
+    }
+
+    #[test]
+    fn within_bip320() {
+        assert!(Version::BIP320_MASK.is_within_bip320());
+        assert!(Version::from_str("00ffe000").unwrap().is_within_bip320());
+        assert!(Version::from(0).is_within_bip320());
+        assert!(!Version::from_str("20000000").unwrap().is_within_bip320());
+        assert!(!Version::from_str("1fffe001").unwrap().is_within_bip320());
+        assert!(!Version::from_str("ffffffff").unwrap().is_within_bip320());
     }
 }
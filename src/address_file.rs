@@ -0,0 +1,123 @@
+use super::*;
+
+/// Reads `path` as a list of addresses for `network`, one per line. Blank
+/// lines and `#` comments are ignored. `kind` names the file in errors.
+pub(crate) fn read<T: FromIterator<Address>>(
+    path: &Path,
+    network: Network,
+    kind: &str,
+) -> Result<T> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {kind} `{}`", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            (!line.is_empty()).then_some((i + 1, line))
+        })
+        .map(|(number, line)| {
+            line.parse::<Address<NetworkUnchecked>>()
+                .map_err(Error::from)
+                .and_then(|address| address.require_network(network).map_err(Error::from))
+                .with_context(|| {
+                    format!(
+                        "invalid address `{line}` on line {number} of {kind} `{}`",
+                        path.display()
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Calls `reload` whenever the process receives SIGHUP, until `cancel` fires.
+pub(crate) fn spawn_reloader(
+    cancel: CancellationToken,
+    tasks: &TaskTracker,
+    reload: impl Fn() + Send + 'static,
+) -> Result {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut sighup =
+            signal(SignalKind::hangup()).context("failed to install SIGHUP handler")?;
+
+        tasks.spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = sighup.recv() => reload(),
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (cancel, tasks, reload);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc";
+    const SECOND: &str = "tb1q6en7qjxgw4ev8xwx94pzdry6a6ky7wlfeqzunz";
+
+    fn read_file(contents: &str, network: Network) -> Result<Vec<Address>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("addresses.txt");
+        fs::write(&path, contents).unwrap();
+        read(&path, network, "address file")
+    }
+
+    #[test]
+    fn comments_and_blank_lines_ignored() {
+        let addresses = read_file(
+            &format!("# hot\n  {FIRST}  # rack 1\n\n{SECOND}\n"),
+            Network::Signet,
+        )
+        .unwrap();
+
+        assert_eq!(
+            addresses
+                .iter()
+                .map(Address::to_string)
+                .collect::<Vec<String>>(),
+            [FIRST, SECOND]
+        );
+    }
+
+    #[test]
+    fn invalid_address_names_line_and_kind() {
+        let err = read_file(&format!("{FIRST}\nnot-an-address\n"), Network::Signet).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("invalid address `not-an-address` on line 2 of address file"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn missing_file_names_kind() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = read::<Vec<Address>>(
+            &dir.path().join("missing.txt"),
+            Network::Signet,
+            "allowlist",
+        )
+        .unwrap_err();
+
+        assert!(
+            err.to_string().starts_with("failed to read allowlist"),
+            "{err}"
+        );
+    }
+}
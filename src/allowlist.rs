@@ -0,0 +1,156 @@
+use super::*;
+
+/// Payout addresses allowed to authorize on a private pool, read from a file
+/// with one address per line. Blank lines and `#` comments are ignored. An
+/// empty list allows every address.
+#[derive(Debug)]
+pub(crate) struct Allowlist {
+    path: PathBuf,
+    network: Network,
+    addresses: RwLock<HashSet<Address>>,
+}
+
+impl Allowlist {
+    pub(crate) fn load(path: PathBuf, network: Network) -> Result<Self> {
+        let addresses = Self::read(&path, network)?;
+
+        Ok(Self {
+            path,
+            network,
+            addresses: RwLock::new(addresses),
+        })
+    }
+
+    pub(crate) fn allows(&self, address: &Address) -> bool {
+        let addresses = self.addresses.read();
+        addresses.is_empty() || addresses.contains(address)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.addresses.read().len()
+    }
+
+    /// Re-reads the file, keeping the current list if it cannot be read.
+    /// Returns the number of addresses now on the list.
+    pub(crate) fn reload(&self) -> Result<usize> {
+        let addresses = Self::read(&self.path, self.network)?;
+        let len = addresses.len();
+        *self.addresses.write() = addresses;
+        Ok(len)
+    }
+
+    fn read(path: &Path, network: Network) -> Result<HashSet<Address>> {
+        address_file::read(path, network, "allowlist")
+    }
+
+    /// Reloads the list whenever the process receives SIGHUP.
+    pub(crate) fn spawn_reloader(
+        self: Arc<Self>,
+        cancel: CancellationToken,
+        tasks: &TaskTracker,
+    ) -> Result {
+        address_file::spawn_reloader(cancel, tasks, move || match self.reload() {
+            Ok(len) => info!(
+                "Reloaded allowlist {} with {len} addresses",
+                self.path.display()
+            ),
+            Err(err) => warn!("Failed to reload allowlist, keeping current list: {err:#}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: &str = "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc";
+    const OTHER: &str = "tb1q6en7qjxgw4ev8xwx94pzdry6a6ky7wlfeqzunz";
+
+    fn address(s: &str) -> Address {
+        s.parse::<Address<NetworkUnchecked>>()
+            .unwrap()
+            .require_network(Network::Signet)
+            .unwrap()
+    }
+
+    fn allowlist(contents: &str) -> (tempfile::TempDir, Allowlist) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        fs::write(&path, contents).unwrap();
+        let allowlist = Allowlist::load(path, Network::Signet).unwrap();
+        (dir, allowlist)
+    }
+
+    #[test]
+    fn listed_address_allowed() {
+        let (_dir, allowlist) = allowlist(&format!("{ALLOWED}\n"));
+        assert_eq!(allowlist.len(), 1);
+        assert!(allowlist.allows(&address(ALLOWED)));
+        assert!(!allowlist.allows(&address(OTHER)));
+    }
+
+    #[test]
+    fn empty_list_allows_everyone() {
+        let (_dir, allowlist) = allowlist("# nobody yet\n\n");
+        assert_eq!(allowlist.len(), 0);
+        assert!(allowlist.allows(&address(ALLOWED)));
+        assert!(allowlist.allows(&address(OTHER)));
+    }
+
+    #[test]
+    fn comments_and_whitespace_ignored() {
+        let (_dir, allowlist) = allowlist(&format!("# farm\n  {ALLOWED}  # rack 1\n\n"));
+        assert_eq!(allowlist.len(), 1);
+        assert!(allowlist.allows(&address(ALLOWED)));
+    }
+
+    #[test]
+    fn invalid_address_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        fs::write(&path, format!("{ALLOWED}\nnot-an-address\n")).unwrap();
+
+        let err = Allowlist::load(path, Network::Signet).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid address `not-an-address` on line 2 of allowlist"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn wrong_network_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        fs::write(&path, format!("{ALLOWED}\n")).unwrap();
+
+        assert!(Allowlist::load(path, Network::Bitcoin).is_err());
+    }
+
+    #[test]
+    fn reload_picks_up_changes() {
+        let (dir, allowlist) = allowlist(&format!("{ALLOWED}\n"));
+        assert!(!allowlist.allows(&address(OTHER)));
+
+        fs::write(
+            dir.path().join("allowlist.txt"),
+            format!("{ALLOWED}\n{OTHER}\n"),
+        )
+        .unwrap();
+
+        assert_eq!(allowlist.reload().unwrap(), 2);
+        assert!(allowlist.allows(&address(OTHER)));
+    }
+
+    #[test]
+    fn failed_reload_keeps_current_list() {
+        let (dir, allowlist) = allowlist(&format!("{ALLOWED}\n"));
+
+        fs::write(dir.path().join("allowlist.txt"), "garbage\n").unwrap();
+
+        assert!(allowlist.reload().is_err());
+        assert_eq!(allowlist.len(), 1);
+        assert!(allowlist.allows(&address(ALLOWED)));
+        assert!(!allowlist.allows(&address(OTHER)));
+    }
+}
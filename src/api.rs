@@ -1,6 +1,6 @@
 use {
     super::*,
-    axum::extract::{Path, State},
+    axum::extract::{Path, Query, State},
     http_server::{
         self, common_routes,
         error::{OptionExt, ServerError, ServerResult},
@@ -35,6 +35,9 @@ pub struct MiningStats {
     pub accepted_work: HashWork,
     pub rejected_work: HashWork,
     pub delivered_hash_days: HashDays,
+    pub version_rolled_shares: u64,
+    pub version_rolling_ratio: f64,
+    pub version_rolled_bits: BTreeMap<u32, u64>,
 }
 
 impl MiningStats {
@@ -60,6 +63,173 @@ impl MiningStats {
             accepted_work: stats.accepted_work,
             rejected_work: stats.rejected_work,
             delivered_hash_days: (stats.accepted_work + stats.rejected_work).to_hash_days(),
+            version_rolled_shares: stats.version_rolled_shares,
+            version_rolling_ratio: stats.version_rolling_ratio(),
+            version_rolled_bits: (0..)
+                .zip(stats.version_rolled_bits)
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shutdown {
+    pub draining_connections: usize,
+}
+
+/// How the pool answers shares while its node is under maintenance. Miners
+/// stay connected and keep getting jobs, but no share is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceMode {
+    /// Reject shares with a maintenance error.
+    Reject,
+    /// Accept shares but throw them away.
+    Discard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Maintenance {
+    pub mode: Option<MaintenanceMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionDetail {
+    pub socket_addr: SocketAddr,
+    pub address: Option<Address<NetworkUnchecked>>,
+    pub worker_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub version_mask: Option<Version>,
+    pub difficulty: Difficulty,
+    pub connected_at: u64,
+}
+
+impl ConnectionDetail {
+    pub(crate) fn from_connection(connection: &metatron::connection::Connection) -> Self {
+        let details = connection.details();
+        Self {
+            socket_addr: connection.socket_addr(),
+            address: details.address.map(|address| address.into_unchecked()),
+            worker_name: details.workername,
+            user_agent: details.user_agent,
+            version_mask: details.version_mask,
+            difficulty: details.difficulty,
+            connected_at: connection.connected_at(),
+        }
+    }
+}
+
+/// Why the pool stopped serving a stratum connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectReason {
+    /// The miner closed the connection.
+    ClientEof,
+    /// The miner never authorized or stopped interacting.
+    Idle,
+    /// The miner sent a line longer than the stratum message limit.
+    OversizedMessage,
+    /// The miner sent a line that is not a stratum message.
+    InvalidMessage,
+    /// The bouncer dropped the miner for sustained rejects.
+    Banned,
+    /// No enonce1 was left to hand out.
+    PoolFull,
+    /// The miner was sent to another upstream.
+    Rerouted,
+    /// Templates stopped arriving from upstream.
+    UpstreamLost,
+    /// The pool shut down or released the connection.
+    Shutdown,
+    /// The miner stopped reading and a write to it timed out.
+    SlowConsumer,
+    /// Reading from or writing to the socket failed.
+    Error,
+}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::ClientEof => "client_eof",
+                Self::Idle => "idle",
+                Self::OversizedMessage => "oversized_message",
+                Self::InvalidMessage => "invalid_message",
+                Self::Banned => "banned",
+                Self::PoolFull => "pool_full",
+                Self::Rerouted => "rerouted",
+                Self::UpstreamLost => "upstream_lost",
+                Self::Shutdown => "shutdown",
+                Self::SlowConsumer => "slow_consumer",
+                Self::Error => "error",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerDisconnects {
+    pub address: Address<NetworkUnchecked>,
+    pub worker_name: String,
+    pub reasons: BTreeMap<DisconnectReason, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Disconnects {
+    pub reasons: BTreeMap<DisconnectReason, u64>,
+    pub workers: Vec<WorkerDisconnects>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FoundBlock {
+    pub height: i32,
+    #[schema(value_type = String)]
+    pub hash: BlockHash,
+    pub found_at: u64,
+    pub finder: String,
+    pub reward: Option<u64>,
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+}
+
+impl FoundBlock {
+    pub(crate) fn with_explorer_url(self, explorer_url: Option<&ExplorerUrl>) -> Self {
+        Self {
+            explorer_url: explorer_url.map(|url| url.block(self.height, self.hash)),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistorySample {
+    pub timestamp: u64,
+    pub hashrate: HashRate,
+    pub sps: f64,
+}
+
+impl From<store::entry::HistorySampleEntry> for HistorySample {
+    fn from(entry: store::entry::HistorySampleEntry) -> Self {
+        Self {
+            timestamp: entry.at_secs,
+            hashrate: entry.hashrate,
+            sps: entry.sps,
+        }
+    }
+}
+
+impl From<store::entry::FoundBlockEntry> for FoundBlock {
+    fn from(entry: store::entry::FoundBlockEntry) -> Self {
+        Self {
+            height: entry.height,
+            hash: entry.hash,
+            found_at: entry.found_at_secs,
+            finder: entry.finder,
+            reward: entry.reward_sat,
+            explorer_url: None,
         }
     }
 }
@@ -69,7 +239,125 @@ pub struct PoolStatus {
     pub block_count: u64,
     pub recent_blocks: Vec<BlockHash>,
     pub uptime_secs: u64,
+    pub stale_templates: u64,
+    pub bitcoind_healthy: bool,
+    pub downstream: DownstreamInfo,
+    #[serde(default)]
+    pub network: Option<NetworkInfo>,
+    #[serde(default)]
+    pub rejects_by_reason: BTreeMap<StratumError, u64>,
+    #[serde(default)]
+    pub near_misses: u64,
+    /// Accepted shares by the power of two exponent at or below their pool
+    /// difficulty.
+    #[serde(default)]
+    pub accepted_by_difficulty: BTreeMap<i32, u64>,
+    #[serde(default)]
+    pub interval_best: Option<IntervalBest>,
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceMode>,
+    #[serde(default)]
+    pub zmq_restarts: u64,
+}
+
+/// Best share found while mining on top of one block.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IntervalBest {
+    pub height: i32,
+    pub best_share: Difficulty,
+    pub network_difficulty: Difficulty,
+}
+
+impl IntervalBest {
+    pub fn ratio(&self) -> f64 {
+        self.best_share.as_f64() / self.network_difficulty.as_f64()
+    }
+}
+
+/// Final pool state, written on shutdown for post-mortems.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub taken_at: u64,
+    pub uptime_secs: u64,
     pub downstream: DownstreamInfo,
+    pub connections: Vec<ConnectionDetail>,
+    pub users: Vec<UserDetail>,
+    pub blocks: Vec<FoundBlock>,
+}
+
+impl PoolSnapshot {
+    pub(crate) fn from_metatron(metatron: &Metatron, now: Instant) -> Self {
+        let mut connections = metatron
+            .connections()
+            .iter()
+            .map(|connection| ConnectionDetail::from_connection(connection))
+            .collect::<Vec<ConnectionDetail>>();
+
+        connections.sort_by_key(|connection| connection.socket_addr);
+
+        let mut users = metatron
+            .users()
+            .iter()
+            .map(|user| user.value().clone())
+            .collect::<Vec<Arc<User>>>();
+
+        users.sort_by_key(|user| user.address.to_string());
+
+        let difficulties = metatron.worker_difficulties();
+
+        Self {
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            uptime_secs: metatron.uptime().as_secs(),
+            downstream: DownstreamInfo::from_metatron(metatron, now),
+            connections,
+            users: users
+                .iter()
+                .map(|user| {
+                    UserDetail::from_user(
+                        user,
+                        now,
+                        metatron.worker_offline_grace(),
+                        difficulties.get(&user.address),
+                        metatron.share_cadence(),
+                    )
+                })
+                .collect(),
+            blocks: metatron
+                .recent_found_blocks(metatron.block_count())
+                .into_iter()
+                .map(FoundBlock::from)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn write(&self, path: &std::path::Path) -> Result {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write snapshot to `{}`", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub height: i32,
+    pub network_difficulty: Difficulty,
+    pub expected_block_time_secs: Option<u64>,
+}
+
+impl NetworkInfo {
+    pub(crate) fn new(workbase: &impl Workbase, hashrate: HashRate) -> Self {
+        let network_difficulty = Difficulty::from(workbase.nbits());
+
+        Self {
+            height: workbase.height(),
+            network_difficulty,
+            expected_block_time_secs: hashrate
+                .expected_block_time(network_difficulty)
+                .map(|time| time.as_secs()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,11 +401,30 @@ pub struct UserSummary {
     pub delivered_hash_days: HashDays,
     pub best_share: Option<Difficulty>,
     pub last_share: Option<u64>,
+    /// Current difficulty summed across the user's connections.
+    pub difficulty: Option<Difficulty>,
+    /// The hashrate `difficulty` expects at the pool's share cadence.
+    pub difficulty_hashrate: Option<HashRate>,
 }
 
 impl UserSummary {
-    pub(crate) fn from_user(user: &User, now: Instant) -> Self {
+    pub(crate) fn from_user(
+        user: &User,
+        now: Instant,
+        difficulties: Option<&BTreeMap<String, Difficulty>>,
+        cadence: Duration,
+    ) -> Self {
         let stats = user.snapshot();
+
+        let difficulty = difficulties.map(|workers| {
+            Difficulty::from(
+                workers
+                    .values()
+                    .map(|difficulty| difficulty.as_f64())
+                    .sum::<f64>(),
+            )
+        });
+
         Self {
             address: user.address.as_unchecked().clone(),
             worker_count: user.worker_count(),
@@ -128,6 +435,9 @@ impl UserSummary {
             last_share: stats
                 .last_share
                 .map(|time| epoch::instant_to_epoch_secs(time, now) as u64),
+            difficulty,
+            difficulty_hashrate: difficulty
+                .map(|difficulty| HashRate::from_difficulty(difficulty, cadence)),
         }
     }
 }
@@ -156,7 +466,13 @@ pub struct UserDetail {
 }
 
 impl UserDetail {
-    pub(crate) fn from_user(user: &User, now: Instant) -> Self {
+    pub(crate) fn from_user(
+        user: &User,
+        now: Instant,
+        grace: Duration,
+        difficulties: Option<&BTreeMap<String, Difficulty>>,
+        cadence: Duration,
+    ) -> Self {
         let mut workers = Vec::new();
         let mut sessions = Vec::new();
 
@@ -166,7 +482,13 @@ impl UserDetail {
                     .sessions()
                     .map(|s| SessionDetail::from_session(&s, now)),
             );
-            workers.push(WorkerDetail::from_worker(&worker, now));
+            workers.push(WorkerDetail::from_worker(
+                &worker,
+                now,
+                grace,
+                difficulties.and_then(|workers| workers.get(worker.workername()).copied()),
+                cadence,
+            ));
         }
 
         let user_stats = user.snapshot();
@@ -182,20 +504,47 @@ impl UserDetail {
     }
 }
 
+/// Whether a worker is submitting shares. Idle workers are connected but
+/// silent; offline workers are silent and disconnected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Active,
+    Idle,
+    #[default]
+    Offline,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerDetail {
     pub name: String,
     pub session_count: usize,
+    #[serde(default)]
+    pub state: WorkerState,
     pub stats: MiningStats,
+    /// Current difficulty summed across the worker's connections.
+    pub difficulty: Option<Difficulty>,
+    /// The hashrate `difficulty` expects at the pool's share cadence.
+    pub difficulty_hashrate: Option<HashRate>,
 }
 
 impl WorkerDetail {
-    pub(crate) fn from_worker(worker: &Worker, now: Instant) -> Self {
+    pub(crate) fn from_worker(
+        worker: &Worker,
+        now: Instant,
+        grace: Duration,
+        difficulty: Option<Difficulty>,
+        cadence: Duration,
+    ) -> Self {
         let stats = worker.snapshot();
         Self {
             name: worker.workername().to_string(),
             session_count: worker.session_count(),
+            state: worker.state(&stats, now, grace),
             stats: MiningStats::from_snapshot(&stats, now),
+            difficulty,
+            difficulty_hashrate: difficulty
+                .map(|difficulty| HashRate::from_difficulty(difficulty, cadence)),
         }
     }
 }
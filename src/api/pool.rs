@@ -1,25 +1,58 @@
 use {
     super::*,
-    crate::http_server::auth::{BearerAuth, NavbarAuth},
+    crate::{
+        event_sink::Event,
+        http_server::auth::{AdminAuth, ApiAuth, BearerAuth, NavbarAuth},
+    },
+    axum::extract::ws::{Message, WebSocketUpgrade},
+    tokio::sync::broadcast::error::RecvError,
 };
 
+/// Most shares sent to each share feed subscriber per second. Shares past
+/// the cap are dropped and reported as a count once the next second starts.
+const SHARE_FEED_MAX_RATE: u32 = 100;
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn router(
     metatron: Arc<Metatron>,
     bitcoin_client: Arc<BitcoindClient>,
     chain: Chain,
+    explorer_url: Option<ExplorerUrl>,
     logs: Arc<logs::Logs>,
     http_api_token: Option<&str>,
     http_admin_token: Option<&str>,
+    cancel_token: CancellationToken,
+    workbase_rx: watch::Receiver<Arc<BlockTemplate>>,
 ) -> axum::Router {
-    axum::Router::new()
+    let mut router = axum::Router::new()
         .route("/", get(home))
         .route("/api/pool/status", get(status))
+        .route("/api/blocks", get(blocks))
+        .route("/api/blocks/hashes", get(block_hashes))
+        .route("/api/stats/history", get(history));
+
+    // AdminAuth lets everyone through when no tokens are configured, so only
+    // expose shutdown, miner connection details and the share feed when there
+    // is an admin token to check.
+    if http_admin_token.is_some() {
+        router = router
+            .route("/admin/shutdown", post(shutdown))
+            .route("/admin/maintenance", post(maintenance))
+            .route("/api/connections", get(connections))
+            .route("/api/disconnects", get(disconnects))
+            .route("/api/ws/shares", get(ws_shares));
+    }
+
+    router
         .with_state(metatron.clone())
         .merge(users::routes(users::Service::Pool, metatron))
         .merge(common_routes())
         .layer(Extension(bitcoin_client))
         .layer(Extension(chain))
+        .layer(Extension(explorer_url))
         .layer(Extension(logs))
+        .layer(Extension(cancel_token))
+        .layer(Extension(workbase_rx))
         .layer(Extension(BearerAuth::new(http_api_token, http_admin_token)))
 }
 
@@ -27,11 +60,198 @@ async fn home(Extension(chain): Extension<Chain>, auth: NavbarAuth) -> Response
     render_page(PoolHtml, chain, auth)
 }
 
-async fn status(State(metatron): State<Arc<Metatron>>) -> Json<PoolStatus> {
+async fn status(
+    State(metatron): State<Arc<Metatron>>,
+    Extension(workbase_rx): Extension<watch::Receiver<Arc<BlockTemplate>>>,
+) -> Json<PoolStatus> {
+    let downstream = DownstreamInfo::from_metatron(&metatron, Instant::now());
+    let network = NetworkInfo::new(workbase_rx.borrow().as_ref(), downstream.stats.hashrate_1hr);
+
     Json(PoolStatus {
         block_count: metatron.block_count() as u64,
         recent_blocks: metatron.recent_blocks(10),
         uptime_secs: metatron.uptime().as_secs(),
-        downstream: DownstreamInfo::from_metatron(&metatron, Instant::now()),
+        stale_templates: metatron.stale_templates(),
+        bitcoind_healthy: metatron.bitcoind_healthy(),
+        downstream,
+        network: Some(network),
+        rejects_by_reason: metatron.rejects(),
+        near_misses: metatron.near_misses(),
+        accepted_by_difficulty: metatron.accepted_by_difficulty(),
+        interval_best: metatron.interval_best(),
+        maintenance: metatron.maintenance(),
+        zmq_restarts: metatron.zmq_restarts(),
+    })
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    window: Option<u64>,
+}
+
+/// Hashrate and SPS samples from the last `window` seconds, oldest first.
+async fn history(
+    _: ApiAuth,
+    State(metatron): State<Arc<Metatron>>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<Vec<HistorySample>> {
+    let since = query.window.map_or(0, |window| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .saturating_sub(window)
+    });
+
+    Json(
+        metatron
+            .history(since)
+            .into_iter()
+            .map(HistorySample::from)
+            .collect(),
+    )
+}
+
+async fn blocks(
+    _: ApiAuth,
+    State(metatron): State<Arc<Metatron>>,
+    Extension(explorer_url): Extension<Option<ExplorerUrl>>,
+) -> Json<Vec<FoundBlock>> {
+    Json(
+        metatron
+            .recent_found_blocks(10)
+            .into_iter()
+            .map(|entry| FoundBlock::from(entry).with_explorer_url(explorer_url.as_ref()))
+            .collect(),
+    )
+}
+
+/// Every found block's hash, oldest first, so the aggregator can count
+/// blocks found by several nodes once.
+async fn block_hashes(_: ApiAuth, State(metatron): State<Arc<Metatron>>) -> Json<Vec<BlockHash>> {
+    let mut hashes = metatron.recent_blocks(metatron.block_count());
+    hashes.reverse();
+    Json(hashes)
+}
+
+async fn connections(
+    _: AdminAuth,
+    State(metatron): State<Arc<Metatron>>,
+) -> Json<Vec<ConnectionDetail>> {
+    let mut connections = metatron
+        .connections()
+        .iter()
+        .map(|connection| ConnectionDetail::from_connection(connection))
+        .collect::<Vec<_>>();
+
+    connections.sort_by_key(|connection| (connection.connected_at, connection.socket_addr));
+
+    Json(connections)
+}
+
+async fn disconnects(_: AdminAuth, State(metatron): State<Arc<Metatron>>) -> Json<Disconnects> {
+    Json(metatron.disconnects())
+}
+
+async fn maintenance(
+    _: AdminAuth,
+    State(metatron): State<Arc<Metatron>>,
+    Json(maintenance): Json<Maintenance>,
+) -> Json<Maintenance> {
+    match maintenance.mode {
+        Some(mode) => info!("Maintenance mode set over HTTP: {mode:?}"),
+        None => info!("Maintenance mode cleared over HTTP"),
+    }
+
+    metatron.set_maintenance(maintenance.mode);
+
+    Json(maintenance)
+}
+
+async fn shutdown(
+    _: AdminAuth,
+    State(metatron): State<Arc<Metatron>>,
+    Extension(cancel_token): Extension<CancellationToken>,
+) -> Json<Shutdown> {
+    let draining_connections = metatron.total_sessions();
+
+    info!("Shutdown requested over HTTP, draining {draining_connections} connections");
+
+    cancel_token.cancel();
+
+    Json(Shutdown {
+        draining_connections,
+    })
+}
+
+/// Streams accepted and rejected shares as they happen, one JSON event per
+/// message, capped at `SHARE_FEED_MAX_RATE` a second.
+async fn ws_shares(
+    _: AdminAuth,
+    State(metatron): State<Arc<Metatron>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(|socket| async move {
+        let (mut sender, mut receiver) = socket.split();
+
+        let mut shares = metatron.subscribe_shares();
+
+        let send_task = async {
+            let mut window = Instant::now();
+            let mut sent = 0;
+            let mut skipped = 0;
+
+            loop {
+                let share = match shares.recv().await {
+                    Ok(share) => share,
+                    Err(RecvError::Lagged(missed)) => {
+                        skipped += missed;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if window.elapsed() >= Duration::from_secs(1) {
+                    window = Instant::now();
+                    sent = 0;
+
+                    if skipped > 0 {
+                        let notice = json!({ "type": "skipped", "count": skipped });
+
+                        if sender
+                            .send(Message::Text(notice.to_string().into()))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+
+                        skipped = 0;
+                    }
+                }
+
+                if sent >= SHARE_FEED_MAX_RATE {
+                    skipped += 1;
+                    continue;
+                }
+
+                sent += 1;
+
+                let Ok(json) = serde_json::to_string(&Event::Share(share)) else {
+                    continue;
+                };
+
+                if sender.send(Message::Text(json.into())).await.is_err() {
+                    break;
+                }
+            }
+        };
+
+        let recv_task = async { while let Some(Ok(_)) = receiver.next().await {} };
+
+        tokio::select! {
+            _ = send_task => {}
+            _ = recv_task => {}
+        }
     })
 }
@@ -10,6 +10,7 @@ pub(crate) fn router(
     router: Arc<Router>,
     bitcoin_client: Arc<BitcoindClient>,
     chain: Chain,
+    explorer_url: Option<ExplorerUrl>,
     logs: Arc<logs::Logs>,
     http_api_token: Option<&str>,
     http_admin_token: Option<&str>,
@@ -24,6 +25,7 @@ pub(crate) fn router(
         .merge(common_routes())
         .layer(Extension(bitcoin_client))
         .layer(Extension(chain))
+        .layer(Extension(explorer_url))
         .layer(Extension(logs))
         .layer(Extension(BearerAuth::new(http_api_token, http_admin_token)))
 }
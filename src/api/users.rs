@@ -147,13 +147,21 @@ async fn users(
     let now = Instant::now();
     let query = UsersQuery::parse(raw_query.as_deref())?;
 
+    let difficulties = state.metatron.worker_difficulties();
+    let cadence = state.metatron.share_cadence();
+
     let mut users = Vec::new();
 
     for entry in state.metatron.users().iter() {
         let user = entry.value();
 
         if query.matches(user) {
-            users.push(UserSummary::from_user(user, now));
+            users.push(UserSummary::from_user(
+                user,
+                now,
+                difficulties.get(&user.address),
+                cadence,
+            ));
         }
     }
 
@@ -176,5 +184,12 @@ async fn user(
         .get(&address)
         .ok_or_not_found(|| format!("User {address}"))?;
 
-    Ok(Json(UserDetail::from_user(&user, Instant::now())).into_response())
+    Ok(Json(UserDetail::from_user(
+        &user,
+        Instant::now(),
+        state.metatron.worker_offline_grace(),
+        state.metatron.worker_difficulties().get(&address),
+        state.metatron.share_cadence(),
+    ))
+    .into_response())
 }
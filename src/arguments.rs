@@ -17,10 +17,25 @@ use {
     .valid(AnsiColor::Green.on_default()),
 )]
 pub(crate) struct Arguments {
+    #[arg(long, help = "Write daily rotated log files to <LOG_FILE_DIR>.")]
+    pub(crate) log_file_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = logs::DEFAULT_LOG_FILE_RETENTION,
+        value_parser = parse_log_file_retention,
+        help = "Keep the <LOG_FILE_RETENTION> most recent log files."
+    )]
+    pub(crate) log_file_retention: usize,
     #[command(subcommand)]
     pub(crate) subcommand: Subcommand,
 }
 
+fn parse_log_file_retention(s: &str) -> Result<usize> {
+    let retention = s.parse::<usize>()?;
+    ensure!(retention > 0, "log file retention must be greater than 0");
+    Ok(retention)
+}
+
 impl Arguments {
     pub(crate) async fn run(
         self,
@@ -30,3 +45,38 @@ impl Arguments {
         self.subcommand.run(cancel_token, logs).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Arguments {
+        match Arguments::try_parse_from(args.split_whitespace()) {
+            Ok(arguments) => arguments,
+            Err(err) => panic!("error parsing arguments: {err}"),
+        }
+    }
+
+    #[test]
+    fn default_log_file_options() {
+        let arguments = parse("para server");
+        assert_eq!(arguments.log_file_dir, None);
+        assert_eq!(
+            arguments.log_file_retention,
+            logs::DEFAULT_LOG_FILE_RETENTION
+        );
+    }
+
+    #[test]
+    fn override_log_file_options() {
+        let arguments = parse("para --log-file-dir /var/log/para --log-file-retention 30 server");
+        assert_eq!(arguments.log_file_dir, Some("/var/log/para".into()));
+        assert_eq!(arguments.log_file_retention, 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "log file retention must be greater than 0")]
+    fn zero_log_file_retention_rejected() {
+        parse("para --log-file-retention 0 server");
+    }
+}
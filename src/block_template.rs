@@ -41,9 +41,11 @@ pub(crate) struct GetBlockTemplate {
     pub(crate) height: u64,
     #[serde(deserialize_with = "version_from_i32")]
     pub(crate) version: Version,
+    #[serde(default)]
     pub(crate) transactions: Vec<TemplateTransaction>,
     #[serde(with = "bitcoin::script::ScriptBuf", default)]
     pub(crate) default_witness_commitment: ScriptBuf,
+    #[serde(default)]
     pub(crate) coinbaseaux: BTreeMap<String, String>,
     #[serde(
         rename = "coinbasevalue",
@@ -78,6 +80,8 @@ pub struct TemplateTransaction {
     pub txid: Txid,
     #[serde(rename = "data", deserialize_with = "tx_from_hex")]
     pub transaction: Transaction,
+    #[serde(with = "bitcoin::amount::serde::as_sat", default)]
+    pub fee: Amount,
 }
 
 fn version_from_i32<'de, D>(d: D) -> Result<Version, D::Error>
@@ -103,3 +107,27 @@ where
     let v = u64::deserialize(d)?;
     Ntime::try_from(v).map_err(de::Error::custom)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_without_transactions() {
+        let template = BlockTemplate::from(
+            serde_json::from_value::<GetBlockTemplate>(serde_json::json!({
+                "bits": "207fffff",
+                "previousblockhash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+                "curtime": 1_700_000_000,
+                "height": 1,
+                "version": 0x2000_0000,
+                "coinbasevalue": 5_000_000_000u64,
+            }))
+            .unwrap(),
+        );
+
+        assert!(template.transactions.is_empty());
+        assert!(template.coinbaseaux.is_empty());
+        assert!(template.merkle_branches.is_empty());
+    }
+}
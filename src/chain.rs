@@ -28,6 +28,22 @@ impl Chain {
         }
     }
 
+    /// Block subsidy at `height` according to the chain's halving schedule.
+    pub(crate) fn subsidy(self, height: u64) -> Amount {
+        let halving_interval = match self {
+            Self::Regtest => 150,
+            _ => 210_000,
+        };
+
+        let halvings = height / halving_interval;
+
+        if halvings >= 64 {
+            return Amount::ZERO;
+        }
+
+        Amount::from_sat((50 * COIN_VALUE) >> halvings)
+    }
+
     pub(crate) fn join_with_data_dir(self, data_dir: impl AsRef<Path>) -> PathBuf {
         match self {
             Self::Mainnet => data_dir.as_ref().to_owned(),
@@ -105,4 +121,72 @@ mod tests {
             "Invalid chain `foo`"
         );
     }
+
+    #[test]
+    fn subsidy() {
+        #[track_caller]
+        fn case(chain: Chain, height: u64, expected: u64) {
+            assert_eq!(chain.subsidy(height), Amount::from_sat(expected));
+        }
+
+        case(Chain::Mainnet, 0, 50 * COIN_VALUE);
+        case(Chain::Mainnet, 209_999, 50 * COIN_VALUE);
+        case(Chain::Mainnet, 210_000, 25 * COIN_VALUE);
+        case(Chain::Mainnet, 840_000, 3 * COIN_VALUE + COIN_VALUE / 8);
+        case(Chain::Signet, 210_000, 25 * COIN_VALUE);
+        case(Chain::Testnet4, 420_000, 25 * COIN_VALUE / 2);
+        case(Chain::Regtest, 149, 50 * COIN_VALUE);
+        case(Chain::Regtest, 150, 25 * COIN_VALUE);
+        case(Chain::Mainnet, 64 * 210_000, 0);
+        case(Chain::Regtest, u64::MAX, 0);
+    }
+
+    #[test]
+    fn subsidy_halves_at_every_boundary() {
+        for (chain, interval) in [
+            (Chain::Mainnet, 210_000),
+            (Chain::Signet, 210_000),
+            (Chain::Testnet, 210_000),
+            (Chain::Testnet4, 210_000),
+            (Chain::Regtest, 150),
+        ] {
+            for halvings in 1..64 {
+                let boundary = halvings * interval;
+
+                assert_eq!(
+                    chain.subsidy(boundary - 1),
+                    Amount::from_sat((50 * COIN_VALUE) >> (halvings - 1)),
+                    "{chain} just before halving {halvings}",
+                );
+                assert_eq!(
+                    chain.subsidy(boundary),
+                    Amount::from_sat((50 * COIN_VALUE) >> halvings),
+                    "{chain} at halving {halvings}",
+                );
+            }
+
+            assert_eq!(chain.subsidy(64 * interval - 1), Amount::ZERO);
+            assert_eq!(chain.subsidy(64 * interval), Amount::ZERO);
+        }
+    }
+
+    #[test]
+    fn subsidy_on_early_regtest_heights() {
+        #[track_caller]
+        fn case(height: u64, expected: u64) {
+            assert_eq!(Chain::Regtest.subsidy(height), Amount::from_sat(expected));
+        }
+
+        case(0, 5_000_000_000);
+        case(1, 5_000_000_000);
+        case(101, 5_000_000_000);
+        case(149, 5_000_000_000);
+        case(150, 2_500_000_000);
+        case(299, 2_500_000_000);
+        case(300, 1_250_000_000);
+        case(450, 625_000_000);
+        case(600, 312_500_000);
+        case(33 * 150 - 1, 1);
+        case(33 * 150, 0);
+    }
 }
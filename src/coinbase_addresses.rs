@@ -0,0 +1,192 @@
+use super::*;
+
+/// The addresses block rewards pay instead of the miner's, rotating by block
+/// height. They are either fixed on the command line or read from a file with
+/// one address per line, which can be re-read to change the payout address
+/// without restarting. An empty list pays the miner.
+#[derive(Debug)]
+pub(crate) struct CoinbaseAddresses {
+    path: Option<PathBuf>,
+    network: Network,
+    addresses: RwLock<Vec<Address>>,
+}
+
+impl CoinbaseAddresses {
+    pub(crate) fn fixed(addresses: Vec<Address>, network: Network) -> Self {
+        Self {
+            path: None,
+            network,
+            addresses: RwLock::new(addresses),
+        }
+    }
+
+    pub(crate) fn load(path: PathBuf, network: Network) -> Result<Self> {
+        let addresses = Self::read(&path, network)?;
+
+        Ok(Self {
+            path: Some(path),
+            network,
+            addresses: RwLock::new(addresses),
+        })
+    }
+
+    pub(crate) fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The address the coinbase of a block at `height` pays. Rotating by
+    /// height rather than per job keeps every job for a block paying the same
+    /// address.
+    pub(crate) fn get(&self, height: i32) -> Option<Address> {
+        let addresses = self.addresses.read();
+
+        if addresses.is_empty() {
+            return None;
+        }
+
+        addresses
+            .get(height.unsigned_abs() as usize % addresses.len())
+            .cloned()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.addresses.read().len()
+    }
+
+    /// Re-reads the file, keeping the current addresses if it cannot be read.
+    /// Returns the previous addresses if they changed. Jobs already sent keep
+    /// paying the old address, every job created afterwards pays the new one.
+    pub(crate) fn reload(&self) -> Result<Option<Vec<Address>>> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+
+        let addresses = Self::read(path, self.network)?;
+
+        let mut current = self.addresses.write();
+
+        if *current == addresses {
+            return Ok(None);
+        }
+
+        Ok(Some(std::mem::replace(&mut *current, addresses)))
+    }
+
+    fn read(path: &Path, network: Network) -> Result<Vec<Address>> {
+        address_file::read(path, network, "coinbase address file")
+    }
+
+    /// Reloads the file whenever the process receives SIGHUP.
+    pub(crate) fn spawn_reloader(
+        self: Arc<Self>,
+        cancel: CancellationToken,
+        tasks: &TaskTracker,
+    ) -> Result {
+        address_file::spawn_reloader(cancel, tasks, move || match self.reload() {
+            Ok(Some(previous)) => info!(
+                "Coinbase addresses changed from [{}] to [{}]",
+                previous
+                    .iter()
+                    .map(Address::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                self.addresses
+                    .read()
+                    .iter()
+                    .map(Address::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            Ok(None) => info!("Coinbase addresses unchanged"),
+            Err(err) => warn!("Failed to reload coinbase addresses, keeping current ones: {err:#}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc";
+    const SECOND: &str = "tb1q6en7qjxgw4ev8xwx94pzdry6a6ky7wlfeqzunz";
+
+    fn address(s: &str) -> Address {
+        s.parse::<Address<NetworkUnchecked>>()
+            .unwrap()
+            .require_network(Network::Signet)
+            .unwrap()
+    }
+
+    fn coinbase_addresses(contents: &str) -> (tempfile::TempDir, CoinbaseAddresses) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coinbase.txt");
+        fs::write(&path, contents).unwrap();
+        let addresses = CoinbaseAddresses::load(path, Network::Signet).unwrap();
+        (dir, addresses)
+    }
+
+    #[test]
+    fn rotates_by_height_in_file_order() {
+        let (_dir, addresses) =
+            coinbase_addresses(&format!("# hot\n{FIRST}\n\n{SECOND}  # cold\n"));
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses.get(100), Some(address(FIRST)));
+        assert_eq!(addresses.get(101), Some(address(SECOND)));
+        assert_eq!(addresses.get(102), Some(address(FIRST)));
+    }
+
+    #[test]
+    fn empty_file_pays_the_miner() {
+        let (_dir, addresses) = coinbase_addresses("# nothing yet\n");
+        assert_eq!(addresses.get(100), None);
+    }
+
+    #[test]
+    fn wrong_network_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coinbase.txt");
+        fs::write(&path, format!("{FIRST}\n")).unwrap();
+
+        let err = CoinbaseAddresses::load(path, Network::Bitcoin).unwrap_err();
+        assert!(
+            err.to_string().starts_with(&format!(
+                "invalid address `{FIRST}` on line 1 of coinbase address file"
+            )),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reload_swaps_addresses() {
+        let (dir, addresses) = coinbase_addresses(&format!("{FIRST}\n"));
+
+        assert_eq!(addresses.reload().unwrap(), None);
+
+        fs::write(dir.path().join("coinbase.txt"), format!("{SECOND}\n")).unwrap();
+
+        assert_eq!(addresses.reload().unwrap(), Some(vec![address(FIRST)]));
+        assert_eq!(addresses.get(100), Some(address(SECOND)));
+    }
+
+    #[test]
+    fn failed_reload_keeps_current_addresses() {
+        let (dir, addresses) = coinbase_addresses(&format!("{FIRST}\n"));
+
+        fs::write(
+            dir.path().join("coinbase.txt"),
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq\n",
+        )
+        .unwrap();
+
+        assert!(addresses.reload().is_err());
+        assert_eq!(addresses.get(100), Some(address(FIRST)));
+    }
+
+    #[test]
+    fn fixed_addresses_never_reload() {
+        let addresses = CoinbaseAddresses::fixed(vec![address(FIRST)], Network::Signet);
+        assert_eq!(addresses.path(), None);
+        assert_eq!(addresses.reload().unwrap(), None);
+        assert_eq!(addresses.get(7), Some(address(FIRST)));
+    }
+}
@@ -1,5 +1,38 @@
 use super::*;
 
+/// Bytes each part of the coinbase scriptSig takes up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptSigBudget {
+    /// BIP34 block height push.
+    pub height: usize,
+    /// Template `coinbaseaux` data.
+    pub aux: usize,
+    /// Space reserved for extranonce1 and extranonce2.
+    pub extranonce: usize,
+    /// Pool signature, timestamp and trailing marker.
+    pub tag: usize,
+}
+
+impl ScriptSigBudget {
+    pub fn total(&self) -> usize {
+        self.height + self.aux + self.extranonce + self.tag
+    }
+}
+
+impl Display for ScriptSigBudget {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "height {} + aux {} + extranonce {} + tag {} = {} bytes",
+            self.height,
+            self.aux,
+            self.extranonce,
+            self.tag,
+            self.total()
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct CoinbaseBuilder {
     address: Address,
@@ -15,6 +48,7 @@ pub struct CoinbaseBuilder {
 
 impl CoinbaseBuilder {
     const MAX_COINBASE_SCRIPT_SIG_SIZE: usize = 100;
+    const MARKER: [u8; 6] = *b"|para|";
 
     pub fn new(
         address: Address,
@@ -52,7 +86,45 @@ impl CoinbaseBuilder {
         self
     }
 
+    /// Computes how many scriptSig bytes the coinbase needs without building
+    /// it.
+    pub fn script_sig_budget(&self) -> Result<ScriptSigBudget> {
+        let height = if self.height > 0 && self.height <= 16 {
+            2
+        } else {
+            let mut minimally_encoded_serialized_cscript = [0u8; 8];
+            1 + write_scriptint(
+                &mut minimally_encoded_serialized_cscript,
+                self.height.try_into().expect("height should always fit"),
+            )
+        };
+
+        let mut aux = 0;
+        for value in self.aux.values() {
+            aux += hex::decode(value)?.len();
+        }
+
+        let tag = self.pool_sig.as_ref().map_or(0, String::len)
+            + self.timestamp.map_or(0, |ts| ts.to_le_bytes().len())
+            + Self::MARKER.len();
+
+        Ok(ScriptSigBudget {
+            height,
+            aux,
+            extranonce: self.enonce1.len() + self.enonce2_size,
+            tag,
+        })
+    }
+
     pub fn build(self) -> Result<(Transaction, String, String)> {
+        let budget = self.script_sig_budget()?;
+
+        ensure!(
+            budget.total() <= Self::MAX_COINBASE_SCRIPT_SIG_SIZE,
+            "Script sig too large: {budget} (max {})",
+            Self::MAX_COINBASE_SCRIPT_SIG_SIZE
+        );
+
         let mut buf: Vec<u8> = Vec::with_capacity(Self::MAX_COINBASE_SCRIPT_SIG_SIZE);
 
         // BIP34 encode block height
@@ -88,7 +160,7 @@ impl CoinbaseBuilder {
             buf.extend_from_slice(&ts.to_le_bytes());
         }
 
-        buf.extend_from_slice(&[0x7c, 0x70, 0x61, 0x72, 0x61, 0x7c]);
+        buf.extend_from_slice(&Self::MARKER);
 
         let script_sig = ScriptBuf::from_bytes(buf);
         let script_sig_size = script_sig.len();
@@ -99,6 +171,11 @@ impl CoinbaseBuilder {
             Self::MAX_COINBASE_SCRIPT_SIG_SIZE
         );
 
+        ensure!(
+            script_sig_size == budget.total(),
+            "Script sig is {script_sig_size} bytes but its budget is {budget}"
+        );
+
         let coinbase = Transaction {
             version: bitcoin::transaction::Version::TWO,
             lock_time: LockTime::ZERO,
@@ -176,6 +253,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn script_sig_budget_for_pool_layout() {
+        let builder = CoinbaseBuilder::new(
+            address(),
+            "abcd1234".parse().unwrap(),
+            8,
+            900_000,
+            Amount::from_sat(50 * COIN_VALUE),
+            ScriptBuf::new(),
+        )
+        .with_timestamp(1_700_000_000)
+        .with_pool_sig("|parasite|".into());
+
+        let budget = builder.script_sig_budget().unwrap();
+
+        assert_eq!(
+            budget,
+            ScriptSigBudget {
+                height: 4,
+                aux: 0,
+                extranonce: 12,
+                tag: 24,
+            }
+        );
+        assert_eq!(budget.total(), 40);
+
+        let (tx, _, _) = builder.build().unwrap();
+        assert_eq!(tx.input[0].script_sig.len(), budget.total());
+    }
+
+    #[test]
+    fn long_tag_overflows_script_sig_budget() {
+        let builder = CoinbaseBuilder::new(
+            address(),
+            "abcd1234".parse().unwrap(),
+            8,
+            900_000,
+            Amount::from_sat(50 * COIN_VALUE),
+            ScriptBuf::new(),
+        )
+        .with_timestamp(1_700_000_000)
+        .with_pool_sig("|parasite|".repeat(8));
+
+        let budget = builder.script_sig_budget().unwrap();
+        assert_eq!(budget.tag, 94);
+        assert_eq!(budget.total(), 110);
+
+        assert_eq!(
+            builder.build().unwrap_err().to_string(),
+            "Script sig too large: height 4 + aux 0 + extranonce 12 + tag 94 = 110 bytes (max 100)"
+        );
+    }
+
     #[test]
     fn split_reassembles_with_zero_enonce2() {
         let (tx, coinb1, coinb2) = CoinbaseBuilder::new(
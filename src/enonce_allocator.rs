@@ -1,7 +1,5 @@
 use super::*;
 
-const MAX_TRACKED_CLIENTS: usize = 1 << 16;
-
 #[derive(Default)]
 struct Allocated {
     pool: HashSet<Extranonce>,
@@ -83,17 +81,15 @@ impl EnonceAllocator {
         }
     }
 
+    /// Hands out the next enonce1 not held by a live or resumable session.
+    /// The counter can land on a held value after it wraps, or after a
+    /// restart when resumed sessions keep their old enonce1, so values in use
+    /// are skipped. Each held value is skipped at most once, so this stops
+    /// after at most one more attempt than there are held values.
     pub(crate) fn next_enonce1(&self) -> Option<Extranonce> {
         let extranonces = self.extranonces.read();
         let max = extranonces.max_clients();
 
-        if max > MAX_TRACKED_CLIENTS {
-            return Some(self.build_enonce1(
-                &extranonces,
-                self.enonce_counter.fetch_add(1, Ordering::Relaxed),
-            ));
-        }
-
         let mut allocated = self.allocated.lock();
 
         if allocated.count_for(&extranonces) >= max {
@@ -116,10 +112,17 @@ impl EnonceAllocator {
 
     fn build_enonce1(&self, extranonces: &Extranonces, counter: u64) -> Extranonce {
         match extranonces {
-            Extranonces::Pool(pool) => {
-                let bytes = counter.to_le_bytes();
-                Extranonce::from_bytes(&bytes[..pool.enonce1_size()])
-            }
+            Extranonces::Pool(pool) => match pool.instance_id() {
+                Some(instance_id) => {
+                    let mut bytes = [0u8; MAX_ENONCE_SIZE];
+
+                    bytes[0] = instance_id;
+                    bytes[1..].copy_from_slice(&counter.to_le_bytes()[..MAX_ENONCE_SIZE - 1]);
+
+                    Extranonce::from_bytes(&bytes[..pool.enonce1_size()])
+                }
+                None => Extranonce::from_bytes(&counter.to_le_bytes()[..pool.enonce1_size()]),
+            },
             Extranonces::Proxy(proxy) => {
                 let upstream = proxy.upstream_enonce1().as_bytes();
                 let extension_size = proxy.extension_size();
@@ -140,9 +143,7 @@ impl EnonceAllocator {
 
     pub(crate) fn track_enonce1(&self, enonce1: &Extranonce) {
         let extranonces = self.extranonces.read();
-        if extranonces.max_clients() <= MAX_TRACKED_CLIENTS {
-            self.allocated.lock().insert(&extranonces, enonce1.clone());
-        }
+        self.allocated.lock().insert(&extranonces, enonce1.clone());
     }
 
     pub(crate) fn enonce2_size(&self) -> usize {
@@ -166,7 +167,12 @@ impl EnonceAllocator {
     pub(crate) fn is_compatible_enonce1(&self, enonce1: &Extranonce) -> bool {
         let extranonces = self.extranonces.read();
         match &*extranonces {
-            Extranonces::Pool(pool) => enonce1.len() == pool.enonce1_size(),
+            Extranonces::Pool(pool) => {
+                enonce1.len() == pool.enonce1_size()
+                    && pool
+                        .instance_id()
+                        .is_none_or(|instance_id| enonce1.as_bytes()[0] == instance_id)
+            }
             Extranonces::Proxy(proxy) => {
                 let upstream = proxy.upstream_enonce1().as_bytes();
                 enonce1.len() == upstream.len() + proxy.extension_size()
@@ -226,6 +232,53 @@ mod tests {
         assert_eq!(v2, v1 + 1);
     }
 
+    fn instance_allocator(instance_id: u8) -> EnonceAllocator {
+        EnonceAllocator::new(
+            Extranonces::Pool(
+                PoolExtranonces::new(ENONCE1_SIZE, 8)
+                    .unwrap()
+                    .with_instance_id(instance_id)
+                    .unwrap(),
+            ),
+            0,
+        )
+    }
+
+    #[test]
+    fn instances_allocate_disjoint_enonce1s() {
+        let a = instance_allocator(1);
+        let b = instance_allocator(2);
+
+        // Line both counters up so that without the prefix they would collide.
+        b.enonce_counter
+            .store(a.enonce_counter.load(Ordering::Relaxed), Ordering::Relaxed);
+
+        let mut seen = HashSet::new();
+
+        for _ in 0..1000 {
+            let enonce1 = a.next_enonce1().unwrap();
+            assert_eq!(enonce1.len(), ENONCE1_SIZE);
+            assert_eq!(enonce1.as_bytes()[0], 1);
+            assert!(seen.insert(enonce1));
+
+            let enonce1 = b.next_enonce1().unwrap();
+            assert_eq!(enonce1.as_bytes()[0], 2);
+            assert!(seen.insert(enonce1), "instances allocated the same enonce1");
+        }
+    }
+
+    #[test]
+    fn instance_rejects_other_instance_enonce1() {
+        let a = instance_allocator(1);
+        let b = instance_allocator(2);
+
+        let enonce1 = a.next_enonce1().unwrap();
+
+        assert!(a.is_compatible_enonce1(&enonce1));
+        assert!(!b.is_compatible_enonce1(&enonce1));
+        assert!(!a.is_compatible_enonce1(&Extranonce::from_bytes(&[1, 0])));
+    }
+
     #[test]
     fn next_enonce1_is_unique() {
         let allocator = pool_allocator();
@@ -236,6 +289,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pool_enonce1_skips_resumed_enonce1() {
+        let allocator = pool_allocator();
+
+        let next = allocator.build_enonce1(
+            &allocator.extranonces(),
+            allocator.enonce_counter.load(Ordering::Relaxed),
+        );
+        allocator.track_enonce1(&next);
+
+        let enonce1 = allocator.next_enonce1().unwrap();
+        assert_ne!(enonce1, next);
+        assert_eq!(allocator.allocated_count(), 2);
+    }
+
+    #[test]
+    fn pool_enonce1_skips_live_enonce1_after_counter_wraps() {
+        let allocator = pool_allocator();
+
+        let start = allocator.enonce_counter.load(Ordering::Relaxed);
+        let live = allocator.next_enonce1().unwrap();
+
+        allocator
+            .enonce_counter
+            .store(start + (1 << 32), Ordering::Relaxed);
+
+        let enonce1 = allocator.next_enonce1().unwrap();
+        assert_ne!(enonce1, live);
+        assert_eq!(allocator.allocated_count(), 2);
+
+        allocator.release_enonce1(&live);
+        assert_eq!(allocator.allocated_count(), 1);
+
+        allocator
+            .enonce_counter
+            .store(start + (2 << 32), Ordering::Relaxed);
+
+        assert_eq!(allocator.next_enonce1().unwrap(), live);
+    }
+
     #[test]
     fn proxy_enonce1() {
         let allocator = proxy_allocator_with_id(2, 7);
@@ -401,14 +494,15 @@ mod tests {
     }
 
     #[test]
-    fn untracked_always_returns_some() {
+    fn large_space_tracks_every_enonce1() {
         let allocator = pool_allocator();
 
-        for _ in 0..1000 {
-            assert!(allocator.next_enonce1().is_some());
-        }
+        let enonce1s = (0..1000)
+            .map(|_| allocator.next_enonce1().unwrap())
+            .collect::<HashSet<_>>();
 
-        assert_eq!(allocator.allocated_count(), 0);
+        assert_eq!(enonce1s.len(), 1000);
+        assert_eq!(allocator.allocated_count(), 1000);
     }
 
     #[test]
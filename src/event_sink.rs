@@ -7,11 +7,16 @@ mod multi;
 
 pub use {
     database::DatabaseSink,
-    event::{BlockFoundEvent, Event, ShareEvent},
+    event::{
+        AcceptedShare, BlockFoundEvent, Event, NearMissEvent, RejectedShare, ShareEvent,
+        ShareOutcome,
+    },
     file::FileSink,
     multi::MultiSink,
 };
 
+pub(crate) use event::ShareOutcomeColumns;
+
 const EVENT_CHANNEL_CAPACITY: usize = 10_000;
 
 pub(crate) async fn build_event_sink(
@@ -106,27 +111,16 @@ pub trait EventSink: Send + Sync {
 #[macro_export]
 macro_rules! rejection_event {
     ($address:expr, $workername:expr, $blockheight:expr, $error:expr) => {
-        $crate::event_sink::Event::Share($crate::event_sink::ShareEvent {
-            timestamp: None,
-            address: $address,
-            workername: $workername,
-            pool_diff: 0.0,
-            share_diff: 0.0,
-            result: false,
-            blockheight: Some($blockheight),
-            reject_reason: Some($error.to_string()),
-        })
+        $crate::rejection_event!($address, $workername, 0.0, 0.0, $blockheight, $error)
     };
     ($address:expr, $workername:expr, $pool_diff:expr, $share_diff:expr, $blockheight:expr, $error:expr) => {
-        $crate::event_sink::Event::Share($crate::event_sink::ShareEvent {
-            timestamp: None,
+        $crate::event_sink::Event::from($crate::event_sink::RejectedShare {
             address: $address,
             workername: $workername,
             pool_diff: $pool_diff,
             share_diff: $share_diff,
-            result: false,
-            blockheight: Some($blockheight),
-            reject_reason: Some($error.to_string()),
+            blockheight: $blockheight,
+            reason: $error.to_string(),
         })
     };
 }
@@ -140,15 +134,12 @@ mod tests {
 
     #[tokio::test]
     async fn multi_sink_broadcasts_to_all() {
-        let event = Event::Share(ShareEvent {
-            timestamp: None,
+        let event = Event::from(AcceptedShare {
             address: "bc1test".into(),
             workername: "rig1".into(),
             pool_diff: 1.0,
             share_diff: 1.5,
-            result: true,
-            blockheight: Some(800000),
-            reject_reason: None,
+            blockheight: 800000,
         });
 
         struct CountingSink {
@@ -31,8 +31,8 @@ impl super::EventSink for DatabaseSink {
                     .bind(share.blockheight)
                     .bind(share.pool_diff)
                     .bind(share.share_diff)
-                    .bind(share.result)
-                    .bind(&share.reject_reason)
+                    .bind(share.outcome.is_accepted())
+                    .bind(share.outcome.reject_reason())
                     .bind(&share.workername)
                     .bind(&share.address)
                     .execute(&self.pool)
@@ -41,9 +41,9 @@ impl super::EventSink for DatabaseSink {
             Event::BlockFound(block) => {
                 sqlx::query(
                     "INSERT INTO blocks (
-                        blockheight, blockhash, workername, username, diff, coinbasevalue, time_found
-                    ) VALUES ($1, $2, $3, $4, $5, $6,
-                        COALESCE(to_timestamp($7), CURRENT_TIMESTAMP))",
+                        blockheight, blockhash, workername, username, diff, coinbasevalue, coinbase_address, time_found
+                    ) VALUES ($1, $2, $3, $4, $5, $6, $7,
+                        COALESCE(to_timestamp($8), CURRENT_TIMESTAMP))",
                 )
                     .bind(block.blockheight)
                     .bind(&block.blockhash)
@@ -51,10 +51,28 @@ impl super::EventSink for DatabaseSink {
                     .bind(&block.address)
                     .bind(block.diff)
                     .bind(block.coinbase_value)
+                    .bind(&block.coinbase_address)
                     .bind(block.timestamp)
                     .execute(&self.pool)
                     .await?
             }
+            Event::NearMiss(near_miss) => {
+                sqlx::query(
+                    "INSERT INTO near_misses (
+                        blockheight, hash, workername, username, sdiff, network_diff, createdate
+                    ) VALUES ($1, $2, $3, $4, $5, $6,
+                        COALESCE(to_timestamp($7), CURRENT_TIMESTAMP))",
+                )
+                    .bind(near_miss.blockheight)
+                    .bind(&near_miss.hash)
+                    .bind(&near_miss.workername)
+                    .bind(&near_miss.address)
+                    .bind(near_miss.share_diff)
+                    .bind(near_miss.network_diff)
+                    .bind(near_miss.timestamp)
+                    .execute(&self.pool)
+                    .await?
+            }
         }
             .rows_affected();
         Ok(rows_changed)
@@ -1,12 +1,18 @@
-use serde::{Deserialize, Serialize};
+use {
+    serde::{Deserialize, Serialize},
+    utoipa::ToSchema,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Event {
     Share(ShareEvent),
     BlockFound(BlockFoundEvent),
+    NearMiss(NearMissEvent),
 }
 
+/// A share as recorded by the sinks: one `shares` row, one JSON line, one
+/// live feed entry. Build it from an [`AcceptedShare`] or a [`RejectedShare`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShareEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -15,9 +21,135 @@ pub struct ShareEvent {
     pub workername: String,
     pub pool_diff: f64,
     pub share_diff: f64,
-    pub result: bool,
+    #[serde(flatten)]
+    pub outcome: ShareOutcome,
     pub blockheight: Option<i32>,
-    pub reject_reason: Option<String>,
+}
+
+/// Whether a share was accepted, and if not, why. Stored and sent as the
+/// `result` and `reject_reason` columns, which can't disagree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ShareOutcomeColumns", into = "ShareOutcomeColumns")]
+pub enum ShareOutcome {
+    Accepted,
+    Rejected(String),
+}
+
+impl ShareOutcome {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Self::Accepted)
+    }
+
+    pub fn reject_reason(&self) -> Option<&str> {
+        match self {
+            Self::Accepted => None,
+            Self::Rejected(reason) => Some(reason),
+        }
+    }
+
+    /// Reads the columns as written by any version of the pool or ckpool. A
+    /// share counts as accepted only with a true `result` and no reason.
+    pub fn from_columns(result: Option<bool>, reject_reason: Option<String>) -> Self {
+        match (result, reject_reason) {
+            (Some(true), None) => Self::Accepted,
+            (_, Some(reason)) => Self::Rejected(reason),
+            (_, None) => Self::Rejected("unknown".into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct ShareOutcomeColumns {
+    result: Option<bool>,
+    reject_reason: Option<String>,
+}
+
+impl From<ShareOutcomeColumns> for ShareOutcome {
+    fn from(columns: ShareOutcomeColumns) -> Self {
+        Self::from_columns(columns.result, columns.reject_reason)
+    }
+}
+
+impl From<ShareOutcome> for ShareOutcomeColumns {
+    fn from(outcome: ShareOutcome) -> Self {
+        Self {
+            result: Some(outcome.is_accepted()),
+            reject_reason: outcome.reject_reason().map(str::to_string),
+        }
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for ShareOutcome {
+    fn from_row(row: &'r sqlx::postgres::PgRow) -> sqlx::Result<Self> {
+        use sqlx::Row;
+
+        Ok(Self::from_columns(
+            row.try_get("result")?,
+            row.try_get("reject_reason")?,
+        ))
+    }
+}
+
+/// A share the pool accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptedShare {
+    pub address: String,
+    pub workername: String,
+    pub pool_diff: f64,
+    pub share_diff: f64,
+    pub blockheight: i32,
+}
+
+/// A share the pool turned down, always with the reason. Shares rejected
+/// before their hash is checked have zero difficulty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedShare {
+    pub address: String,
+    pub workername: String,
+    pub pool_diff: f64,
+    pub share_diff: f64,
+    pub blockheight: i32,
+    pub reason: String,
+}
+
+impl From<AcceptedShare> for ShareEvent {
+    fn from(share: AcceptedShare) -> Self {
+        Self {
+            timestamp: None,
+            address: share.address,
+            workername: share.workername,
+            pool_diff: share.pool_diff,
+            share_diff: share.share_diff,
+            outcome: ShareOutcome::Accepted,
+            blockheight: Some(share.blockheight),
+        }
+    }
+}
+
+impl From<RejectedShare> for ShareEvent {
+    fn from(share: RejectedShare) -> Self {
+        Self {
+            timestamp: None,
+            address: share.address,
+            workername: share.workername,
+            pool_diff: share.pool_diff,
+            share_diff: share.share_diff,
+            outcome: ShareOutcome::Rejected(share.reason),
+            blockheight: Some(share.blockheight),
+        }
+    }
+}
+
+impl From<AcceptedShare> for Event {
+    fn from(share: AcceptedShare) -> Self {
+        Self::Share(share.into())
+    }
+}
+
+impl From<RejectedShare> for Event {
+    fn from(share: RejectedShare) -> Self {
+        Self::Share(share.into())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +162,20 @@ pub struct BlockFoundEvent {
     pub workername: String,
     pub diff: f64,
     pub coinbase_value: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coinbase_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearMissEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    pub blockheight: i32,
+    pub hash: String,
+    pub address: String,
+    pub workername: String,
+    pub share_diff: f64,
+    pub network_diff: f64,
 }
 
 #[cfg(test)]
@@ -37,16 +183,14 @@ mod tests {
     use super::*;
 
     fn test_share() -> Event {
-        Event::Share(ShareEvent {
-            timestamp: None,
+        AcceptedShare {
             address: "bc1test".into(),
             workername: "rig1".into(),
             pool_diff: 1.0,
             share_diff: 1.5,
-            result: true,
-            blockheight: Some(800000),
-            reject_reason: None,
-        })
+            blockheight: 800000,
+        }
+        .into()
     }
 
     #[test]
@@ -55,4 +199,86 @@ mod tests {
         let json = serde_json::to_string(&share).unwrap();
         assert!(json.contains("\"type\":\"share\""));
     }
+
+    #[test]
+    fn accepted_share_row() {
+        assert_eq!(
+            serde_json::to_value(test_share()).unwrap(),
+            serde_json::json!({
+                "type": "share",
+                "address": "bc1test",
+                "workername": "rig1",
+                "pool_diff": 1.0,
+                "share_diff": 1.5,
+                "result": true,
+                "blockheight": 800000,
+                "reject_reason": null,
+            })
+        );
+    }
+
+    #[test]
+    fn rejected_share_row() {
+        let share = Event::from(RejectedShare {
+            address: "bc1test".into(),
+            workername: "rig1".into(),
+            pool_diff: 1.0,
+            share_diff: 0.5,
+            blockheight: 800000,
+            reason: "above target".into(),
+        });
+
+        assert_eq!(
+            serde_json::to_value(share).unwrap(),
+            serde_json::json!({
+                "type": "share",
+                "address": "bc1test",
+                "workername": "rig1",
+                "pool_diff": 1.0,
+                "share_diff": 0.5,
+                "result": false,
+                "blockheight": 800000,
+                "reject_reason": "above target",
+            })
+        );
+    }
+
+    #[test]
+    fn share_outcome_from_columns() {
+        assert_eq!(
+            ShareOutcome::from_columns(Some(true), None),
+            ShareOutcome::Accepted
+        );
+        assert_eq!(
+            ShareOutcome::from_columns(Some(false), Some("Stale".into())),
+            ShareOutcome::Rejected("Stale".into())
+        );
+        assert_eq!(
+            ShareOutcome::from_columns(Some(true), Some("Stale".into())),
+            ShareOutcome::Rejected("Stale".into())
+        );
+        assert_eq!(
+            ShareOutcome::from_columns(None, None),
+            ShareOutcome::Rejected("unknown".into())
+        );
+    }
+
+    #[test]
+    fn share_event_round_trips() {
+        let Event::Share(share) = serde_json::from_value::<Event>(serde_json::json!({
+            "type": "share",
+            "address": "bc1test",
+            "workername": "rig1",
+            "pool_diff": 1.0,
+            "share_diff": 0.5,
+            "result": false,
+            "blockheight": 800000,
+            "reject_reason": "above target",
+        }))
+        .unwrap() else {
+            panic!("expected a share event");
+        };
+
+        assert_eq!(share.outcome, ShareOutcome::Rejected("above target".into()));
+    }
 }
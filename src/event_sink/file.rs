@@ -53,6 +53,7 @@ impl FileSink {
                 match &mut event_with_timestamp {
                     Event::Share(s) if s.timestamp.is_none() => s.timestamp = Some(now()),
                     Event::BlockFound(b) if b.timestamp.is_none() => b.timestamp = Some(now()),
+                    Event::NearMiss(n) if n.timestamp.is_none() => n.timestamp = Some(now()),
                     _ => {}
                 }
                 let json = serde_json::to_string(&event_with_timestamp)?;
@@ -88,10 +89,10 @@ impl FileSink {
                     quote_if_needed(&s.workername),
                     s.pool_diff,
                     s.share_diff,
-                    s.result,
-                    s.reject_reason
-                        .as_ref()
-                        .map(|r| quote_if_needed(r))
+                    s.outcome.is_accepted(),
+                    s.outcome
+                        .reject_reason()
+                        .map(quote_if_needed)
                         .unwrap_or_default()
                 )
             }
@@ -108,6 +109,20 @@ impl FileSink {
                     b.diff
                 )
             }
+            Event::NearMiss(n) => {
+                let timestamp = n.timestamp.unwrap_or(default_timestamp);
+                format!(
+                    "{},{},{},{},{},{},{},{}",
+                    timestamp,
+                    "near_miss",
+                    quote_if_needed(&n.address),
+                    quote_if_needed(&n.workername),
+                    n.blockheight,
+                    quote_if_needed(&n.hash),
+                    n.share_diff,
+                    n.network_diff
+                )
+            }
         }
     }
 }
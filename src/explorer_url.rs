@@ -0,0 +1,87 @@
+use super::*;
+
+/// Template for links to a block on a block explorer, with `{hash}` and
+/// `{height}` replaced by the block's hash and height.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ExplorerUrl(String);
+
+impl ExplorerUrl {
+    /// mempool.space for every chain it indexes. Regtest has no public
+    /// explorer, so its blocks go without links.
+    pub(crate) fn default_for(chain: Chain) -> Option<Self> {
+        let path = match chain {
+            Chain::Mainnet => "",
+            Chain::Regtest => return None,
+            Chain::Signet => "/signet",
+            Chain::Testnet => "/testnet",
+            Chain::Testnet4 => "/testnet4",
+        };
+
+        Some(Self(format!("https://mempool.space{path}/block/{{hash}}")))
+    }
+
+    pub(crate) fn block(&self, height: i32, hash: impl Display) -> String {
+        self.0
+            .replace("{height}", &height.to_string())
+            .replace("{hash}", &hash.to_string())
+    }
+}
+
+impl FromStr for ExplorerUrl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ensure!(
+            s.contains("{hash}") || s.contains("{height}"),
+            "explorer URL `{s}` must contain `{{hash}}` or `{{height}}`"
+        );
+
+        Ok(Self(s.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054";
+
+    #[test]
+    fn default_for_chain() {
+        assert_eq!(
+            ExplorerUrl::default_for(Chain::Mainnet)
+                .unwrap()
+                .block(850000, HASH),
+            format!("https://mempool.space/block/{HASH}")
+        );
+        assert_eq!(
+            ExplorerUrl::default_for(Chain::Signet)
+                .unwrap()
+                .block(850000, HASH),
+            format!("https://mempool.space/signet/block/{HASH}")
+        );
+        assert_eq!(ExplorerUrl::default_for(Chain::Regtest), None);
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let url = "https://example.com/{height}/{hash}"
+            .parse::<ExplorerUrl>()
+            .unwrap();
+        assert_eq!(
+            url.block(850000, HASH),
+            format!("https://example.com/850000/{HASH}")
+        );
+    }
+
+    #[test]
+    fn template_without_placeholder_rejected() {
+        assert_eq!(
+            "https://example.com"
+                .parse::<ExplorerUrl>()
+                .unwrap_err()
+                .to_string(),
+            "explorer URL `https://example.com` must contain `{hash}` or `{height}`"
+        );
+    }
+}
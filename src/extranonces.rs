@@ -4,6 +4,7 @@ use super::*;
 pub(crate) struct PoolExtranonces {
     enonce1_size: usize,
     enonce2_size: usize,
+    instance_id: Option<u8>,
 }
 
 #[derive(Debug)]
@@ -49,6 +50,23 @@ impl PoolExtranonces {
         Ok(Self {
             enonce1_size,
             enonce2_size,
+            instance_id: None,
+        })
+    }
+
+    /// Reserves the first enonce1 byte for `instance_id`, so instances with
+    /// different ids never hand out the same enonce1. The remaining bytes are
+    /// allocated per connection.
+    pub(crate) fn with_instance_id(self, instance_id: u8) -> Result<Self> {
+        ensure!(
+            self.enonce1_size > 1,
+            "enonce1_size {} leaves no room after the instance id prefix",
+            self.enonce1_size
+        );
+
+        Ok(Self {
+            instance_id: Some(instance_id),
+            ..self
         })
     }
 
@@ -56,13 +74,21 @@ impl PoolExtranonces {
         self.enonce1_size
     }
 
+    pub(crate) fn instance_id(&self) -> Option<u8> {
+        self.instance_id
+    }
+
+    fn counter_size(&self) -> usize {
+        self.enonce1_size - usize::from(self.instance_id.is_some())
+    }
+
     pub(crate) fn enonce2_size(&self) -> usize {
         self.enonce2_size
     }
 
     pub(crate) fn max_clients(&self) -> usize {
         1usize
-            .checked_shl((self.enonce1_size * 8) as u32)
+            .checked_shl((self.counter_size() * 8) as u32)
             .unwrap_or(usize::MAX)
     }
 }
@@ -334,6 +360,17 @@ mod tests {
         assert_eq!(PoolExtranonces::new(4, 8).unwrap().max_clients(), 1 << 32);
     }
 
+    #[test]
+    fn pool_instance_id_reserves_a_byte() {
+        let p = PoolExtranonces::new(4, 8)
+            .unwrap()
+            .with_instance_id(7)
+            .unwrap();
+        assert_eq!(p.instance_id(), Some(7));
+        assert_eq!(p.enonce1_size(), 4);
+        assert_eq!(p.max_clients(), 1 << 24);
+    }
+
     #[test]
     fn proxy_max_clients() {
         assert_eq!(
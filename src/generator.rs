@@ -1,8 +1,102 @@
 use super::*;
 
+/// Coinbase value changes smaller than one part in this many are not worth
+/// refreshing templates faster for.
+const MATERIAL_COINBASE_CHANGE: u64 = 1000;
+
+/// Stretches the template refresh interval while bitcoind keeps handing out
+/// the same work, doubling it up to `max`. Drops back to `base` as soon as the
+/// tip or fees move.
+struct UpdateBackoff {
+    base: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl UpdateBackoff {
+    fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            current: base,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.current
+    }
+
+    fn observe(&mut self, previous: &BlockTemplate, next: &BlockTemplate) {
+        let interval = if materially_unchanged(previous, next) {
+            (self.current * 2).min(self.max)
+        } else {
+            self.base
+        };
+
+        if interval != self.current {
+            debug!("Template update interval now {}s", interval.as_secs());
+        }
+
+        self.current = interval;
+    }
+
+    fn reset(&mut self) {
+        self.current = self.base;
+    }
+}
+
+/// How often the mempool is polled when refreshing templates on fees.
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Refreshes the template between regular updates once the mempool has gained
+/// enough fees since the last one to raise the coinbase value by more than
+/// `threshold` percent. This matters most right after a block, when the
+/// template is nearly empty and the mempool refills quickly. Not every new
+/// fee fits in the block, so growth of the mempool as a whole overestimates
+/// the gain and errs towards refreshing.
+struct FeeTrigger {
+    threshold: f64,
+    baseline: Option<Amount>,
+}
+
+impl FeeTrigger {
+    fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            baseline: None,
+        }
+    }
+
+    /// Whether the mempool holding `mempool_fees` warrants replacing a
+    /// template paying `coinbase_value`. Without a baseline from the last
+    /// refresh, the first reading becomes it.
+    fn observe(&mut self, mempool_fees: Amount, coinbase_value: Amount) -> bool {
+        let baseline = *self.baseline.get_or_insert(mempool_fees);
+
+        let gain = mempool_fees.to_sat().saturating_sub(baseline.to_sat());
+
+        gain as f64 > coinbase_value.to_sat() as f64 * self.threshold / 100.0
+    }
+
+    /// Measures later readings against `mempool_fees`, as read when the
+    /// current template was fetched.
+    fn rebase(&mut self, mempool_fees: Option<Amount>) {
+        self.baseline = mempool_fees;
+    }
+}
+
+fn materially_unchanged(previous: &BlockTemplate, next: &BlockTemplate) -> bool {
+    let previous_value = previous.coinbase_value.to_sat();
+
+    previous.previous_block_hash == next.previous_block_hash
+        && next.coinbase_value.to_sat().abs_diff(previous_value) * MATERIAL_COINBASE_CHANGE
+            < previous_value
+}
+
 pub(crate) async fn spawn_generator(
     rpc: Arc<BitcoindClient>,
     settings: Arc<Settings>,
+    metatron: Arc<Metatron>,
     cancel: CancellationToken,
     tasks: &TaskTracker,
 ) -> Result<watch::Receiver<Arc<BlockTemplate>>> {
@@ -13,63 +107,76 @@ pub(crate) async fn spawn_generator(
     let initial = get_block_template(&rpc, &settings).await?;
     let (tx, rx) = watch::channel(Arc::new(initial));
 
-    let mut subscription = Zmq::connect(settings.clone()).await?;
+    let subscription = Zmq::connect(settings.clone()).await?;
+
+    let mut blockhash_rx = spawn_zmq(subscription, &settings, &metatron, &cancel, tasks);
 
-    let mut ticker = ticker(settings.update_interval());
+    let mut backoff =
+        UpdateBackoff::new(settings.update_interval(), settings.max_update_interval());
 
     let bitcoind_timeout = settings.bitcoind_timeout();
 
-    tasks.spawn(async move {
-        let fetch = || async {
-            let template = get_block_template(&rpc, &settings).await?;
-            tx.send_replace(Arc::new(template));
-            Ok::<_, Error>(())
-        };
+    let mut fee_trigger = settings.fee_refresh_threshold().map(FeeTrigger::new);
+
+    rebase_fee_trigger(fee_trigger.as_mut(), &rpc).await;
 
-        let mut rpc_fail_since: Option<Instant> = None;
-        let mut zmq_fail_since: Option<Instant> = None;
+    let mut mempool_poll = interval(MEMPOOL_POLL_INTERVAL);
+    mempool_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    tasks.spawn(async move {
+        let mut next_update = tokio::time::Instant::now() + backoff.interval();
 
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => break,
-                result = subscription.recv_blockhash() => {
-                    match result {
-                        Ok(blockhash) => {
-                            info!("ZMQ blockhash {blockhash}");
+                blockhash = blockhash_rx.recv() => {
+                    let Some(blockhash) = blockhash else {
+                        break;
+                    };
+
+                    info!("ZMQ blockhash {blockhash}");
+                    backoff.reset();
+                }
+                _ = tokio::time::sleep_until(next_update) => {}
+                _ = mempool_poll.tick(), if fee_trigger.is_some() => {
+                    let Some(trigger) = fee_trigger.as_mut() else {
+                        continue;
+                    };
+
+                    let coinbase_value = tx.borrow().coinbase_value;
+
+                    match get_mempool_fees(&rpc).await {
+                        Ok(fees) if trigger.observe(fees, coinbase_value) => {
+                            info!("Mempool fees up to {fees}, refreshing template early");
                         }
+                        Ok(_) => continue,
                         Err(err) => {
-                            error!("ZMQ receive error: {err}");
-                            if !zmq_reconnect(
-                                &mut subscription,
-                                &mut zmq_fail_since,
-                                bitcoind_timeout,
-                                &settings,
-                                &cancel,
-                            )
-                            .await
-                            {
-                                break;
-                            }
+                            warn!("Failed to poll mempool fees: {err}");
                             continue;
                         }
                     }
                 }
-                _ = ticker.tick() => {}
             }
 
-            match fetch().await {
-                Ok(()) => rpc_fail_since = None,
-                Err(err) => {
-                    warn!("Failed to fetch new block template: {err}");
-                    if timed_out(&mut rpc_fail_since, bitcoind_timeout) {
-                        error!(
-                            "bitcoind RPC unavailable for over {bitcoind_timeout:?}, shutting down"
-                        );
-                        cancel.cancel();
-                        break;
-                    }
-                }
+            let previous = tx.borrow().clone();
+
+            if !fetch_with_retry(
+                || get_block_template(&rpc, &settings),
+                &tx,
+                &metatron,
+                bitcoind_timeout,
+                &cancel,
+            )
+            .await
+            {
+                break;
             }
+
+            backoff.observe(&previous, &tx.borrow());
+
+            rebase_fee_trigger(fee_trigger.as_mut(), &rpc).await;
+
+            next_update = tokio::time::Instant::now() + backoff.interval();
         }
         info!("Shutting down generator");
     });
@@ -77,6 +184,86 @@ pub(crate) async fn spawn_generator(
     Ok(rx)
 }
 
+/// Forwards block hashes from ZMQ to the generator from a task of its own,
+/// which the ZMQ watchdog respawns should it ever stop.
+fn spawn_zmq(
+    subscription: Zmq,
+    settings: &Arc<Settings>,
+    metatron: &Arc<Metatron>,
+    cancel: &CancellationToken,
+    tasks: &TaskTracker,
+) -> mpsc::Receiver<BlockHash> {
+    let (tx, rx) = mpsc::channel(16);
+    let mut subscription = Some(subscription);
+
+    let task_settings = settings.clone();
+    let task_cancel = cancel.clone();
+
+    spawn_zmq_watchdog(
+        move || {
+            let subscription = subscription.take();
+            let settings = task_settings.clone();
+            let cancel = task_cancel.clone();
+            let tx = tx.clone();
+
+            async move {
+                let subscription = match subscription {
+                    Some(subscription) => subscription,
+                    None => Zmq::connect(settings.clone()).await?,
+                };
+
+                forward_blockhashes(subscription, tx, &settings, &cancel).await;
+
+                Ok(())
+            }
+        },
+        Duration::from_secs(1),
+        metatron.clone(),
+        settings.alerts_ntfy_channel().map(String::from),
+        cancel.clone(),
+        tasks,
+    );
+
+    rx
+}
+
+async fn forward_blockhashes(
+    mut subscription: Zmq,
+    tx: mpsc::Sender<BlockHash>,
+    settings: &Arc<Settings>,
+    cancel: &CancellationToken,
+) {
+    let bitcoind_timeout = settings.bitcoind_timeout();
+    let mut zmq_fail_since: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            result = subscription.recv_blockhash() => match result {
+                Ok(blockhash) => {
+                    if tx.send(blockhash).await.is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    error!("ZMQ receive error: {err}");
+                    if !zmq_reconnect(
+                        &mut subscription,
+                        &mut zmq_fail_since,
+                        bitcoind_timeout,
+                        settings,
+                        cancel,
+                    )
+                    .await
+                    {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
 async fn zmq_reconnect(
     subscription: &mut Zmq,
     zmq_fail_since: &mut Option<Instant>,
@@ -112,8 +299,65 @@ async fn zmq_reconnect(
     }
 }
 
-fn timed_out(fail_since: &mut Option<Instant>, timeout: Duration) -> bool {
-    fail_since.get_or_insert_with(Instant::now).elapsed() > timeout
+/// Fetches a fresh template, retrying with backoff while bitcoind is
+/// unreachable. The last good template keeps being served in the meantime and
+/// the template watchdog flags it as stale. Returns false if bitcoind stays
+/// down for longer than `bitcoind_timeout` or the generator is cancelled.
+async fn fetch_with_retry<F, Fut>(
+    mut fetch: F,
+    tx: &watch::Sender<Arc<BlockTemplate>>,
+    metatron: &Metatron,
+    bitcoind_timeout: Duration,
+    cancel: &CancellationToken,
+) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<BlockTemplate>>,
+{
+    let mut fail_since: Option<Instant> = None;
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        let err = match fetch().await {
+            Ok(template) => {
+                if let Some(fail_start) = fail_since {
+                    info!("bitcoind RPC recovered after {:?}", fail_start.elapsed());
+                    metatron.set_bitcoind_healthy(true);
+                }
+
+                let height = template.height;
+                let prevhash = template.previous_block_hash;
+
+                let previous = tx.send_replace(Arc::new(template));
+
+                if previous.previous_block_hash != prevhash {
+                    metatron.announce_new_block(NewBlock { height, prevhash });
+                }
+
+                return true;
+            }
+            Err(err) => err,
+        };
+
+        warn!("Failed to fetch new block template: {err}");
+        metatron.set_bitcoind_healthy(false);
+
+        let fail_start = *fail_since.get_or_insert_with(Instant::now);
+        let remaining = bitcoind_timeout.saturating_sub(fail_start.elapsed());
+
+        if remaining.is_zero() {
+            error!("bitcoind RPC unavailable for over {bitcoind_timeout:?}, shutting down");
+            cancel.cancel();
+            return false;
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => return false,
+            _ = sleep(backoff.min(remaining)) => {}
+        }
+
+        backoff = (backoff * 2).min(Duration::from_secs(30));
+    }
 }
 
 async fn verify_zmq_hashblock(rpc: &BitcoindClient, settings: &Settings) -> Result<()> {
@@ -144,19 +388,65 @@ async fn verify_zmq_hashblock(rpc: &BitcoindClient, settings: &Settings) -> Resu
     Ok(())
 }
 
-pub(crate) async fn get_block_template(
-    bitcoin_rpc_client: &BitcoindClient,
-    settings: &Settings,
-) -> Result<BlockTemplate> {
+/// Capabilities advertised to bitcoind in every getblocktemplate request.
+const GBT_CAPABILITIES: &[&str] = &["coinbasetxn", "workid", "coinbase/append"];
+
+/// bitcoind refuses to hand out templates unless the request acknowledges
+/// segwit, and the template then carries witness data and the
+/// `default_witness_commitment` the coinbase commits to. Signet needs its own
+/// rule, and any further softfork rules come from settings.
+fn template_request(settings: &Settings) -> serde_json::Value {
     let mut rules = vec!["segwit"];
+
     if settings.chain().network() == Network::Signet {
         rules.push("signet");
     }
 
-    let params = json!({
-        "capabilities": ["coinbasetxn", "workid", "coinbase/append"],
+    for rule in settings.gbt_rules() {
+        if !rules.contains(&rule.as_str()) {
+            rules.push(rule);
+        }
+    }
+
+    json!({
+        "capabilities": GBT_CAPABILITIES,
         "rules": rules,
-    });
+    })
+}
+
+/// Total fees of the transactions in bitcoind's mempool.
+async fn rebase_fee_trigger(trigger: Option<&mut FeeTrigger>, rpc: &BitcoindClient) {
+    let Some(trigger) = trigger else {
+        return;
+    };
+
+    trigger.rebase(
+        get_mempool_fees(rpc)
+            .await
+            .inspect_err(|err| warn!("Failed to read mempool fees for a new template: {err}"))
+            .ok(),
+    );
+}
+
+async fn get_mempool_fees(rpc: &BitcoindClient) -> Result<Amount> {
+    #[derive(Debug, Deserialize)]
+    struct MempoolInfo {
+        total_fee: f64,
+    }
+
+    let info: MempoolInfo = rpc
+        .call_raw("getmempoolinfo", &[])
+        .await
+        .context("failed to call getmempoolinfo")?;
+
+    Ok(Amount::from_btc(info.total_fee)?)
+}
+
+pub(crate) async fn get_block_template(
+    bitcoin_rpc_client: &BitcoindClient,
+    settings: &Settings,
+) -> Result<BlockTemplate> {
+    let params = template_request(settings);
 
     let gbt: block_template::GetBlockTemplate = bitcoin_rpc_client
         .call_raw("getblocktemplate", &[params])
@@ -168,3 +458,260 @@ pub(crate) async fn get_block_template(
 
     Ok(block_template)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(height: u64) -> BlockTemplate {
+        BlockTemplate {
+            height,
+            ..Default::default()
+        }
+    }
+
+    fn template_with(previous_block_hash: BlockHash, coinbase_value: u64) -> BlockTemplate {
+        BlockTemplate {
+            previous_block_hash,
+            coinbase_value: Amount::from_sat(coinbase_value),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn template_request_acknowledges_segwit() {
+        assert_eq!(
+            template_request(&Settings::from_pool_args("para pool --chain regtest")),
+            json!({
+                "capabilities": ["coinbasetxn", "workid", "coinbase/append"],
+                "rules": ["segwit"],
+            })
+        );
+
+        assert_eq!(
+            template_request(&Settings::from_pool_args("para pool --chain signet"))["rules"],
+            json!(["segwit", "signet"])
+        );
+    }
+
+    #[test]
+    fn template_request_includes_configured_rules() {
+        assert_eq!(
+            template_request(&Settings::from_pool_args(
+                "para pool --chain signet --gbt-rule segwit --gbt-rule taproot2 --gbt-rule signet"
+            ))["rules"],
+            json!(["segwit", "signet", "taproot2"])
+        );
+    }
+
+    #[test]
+    fn unchanged_templates_lengthen_interval_up_to_cap() {
+        let mut backoff = UpdateBackoff::new(Duration::from_secs(10), Duration::from_secs(60));
+
+        let template = template_with(BlockHash::all_zeros(), 312_500_000);
+
+        for expected in [20, 40, 60, 60] {
+            backoff.observe(&template, &template);
+            assert_eq!(backoff.interval(), Duration::from_secs(expected));
+        }
+    }
+
+    #[test]
+    fn small_fee_changes_are_unchanged() {
+        let mut backoff = UpdateBackoff::new(Duration::from_secs(10), Duration::from_secs(60));
+
+        backoff.observe(
+            &template_with(BlockHash::all_zeros(), 312_500_000),
+            &template_with(BlockHash::all_zeros(), 312_510_000),
+        );
+
+        assert_eq!(backoff.interval(), Duration::from_secs(20));
+
+        backoff.observe(
+            &template_with(BlockHash::all_zeros(), 312_510_000),
+            &template_with(BlockHash::all_zeros(), 313_000_000),
+        );
+
+        assert_eq!(backoff.interval(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn new_block_resets_interval() {
+        let mut backoff = UpdateBackoff::new(Duration::from_secs(10), Duration::from_secs(60));
+
+        let template = template_with(BlockHash::all_zeros(), 312_500_000);
+
+        backoff.observe(&template, &template);
+        backoff.observe(&template, &template);
+        assert_eq!(backoff.interval(), Duration::from_secs(40));
+
+        backoff.reset();
+        assert_eq!(backoff.interval(), Duration::from_secs(10));
+
+        backoff.observe(&template, &template);
+        assert_eq!(backoff.interval(), Duration::from_secs(20));
+
+        backoff.observe(
+            &template,
+            &template_with(BlockHash::from_byte_array([1; 32]), 312_500_000),
+        );
+        assert_eq!(backoff.interval(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn no_backoff_without_max() {
+        let mut backoff = UpdateBackoff::new(Duration::from_secs(10), Duration::from_secs(10));
+
+        let template = template_with(BlockHash::all_zeros(), 312_500_000);
+
+        backoff.observe(&template, &template);
+        assert_eq!(backoff.interval(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn fee_jump_triggers_refresh() {
+        let mut trigger = FeeTrigger::new(1.0);
+
+        let coinbase_value = Amount::from_sat(312_500_000);
+
+        assert!(!trigger.observe(Amount::from_sat(1_000_000), coinbase_value));
+        assert!(trigger.observe(Amount::from_sat(5_000_000), coinbase_value));
+    }
+
+    #[test]
+    fn trivial_fee_change_does_not_trigger_refresh() {
+        let mut trigger = FeeTrigger::new(1.0);
+
+        let coinbase_value = Amount::from_sat(312_500_000);
+
+        assert!(!trigger.observe(Amount::from_sat(1_000_000), coinbase_value));
+        assert!(!trigger.observe(Amount::from_sat(1_010_000), coinbase_value));
+        assert!(!trigger.observe(Amount::from_sat(900_000), coinbase_value));
+        assert!(!trigger.observe(Amount::from_sat(4_125_000), coinbase_value));
+    }
+
+    #[test]
+    fn refresh_rebases_fees_on_template_fetch() {
+        let mut trigger = FeeTrigger::new(1.0);
+
+        let coinbase_value = Amount::from_sat(312_500_000);
+
+        trigger.rebase(Some(Amount::from_sat(1_000_000)));
+
+        assert!(trigger.observe(Amount::from_sat(5_000_000), coinbase_value));
+
+        trigger.rebase(Some(Amount::from_sat(5_000_000)));
+
+        assert!(!trigger.observe(Amount::from_sat(6_000_000), coinbase_value));
+        assert!(trigger.observe(Amount::from_sat(8_200_000), coinbase_value));
+    }
+
+    #[test]
+    fn refresh_without_fee_reading_uses_next_poll() {
+        let mut trigger = FeeTrigger::new(1.0);
+
+        let coinbase_value = Amount::from_sat(312_500_000);
+
+        trigger.rebase(Some(Amount::from_sat(1_000_000)));
+        trigger.rebase(None);
+
+        assert!(!trigger.observe(Amount::from_sat(5_000_000), coinbase_value));
+        assert!(!trigger.observe(Amount::from_sat(6_000_000), coinbase_value));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_recovers_after_rpc_failures() {
+        let (metatron, _dir) = Metatron::test();
+        let (tx, rx) = watch::channel(Arc::new(template(1)));
+        let cancel = CancellationToken::new();
+
+        let mut failures = 3;
+
+        let recovered = fetch_with_retry(
+            || {
+                let result = if failures > 0 {
+                    failures -= 1;
+                    assert_eq!(rx.borrow().height, 1);
+                    Err(anyhow!("connection refused"))
+                } else {
+                    Ok(template(2))
+                };
+
+                async move { result }
+            },
+            &tx,
+            &metatron,
+            Duration::from_secs(60),
+            &cancel,
+        )
+        .await;
+
+        assert!(recovered);
+        assert!(metatron.bitcoind_healthy());
+        assert_eq!(rx.borrow().height, 2);
+        assert!(!cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn only_new_prevhash_announces_new_block() {
+        let (metatron, _dir) = Metatron::test();
+        let (tx, _rx) = watch::channel(Arc::new(template(1)));
+        let cancel = CancellationToken::new();
+
+        let mut new_blocks = metatron.subscribe_new_blocks();
+
+        let tip = BlockHash::from_byte_array([1; 32]);
+
+        for template in [
+            template_with(tip, 312_500_000),
+            template_with(tip, 312_600_000),
+        ] {
+            assert!(
+                fetch_with_retry(
+                    || {
+                        let template = BlockTemplate {
+                            height: 2,
+                            ..template.clone()
+                        };
+                        async move { Ok(template) }
+                    },
+                    &tx,
+                    &metatron,
+                    Duration::from_secs(60),
+                    &cancel,
+                )
+                .await
+            );
+        }
+
+        assert_eq!(
+            new_blocks.try_recv().unwrap(),
+            NewBlock {
+                height: 2,
+                prevhash: tip,
+            }
+        );
+        assert!(new_blocks.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_gives_up_after_bitcoind_timeout() {
+        let (metatron, _dir) = Metatron::test();
+        let (tx, rx) = watch::channel(Arc::new(template(1)));
+        let cancel = CancellationToken::new();
+
+        let recovered = fetch_with_retry(
+            || async { Err(anyhow!("connection refused")) },
+            &tx,
+            &metatron,
+            Duration::ZERO,
+            &cancel,
+        )
+        .await;
+
+        assert!(!recovered);
+        assert!(!metatron.bitcoind_healthy());
+        assert_eq!(rx.borrow().height, 1);
+        assert!(cancel.is_cancelled());
+    }
+}
@@ -46,6 +46,27 @@ impl HashRate {
         self.0 / HASHES_PER_DIFF_1 as f64
     }
 
+    /// The hashrate that sustains one share at `difficulty` every `cadence`,
+    /// so an assigned difficulty can be read as the hashrate it expects.
+    pub fn from_difficulty(difficulty: Difficulty, cadence: Duration) -> Self {
+        if cadence.is_zero() {
+            return Self::ZERO;
+        }
+
+        Self::from_dsps(difficulty.as_f64() / cadence.as_secs_f64())
+    }
+
+    /// Expected time to find a block at `network_difficulty` when hashing at
+    /// this rate, or `None` while there is no hashrate.
+    pub fn expected_block_time(self, network_difficulty: Difficulty) -> Option<Duration> {
+        if self.0 == 0.0 {
+            return None;
+        }
+
+        Duration::try_from_secs_f64(network_difficulty.as_f64() * HASHES_PER_DIFF_1 as f64 / self.0)
+            .ok()
+    }
+
     #[cfg(test)]
     pub(crate) fn estimate(total_difficulty: f64, window: Duration) -> Self {
         if window.is_zero() {
@@ -172,6 +193,52 @@ mod tests {
         assert_eq!(rate, HashRate::ZERO);
     }
 
+    #[test]
+    fn expected_block_time() {
+        let secs = |dsps: f64| {
+            HashRate::from_dsps(dsps)
+                .expected_block_time(Difficulty::from(600.0))
+                .unwrap()
+                .as_secs()
+        };
+
+        assert_eq!(secs(1.0), 600);
+        assert_eq!(secs(2.0), 300);
+        assert_eq!(secs(600.0), 1);
+        assert_eq!(
+            HashRate::ZERO.expected_block_time(Difficulty::from(600.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn hashrate_from_difficulty() {
+        let cases = [
+            (1.0, 1.0, 4.294_967_296e9, "4.29 GH/s"),
+            (1000.0, 3.33, 1.289_780_0e12, "1.28 TH/s"),
+            (65536.0, 10.0, 2.814_749_8e13, "28.14 TH/s"),
+            (1e9, 5.0, 8.589_934_6e17, "858.99 PH/s"),
+        ];
+
+        for (difficulty, cadence, hps, display) in cases {
+            let rate = HashRate::from_difficulty(
+                Difficulty::from(difficulty),
+                Duration::from_secs_f64(cadence),
+            );
+            assert!(
+                (rate.as_hps() - hps).abs() / hps < 1e-4,
+                "{difficulty} every {cadence}s: {} H/s",
+                rate.as_hps()
+            );
+            assert_eq!(rate.to_string(), display);
+        }
+
+        assert_eq!(
+            HashRate::from_difficulty(Difficulty::from(1000.0), Duration::ZERO),
+            HashRate::ZERO
+        );
+    }
+
     #[test]
     fn hashrate_display_formatting() {
         let cases = [
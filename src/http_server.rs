@@ -17,7 +17,6 @@ use {
     sysinfo::DiskRefreshKind,
 };
 
-pub(crate) mod accept_json;
 pub(crate) mod auth;
 pub(crate) mod error;
 pub(crate) mod templates;
@@ -409,11 +408,14 @@ pub struct BitcoinStatus {
     pub network_difficulty: Difficulty,
     pub network_hashrate: HashRate,
     pub mempool_txs: u32,
+    #[serde(default)]
+    pub explorer_url: Option<String>,
 }
 
 pub(crate) async fn bitcoin_status(
     _: AdminAuth,
     Extension(client): Extension<Arc<BitcoindClient>>,
+    Extension(explorer_url): Extension<Option<ExplorerUrl>>,
 ) -> ServerResult<Json<BitcoinStatus>> {
     #[derive(Debug, Deserialize)]
     struct GetMiningInfoResponse {
@@ -428,10 +430,23 @@ pub(crate) async fn bitcoin_status(
         .await
         .map_err(|e| ServerError::Internal(e.into()))?;
 
+    let explorer_url = match explorer_url {
+        Some(explorer_url) => {
+            let hash: BlockHash = client
+                .call_raw("getblockhash", &[json!(info.blocks)])
+                .await
+                .map_err(|e| ServerError::Internal(e.into()))?;
+
+            Some(explorer_url.block(info.blocks as i32, hash))
+        }
+        None => None,
+    };
+
     Ok(Json(BitcoinStatus {
         height: info.blocks,
         network_difficulty: Difficulty::from(info.difficulty),
         network_hashrate: HashRate::from_hps(info.networkhashps),
         mempool_txs: info.pooledtx,
+        explorer_url,
     }))
 }
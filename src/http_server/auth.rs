@@ -8,7 +8,10 @@ use {
             request::Parts,
         },
     },
-    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    base64::{
+        Engine,
+        engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    },
     bitcoin::hashes::{Hash, sha256},
     std::convert::Infallible,
 };
@@ -135,9 +138,30 @@ impl BearerAuth {
             return Some(token.to_string());
         }
 
+        if let Some(token) = Self::basic_token(headers) {
+            return Some(token);
+        }
+
         Self::cookie_token(headers)
     }
 
+    /// Takes the token from the password of HTTP basic credentials, so
+    /// clients that only speak basic auth, like `curl -u` or Prometheus
+    /// scrapers, can authenticate. The username is ignored.
+    fn basic_token(headers: &HeaderMap) -> Option<String> {
+        let header = headers.get(AUTHORIZATION)?.to_str().ok()?;
+        let (scheme, credentials) = header.split_once(' ')?;
+
+        if !scheme.eq_ignore_ascii_case("Basic") {
+            return None;
+        }
+
+        let credentials = String::from_utf8(STANDARD.decode(credentials).ok()?).ok()?;
+        let (_username, password) = credentials.split_once(':')?;
+
+        (!password.is_empty()).then(|| password.to_string())
+    }
+
     fn bearer_token(headers: &HeaderMap) -> Option<&str> {
         let header = headers.get(AUTHORIZATION)?.to_str().ok()?;
         let (scheme, token) = header.split_once(' ')?;
@@ -281,6 +305,41 @@ mod tests {
         case(None, None, None, true, true).await;
     }
 
+    #[tokio::test]
+    async fn basic() {
+        fn header(credentials: &str) -> String {
+            format!("Basic {}", STANDARD.encode(credentials))
+        }
+
+        let auth = BearerAuth::new(Some("foo"), Some("bar"));
+
+        assert!(
+            check::<ApiAuth>(Some(auth.clone()), request(Some(&header("user:foo"))))
+                .await
+                .is_ok()
+        );
+        assert!(
+            check::<AdminAuth>(Some(auth.clone()), request(Some(&header(":bar"))))
+                .await
+                .is_ok()
+        );
+        assert!(
+            check::<AdminAuth>(Some(auth.clone()), request(Some(&header("bar:foo"))))
+                .await
+                .is_err()
+        );
+        assert!(
+            check::<ApiAuth>(Some(auth.clone()), request(Some(&header("foo:"))))
+                .await
+                .is_err()
+        );
+        assert!(
+            check::<ApiAuth>(Some(auth), request(Some(&header("foo"))))
+                .await
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn cookie() {
         let auth = BearerAuth::new(Some("foo"), Some("bar"));
@@ -1,12 +1,109 @@
 use super::*;
 
+/// The coinbase a miner assembles for a job, and the merkle root that commits
+/// a candidate header to it and the rest of the template.
+#[derive(Debug)]
+pub(crate) struct Coinbase {
+    pub(crate) transaction: Transaction,
+    pub(crate) merkle_root: MerkleNode,
+}
+
+/// A job's coinbase, decoded and checked once when the job is built with a
+/// zeroed enonce2, so each submit only splices in its own enonce2.
+#[derive(Debug)]
+pub(crate) struct CoinbaseParts {
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+    transaction: Transaction,
+    /// Where the enonce2 starts in the coinbase scriptSig.
+    enonce2_offset: usize,
+    enonce2_size: usize,
+}
+
+impl CoinbaseParts {
+    pub(crate) fn decode(
+        coinb1: &str,
+        enonce1: &Extranonce,
+        enonce2_size: usize,
+        coinb2: &str,
+    ) -> Result<Self> {
+        let mut prefix = hex::decode(coinb1).context("failed to decode coinb1 hex")?;
+        prefix.extend_from_slice(enonce1.as_bytes());
+
+        let suffix = hex::decode(coinb2).context("failed to decode coinb2 hex")?;
+
+        let coinbase_bin = [prefix.as_slice(), &vec![0; enonce2_size], &suffix].concat();
+
+        let mut cursor = bitcoin::io::Cursor::new(&coinbase_bin);
+        let transaction = Transaction::consensus_decode_from_finite_reader(&mut cursor)
+            .context("failed to decode coinbase transaction")?;
+
+        ensure!(
+            cursor.position() == coinbase_bin.len() as u64,
+            "coinbase has {} trailing bytes",
+            coinbase_bin.len() as u64 - cursor.position()
+        );
+
+        ensure!(
+            transaction.is_coinbase(),
+            "coinbase does not spend the null outpoint"
+        );
+
+        let script_sig = transaction.input[0].script_sig.as_bytes();
+
+        let script_sig_start = 4
+            + VarInt::from(transaction.input.len()).size()
+            + 36
+            + VarInt::from(script_sig.len()).size();
+
+        ensure!(
+            coinbase_bin.get(script_sig_start..script_sig_start + script_sig.len())
+                == Some(script_sig)
+                && script_sig_start <= prefix.len()
+                && prefix.len() + enonce2_size <= script_sig_start + script_sig.len(),
+            "coinbase extranonce is not in its scriptSig"
+        );
+
+        Ok(Self {
+            enonce2_offset: prefix.len() - script_sig_start,
+            prefix,
+            suffix,
+            transaction,
+            enonce2_size,
+        })
+    }
+
+    /// The block height the coinbase commits to under BIP34, if it has one.
+    pub(crate) fn height(&self) -> Option<u64> {
+        match self.transaction.input[0]
+            .script_sig
+            .instructions_minimal()
+            .next()?
+            .ok()?
+        {
+            Instruction::PushBytes(bytes) => read_scriptint(bytes.as_bytes()).ok()?.try_into().ok(),
+            Instruction::Op(op) => (OP_PUSHNUM_1.to_u8()..=OP_PUSHNUM_16.to_u8())
+                .contains(&op.to_u8())
+                .then(|| u64::from(op.to_u8() - OP_PUSHNUM_1.to_u8()) + 1),
+        }
+    }
+
+    fn assemble(&self, enonce2: &Extranonce) -> Vec<u8> {
+        [self.prefix.as_slice(), enonce2.as_bytes(), &self.suffix].concat()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Job<W: Workbase> {
     pub(crate) job_id: JobId,
     pub(crate) upstream_job_id: JobId,
     pub(crate) coinb1: String,
     pub(crate) coinb2: String,
-    pub(crate) enonce1: Extranonce,
+    pub(crate) coinbase_parts: CoinbaseParts,
+    /// The address this job's coinbase pays, fixed when it was built so a
+    /// reload of the coinbase addresses doesn't change what a found block is
+    /// recorded as paying. `None` in proxy mode, where upstream builds it.
+    pub(crate) payout_address: Option<Address>,
     pub(crate) version_mask: Option<Version>,
     pub(crate) workbase: Arc<W>,
 }
@@ -32,6 +129,45 @@ impl<W: Workbase> Job<W> {
         self.workbase.ntime()
     }
 
+    /// The size of the enonce2 this job's coinbase was built for.
+    pub(crate) fn enonce2_size(&self) -> usize {
+        self.coinbase_parts.enonce2_size
+    }
+
+    /// The coinbase `enonce2` completes, exactly as the miner hashed it.
+    /// `enonce2` must be [`Self::enonce2_size`] bytes, as checked on submit.
+    pub(crate) fn coinbase(&self, enonce2: &Extranonce) -> Coinbase {
+        let parts = &self.coinbase_parts;
+
+        let mut transaction = parts.transaction.clone();
+
+        let mut script_sig = transaction.input[0].script_sig.to_bytes();
+        script_sig.splice(
+            parts.enonce2_offset..parts.enonce2_offset + parts.enonce2_size,
+            enonce2.as_bytes().iter().copied(),
+        );
+        transaction.input[0].script_sig = ScriptBuf::from_bytes(script_sig);
+
+        let merkle_root = coinbase_merkle_root(&parts.assemble(enonce2), self.merkle_branches());
+
+        Coinbase {
+            transaction,
+            merkle_root,
+        }
+    }
+
+    /// The candidate header for `submit`, rolled to `version`.
+    pub(crate) fn header(&self, coinbase: &Coinbase, submit: &Submit, version: Version) -> Header {
+        Header {
+            version: version.into(),
+            prev_blockhash: self.prevhash().into(),
+            merkle_root: coinbase.merkle_root.into(),
+            time: submit.ntime.into(),
+            bits: self.nbits().to_compact(),
+            nonce: submit.nonce.into(),
+        }
+    }
+
     pub(crate) fn notify(&self, clean_jobs: bool) -> Result<Notify> {
         Ok(Notify {
             job_id: self.job_id,
@@ -46,3 +182,188 @@ impl<W: Workbase> Job<W> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, bitcoin::constants::genesis_block};
+
+    // The genesis coinbase split the way a pool would hand it to a miner:
+    // everything up to the scriptSig length, four bytes of enonce1, two bytes
+    // of enonce2 and the rest.
+    fn genesis(coinb1_end: usize, trailing: &str) -> Result<(Job<Notify>, Extranonce)> {
+        let coinbase = encode::serialize_hex(&genesis_block(Network::Bitcoin).txdata[0]);
+
+        let workbase = Arc::new(Notify {
+            job_id: JobId::new(0),
+            prevhash: BlockHash::all_zeros().into(),
+            coinb1: coinbase[..coinb1_end].into(),
+            coinb2: format!("{}{trailing}", &coinbase[coinb1_end + 12..]),
+            merkle_branches: Vec::new(),
+            version: Version(block::Version::ONE),
+            nbits: "1d00ffff".parse().unwrap(),
+            ntime: 1231006505.into(),
+            clean_jobs: true,
+        });
+
+        let job = workbase.create_job(
+            &coinbase[coinb1_end..coinb1_end + 8].parse().unwrap(),
+            2,
+            None,
+            JobId::new(1),
+            None,
+        )?;
+
+        Ok((
+            job,
+            coinbase[coinb1_end + 8..coinb1_end + 12].parse().unwrap(),
+        ))
+    }
+
+    #[test]
+    fn coinbase_matches_genesis_block() {
+        let (job, enonce2) = genesis(84, "").unwrap();
+
+        assert_eq!(job.enonce2_size(), 2);
+
+        let coinbase = job.coinbase(&enonce2);
+
+        assert_eq!(
+            coinbase.transaction,
+            genesis_block(Network::Bitcoin).txdata[0]
+        );
+
+        assert_eq!(
+            bitcoin::TxMerkleNode::from(coinbase.merkle_root).to_string(),
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+        );
+
+        let submit = Submit {
+            username: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4.worker"
+                .parse()
+                .unwrap(),
+            job_id: job.job_id,
+            enonce2,
+            ntime: job.ntime(),
+            nonce: 2083236893.into(),
+            version_bits: None,
+        };
+
+        assert_eq!(
+            job.header(&coinbase, &submit, job.version()),
+            genesis_block(Network::Bitcoin).header,
+        );
+    }
+
+    #[test]
+    fn coinbase_splices_each_enonce2() {
+        let (job, _) = genesis(84, "").unwrap();
+
+        let enonce2 = "abcd".parse::<Extranonce>().unwrap();
+        let coinbase = job.coinbase(&enonce2);
+
+        assert_eq!(
+            &coinbase.transaction.input[0].script_sig.as_bytes()[4..6],
+            enonce2.as_bytes()
+        );
+
+        assert_eq!(
+            bitcoin::TxMerkleNode::from(coinbase.merkle_root),
+            coinbase.transaction.compute_txid().into(),
+        );
+    }
+
+    #[test]
+    fn coinbase_with_trailing_bytes_fails_job_build() {
+        assert_eq!(
+            genesis(84, "00").unwrap_err().to_string(),
+            "coinbase has 1 trailing bytes",
+        );
+    }
+
+    #[test]
+    fn truncated_coinbase_fails_job_build() {
+        let coinbase = encode::serialize_hex(&genesis_block(Network::Bitcoin).txdata[0]);
+
+        let workbase = Arc::new(Notify {
+            job_id: JobId::new(0),
+            prevhash: BlockHash::all_zeros().into(),
+            coinb1: coinbase[..84].into(),
+            coinb2: coinbase[96..coinbase.len() - 2].into(),
+            merkle_branches: Vec::new(),
+            version: Version(block::Version::ONE),
+            nbits: "1d00ffff".parse().unwrap(),
+            ntime: 1231006505.into(),
+            clean_jobs: true,
+        });
+
+        assert_eq!(
+            workbase
+                .create_job(
+                    &coinbase[84..92].parse().unwrap(),
+                    2,
+                    None,
+                    JobId::new(1),
+                    None
+                )
+                .unwrap_err()
+                .to_string(),
+            "failed to decode coinbase transaction",
+        );
+    }
+
+    #[test]
+    fn extranonce_outside_script_sig_fails_job_build() {
+        assert_eq!(
+            genesis(20, "").unwrap_err().to_string(),
+            "coinbase extranonce is not in its scriptSig",
+        );
+    }
+
+    #[test]
+    fn invalid_coinbase_hex_fails_job_build() {
+        let workbase = Arc::new(Notify {
+            job_id: JobId::new(0),
+            prevhash: BlockHash::all_zeros().into(),
+            coinb1: "zz".into(),
+            coinb2: "00".into(),
+            merkle_branches: Vec::new(),
+            version: Version(block::Version::ONE),
+            nbits: "1d00ffff".parse().unwrap(),
+            ntime: 1231006505.into(),
+            clean_jobs: true,
+        });
+
+        assert_eq!(
+            workbase
+                .create_job(&"00000000".parse().unwrap(), 2, None, JobId::new(1), None)
+                .unwrap_err()
+                .to_string(),
+            "failed to decode coinb1 hex",
+        );
+    }
+
+    #[test]
+    fn coinbase_height_reads_bip34_push() {
+        // A one-input, one-output coinbase whose scriptSig is the height push
+        // followed by four bytes each of enonce1 and enonce2.
+        fn height(push: &str) -> Option<u64> {
+            CoinbaseParts::decode(
+                &format!(
+                    "0100000001{}ffffffff{:02x}{push}",
+                    "00".repeat(32),
+                    push.len() / 2 + 8
+                ),
+                &"00000000".parse().unwrap(),
+                4,
+                "ffffffff0100000000000000000000000000",
+            )
+            .unwrap()
+            .height()
+        }
+
+        assert_eq!(height("0300350c"), Some(800_000));
+        assert_eq!(height("5500"), Some(5));
+        assert_eq!(height("6000"), Some(16));
+        assert_eq!(height("6a00"), None);
+    }
+}
@@ -65,7 +65,7 @@ mod tests {
             let enonce1 = Extranonce::random(ENONCE1_SIZE);
             Arc::new(
                 workbase
-                    .create_job(&enonce1, 8, Self::test_address().as_ref(), job_id, None)
+                    .create_job(&enonce1, 4, Self::test_address().as_ref(), job_id, None)
                     .unwrap(),
             )
         }
@@ -109,6 +109,7 @@ mod tests {
         }
     }
 
+    // Its scriptSig leaves room for a four byte enonce1 and a four byte enonce2.
     fn sample_notify(clean_jobs: bool, job_id: JobId) -> Notify {
         Notify {
             job_id,
@@ -275,7 +276,7 @@ mod tests {
         let job = workbase
             .create_job(
                 &enonce1,
-                8,
+                4,
                 W::test_address().as_ref(),
                 job_id,
                 version_mask,
@@ -283,9 +284,20 @@ mod tests {
             .unwrap();
 
         assert_eq!(job.job_id, job_id);
-        assert_eq!(job.enonce1, enonce1);
         assert_eq!(job.version_mask, version_mask);
+        assert_eq!(job.enonce2_size(), 4);
         assert!(Arc::ptr_eq(&job.workbase, &workbase));
+
+        let enonce2 = Extranonce::random(4);
+        let extranonce = [enonce1.as_bytes(), enonce2.as_bytes()].concat();
+
+        assert!(
+            job.coinbase(&enonce2).transaction.input[0]
+                .script_sig
+                .as_bytes()
+                .windows(extranonce.len())
+                .any(|window| window == extranonce)
+        );
     }
 
     fn check_clean_jobs_returns_true_for_new_work<W: TestWorkbaseFactory>() {
@@ -341,7 +353,7 @@ mod tests {
         let enonce1 = Extranonce::random(ENONCE1_SIZE);
         let job1 = Arc::new(
             workbase1
-                .create_job(&enonce1, 8, W::test_address().as_ref(), job_id, None)
+                .create_job(&enonce1, 4, W::test_address().as_ref(), job_id, None)
                 .unwrap(),
         );
 
@@ -352,7 +364,7 @@ mod tests {
         let enonce2 = Extranonce::random(ENONCE1_SIZE);
         let job2 = Arc::new(
             workbase2
-                .create_job(&enonce2, 8, W::test_address().as_ref(), job_id, None)
+                .create_job(&enonce2, 4, W::test_address().as_ref(), job_id, None)
                 .unwrap(),
         );
 
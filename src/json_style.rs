@@ -0,0 +1,166 @@
+use {
+    super::*,
+    serde_json::ser::{Formatter, Serializer},
+    std::io,
+};
+
+/// How JSON written to miners is laid out. Either way every message stays on
+/// a single line, since stratum frames messages with newlines.
+#[derive(Default, clap::ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum JsonStyle {
+    /// No whitespace at all, e.g. `{"id":1,"result":true}`.
+    #[default]
+    Compact,
+    /// A space after every `,` and `:`, e.g. `{"id": 1, "result": true}`,
+    /// like ckpool and Python's `json.dumps` write it.
+    Spaced,
+}
+
+impl JsonStyle {
+    pub(crate) fn to_json(self, value: &impl Serialize) -> Result<String> {
+        let mut buffer = Vec::new();
+
+        match self {
+            Self::Compact => value.serialize(&mut Serializer::new(&mut buffer))?,
+            Self::Spaced => value.serialize(&mut Serializer::with_formatter(
+                &mut buffer,
+                SpacedFormatter,
+            ))?,
+        }
+
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+struct SpacedFormatter;
+
+impl Formatter for SpacedFormatter {
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b": ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        tokio_util::{bytes::BytesMut, codec::Encoder},
+    };
+
+    fn frame(style: JsonStyle, message: &Message) -> String {
+        let mut buffer = BytesMut::new();
+
+        LinesCodec::new()
+            .encode(style.to_json(message).unwrap(), &mut buffer)
+            .unwrap();
+
+        String::from_utf8(buffer.to_vec()).unwrap()
+    }
+
+    fn notify() -> Message {
+        Message::Notification {
+            method: Method::Notify(Notify {
+                job_id: "bf".parse().unwrap(),
+                prevhash: "4d16b6f85af6e2198f44ae2a6de67f78487ae5611b77c6c0440b921e00000000"
+                    .parse()
+                    .unwrap(),
+                coinb1: "01000000".into(),
+                coinb2: "ffffffff".into(),
+                merkle_branches: Vec::new(),
+                version: "20000000".parse().unwrap(),
+                nbits: "1c2ac4af".parse().unwrap(),
+                ntime: "504e86b9".parse().unwrap(),
+                clean_jobs: true,
+            }),
+        }
+    }
+
+    fn set_difficulty() -> Message {
+        Message::Notification {
+            method: Method::SetDifficulty(SetDifficulty(Difficulty::from(2))),
+        }
+    }
+
+    fn response() -> Message {
+        Message::Response {
+            id: Id::Number(1),
+            result: Some(json!(true)),
+            error: None,
+            reject_reason: None,
+        }
+    }
+
+    #[test]
+    fn compact_frames() {
+        assert_eq!(
+            frame(JsonStyle::Compact, &notify()),
+            "{\"method\":\"mining.notify\",\"params\":[\"bf\",\"4d16b6f85af6e2198f44ae2a6de67f78487ae5611b77c6c0440b921e00000000\",\"01000000\",\"ffffffff\",[],\"20000000\",\"1c2ac4af\",\"504e86b9\",true]}\n"
+        );
+
+        assert_eq!(
+            frame(JsonStyle::Compact, &set_difficulty()),
+            "{\"method\":\"mining.set_difficulty\",\"params\":[2]}\n"
+        );
+
+        assert_eq!(
+            frame(JsonStyle::Compact, &response()),
+            "{\"id\":1,\"result\":true,\"error\":null}\n"
+        );
+    }
+
+    #[test]
+    fn spaced_frames() {
+        assert_eq!(
+            frame(JsonStyle::Spaced, &set_difficulty()),
+            "{\"method\": \"mining.set_difficulty\", \"params\": [2]}\n"
+        );
+
+        assert_eq!(
+            frame(JsonStyle::Spaced, &response()),
+            "{\"id\": 1, \"result\": true, \"error\": null}\n"
+        );
+    }
+
+    #[test]
+    fn frames_are_single_newline_terminated_lines() {
+        for style in [JsonStyle::Compact, JsonStyle::Spaced] {
+            for message in [notify(), set_difficulty(), response()] {
+                let frame = frame(style, &message);
+
+                assert_eq!(frame.matches('\n').count(), 1, "{frame}");
+                assert!(frame.ends_with("}\n"), "{frame}");
+                assert!(!frame.contains('\r'), "{frame}");
+                assert!(!frame.starts_with(char::is_whitespace), "{frame}");
+
+                assert_eq!(
+                    serde_json::from_str::<Message>(frame.trim_end()).unwrap(),
+                    message
+                );
+            }
+        }
+    }
+}
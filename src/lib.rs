@@ -1,12 +1,13 @@
 use {
+    allowlist::Allowlist,
     anyhow::{Context, Error, anyhow, bail, ensure},
     arguments::Arguments,
     async_trait::async_trait,
     axum::{
         Extension, Json,
-        extract::{DefaultBodyLimit, FromRequestParts},
+        extract::DefaultBodyLimit,
         http::{
-            self, HeaderValue, StatusCode,
+            HeaderValue, StatusCode,
             header::{CONTENT_DISPOSITION, CONTENT_TYPE},
         },
         response::{IntoResponse, Response},
@@ -23,7 +24,8 @@ use {
         consensus::{self, Decodable, encode},
         hashes::Hash,
         locktime::absolute::LockTime,
-        script::write_scriptint,
+        opcodes::all::{OP_PUSHNUM_1, OP_PUSHNUM_16},
+        script::{Instruction, read_scriptint, write_scriptint},
         secp256k1::Secp256k1,
         sign_message::MessageSignature,
     },
@@ -35,10 +37,12 @@ use {
     boilerplate::{Boilerplate, Trusted},
     chain::Chain,
     clap::{Args, Parser},
+    coinbase_addresses::CoinbaseAddresses,
     coinbase_builder::CoinbaseBuilder,
     dashmap::DashMap,
     decay::{DecayingAverage, calculate_time_bias},
     enonce_allocator::EnonceAllocator,
+    explorer_url::ExplorerUrl,
     extranonces::{Extranonces, PoolExtranonces, ProxyExtranonces},
     futures::{
         sink::SinkExt,
@@ -46,18 +50,21 @@ use {
     },
     generator::spawn_generator,
     hash::{HashDays, HashPrice, HashRate, HashValue, HashWork},
-    job::Job,
+    job::{Coinbase, CoinbaseParts, Job},
     jobs::Jobs,
+    json_style::JsonStyle,
     logs::logs_enabled,
     lru::LruCache,
     metatron::{
-        Metatron,
+        Metatron, NewBlock,
         session::{Session, SessionId},
         stats::Stats,
         user::User,
         worker::Worker,
     },
     parking_lot::{Mutex, RwLock},
+    password_policy::PasswordPolicy,
+    payout_xpub::PayoutXpub,
     reqwest::Url,
     retry::{Backoff, BackoffEnd, retry_with_backoff},
     router::{
@@ -78,6 +85,7 @@ use {
     serde_json::json,
     serde_with::{DeserializeFromStr, SerializeDisplay},
     settings::{BitcoinOptions, PoolOptions, ProxyOptions, RouterOptions, Settings},
+    share_log_policy::{ShareLogPolicy, ShareSampler},
     snafu::Snafu,
     sqlx::{Pool, Postgres, postgres::PgPoolOptions},
     std::{
@@ -89,14 +97,14 @@ use {
         io::{self, Write},
         iter::Sum,
         net::{IpAddr, SocketAddr, ToSocketAddrs},
-        num::NonZeroUsize,
+        num::{NonZeroU32, NonZeroUsize},
         ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
         path::{Path, PathBuf},
         process,
         str::FromStr,
         sync::{
             Arc, LazyLock, OnceLock,
-            atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+            atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering},
         },
         thread,
         time::{Duration, Instant, SystemTime, UNIX_EPOCH},
@@ -105,9 +113,9 @@ use {
     stratifier::Stratifier,
     stratum::{
         Authorize, Configure, Difficulty, Extranonce, Id, JobId, MAX_MESSAGE_SIZE, MerkleNode,
-        Message, Method, Nbits, Nonce, Notify, Ntime, PETA, PrevHash, Reconnect, SetDifficulty,
-        StratumError, Submit, Subscribe, SubscribeResponse, Username, Version, format_si,
-        merkle_root, parse_si,
+        Message, Method, Nbits, Nonce, Notify, Ntime, PETA, PrevHash, Reconnect, Resume,
+        SetDifficulty, ShowMessage, StratumError, Submit, Subscribe, SubscribeResponse, Username,
+        Version, coinbase_merkle_root, format_si, parse_si,
     },
     subcommand::server::account::Account,
     sysinfo::{Disks, System},
@@ -123,7 +131,7 @@ use {
         time::{Interval, MissedTickBehavior, interval, sleep, timeout},
     },
     tokio_util::{
-        codec::{FramedRead, FramedWrite, LinesCodec},
+        codec::{FramedRead, FramedWrite, LinesCodec, LinesCodecError},
         sync::CancellationToken,
         task::TaskTracker,
     },
@@ -133,34 +141,44 @@ use {
     upstream::Upstream,
     upstream_target::UpstreamTarget,
     utoipa::{OpenApi, ToSchema},
-    vardiff::Vardiff,
+    vardiff::{DifficultyTransition, Vardiff},
     wallet::Wallet,
+    watchdog::{spawn_template_watchdog, spawn_zmq_watchdog},
     workbase::Workbase,
+    worker_name_policy::WorkerNamePolicy,
     zeromq::{Endpoint, Socket, SocketEvent, SocketRecv, SubSocket},
     zmq::Zmq,
 };
 
+mod address_file;
+mod allowlist;
 pub mod api;
 mod arguments;
 mod block_template;
 mod chain;
 pub mod ckpool;
+mod coinbase_addresses;
 mod coinbase_builder;
 mod decay;
 mod enonce_allocator;
 mod epoch;
 mod event_sink;
+mod explorer_url;
 mod extranonces;
 mod generator;
 pub mod hash;
 mod http_server;
 mod job;
 mod jobs;
+mod json_style;
 mod logs;
 mod metatron;
+mod password_policy;
+mod payout_xpub;
 mod retry;
 pub mod router;
 pub mod settings;
+mod share_log_policy;
 mod signal;
 mod store;
 mod stratifier;
@@ -170,17 +188,19 @@ mod upstream;
 mod upstream_target;
 mod vardiff;
 mod wallet;
+mod watchdog;
 mod workbase;
+mod worker_name_policy;
 mod zmq;
 
 pub const COIN_VALUE: u64 = 100_000_000;
+pub const DEFAULT_STRATUM_PORT: u16 = 42069;
 pub const USER_AGENT: &str = concat!("para/", env!("CARGO_PKG_VERSION"));
 pub const MIN_ENONCE_SIZE: usize = 2;
 pub const MAX_ENONCE_SIZE: usize = 8;
 pub const ENONCE1_SIZE: usize = 4;
 pub const ENONCE1_EXTENSION_SIZE: usize = 2;
 pub const SHARE_CHANNEL_CAPACITY: usize = 100_000;
-pub const SUBSCRIPTION_ID: &str = "deadbeef";
 pub const LRU_CACHE_SIZE: usize = 256;
 pub const SESSION_TTL: Duration = Duration::from_secs(600);
 /// Max ntime forward roll in seconds. Conservative margin under Bitcoin's 2-hour limit.
@@ -192,30 +212,48 @@ fn target_as_block_hash(target: bitcoin::Target) -> BlockHash {
     BlockHash::from_raw_hash(Hash::from_byte_array(target.to_le_bytes()))
 }
 
-fn ensure_port(stratum_endpoint: &str) -> String {
+fn ensure_port(stratum_endpoint: &str, default_port: u16) -> String {
     if stratum_endpoint.parse::<SocketAddr>().is_ok() {
         return stratum_endpoint.to_string();
     }
 
     if stratum_endpoint.starts_with('[') && stratum_endpoint.ends_with(']') {
-        return format!("{stratum_endpoint}:42069");
+        return format!("{stratum_endpoint}:{default_port}");
     }
 
     if let Ok(addr) = stratum_endpoint.parse::<std::net::IpAddr>() {
         return if addr.is_ipv6() {
-            format!("[{stratum_endpoint}]:42069")
+            format!("[{stratum_endpoint}]:{default_port}")
         } else {
-            format!("{stratum_endpoint}:42069")
+            format!("{stratum_endpoint}:{default_port}")
         };
     }
 
     if stratum_endpoint.contains(':') {
         stratum_endpoint.to_string()
     } else {
-        format!("{stratum_endpoint}:42069")
+        format!("{stratum_endpoint}:{default_port}")
     }
 }
 
+/// Adds `default_port` to `stratum_endpoint` if it has none and checks that
+/// the host resolves, so a typo fails up front instead of as a connect timeout.
+async fn resolve_stratum_endpoint(stratum_endpoint: &str, default_port: u16) -> Result<String> {
+    let endpoint = ensure_port(stratum_endpoint, default_port);
+
+    let resolved = tokio::net::lookup_host(&endpoint)
+        .await
+        .with_context(|| format!("failed to resolve stratum endpoint `{endpoint}`"))?
+        .next();
+
+    ensure!(
+        resolved.is_some(),
+        "stratum endpoint `{endpoint}` did not resolve to any address"
+    );
+
+    Ok(endpoint)
+}
+
 fn integration_test() -> bool {
     std::env::var_os("PARA_INTEGRATION_TEST").is_some()
 }
@@ -227,10 +265,14 @@ fn ticker(period: Duration) -> Interval {
 }
 
 pub fn main() {
-    let (logs, _guard) = logs::init();
-
     let args = Arguments::parse();
 
+    let (logs, _guards) = logs::init(args.log_file_dir.as_deref(), args.log_file_retention)
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err:#}");
+            process::exit(1);
+        });
+
     Runtime::new()
         .expect("Failed to create tokio runtime")
         .block_on(async {
@@ -272,7 +314,7 @@ mod tests {
     fn ensure_port_cases() {
         #[track_caller]
         fn case(input: &str, expected: &str) {
-            assert_eq!(ensure_port(input), expected);
+            assert_eq!(ensure_port(input, DEFAULT_STRATUM_PORT), expected);
         }
 
         case("foo", "foo:42069");
@@ -283,4 +325,45 @@ mod tests {
         case("[::1]", "[::1]:42069");
         case("[::1]:3333", "[::1]:3333");
     }
+
+    #[tokio::test]
+    async fn resolve_stratum_endpoint_hostname_only() {
+        assert_eq!(
+            resolve_stratum_endpoint("localhost", DEFAULT_STRATUM_PORT)
+                .await
+                .unwrap(),
+            "localhost:42069"
+        );
+        assert_eq!(
+            resolve_stratum_endpoint("localhost", 3333).await.unwrap(),
+            "localhost:3333"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_stratum_endpoint_host_and_port() {
+        assert_eq!(
+            resolve_stratum_endpoint("127.0.0.1:3333", DEFAULT_STRATUM_PORT)
+                .await
+                .unwrap(),
+            "127.0.0.1:3333"
+        );
+        assert_eq!(
+            resolve_stratum_endpoint("localhost:3333", DEFAULT_STRATUM_PORT)
+                .await
+                .unwrap(),
+            "localhost:3333"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_stratum_endpoint_unresolvable_host() {
+        assert_eq!(
+            resolve_stratum_endpoint("para.invalid", DEFAULT_STRATUM_PORT)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "failed to resolve stratum endpoint `para.invalid:42069`"
+        );
+    }
 }
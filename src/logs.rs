@@ -1,7 +1,17 @@
-use super::*;
+use {
+    super::*,
+    tracing_appender::{
+        non_blocking::WorkerGuard,
+        rolling::{RollingFileAppender, Rotation},
+    },
+};
 
 const BACKLOG_SIZE: usize = 100;
 const CHANNEL_CAPACITY: usize = 1000;
+const LOG_FILE_PREFIX: &str = "para";
+const LOG_FILE_SUFFIX: &str = "log";
+
+pub(crate) const DEFAULT_LOG_FILE_RETENTION: usize = 7;
 
 enum Msg {
     Event(tracing::Level, String),
@@ -44,8 +54,47 @@ pub(crate) fn logs_enabled() -> bool {
     std::env::var_os("RUST_LOG").is_some()
 }
 
-pub(crate) fn init() -> (Arc<Logs>, tracing_appender::non_blocking::WorkerGuard) {
+/// Daily rotated `para.<date>.log` files. Opening the appender prunes the
+/// oldest files so that, counting the one it opens, at most `retention`
+/// remain, and it prunes again on every rotation.
+pub(crate) fn rolling_appender(dir: &Path, retention: usize) -> Result<RollingFileAppender> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(retention)
+        .build(dir)
+        .with_context(|| format!("failed to open log files in `{}`", dir.display()))
+}
+
+/// The returned guards flush buffered log lines when dropped, so they must
+/// be held for the lifetime of the process.
+pub(crate) fn init(
+    log_file_dir: Option<&Path>,
+    log_file_retention: usize,
+) -> Result<(Arc<Logs>, Vec<WorkerGuard>)> {
     let (writer, guard) = non_blocking(io::stderr());
+    let mut guards = vec![guard];
+
+    let file_layer = match log_file_dir {
+        Some(dir) => {
+            let (writer, guard) = non_blocking(rolling_appender(dir, log_file_retention)?);
+            guards.push(guard);
+
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_target(false)
+                    .with_writer(writer)
+                    .with_filter(
+                        EnvFilter::try_from_default_env()
+                            .unwrap_or_else(|_| EnvFilter::new("warn,para=info")),
+                    ),
+            )
+        }
+        None => None,
+    };
+
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
     let (broadcast_tx, _) = broadcast::channel(CHANNEL_CAPACITY);
 
@@ -61,6 +110,7 @@ pub(crate) fn init() -> (Arc<Logs>, tracing_appender::non_blocking::WorkerGuard)
                 .with_writer(writer)
                 .with_filter(fmt_filter),
         )
+        .with(file_layer)
         .with(StreamLayer(tx.clone()).with_filter(ls_filter))
         .init();
 
@@ -94,7 +144,7 @@ pub(crate) fn init() -> (Arc<Logs>, tracing_appender::non_blocking::WorkerGuard)
         }
     });
 
-    (logs, guard)
+    Ok((logs, guards))
 }
 
 struct StreamLayer(tokio::sync::mpsc::UnboundedSender<Msg>);
@@ -136,3 +186,62 @@ impl tracing::field::Visit for LogVisitor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_files(dir: &Path) -> Vec<String> {
+        let mut files = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn rolling_appender_prunes_oldest_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for day in 1..=5 {
+            fs::write(dir.path().join(format!("para.2020-01-0{day}.log")), "").unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let _appender = rolling_appender(dir.path(), 3).unwrap();
+
+        let files = log_files(dir.path());
+
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[..2], ["para.2020-01-04.log", "para.2020-01-05.log"]);
+        assert!(files[2].starts_with("para.") && files[2].ends_with(".log"));
+    }
+
+    #[test]
+    fn rolling_appender_ignores_unrelated_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join("pool.status"), "").unwrap();
+        fs::write(dir.path().join("para.2020-01-01.log"), "").unwrap();
+
+        let _appender = rolling_appender(dir.path(), 1).unwrap();
+
+        let files = log_files(dir.path());
+
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&"pool.status".into()));
+        assert!(!files.contains(&"para.2020-01-01.log".into()));
+    }
+
+    #[test]
+    fn rolling_appender_keeps_files_under_retention() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join("para.2020-01-01.log"), "").unwrap();
+
+        let _appender = rolling_appender(dir.path(), 3).unwrap();
+
+        assert_eq!(log_files(dir.path()).len(), 2);
+    }
+}
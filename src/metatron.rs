@@ -1,18 +1,64 @@
 use {
     super::*,
+    api::{DisconnectReason, Disconnects, IntervalBest, MaintenanceMode, WorkerDisconnects},
     bdk_wallet::ChangeSet,
+    connection::Connection,
+    event_sink::ShareEvent,
     session::{Session, SessionId},
     stats::Stats,
-    stratifier::state::Authorization,
+    store::entry::{FoundBlockEntry, HistorySampleEntry},
+    stratifier::state::{Authorization, Identity},
     user::User,
     worker::Worker,
 };
 
+pub(crate) mod connection;
 pub(crate) mod session;
 pub(crate) mod stats;
 pub(crate) mod user;
 pub(crate) mod worker;
 
+/// A day of hashrate history at one sample a minute.
+const HISTORY_SIZE: usize = 1440;
+
+/// How long a worker may go without a share before it stops counting as
+/// active, unless configured otherwise.
+const DEFAULT_WORKER_OFFLINE_GRACE: Duration = Duration::from_secs(60);
+const DEFAULT_SHARE_CADENCE: Duration = Duration::from_millis(3330);
+
+/// Exponent of the lowest accepted difficulty bucket. Shares below it are
+/// counted there, and the buckets run well past the largest difficulty.
+const MIN_DIFFICULTY_EXPONENT: i32 = -32;
+const DIFFICULTY_BUCKETS: usize = 128;
+
+/// How long a worker's disconnect counts are kept after its last disconnect.
+const WORKER_DISCONNECTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// New blocks announced before lagging subscribers start missing some.
+const NEW_BLOCK_CAPACITY: usize = 16;
+
+/// Shares buffered for the live share feed before lagging subscribers start
+/// missing some.
+const SHARE_FEED_CAPACITY: usize = 1024;
+
+/// The pool rotated onto a new chain tip: templates now build on `prevhash`
+/// at `height`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NewBlock {
+    pub(crate) height: u64,
+    pub(crate) prevhash: BlockHash,
+}
+
+/// What a `mining.resume` token gives back: the enonce1, which must still be
+/// held for resumption, and the state that renegotiating would rebuild.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ResumeTicket {
+    pub(crate) enonce1: Extranonce,
+    pub(crate) difficulty: Difficulty,
+    pub(crate) version_mask: Option<Version>,
+    pub(crate) expires: Instant,
+}
+
 struct OrderSlot {
     stats: Mutex<Stats>,
     sessions: DashMap<SessionId, Arc<Session>>,
@@ -37,11 +83,31 @@ impl OrderSlot {
 pub(crate) struct Metatron {
     store: Arc<Store>,
     blocks: RwLock<Vec<BlockHash>>,
+    found_blocks: RwLock<Vec<FoundBlockEntry>>,
+    history: RwLock<VecDeque<HistorySampleEntry>>,
     counter: AtomicU32,
     disconnected: DashMap<Extranonce, (Arc<Session>, Instant, Arc<EnonceAllocator>)>,
+    resume_tickets: DashMap<String, ResumeTicket>,
     started: Instant,
     orders: DashMap<u32, OrderSlot>,
     users: DashMap<Address, Arc<User>>,
+    stale_templates: AtomicU64,
+    bitcoind_healthy: AtomicBool,
+    connections: DashMap<SocketAddr, Arc<Connection>>,
+    disconnects: Mutex<BTreeMap<DisconnectReason, u64>>,
+    rejects: Mutex<BTreeMap<StratumError, u64>>,
+    accepted_by_difficulty: [AtomicU64; DIFFICULTY_BUCKETS],
+    near_misses: AtomicU64,
+    zmq_restarts: AtomicU64,
+    interval_best: Mutex<Option<IntervalBest>>,
+    interval_height: AtomicI64,
+    interval_best_share: AtomicU64,
+    maintenance: Mutex<Option<MaintenanceMode>>,
+    worker_offline_grace: Mutex<Duration>,
+    share_cadence: Mutex<Duration>,
+    worker_disconnects: DashMap<(Address, String), (BTreeMap<DisconnectReason, u64>, Instant)>,
+    new_blocks: broadcast::Sender<NewBlock>,
+    shares: broadcast::Sender<ShareEvent>,
 }
 
 impl Metatron {
@@ -56,15 +122,37 @@ impl Metatron {
             .collect::<Result<_>>()?;
 
         let blocks = store.read_blocks()?;
+        let found_blocks = store.read_found_blocks()?;
+        let history = store.read_history()?;
 
         Ok(Self {
             store,
             blocks: RwLock::new(blocks),
+            found_blocks: RwLock::new(found_blocks),
+            history: RwLock::new(history),
             counter: AtomicU32::new(0),
             disconnected: DashMap::new(),
+            resume_tickets: DashMap::new(),
             started: Instant::now(),
             orders: DashMap::new(),
             users,
+            stale_templates: AtomicU64::new(0),
+            bitcoind_healthy: AtomicBool::new(true),
+            connections: DashMap::new(),
+            disconnects: Mutex::new(BTreeMap::new()),
+            rejects: Mutex::new(BTreeMap::new()),
+            accepted_by_difficulty: std::array::from_fn(|_| AtomicU64::new(0)),
+            near_misses: AtomicU64::new(0),
+            zmq_restarts: AtomicU64::new(0),
+            interval_best: Mutex::new(None),
+            interval_height: AtomicI64::new(-1),
+            interval_best_share: AtomicU64::new(0),
+            maintenance: Mutex::new(None),
+            worker_offline_grace: Mutex::new(DEFAULT_WORKER_OFFLINE_GRACE),
+            share_cadence: Mutex::new(DEFAULT_SHARE_CADENCE),
+            worker_disconnects: DashMap::new(),
+            new_blocks: broadcast::channel(NEW_BLOCK_CAPACITY).0,
+            shares: broadcast::channel(SHARE_FEED_CAPACITY).0,
         })
     }
 
@@ -76,6 +164,7 @@ impl Metatron {
         info!("Spawning metatron session cleanup task");
 
         let metatron = self.clone();
+        let mut new_blocks = self.subscribe_new_blocks();
 
         tasks.spawn(async move {
             let mut cleanup_interval = ticker(Duration::from_secs(60));
@@ -91,7 +180,22 @@ impl Metatron {
 
                     _ = cleanup_interval.tick() => {
                         metatron.cleanup_expired(Instant::now());
+                        metatron.record_history_sample(
+                            SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                            Instant::now(),
+                        );
                         info!("{}", metatron.status_line());
+
+                        if let Some(line) = metatron.difficulty_line() {
+                            info!("{line}");
+                        }
+                    }
+
+                    Ok(NewBlock { height, prevhash }) = new_blocks.recv() => {
+                        info!("Chain tip moved to {prevhash}, now mining height {height}");
                     }
                 }
             }
@@ -107,6 +211,12 @@ impl Metatron {
                 }
                 keep
             });
+
+        self.resume_tickets.retain(|_, ticket| now < ticket.expires);
+
+        self.worker_disconnects.retain(|_, (_, last_disconnect)| {
+            now.duration_since(*last_disconnect) < WORKER_DISCONNECTS_TTL
+        });
     }
 
     pub(crate) fn new_session(&self, auth: Arc<Authorization>, order_id: u32) -> Arc<Session> {
@@ -135,7 +245,9 @@ impl Metatron {
         session
     }
 
-    pub(crate) fn retire_session(&self, session: Arc<Session>, allocator: Arc<EnonceAllocator>) {
+    /// Removes a session from its worker and order without holding its
+    /// enonce1 for resumption.
+    pub(crate) fn close_session(&self, session: &Session) {
         if let Some(user) = self.users.get(session.address())
             && let Some(worker) = user.workers.get(session.workername())
         {
@@ -145,6 +257,10 @@ impl Metatron {
         if let Some(slot) = self.orders.get(&session.id().order_id()) {
             slot.sessions.remove(&session.id());
         }
+    }
+
+    pub(crate) fn retire_session(&self, session: Arc<Session>, allocator: Arc<EnonceAllocator>) {
+        self.close_session(&session);
 
         self.disconnected.insert(
             session.enonce1().clone(),
@@ -160,6 +276,19 @@ impl Metatron {
             .is_some()
     }
 
+    pub(crate) fn issue_resume_ticket(&self, token: String, ticket: ResumeTicket) {
+        self.resume_tickets.insert(token, ticket);
+    }
+
+    /// Takes the ticket for `token`. Each token works once, and not at all
+    /// once it has expired.
+    pub(crate) fn take_resume_ticket(&self, token: &str, now: Instant) -> Option<ResumeTicket> {
+        self.resume_tickets
+            .remove(token)
+            .map(|(_, ticket)| ticket)
+            .filter(|ticket| now < ticket.expires)
+    }
+
     pub(crate) fn disconnected_info(
         &self,
         enonce1: &Extranonce,
@@ -191,8 +320,9 @@ impl Metatron {
         }
     }
 
-    pub(crate) fn record_block(&self, blockhash: BlockHash) {
-        self.blocks.write().push(blockhash);
+    pub(crate) fn record_block(&self, block: FoundBlockEntry) {
+        self.blocks.write().push(block.hash);
+        self.found_blocks.write().push(block);
     }
 
     pub(crate) fn snapshot(&self) -> Stats {
@@ -212,6 +342,47 @@ impl Metatron {
         self.blocks.read().iter().rev().take(n).copied().collect()
     }
 
+    /// The `n` most recently found blocks with their details, newest first.
+    /// Blocks found before details were recorded only appear in
+    /// `recent_blocks`.
+    pub(crate) fn recent_found_blocks(&self, n: usize) -> Vec<FoundBlockEntry> {
+        self.found_blocks
+            .read()
+            .iter()
+            .rev()
+            .take(n)
+            .cloned()
+            .collect()
+    }
+
+    /// Samples the pool's hashrate and SPS, keeping the newest
+    /// `HISTORY_SIZE` samples.
+    pub(crate) fn record_history_sample(&self, at_secs: u64, now: Instant) {
+        let stats = self.snapshot();
+
+        let mut history = self.history.write();
+
+        history.push_back(HistorySampleEntry {
+            at_secs,
+            hashrate: stats.hashrate_1m(now),
+            sps: stats.sps_1m(now),
+        });
+
+        while history.len() > HISTORY_SIZE {
+            history.pop_front();
+        }
+    }
+
+    /// Samples taken at or after `since_secs`, oldest first.
+    pub(crate) fn history(&self, since_secs: u64) -> Vec<HistorySampleEntry> {
+        self.history
+            .read()
+            .iter()
+            .filter(|sample| sample.at_secs >= since_secs)
+            .cloned()
+            .collect()
+    }
+
     pub(crate) fn total_sessions(&self) -> usize {
         self.users.iter().map(|user| user.session_count()).sum()
     }
@@ -230,6 +401,234 @@ impl Metatron {
             .count()
     }
 
+    pub(crate) fn register_connection(
+        &self,
+        socket_addr: SocketAddr,
+        difficulty: Difficulty,
+    ) -> Arc<Connection> {
+        let connection = Arc::new(Connection::new(socket_addr, difficulty));
+        self.connections.insert(socket_addr, connection.clone());
+        connection
+    }
+
+    /// Leaves the entry alone if a newer connection has taken over the same
+    /// socket address.
+    pub(crate) fn remove_connection(&self, connection: &Arc<Connection>) {
+        self.connections
+            .remove_if(&connection.socket_addr(), |_, registered| {
+                Arc::ptr_eq(registered, connection)
+            });
+    }
+
+    /// Counts a closed connection under `reason`, and under its worker too
+    /// once the connection had authorized. A worker's counts are dropped a
+    /// day after its last disconnect.
+    pub(crate) fn record_disconnect(
+        &self,
+        connection: &Connection,
+        reason: DisconnectReason,
+        now: Instant,
+    ) {
+        *self.disconnects.lock().entry(reason).or_default() += 1;
+
+        let details = connection.details();
+
+        if let (Some(address), Some(workername)) = (details.address, details.workername) {
+            let mut entry = self
+                .worker_disconnects
+                .entry((address, workername))
+                .or_insert_with(|| (BTreeMap::new(), now));
+
+            let (reasons, last_disconnect) = entry.value_mut();
+            *reasons.entry(reason).or_default() += 1;
+            *last_disconnect = now;
+        }
+    }
+
+    pub(crate) fn record_reject(&self, reason: StratumError) {
+        *self.rejects.lock().entry(reason).or_default() += 1;
+    }
+
+    pub(crate) fn rejects(&self) -> BTreeMap<StratumError, u64> {
+        self.rejects.lock().clone()
+    }
+
+    /// Counts an accepted share in the power of two bucket holding its pool
+    /// difficulty, keyed by exponent, so difficulty 1024 up to 2048 counts
+    /// under 10.
+    pub(crate) fn record_accepted_difficulty(&self, pool_diff: Difficulty) {
+        let index = (difficulty_bucket(pool_diff) - MIN_DIFFICULTY_EXPONENT)
+            .clamp(0, DIFFICULTY_BUCKETS as i32 - 1) as usize;
+
+        self.accepted_by_difficulty[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn accepted_by_difficulty(&self) -> BTreeMap<i32, u64> {
+        self.accepted_by_difficulty
+            .iter()
+            .zip(MIN_DIFFICULTY_EXPONENT..)
+            .map(|(count, exponent)| (exponent, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    fn difficulty_line(&self) -> Option<String> {
+        let buckets = self.accepted_by_difficulty();
+
+        if buckets.is_empty() {
+            return None;
+        }
+
+        Some(format!(
+            "accepted_by_difficulty: {}",
+            buckets
+                .iter()
+                .map(|(exponent, count)| format!("2^{exponent}={count}"))
+                .collect::<Vec<String>>()
+                .join("  ")
+        ))
+    }
+
+    /// Tracks the best share mined on top of each block, logging it once
+    /// the next block arrives. Returns whether the share came within
+    /// `near_miss_factor` of the network difficulty.
+    pub(crate) fn record_interval_share(
+        &self,
+        height: i32,
+        share_diff: Difficulty,
+        network_diff: Difficulty,
+        near_miss_factor: Option<f64>,
+    ) -> bool {
+        // Most shares neither start a new interval nor beat its best, so
+        // check a lock-free snapshot before taking the mutex. The snapshot
+        // only ever lets a share skip an update the mutex would also skip.
+        let seen_height = self.interval_height.load(Ordering::Acquire);
+        let seen_best = self.interval_best_share.load(Ordering::Acquire);
+
+        if i64::from(height) != seen_height || share_diff.as_f64().to_bits() > seen_best {
+            self.update_interval_best(height, share_diff, network_diff);
+        }
+
+        let near_miss = share_diff < network_diff
+            && near_miss_factor
+                .is_some_and(|factor| share_diff.as_f64() * factor >= network_diff.as_f64());
+
+        if near_miss {
+            self.near_misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        near_miss
+    }
+
+    fn update_interval_best(&self, height: i32, share_diff: Difficulty, network_diff: Difficulty) {
+        let mut interval_best = self.interval_best.lock();
+
+        match *interval_best {
+            Some(ref mut current) if current.height == height => {
+                current.best_share = current.best_share.max(share_diff);
+            }
+            Some(current) if current.height > height => return,
+            _ => {
+                if let Some(previous) = interval_best.replace(IntervalBest {
+                    height,
+                    best_share: share_diff,
+                    network_difficulty: network_diff,
+                }) {
+                    info!(
+                        "Best share at height {}: {} ({:.4}% of network difficulty {})",
+                        previous.height,
+                        previous.best_share,
+                        previous.ratio() * 100.0,
+                        previous.network_difficulty,
+                    );
+                }
+            }
+        }
+
+        if let Some(current) = *interval_best {
+            // Difficulties are positive, so their bit patterns order the same
+            // way the values do. The best is stored before the height so a
+            // reader seeing the new height also sees its best.
+            self.interval_best_share
+                .store(current.best_share.as_f64().to_bits(), Ordering::Release);
+            self.interval_height
+                .store(current.height.into(), Ordering::Release);
+        }
+    }
+
+    pub(crate) fn near_misses(&self) -> u64 {
+        self.near_misses.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn interval_best(&self) -> Option<IntervalBest> {
+        *self.interval_best.lock()
+    }
+
+    pub(crate) fn disconnects(&self) -> Disconnects {
+        let mut workers = self
+            .worker_disconnects
+            .iter()
+            .map(|entry| {
+                let (address, workername) = entry.key();
+                WorkerDisconnects {
+                    address: address.clone().into_unchecked(),
+                    worker_name: workername.clone(),
+                    reasons: entry.value().0.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        workers.sort_by_key(|worker| {
+            (
+                worker.address.assume_checked_ref().to_string(),
+                worker.worker_name.clone(),
+            )
+        });
+
+        Disconnects {
+            reasons: self.disconnects.lock().clone(),
+            workers,
+        }
+    }
+
+    pub(crate) fn connections(&self) -> Vec<Arc<Connection>> {
+        self.connections
+            .iter()
+            .map(|connection| connection.clone())
+            .collect()
+    }
+
+    /// The current difficulty of every authorized connection, summed per
+    /// worker and keyed by address, then workername.
+    pub(crate) fn worker_difficulties(&self) -> HashMap<Address, BTreeMap<String, Difficulty>> {
+        let mut totals = HashMap::<Address, BTreeMap<String, f64>>::new();
+
+        for connection in self.connections.iter() {
+            let details = connection.details();
+
+            if let (Some(address), Some(workername)) = (details.address, details.workername) {
+                *totals
+                    .entry(address)
+                    .or_default()
+                    .entry(workername)
+                    .or_default() += details.difficulty.as_f64();
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(address, workers)| {
+                (
+                    address,
+                    workers
+                        .into_iter()
+                        .map(|(workername, difficulty)| (workername, Difficulty::from(difficulty)))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
     pub(crate) fn users(&self) -> &DashMap<Address, Arc<User>> {
         &self.users
     }
@@ -246,6 +645,92 @@ impl Metatron {
         self.started.elapsed()
     }
 
+    pub(crate) fn record_stale_template(&self) {
+        self.stale_templates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stale_templates(&self) -> u64 {
+        self.stale_templates.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_zmq_restart(&self) {
+        self.zmq_restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn zmq_restarts(&self) -> u64 {
+        self.zmq_restarts.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_maintenance(&self, mode: Option<MaintenanceMode>) {
+        *self.maintenance.lock() = mode;
+    }
+
+    pub(crate) fn maintenance(&self) -> Option<MaintenanceMode> {
+        *self.maintenance.lock()
+    }
+
+    pub(crate) fn set_worker_offline_grace(&self, grace: Duration) {
+        *self.worker_offline_grace.lock() = grace;
+    }
+
+    pub(crate) fn worker_offline_grace(&self) -> Duration {
+        *self.worker_offline_grace.lock()
+    }
+
+    /// How often vardiff aims for each connection to submit a share, which
+    /// turns an assigned difficulty into the hashrate it expects.
+    pub(crate) fn set_share_cadence(&self, cadence: Duration) {
+        *self.share_cadence.lock() = cadence;
+    }
+
+    pub(crate) fn share_cadence(&self) -> Duration {
+        *self.share_cadence.lock()
+    }
+
+    /// Tells subscribers the pool moved onto a new prevhash. Template
+    /// refreshes on the same tip are not announced.
+    pub(crate) fn announce_new_block(&self, new_block: NewBlock) {
+        self.new_blocks.send(new_block).ok();
+    }
+
+    pub(crate) fn subscribe_new_blocks(&self) -> broadcast::Receiver<NewBlock> {
+        self.new_blocks.subscribe()
+    }
+
+    /// Passes an accepted or rejected share on to live feed subscribers,
+    /// stamped with the current time. Does nothing while nobody is watching.
+    pub(crate) fn announce_share(&self, share: &ShareEvent) {
+        if self.shares.receiver_count() == 0 {
+            return;
+        }
+
+        self.shares
+            .send(ShareEvent {
+                timestamp: share.timestamp.or_else(|| {
+                    Some(
+                        SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs() as i64,
+                    )
+                }),
+                ..share.clone()
+            })
+            .ok();
+    }
+
+    pub(crate) fn subscribe_shares(&self) -> broadcast::Receiver<ShareEvent> {
+        self.shares.subscribe()
+    }
+
+    pub(crate) fn set_bitcoind_healthy(&self, healthy: bool) {
+        self.bitcoind_healthy.store(healthy, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bitcoind_healthy(&self) -> bool {
+        self.bitcoind_healthy.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn record_order_accepted(
         &self,
         order_id: u32,
@@ -306,6 +791,8 @@ impl Metatron {
         txn.merge_wallet(wallet_delta)?;
         txn.write_users(&self.snapshot_users())?;
         txn.write_blocks(&self.blocks.read())?;
+        txn.write_found_blocks(&self.found_blocks.read())?;
+        txn.write_history(&self.history.read())?;
         txn.commit()?;
 
         debug!("persist took {:?}", start.elapsed());
@@ -327,6 +814,26 @@ impl Metatron {
         txn.commit()
     }
 
+    /// The derivation index of `account`'s payout address, assigning one the
+    /// first time the account authorizes while fewer than `max_accounts`
+    /// have been. Writes to the store, so call it off the async runtime.
+    pub(crate) fn account_index(&self, account: &str, max_accounts: u32) -> Result<Option<u32>> {
+        let txn = self.store.begin()?;
+        let index = txn.account_index(account, max_accounts)?;
+        txn.commit()?;
+        Ok(index)
+    }
+
+    /// The index `account_index` would return for `account` right now,
+    /// without assigning one.
+    pub(crate) fn peek_account_index(
+        &self,
+        account: &str,
+        max_accounts: u32,
+    ) -> Result<Option<u32>> {
+        self.store.peek_account_index(account, max_accounts)
+    }
+
     fn snapshot_users(&self) -> Vec<(Address, store::entry::UserEntry)> {
         let now = Instant::now();
 
@@ -421,6 +928,12 @@ impl StatusLine for Metatron {
     }
 }
 
+/// Difficulty only roughly survives the round trip through a target, so
+/// exact powers of two are nudged up before flooring to stay in their bucket.
+fn difficulty_bucket(difficulty: Difficulty) -> i32 {
+    (difficulty.as_f64() * (1.0 + 1e-9)).log2().floor() as i32
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, std::thread};
@@ -451,6 +964,35 @@ mod tests {
         })
     }
 
+    #[test]
+    fn accepted_shares_bucketed_by_difficulty() {
+        let (metatron, _dir) = Metatron::test();
+
+        for difficulty in [0.001, 1.0, 1.5, 1000.0, 1024.0, 2047.0, 2048.0, 1_000_000.0] {
+            metatron.record_accepted_difficulty(Difficulty::from(difficulty));
+        }
+
+        assert_eq!(
+            metatron.accepted_by_difficulty(),
+            [(-10, 1), (0, 2), (9, 1), (10, 2), (11, 1), (19, 1)].into()
+        );
+
+        assert_eq!(
+            metatron.difficulty_line().unwrap(),
+            "accepted_by_difficulty: 2^-10=1  2^0=2  2^9=1  2^10=2  2^11=1  2^19=1"
+        );
+    }
+
+    #[test]
+    fn tiny_difficulties_land_in_lowest_bucket() {
+        let (metatron, _dir) = Metatron::test();
+
+        metatron.record_accepted_difficulty(Difficulty::from(1e-20));
+        metatron.record_accepted_difficulty(Difficulty::from(1e-12));
+
+        assert_eq!(metatron.accepted_by_difficulty(), [(-32, 2)].into());
+    }
+
     #[test]
     fn new_metatron_starts_at_zero() {
         let (metatron, _dir) = Metatron::test();
@@ -506,21 +1048,117 @@ mod tests {
         assert_eq!(stats.rejected_shares, 1);
     }
 
+    #[test]
+    fn version_rolling_ratio() {
+        let (metatron, _dir) = Metatron::test();
+        let rolling = metatron.new_session(test_auth("deadbeef", "foo"), 0);
+        let plain = metatron.new_session(test_auth("cafebabe", "bar"), 0);
+        let pool_diff = Difficulty::from(100.0);
+
+        rolling.record_version_rolled(Version::from_str("00002000").unwrap());
+        rolling.record_accepted(pool_diff, pool_diff);
+        rolling.record_version_rolled(Version::from_str("00006000").unwrap());
+        rolling.record_accepted(pool_diff, pool_diff);
+        plain.record_accepted(pool_diff, pool_diff);
+        plain.record_accepted(pool_diff, pool_diff);
+
+        let stats = api::MiningStats::from_snapshot(&rolling.snapshot(), Instant::now());
+        assert_eq!(stats.version_rolled_shares, 2);
+        assert_eq!(stats.version_rolling_ratio, 1.0);
+        assert_eq!(stats.version_rolled_bits, [(13, 2), (14, 1)].into());
+
+        let stats = api::MiningStats::from_snapshot(&plain.snapshot(), Instant::now());
+        assert_eq!(stats.version_rolled_shares, 0);
+        assert_eq!(stats.version_rolling_ratio, 0.0);
+        assert!(stats.version_rolled_bits.is_empty());
+
+        let stats = api::MiningStats::from_snapshot(&metatron.snapshot(), Instant::now());
+        assert_eq!(stats.accepted_shares, 4);
+        assert_eq!(stats.version_rolled_shares, 2);
+        assert_eq!(stats.version_rolling_ratio, 0.5);
+        assert_eq!(stats.version_rolled_bits, [(13, 2), (14, 1)].into());
+    }
+
+    fn found_block(byte: u8, height: i32) -> FoundBlockEntry {
+        FoundBlockEntry {
+            height,
+            hash: BlockHash::from_byte_array([byte; 32]),
+            found_at_secs: 1_700_000_000 + height as u64,
+            finder: "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.foo".into(),
+            reward_sat: Some(312_500_000),
+        }
+    }
+
     #[test]
     fn record_block_stores_hash() {
         let (metatron, _dir) = Metatron::test();
 
-        let h1 = BlockHash::from_byte_array([1u8; 32]);
-        let h2 = BlockHash::from_byte_array([2u8; 32]);
+        let b1 = found_block(1, 100);
+        let b2 = found_block(2, 101);
 
-        metatron.record_block(h1);
+        metatron.record_block(b1.clone());
         assert_eq!(metatron.block_count(), 1);
-        assert_eq!(metatron.recent_blocks(1), vec![h1]);
+        assert_eq!(metatron.recent_blocks(1), vec![b1.hash]);
 
-        metatron.record_block(h2);
+        metatron.record_block(b2.clone());
         assert_eq!(metatron.block_count(), 2);
-        assert_eq!(metatron.recent_blocks(1), vec![h2]);
-        assert_eq!(metatron.recent_blocks(2), vec![h2, h1]);
+        assert_eq!(metatron.recent_blocks(1), vec![b2.hash]);
+        assert_eq!(metatron.recent_blocks(2), vec![b2.hash, b1.hash]);
+        assert_eq!(metatron.recent_found_blocks(5), vec![b2, b1]);
+    }
+
+    #[test]
+    fn found_blocks_survive_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.redb");
+
+        {
+            let store = Arc::new(Store::open(&path, Chain::Regtest).unwrap());
+            let metatron = Metatron::test_with_store(store);
+            metatron.record_block(found_block(1, 100));
+            metatron.persist(&[], &Default::default()).unwrap();
+        }
+
+        let store = Arc::new(Store::open(&path, Chain::Regtest).unwrap());
+        let metatron = Metatron::test_with_store(store);
+
+        assert_eq!(metatron.block_count(), 1);
+        assert_eq!(metatron.recent_found_blocks(5), vec![found_block(1, 100)]);
+    }
+
+    #[test]
+    fn history_samples_in_chronological_order() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.redb");
+
+        {
+            let store = Arc::new(Store::open(&path, Chain::Regtest).unwrap());
+            let metatron = Metatron::test_with_store(store);
+
+            for at_secs in 0..HISTORY_SIZE as u64 + 10 {
+                metatron.record_history_sample(at_secs, Instant::now());
+            }
+
+            metatron.persist(&[], &Default::default()).unwrap();
+        }
+
+        let store = Arc::new(Store::open(&path, Chain::Regtest).unwrap());
+        let metatron = Metatron::test_with_store(store);
+
+        let history = metatron.history(0);
+        assert_eq!(history.len(), HISTORY_SIZE);
+        assert_eq!(history.first().unwrap().at_secs, 10);
+        assert!(history.is_sorted_by_key(|sample| sample.at_secs));
+
+        let recent = metatron.history(HISTORY_SIZE as u64 + 7);
+        assert_eq!(
+            recent
+                .iter()
+                .map(|sample| sample.at_secs)
+                .collect::<Vec<u64>>(),
+            [1447, 1448, 1449]
+        );
+        assert_eq!(recent[0].hashrate, HashRate::ZERO);
     }
 
     #[test]
@@ -560,6 +1198,72 @@ mod tests {
         assert_eq!(metatron.total_disconnected(), 0);
     }
 
+    #[test]
+    fn resume_tickets_work_once_until_expiry() {
+        let (metatron, _dir) = Metatron::test();
+        let now = Instant::now();
+
+        let ticket = ResumeTicket {
+            enonce1: "deadbeef".parse().unwrap(),
+            difficulty: Difficulty::from(512),
+            version_mask: None,
+            expires: now + Duration::from_secs(30),
+        };
+
+        assert_eq!(metatron.take_resume_ticket("token", now), None);
+
+        metatron.issue_resume_ticket("token".into(), ticket.clone());
+        assert_eq!(
+            metatron.take_resume_ticket("token", now),
+            Some(ticket.clone())
+        );
+        assert_eq!(metatron.take_resume_ticket("token", now), None);
+
+        metatron.issue_resume_ticket("token".into(), ticket.clone());
+        assert_eq!(metatron.take_resume_ticket("token", ticket.expires), None);
+
+        metatron.issue_resume_ticket("token".into(), ticket.clone());
+        metatron.cleanup_expired(ticket.expires);
+        assert_eq!(
+            metatron.take_resume_ticket("token", now),
+            None,
+            "cleanup drops expired tickets"
+        );
+    }
+
+    #[test]
+    fn worker_difficulties_sum_each_workers_connections() {
+        let (metatron, _dir) = Metatron::test();
+
+        for (port, workername, difficulty) in [(1, "foo", 100), (2, "foo", 200), (3, "bar", 50)] {
+            metatron
+                .register_connection(([127, 0, 0, 1], port).into(), Difficulty::from(1))
+                .update(
+                    Some(&Identity::Authorized(test_auth("deadbeef", workername))),
+                    None,
+                    Difficulty::from(difficulty),
+                );
+        }
+
+        metatron.register_connection(([127, 0, 0, 1], 4).into(), Difficulty::from(1000));
+
+        let difficulties = metatron.worker_difficulties();
+
+        assert_eq!(
+            difficulties.len(),
+            1,
+            "unauthorized connections are left out"
+        );
+
+        assert_eq!(
+            difficulties[&test_address()]
+                .iter()
+                .map(|(workername, difficulty)| (workername.as_str(), difficulty.as_f64().round()))
+                .collect::<Vec<(&str, f64)>>(),
+            [("bar", 50.0), ("foo", 300.0)],
+        );
+    }
+
     #[test]
     fn retire_session_folds_stats() {
         let (metatron, _dir) = Metatron::test();
@@ -727,6 +1431,38 @@ mod tests {
         assert_eq!(allocator.allocated_count(), 0);
     }
 
+    #[test]
+    fn cleanup_drops_stale_worker_disconnects() {
+        let (metatron, _dir) = Metatron::test();
+        let now = Instant::now();
+        let address = test_address();
+
+        metatron.worker_disconnects.insert(
+            (address.clone(), "stale".into()),
+            (BTreeMap::from([(DisconnectReason::Idle, 1)]), now),
+        );
+
+        metatron.worker_disconnects.insert(
+            (address, "fresh".into()),
+            (
+                BTreeMap::from([(DisconnectReason::Idle, 1)]),
+                now + WORKER_DISCONNECTS_TTL,
+            ),
+        );
+
+        metatron.cleanup_expired(now + WORKER_DISCONNECTS_TTL);
+
+        assert_eq!(
+            metatron
+                .disconnects()
+                .workers
+                .iter()
+                .map(|worker| worker.worker_name.as_str())
+                .collect::<Vec<&str>>(),
+            ["fresh"]
+        );
+    }
+
     #[test]
     fn order_sessions_are_isolated() {
         let (metatron, _dir) = Metatron::test();
@@ -872,4 +1608,41 @@ mod tests {
             Some(Difficulty::from(800.0))
         );
     }
+
+    #[test]
+    fn near_miss_within_factor() {
+        let (metatron, _dir) = Metatron::test();
+        let network = Difficulty::from(1000.0);
+
+        assert!(metatron.record_interval_share(1, Difficulty::from(150.0), network, Some(10.0)));
+        assert!(!metatron.record_interval_share(1, Difficulty::from(50.0), network, Some(10.0)));
+        assert!(!metatron.record_interval_share(1, Difficulty::from(2000.0), network, Some(10.0)));
+        assert!(!metatron.record_interval_share(1, Difficulty::from(150.0), network, None));
+
+        assert_eq!(metatron.near_misses(), 1);
+    }
+
+    #[test]
+    fn interval_best_resets_on_new_height() {
+        let (metatron, _dir) = Metatron::test();
+        let network = Difficulty::from(1000.0);
+
+        assert_eq!(metatron.interval_best(), None);
+
+        metatron.record_interval_share(1, Difficulty::from(100.0), network, None);
+        metatron.record_interval_share(1, Difficulty::from(300.0), network, None);
+        metatron.record_interval_share(1, Difficulty::from(200.0), network, None);
+
+        let best = metatron.interval_best().unwrap();
+        assert_eq!(best.height, 1);
+        assert_eq!(best.best_share, Difficulty::from(300.0));
+        assert!((best.ratio() - 0.3).abs() < 0.001);
+
+        metatron.record_interval_share(2, Difficulty::from(10.0), network, None);
+        metatron.record_interval_share(1, Difficulty::from(500.0), network, None);
+
+        let best = metatron.interval_best().unwrap();
+        assert_eq!(best.height, 2);
+        assert_eq!(best.best_share, Difficulty::from(10.0));
+    }
 }
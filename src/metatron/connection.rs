@@ -0,0 +1,70 @@
+use super::*;
+
+/// A live stratum connection as seen by the pool, kept around for diagnosing
+/// how particular miner firmware behaves. The stratifier serving the
+/// connection keeps the details current.
+pub(crate) struct Connection {
+    socket_addr: SocketAddr,
+    connected_at: u64,
+    details: Mutex<ConnectionDetails>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionDetails {
+    pub(crate) user_agent: Option<String>,
+    pub(crate) address: Option<Address>,
+    pub(crate) workername: Option<String>,
+    pub(crate) version_mask: Option<Version>,
+    pub(crate) difficulty: Difficulty,
+}
+
+impl Connection {
+    pub(crate) fn new(socket_addr: SocketAddr, difficulty: Difficulty) -> Self {
+        Self {
+            socket_addr,
+            connected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            details: Mutex::new(ConnectionDetails {
+                difficulty,
+                ..ConnectionDetails::default()
+            }),
+        }
+    }
+
+    pub(crate) fn socket_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    pub(crate) fn connected_at(&self) -> u64 {
+        self.connected_at
+    }
+
+    pub(crate) fn details(&self) -> ConnectionDetails {
+        self.details.lock().clone()
+    }
+
+    pub(crate) fn set_user_agent(&self, user_agent: &str) {
+        self.details.lock().user_agent = Some(user_agent.into());
+    }
+
+    pub(crate) fn update(
+        &self,
+        identity: Option<&Identity>,
+        version_mask: Option<Version>,
+        difficulty: Difficulty,
+    ) {
+        let mut details = self.details.lock();
+
+        if details.address.is_none()
+            && let Some(identity) = identity
+        {
+            details.address = Some(identity.address().clone());
+            details.workername = Some(identity.username().workername().to_string());
+        }
+
+        details.version_mask = version_mask;
+        details.difficulty = difficulty;
+    }
+}
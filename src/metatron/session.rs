@@ -98,6 +98,10 @@ impl Session {
             .record_accepted(pool_diff, share_diff, now);
     }
 
+    pub(crate) fn record_version_rolled(&self, version_bits: Version) {
+        self.stats.lock().record_version_rolled(version_bits);
+    }
+
     pub(crate) fn record_rejected(&self, pool_diff: Difficulty) {
         self.stats.lock().record_rejected(pool_diff);
     }
@@ -14,6 +14,8 @@ pub(crate) struct Stats {
     pub(crate) rejected_work: HashWork,
     pub(crate) last_share: Option<Instant>,
     pub(crate) best_share: Option<Difficulty>,
+    pub(crate) version_rolled_shares: u64,
+    pub(crate) version_rolled_bits: [u64; 32],
     pub(crate) dsps_1m: DecayingAverage,
     pub(crate) dsps_5m: DecayingAverage,
     pub(crate) dsps_15m: DecayingAverage,
@@ -44,6 +46,8 @@ impl Stats {
                 .last_share
                 .map(|last_share| instant_to_epoch_secs(last_share, now)),
             best_share: self.best_share,
+            version_rolled_shares: self.version_rolled_shares,
+            version_rolled_bits: self.version_rolled_bits,
             dsps_1m: self.dsps_1m.to_entry(now),
             dsps_5m: self.dsps_5m.to_entry(now),
             dsps_15m: self.dsps_15m.to_entry(now),
@@ -71,6 +75,8 @@ impl Stats {
             rejected_work: entry.rejected_work,
             last_share,
             best_share: entry.best_share,
+            version_rolled_shares: entry.version_rolled_shares,
+            version_rolled_bits: entry.version_rolled_bits,
             dsps_1m: DecayingAverage::from_entry(entry.dsps_1m)?,
             dsps_5m: DecayingAverage::from_entry(entry.dsps_5m)?,
             dsps_15m: DecayingAverage::from_entry(entry.dsps_15m)?,
@@ -93,6 +99,8 @@ impl Stats {
             rejected_work: HashWork::ZERO,
             last_share: None,
             best_share: None,
+            version_rolled_shares: 0,
+            version_rolled_bits: [0; 32],
             dsps_1m: DecayingAverage::new(Duration::from_mins(1)),
             dsps_5m: DecayingAverage::new(Duration::from_mins(5)),
             dsps_15m: DecayingAverage::new(Duration::from_mins(15)),
@@ -135,6 +143,28 @@ impl Stats {
         }
     }
 
+    /// Records the version bits rolled by an accepted share, counting how often
+    /// each bit position is used.
+    pub(crate) fn record_version_rolled(&mut self, version_bits: Version) {
+        let bits = version_bits.0.to_consensus() as u32;
+
+        self.version_rolled_shares += 1;
+
+        for (bit, count) in self.version_rolled_bits.iter_mut().enumerate() {
+            if bits & (1 << bit) != 0 {
+                *count += 1;
+            }
+        }
+    }
+
+    pub(crate) fn version_rolling_ratio(&self) -> f64 {
+        if self.accepted_shares == 0 {
+            0.0
+        } else {
+            self.version_rolled_shares as f64 / self.accepted_shares as f64
+        }
+    }
+
     pub(crate) fn record_rejected(&mut self, pool_diff: Difficulty) {
         self.rejected_shares += 1;
         self.rejected_work += HashWork::from_difficulty(pool_diff);
@@ -145,6 +175,16 @@ impl Stats {
         self.rejected_shares += other.rejected_shares;
         self.accepted_work += other.accepted_work;
         self.rejected_work += other.rejected_work;
+        self.version_rolled_shares += other.version_rolled_shares;
+
+        for (count, other_count) in self
+            .version_rolled_bits
+            .iter_mut()
+            .zip(other.version_rolled_bits)
+        {
+            *count += other_count;
+        }
+
         self.dsps_1m.absorb(other.dsps_1m, now);
         self.dsps_5m.absorb(other.dsps_5m, now);
         self.dsps_15m.absorb(other.dsps_15m, now);
@@ -1,4 +1,15 @@
-use {super::*, crate::store::entry::WorkerEntry};
+use {
+    super::*,
+    crate::{api::WorkerState, store::entry::WorkerEntry},
+};
+
+/// Number of expected share intervals a worker may go without a share
+/// before it stops counting as active.
+const SILENT_SHARE_INTERVALS: f64 = 4.0;
+
+/// Upper bound on the silence allowed for workers with very sparse shares,
+/// so a worker that stopped mining eventually stops counting as active.
+const MAX_SCALED_GRACE: Duration = Duration::from_secs(3600);
 
 pub(crate) struct Worker {
     workername: String,
@@ -57,6 +68,34 @@ impl Worker {
             })
     }
 
+    /// Classifies the worker by how long it has been silent. A worker stays
+    /// active for `grace` after its last share, or for several expected
+    /// share intervals when its difficulty spaces shares further apart, so
+    /// ordinary gaps between shares don't flap it to idle.
+    pub(crate) fn state(&self, stats: &Stats, now: Instant, grace: Duration) -> WorkerState {
+        let sps = stats.sps_1hr(now);
+
+        let scaled = if sps > 0.0 {
+            Duration::from_secs_f64(
+                (SILENT_SHARE_INTERVALS / sps).min(MAX_SCALED_GRACE.as_secs_f64()),
+            )
+        } else {
+            Duration::ZERO
+        };
+
+        let active = stats
+            .last_share
+            .is_some_and(|last| now.saturating_duration_since(last) <= grace.max(scaled));
+
+        if active {
+            WorkerState::Active
+        } else if self.session_count() > 0 {
+            WorkerState::Idle
+        } else {
+            WorkerState::Offline
+        }
+    }
+
     pub(crate) fn to_entry(&self, now: Instant) -> WorkerEntry {
         WorkerEntry {
             workername: self.workername.clone(),
@@ -64,3 +103,63 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRACE: Duration = Duration::from_secs(60);
+
+    /// A worker whose difficulty has it submitting a share every 90 seconds
+    /// for an hour.
+    fn sparse_worker(start: Instant) -> (Worker, Instant) {
+        let worker = Worker::new("rig".into());
+        let mut stats = Stats::new();
+        let mut last = start;
+
+        for i in 1..=40 {
+            last = start + Duration::from_secs(90 * i);
+            stats.record_accepted(Difficulty::from(1000), Difficulty::from(1000), last);
+        }
+
+        *worker.lifetime.lock() = stats;
+
+        (worker, last)
+    }
+
+    #[test]
+    fn sparse_but_expected_shares_stay_active() {
+        let (worker, last) = sparse_worker(Instant::now());
+        let stats = worker.snapshot();
+
+        assert_eq!(
+            worker.state(&stats, last + Duration::from_secs(30), GRACE),
+            WorkerState::Active
+        );
+        assert_eq!(
+            worker.state(&stats, last + Duration::from_secs(120), GRACE),
+            WorkerState::Active
+        );
+    }
+
+    #[test]
+    fn long_silence_goes_offline() {
+        let (worker, last) = sparse_worker(Instant::now());
+        let stats = worker.snapshot();
+
+        assert_eq!(
+            worker.state(&stats, last + Duration::from_secs(7200), GRACE),
+            WorkerState::Offline
+        );
+    }
+
+    #[test]
+    fn never_shared_is_offline() {
+        let worker = Worker::new("rig".into());
+        let stats = worker.snapshot();
+        assert_eq!(
+            worker.state(&stats, Instant::now(), GRACE),
+            WorkerState::Offline
+        );
+    }
+}
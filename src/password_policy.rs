@@ -0,0 +1,117 @@
+use super::*;
+
+/// What `mining.authorize` requires of the password. Most miners send `x` or
+/// nothing, which the default accepts like ckpool does. Pools that use the
+/// password for difficulty hints or authentication can insist on one.
+#[derive(Default, clap::ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum PasswordPolicy {
+    /// Accept any password, or none.
+    #[default]
+    Optional,
+    /// Reject a missing or empty password.
+    Required,
+    /// Reject a password without a `d=<DIFFICULTY>` hint.
+    DifficultyHint,
+}
+
+impl PasswordPolicy {
+    /// Returns the difficulty hint in `password`, or why the password is
+    /// rejected. Hints are comma separated from anything else in the password,
+    /// as in `x,d=1024`, and malformed ones are ignored unless a hint is
+    /// required. The default policy ignores the password entirely.
+    pub(crate) fn check(self, password: Option<&str>) -> Result<Option<Difficulty>> {
+        if self == Self::Optional {
+            return Ok(None);
+        }
+
+        let password = password.map(str::trim).unwrap_or_default();
+
+        ensure!(!password.is_empty(), "password required");
+
+        let hint = password
+            .split(',')
+            .find_map(|token| token.trim().strip_prefix("d="));
+
+        match (self, hint) {
+            (Self::DifficultyHint, None) => {
+                bail!("password must include a difficulty hint like `d=1024`")
+            }
+            (Self::DifficultyHint, Some(hint)) => hint
+                .parse()
+                .map(Some)
+                .map_err(|err| anyhow!("invalid difficulty hint `d={hint}`: {err}")),
+            (_, hint) => Ok(hint.and_then(|hint| hint.parse().ok())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optional_accepts_anything() {
+        assert_eq!(PasswordPolicy::Optional.check(None).unwrap(), None);
+        assert_eq!(PasswordPolicy::Optional.check(Some("")).unwrap(), None);
+        assert_eq!(PasswordPolicy::Optional.check(Some("x")).unwrap(), None);
+        assert_eq!(
+            PasswordPolicy::Optional.check(Some("d=nope")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn optional_ignores_hint() {
+        assert_eq!(
+            PasswordPolicy::Optional.check(Some("x,d=1024")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn required_reads_hint() {
+        assert_eq!(
+            PasswordPolicy::Required.check(Some("x,d=1024")).unwrap(),
+            Some(Difficulty::from(1024))
+        );
+    }
+
+    #[test]
+    fn required_rejects_missing_password() {
+        for password in [None, Some(""), Some("  ")] {
+            assert_eq!(
+                PasswordPolicy::Required
+                    .check(password)
+                    .unwrap_err()
+                    .to_string(),
+                "password required"
+            );
+        }
+
+        assert_eq!(PasswordPolicy::Required.check(Some("x")).unwrap(), None);
+    }
+
+    #[test]
+    fn difficulty_hint_required() {
+        assert_eq!(
+            PasswordPolicy::DifficultyHint
+                .check(Some("x"))
+                .unwrap_err()
+                .to_string(),
+            "password must include a difficulty hint like `d=1024`"
+        );
+
+        assert!(
+            PasswordPolicy::DifficultyHint
+                .check(Some("d=nope"))
+                .unwrap_err()
+                .to_string()
+                .starts_with("invalid difficulty hint `d=nope`")
+        );
+
+        assert_eq!(
+            PasswordPolicy::DifficultyHint.check(Some("d=0.5")).unwrap(),
+            Some(Difficulty::from(0.5))
+        );
+    }
+}
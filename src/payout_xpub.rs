@@ -0,0 +1,109 @@
+use {
+    super::*,
+    bitcoin::{
+        CompressedPublicKey, NetworkKind,
+        bip32::{ChildNumber, Xpub},
+    },
+};
+
+/// Longest account id accepted in place of an address.
+const MAX_ACCOUNT_LENGTH: usize = 64;
+
+/// Derives a distinct payout address for each account from an extended
+/// public key, so miners can authorize with an account id instead of an
+/// address. Addresses are P2WPKH at `m/0/<index>` below the xpub.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PayoutXpub {
+    xpub: Xpub,
+    network: Network,
+}
+
+impl PayoutXpub {
+    pub(crate) fn new(xpub: Xpub, chain: Chain) -> Result<Self> {
+        let network = chain.network();
+
+        ensure!(
+            xpub.network == NetworkKind::from(network),
+            "payout xpub is for {} networks, not {chain}",
+            if xpub.network.is_mainnet() {
+                "main"
+            } else {
+                "test"
+            }
+        );
+
+        Ok(Self { xpub, network })
+    }
+
+    pub(crate) fn derive(&self, index: u32) -> Result<Address> {
+        let child = self.xpub.derive_pub(
+            &Secp256k1::verification_only(),
+            &[
+                ChildNumber::from_normal_idx(0)?,
+                ChildNumber::from_normal_idx(index)?,
+            ],
+        )?;
+
+        Ok(Address::p2wpkh(
+            &CompressedPublicKey(child.public_key),
+            self.network,
+        ))
+    }
+
+    /// Whether `account` is usable as an account id: ASCII letters, digits,
+    /// `-` and `_`, at most 64 characters.
+    pub(crate) fn is_account(account: &str) -> bool {
+        !account.is_empty()
+            && account.len() <= MAX_ACCOUNT_LENGTH
+            && account
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_xpub(network: NetworkKind) -> Xpub {
+    Xpub::from_priv(
+        &Secp256k1::new(),
+        &bitcoin::bip32::Xpriv::new_master(network, &[7; 32]).unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_distinct_addresses() {
+        let payout = PayoutXpub::new(test_xpub(NetworkKind::Test), Chain::Regtest).unwrap();
+
+        let first = payout.derive(0).unwrap();
+        let second = payout.derive(1).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(first, payout.derive(0).unwrap());
+        assert!(first.to_string().starts_with("bcrt1q"));
+    }
+
+    #[test]
+    fn rejects_xpub_for_other_chain() {
+        assert_eq!(
+            PayoutXpub::new(test_xpub(NetworkKind::Main), Chain::Signet)
+                .unwrap_err()
+                .to_string(),
+            "payout xpub is for main networks, not signet"
+        );
+
+        assert!(PayoutXpub::new(test_xpub(NetworkKind::Test), Chain::Mainnet).is_err());
+        assert!(PayoutXpub::new(test_xpub(NetworkKind::Main), Chain::Mainnet).is_ok());
+    }
+
+    #[test]
+    fn account_ids() {
+        assert!(PayoutXpub::is_account("alice"));
+        assert!(PayoutXpub::is_account("acct-42_b"));
+        assert!(!PayoutXpub::is_account(""));
+        assert!(!PayoutXpub::is_account("alice bob"));
+        assert!(!PayoutXpub::is_account(&"a".repeat(65)));
+    }
+}
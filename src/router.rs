@@ -720,6 +720,7 @@ impl Router {
                     event_tx,
                     start_diff,
                     Some(order.clone()),
+                    None,
                 );
 
                 if let Err(err) = stratifier.serve().await {
@@ -859,8 +860,15 @@ mod tests {
                     start_diff: Difficulty::default(),
                     min_diff: None,
                     max_diff: None,
+                    difficulty_transition: DifficultyTransition::default(),
                     vardiff_period: 3.33,
+                    vardiff_target_spm: None,
                     vardiff_window: 300.0,
+                    max_batch_size: 16,
+                    json_style: JsonStyle::default(),
+                    announce_difficulty: false,
+                    legacy_version_mask: false,
+                    explorer_url: None,
                     acme_domain: Vec::new(),
                     acme_contact: Vec::new(),
                     acme_cache: PathBuf::from("acme-cache"),
@@ -899,8 +907,15 @@ mod tests {
                     start_diff: Difficulty::default(),
                     min_diff: None,
                     max_diff: None,
+                    difficulty_transition: DifficultyTransition::default(),
                     vardiff_period: 3.33,
+                    vardiff_target_spm: None,
                     vardiff_window: 300.0,
+                    max_batch_size: 16,
+                    json_style: JsonStyle::default(),
+                    announce_difficulty: false,
+                    legacy_version_mask: false,
+                    explorer_url: None,
                     acme_domain: Vec::new(),
                     acme_contact: Vec::new(),
                     acme_cache: PathBuf::from("acme-cache"),
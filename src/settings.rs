@@ -1,4 +1,4 @@
-use super::*;
+use {super::*, api::MaintenanceMode};
 
 mod bitcoin_options;
 mod common_options;
@@ -29,20 +29,47 @@ pub(crate) struct Settings {
     acme_cache: PathBuf,
     data_dir: Option<PathBuf>,
     update_interval: Duration,
+    max_update_interval: Duration,
+    fee_refresh_threshold: Option<f64>,
     version_mask: Version,
     start_diff: Difficulty,
     min_diff: Option<Difficulty>,
     max_diff: Option<Difficulty>,
+    difficulty_transition: DifficultyTransition,
     vardiff_period: Duration,
     vardiff_window: Duration,
+    max_batch_size: usize,
+    json_style: JsonStyle,
+    announce_difficulty: bool,
+    legacy_version_mask: bool,
+    explorer_url: Option<ExplorerUrl>,
     zmq_block_notifications: Endpoint,
     enonce1_size: usize,
     enonce2_size: usize,
+    instance_id: Option<u8>,
     enonce1_extension_size: usize,
     bitcoind_timeout: Duration,
+    stale_template_threshold: Duration,
+    alerts_ntfy_channel: Option<String>,
+    coinbase_value_tolerance: Amount,
+    share_tolerance: f64,
+    near_miss_factor: Option<f64>,
+    share_log_policy: ShareLogPolicy,
+    maintenance: Option<MaintenanceMode>,
+    worker_offline_grace: Duration,
     disable_bouncer: bool,
     database_url: Option<String>,
     events_file: Option<PathBuf>,
+    address_allowlist: Option<PathBuf>,
+    payout_xpub: Option<PayoutXpub>,
+    max_payout_accounts: u32,
+    worker_name_policy: WorkerNamePolicy,
+    password_policy: PasswordPolicy,
+    shutdown_snapshot: Option<PathBuf>,
+    resume_window: Option<Duration>,
+    max_clock_skew: Duration,
+    coinbase_addresses: Arc<CoinbaseAddresses>,
+    gbt_rules: Vec<String>,
     high_diff_port: Option<u16>,
     tick_interval: Duration,
     descriptor: Option<String>,
@@ -61,7 +88,7 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             address: "0.0.0.0".into(),
-            port: 42069,
+            port: DEFAULT_STRATUM_PORT,
             http_port: None,
             upstream_targets: Vec::new(),
             timeout: Duration::from_secs(30),
@@ -76,20 +103,47 @@ impl Default for Settings {
             acme_cache: PathBuf::from("acme-cache"),
             data_dir: None,
             update_interval: Duration::from_secs(10),
+            max_update_interval: Duration::from_secs(10),
+            fee_refresh_threshold: None,
             version_mask: Version::default(),
             start_diff: Difficulty::default(),
             min_diff: None,
             max_diff: None,
+            difficulty_transition: DifficultyTransition::default(),
             vardiff_period: Duration::from_secs_f64(3.33),
             vardiff_window: Duration::from_secs(300),
+            max_batch_size: 16,
+            json_style: JsonStyle::default(),
+            announce_difficulty: false,
+            legacy_version_mask: false,
+            explorer_url: None,
             zmq_block_notifications: "tcp://127.0.0.1:28332".parse().unwrap(),
             enonce1_size: ENONCE1_SIZE,
             enonce2_size: MAX_ENONCE_SIZE,
+            instance_id: None,
             enonce1_extension_size: ENONCE1_EXTENSION_SIZE,
             bitcoind_timeout: Duration::from_secs(60),
+            stale_template_threshold: Duration::from_secs(120),
+            alerts_ntfy_channel: None,
+            coinbase_value_tolerance: Amount::ZERO,
+            share_tolerance: 0.0,
+            near_miss_factor: None,
+            share_log_policy: ShareLogPolicy::default(),
+            maintenance: None,
+            worker_offline_grace: Duration::from_secs(60),
             disable_bouncer: false,
             database_url: None,
             events_file: None,
+            address_allowlist: None,
+            payout_xpub: None,
+            max_payout_accounts: 100_000,
+            worker_name_policy: WorkerNamePolicy::default(),
+            password_policy: PasswordPolicy::default(),
+            shutdown_snapshot: None,
+            resume_window: None,
+            max_clock_skew: Duration::from_secs(7200),
+            coinbase_addresses: Arc::new(CoinbaseAddresses::fixed(Vec::new(), Network::Bitcoin)),
+            gbt_rules: Vec::new(),
             high_diff_port: None,
             tick_interval: Duration::from_secs(60),
             descriptor: None,
@@ -107,6 +161,20 @@ impl Default for Settings {
 }
 
 impl Settings {
+    /// Settings for `para pool` run with `args`, for tests.
+    #[cfg(test)]
+    pub(crate) fn from_pool_args(args: &str) -> Self {
+        match crate::arguments::Arguments::try_parse_from(args.split_whitespace())
+            .unwrap()
+            .subcommand
+        {
+            crate::subcommand::Subcommand::Pool(pool) => {
+                Self::from_pool_options(pool.options).unwrap()
+            }
+            subcommand => panic!("unexpected subcommand: {subcommand:?}"),
+        }
+    }
+
     pub(crate) fn from_bitcoin_options(options: BitcoinOptions) -> Result<Self> {
         let settings = Self::from_bitcoin_options_unvalidated(options);
 
@@ -144,8 +212,15 @@ impl Settings {
             start_diff,
             min_diff,
             max_diff,
+            difficulty_transition,
             vardiff_period,
+            vardiff_target_spm,
             vardiff_window,
+            max_batch_size,
+            json_style,
+            announce_difficulty,
+            legacy_version_mask,
+            explorer_url,
             acme_domain,
             acme_contact,
             acme_cache,
@@ -167,8 +242,21 @@ impl Settings {
             start_diff,
             min_diff,
             max_diff,
-            vardiff_period: Self::duration_from_secs_f64(vardiff_period, "vardiff_period")?,
+            difficulty_transition,
+            vardiff_period: match vardiff_target_spm {
+                Some(spm) => {
+                    ensure!(spm.is_finite(), "vardiff_target_spm must be finite");
+                    ensure!(spm > 0.0, "vardiff_target_spm must be greater than 0");
+                    Self::duration_from_secs_f64(60.0 / spm, "vardiff_target_spm")?
+                }
+                None => Self::duration_from_secs_f64(vardiff_period, "vardiff_period")?,
+            },
             vardiff_window: Self::duration_from_secs_f64(vardiff_window, "vardiff_window")?,
+            max_batch_size,
+            json_style,
+            announce_difficulty,
+            legacy_version_mask,
+            explorer_url,
             http_api_token,
             http_admin_token,
             ..Self::from_bitcoin_options_unvalidated(bitcoin)
@@ -210,28 +298,105 @@ impl Settings {
             common,
             high_diff_port,
             update_interval,
+            max_update_interval,
+            fee_refresh_threshold,
             version_mask,
             zmq_block_notifications,
             enonce1_size,
             enonce2_size,
+            instance_id,
             bitcoind_timeout,
+            stale_template_threshold,
+            alerts_ntfy_channel,
+            coinbase_value_tolerance,
+            share_tolerance,
+            near_miss_factor,
+            share_log_min_diff,
+            share_log_sample,
+            maintenance,
+            worker_offline_grace,
             disable_bouncer,
             database_url,
             events_file,
+            address_allowlist,
+            payout_xpub,
+            max_payout_accounts,
+            worker_name_max_length,
+            worker_name_charset,
+            password_policy,
+            coinbase_address,
+            coinbase_address_file,
+            gbt_rule,
+            shutdown_snapshot,
+            resume_window,
+            max_clock_skew,
         } = options;
 
+        let common = Self::from_common_options(common)?;
+
+        let coinbase_addresses = coinbase_address
+            .into_iter()
+            .map(|address| {
+                let unchecked = address.clone().assume_checked();
+                address
+                    .require_network(common.chain.network())
+                    .map_err(|_| {
+                        anyhow!(
+                            "coinbase address `{unchecked}` is not valid for {}",
+                            common.chain
+                        )
+                    })
+            })
+            .collect::<Result<Vec<Address>>>()?;
+
+        let coinbase_addresses = Arc::new(match coinbase_address_file {
+            Some(path) => CoinbaseAddresses::load(path, common.chain.network())?,
+            None => CoinbaseAddresses::fixed(coinbase_addresses, common.chain.network()),
+        });
+
         let settings = Self {
             high_diff_port,
             update_interval: Duration::from_secs(update_interval),
+            max_update_interval: Duration::from_secs(
+                max_update_interval.unwrap_or(update_interval),
+            ),
+            fee_refresh_threshold,
             version_mask,
             zmq_block_notifications,
             enonce1_size,
             enonce2_size,
+            instance_id,
             bitcoind_timeout: Duration::from_secs(bitcoind_timeout),
+            stale_template_threshold: Duration::from_secs(stale_template_threshold),
+            alerts_ntfy_channel,
+            coinbase_value_tolerance: Amount::from_sat(coinbase_value_tolerance),
+            share_tolerance,
+            near_miss_factor,
+            share_log_policy: ShareLogPolicy::new(share_log_min_diff, share_log_sample),
+            maintenance,
+            worker_offline_grace: Duration::from_secs(worker_offline_grace),
             disable_bouncer,
             database_url,
             events_file,
-            ..Self::from_common_options(common)?
+            address_allowlist,
+            payout_xpub: payout_xpub
+                .map(|xpub| {
+                    PayoutXpub::new(
+                        xpub.parse()
+                            .with_context(|| format!("invalid payout xpub `{xpub}`"))?,
+                        common.chain,
+                    )
+                })
+                .transpose()?,
+            max_payout_accounts,
+            worker_name_policy: WorkerNamePolicy::new(worker_name_max_length, worker_name_charset),
+            password_policy,
+            coinbase_addresses,
+            gbt_rules: gbt_rule,
+            shutdown_snapshot,
+            resume_window: resume_window.map(Duration::from_secs),
+            max_clock_skew: Duration::from_secs(max_clock_skew),
+            ..common
         };
 
         settings.validate()?;
@@ -428,6 +593,12 @@ impl Settings {
             !self.update_interval.is_zero(),
             "update_interval must be greater than 0"
         );
+        ensure!(
+            self.max_update_interval >= self.update_interval,
+            "max_update_interval ({}s) must be >= update_interval ({}s)",
+            self.max_update_interval.as_secs(),
+            self.update_interval.as_secs()
+        );
         ensure!(
             !self.vardiff_period.is_zero(),
             "vardiff_period must be greater than 0"
@@ -440,14 +611,57 @@ impl Settings {
             !self.bitcoind_timeout.is_zero(),
             "bitcoind_timeout must be greater than 0"
         );
+        ensure!(
+            !self.stale_template_threshold.is_zero(),
+            "stale_template_threshold must be greater than 0"
+        );
+        ensure!(
+            self.update_interval < self.stale_template_threshold,
+            "update_interval ({}s) must be < stale_template_threshold ({}s)",
+            self.update_interval.as_secs(),
+            self.stale_template_threshold.as_secs()
+        );
+        ensure!(
+            self.max_update_interval < self.stale_template_threshold,
+            "max_update_interval ({}s) must be < stale_template_threshold ({}s), or every quiet stretch alerts as stale",
+            self.max_update_interval.as_secs(),
+            self.stale_template_threshold.as_secs()
+        );
         ensure!(
             !self.tick_interval.is_zero(),
             "tick_interval must be greater than 0"
         );
+        ensure!(
+            self.resume_window
+                .is_none_or(|window| !window.is_zero() && window <= SESSION_TTL),
+            "resume window must be between 1 and {} seconds, as sessions are only held that long",
+            SESSION_TTL.as_secs()
+        );
+        ensure!(
+            !self.max_clock_skew.is_zero(),
+            "max_clock_skew must be greater than 0"
+        );
         ensure!(
             self.http_api_token.is_none() || self.http_admin_token.is_some(),
             "--http-admin-token is required when --http-api-token is set"
         );
+        ensure!(
+            self.share_log_policy == ShareLogPolicy::default() || self.database_url.is_none(),
+            "--share-log-min-diff and --share-log-sample cannot be used with --database-url, \
+             as payouts are credited from the shares it records"
+        );
+
+        ensure!(
+            self.version_mask.is_within_bip320(),
+            "version_mask ({}) must only set bits within the BIP320 mask ({})",
+            self.version_mask,
+            Version::BIP320_MASK
+        );
+
+        ensure!(
+            self.start_diff.as_f64() > 0.0,
+            "start_diff must be greater than 0"
+        );
 
         if let Some(min) = self.min_diff {
             ensure!(
@@ -556,6 +770,25 @@ impl Settings {
         Ok(())
     }
 
+    /// A copy that is safe to print, with credentials, tokens and anything
+    /// that may hold a private key replaced.
+    pub(crate) fn redacted(&self) -> Self {
+        fn redact(value: &Option<String>) -> Option<String> {
+            value.as_ref().map(|_| "<redacted>".into())
+        }
+
+        Self {
+            bitcoin_rpc_password: redact(&self.bitcoin_rpc_password),
+            database_url: redact(&self.database_url),
+            alerts_ntfy_channel: redact(&self.alerts_ntfy_channel),
+            descriptor: redact(&self.descriptor),
+            change_descriptor: redact(&self.change_descriptor),
+            http_api_token: redact(&self.http_api_token),
+            http_admin_token: redact(&self.http_admin_token),
+            ..self.clone()
+        }
+    }
+
     fn validate_bitcoin_rpc_credentials(&self) -> Result<()> {
         match (&self.bitcoin_rpc_username, &self.bitcoin_rpc_password) {
             (None, Some(_)) => bail!("no bitcoin RPC username specified"),
@@ -602,10 +835,48 @@ impl Settings {
         self.update_interval
     }
 
+    pub(crate) fn max_update_interval(&self) -> Duration {
+        self.max_update_interval
+    }
+
+    pub(crate) fn fee_refresh_threshold(&self) -> Option<f64> {
+        self.fee_refresh_threshold
+    }
+
     pub(crate) fn bitcoind_timeout(&self) -> Duration {
         self.bitcoind_timeout
     }
 
+    pub(crate) fn stale_template_threshold(&self) -> Duration {
+        self.stale_template_threshold
+    }
+
+    pub(crate) fn alerts_ntfy_channel(&self) -> Option<&str> {
+        self.alerts_ntfy_channel.as_deref()
+    }
+
+    pub(crate) fn coinbase_value_tolerance(&self) -> Amount {
+        self.coinbase_value_tolerance
+    }
+
+    /// Fraction of the pool difficulty a share may fall short by and still
+    /// be accepted. Zero, the default, only accepts shares meeting it.
+    pub(crate) fn share_tolerance(&self) -> f64 {
+        self.share_tolerance
+    }
+
+    pub(crate) fn near_miss_factor(&self) -> Option<f64> {
+        self.near_miss_factor
+    }
+
+    pub(crate) fn maintenance(&self) -> Option<MaintenanceMode> {
+        self.maintenance
+    }
+
+    pub(crate) fn worker_offline_grace(&self) -> Duration {
+        self.worker_offline_grace
+    }
+
     pub(crate) fn version_mask(&self) -> Version {
         self.version_mask
     }
@@ -622,6 +893,10 @@ impl Settings {
         self.max_diff
     }
 
+    pub(crate) fn difficulty_transition(&self) -> DifficultyTransition {
+        self.difficulty_transition
+    }
+
     pub(crate) fn vardiff_period(&self) -> Duration {
         self.vardiff_period
     }
@@ -630,6 +905,30 @@ impl Settings {
         self.vardiff_window
     }
 
+    pub(crate) fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+
+    pub(crate) fn json_style(&self) -> JsonStyle {
+        self.json_style
+    }
+
+    pub(crate) fn announce_difficulty(&self) -> bool {
+        self.announce_difficulty
+    }
+
+    pub(crate) fn legacy_version_mask(&self) -> bool {
+        self.legacy_version_mask
+    }
+
+    /// Where found blocks link to, falling back to the chain's public
+    /// explorer.
+    pub(crate) fn explorer_url(&self) -> Option<ExplorerUrl> {
+        self.explorer_url
+            .clone()
+            .or_else(|| ExplorerUrl::default_for(self.chain))
+    }
+
     pub(crate) fn zmq_block_notifications(&self) -> &Endpoint {
         &self.zmq_block_notifications
     }
@@ -638,6 +937,10 @@ impl Settings {
         self.enonce1_size
     }
 
+    pub(crate) fn instance_id(&self) -> Option<u8> {
+        self.instance_id
+    }
+
     pub(crate) fn enonce2_size(&self) -> usize {
         self.enonce2_size
     }
@@ -658,6 +961,59 @@ impl Settings {
         self.events_file.clone()
     }
 
+    /// The address the coinbase of a block at `height` pays when a rotation
+    /// is configured.
+    pub(crate) fn coinbase_address(&self, height: i32) -> Option<Address> {
+        self.coinbase_addresses.get(height)
+    }
+
+    pub(crate) fn coinbase_addresses(&self) -> &Arc<CoinbaseAddresses> {
+        &self.coinbase_addresses
+    }
+
+    pub(crate) fn gbt_rules(&self) -> &[String] {
+        &self.gbt_rules
+    }
+
+    pub(crate) fn address_allowlist(&self) -> Option<&Path> {
+        self.address_allowlist.as_deref()
+    }
+
+    pub(crate) fn payout_xpub(&self) -> Option<&PayoutXpub> {
+        self.payout_xpub.as_ref()
+    }
+
+    pub(crate) fn max_payout_accounts(&self) -> u32 {
+        self.max_payout_accounts
+    }
+
+    pub(crate) fn share_log_policy(&self) -> ShareLogPolicy {
+        self.share_log_policy
+    }
+
+    pub(crate) fn worker_name_policy(&self) -> &WorkerNamePolicy {
+        &self.worker_name_policy
+    }
+
+    pub(crate) fn password_policy(&self) -> PasswordPolicy {
+        self.password_policy
+    }
+
+    pub(crate) fn shutdown_snapshot(&self) -> Option<&Path> {
+        self.shutdown_snapshot.as_deref()
+    }
+
+    /// How long after a disconnect a miner may resume its session with
+    /// `mining.resume`, or `None` if resume tokens are not handed out.
+    pub(crate) fn resume_window(&self) -> Option<Duration> {
+        self.resume_window
+    }
+
+    /// How far a share's ntime may stray from the pool's clock.
+    pub(crate) fn max_clock_skew(&self) -> Duration {
+        self.max_clock_skew
+    }
+
     pub(crate) fn high_diff_port(&self) -> Option<u16> {
         self.high_diff_port
     }
@@ -831,6 +1187,238 @@ mod tests {
         assert_eq!(settings.enonce2_size, MAX_ENONCE_SIZE);
     }
 
+    #[test]
+    fn pool_worker_name_policy() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.worker_name_policy(), &WorkerNamePolicy::default());
+
+        let options =
+            parse_pool_options("para pool --worker-name-max-length 32 --worker-name-charset -_");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(
+            settings.worker_name_policy(),
+            &WorkerNamePolicy::new(Some(32), Some("-_".into()))
+        );
+    }
+
+    #[test]
+    fn pool_address_allowlist() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.address_allowlist(), None);
+
+        let options = parse_pool_options("para pool --address-allowlist /etc/para/allowlist");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(
+            settings.address_allowlist(),
+            Some(Path::new("/etc/para/allowlist"))
+        );
+    }
+
+    #[test]
+    fn pool_payout_xpub() {
+        use bitcoin::NetworkKind;
+
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.payout_xpub(), None);
+
+        let xpub = crate::payout_xpub::test_xpub(NetworkKind::Test);
+
+        let options = parse_pool_options(&format!("para pool --chain signet --payout-xpub {xpub}"));
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(
+            settings.payout_xpub(),
+            Some(&PayoutXpub::new(xpub, Chain::Signet).unwrap())
+        );
+
+        let options =
+            parse_pool_options(&format!("para pool --chain mainnet --payout-xpub {xpub}"));
+        assert!(Settings::from_pool_options(options).is_err());
+    }
+
+    #[test]
+    fn pool_max_payout_accounts() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.max_payout_accounts(), 100_000);
+
+        let xpub = crate::payout_xpub::test_xpub(bitcoin::NetworkKind::Test);
+
+        let settings = Settings::from_pool_options(parse_pool_options(&format!(
+            "para pool --chain signet --payout-xpub {xpub} --max-payout-accounts 5"
+        )))
+        .unwrap();
+        assert_eq!(settings.max_payout_accounts(), 5);
+
+        assert!(
+            Arguments::try_parse_from("para pool --max-payout-accounts 5".split_whitespace())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn pool_shutdown_snapshot() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.shutdown_snapshot(), None);
+
+        let options = parse_pool_options("para pool --shutdown-snapshot /var/lib/para/final.json");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(
+            settings.shutdown_snapshot(),
+            Some(Path::new("/var/lib/para/final.json"))
+        );
+    }
+
+    #[test]
+    fn pool_explorer_url() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(
+            settings.explorer_url(),
+            ExplorerUrl::default_for(Chain::Mainnet)
+        );
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --chain regtest")).unwrap();
+        assert_eq!(settings.explorer_url(), None);
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --chain regtest --explorer-url http://localhost/{height}",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.explorer_url().unwrap().block(7, "00"),
+            "http://localhost/7"
+        );
+    }
+
+    #[test]
+    fn pool_share_log_policy() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.share_log_policy(), ShareLogPolicy::default());
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --share-log-min-diff 1000 --share-log-sample 100",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.share_log_policy(),
+            ShareLogPolicy::new(Some(Difficulty::from(1000)), NonZeroU32::new(100))
+        );
+
+        assert!(
+            Arguments::try_parse_from("para pool --share-log-sample 0".split_whitespace()).is_err()
+        );
+
+        assert_eq!(
+            Settings::from_pool_options(parse_pool_options(
+                "para pool --share-log-sample 100 --database-url postgres://localhost/para",
+            ))
+            .unwrap_err()
+            .to_string(),
+            "--share-log-min-diff and --share-log-sample cannot be used with --database-url, \
+             as payouts are credited from the shares it records"
+        );
+    }
+
+    #[test]
+    fn pool_password_policy() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.password_policy(), PasswordPolicy::Optional);
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --password-policy difficulty-hint",
+        ))
+        .unwrap();
+        assert_eq!(settings.password_policy(), PasswordPolicy::DifficultyHint);
+    }
+
+    #[test]
+    fn pool_max_clock_skew() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.max_clock_skew(), Duration::from_secs(7200));
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --max-clock-skew 600"))
+                .unwrap();
+        assert_eq!(settings.max_clock_skew(), Duration::from_secs(600));
+
+        assert_error_contains(
+            pool_settings_error("para pool --max-clock-skew 0"),
+            "max_clock_skew must be greater than 0",
+        );
+    }
+
+    #[test]
+    fn pool_resume_window() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.resume_window(), None);
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --resume-window 30"))
+                .unwrap();
+        assert_eq!(settings.resume_window(), Some(Duration::from_secs(30)));
+
+        assert_error_contains(
+            pool_settings_error("para pool --resume-window 601"),
+            "resume window must be between 1 and 600 seconds",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --resume-window 0"),
+            "resume window must be between 1 and 600 seconds",
+        );
+    }
+
+    #[test]
+    fn pool_difficulty_transition() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(
+            settings.difficulty_transition(),
+            DifficultyTransition::Lenient
+        );
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --difficulty-transition strict",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.difficulty_transition(),
+            DifficultyTransition::Strict
+        );
+    }
+
+    #[test]
+    fn pool_coinbase_address_rotates_by_height() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.coinbase_address(100), None);
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --chain signet \
+                --coinbase-address tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc \
+                --coinbase-address tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+        ))
+        .unwrap();
+
+        let first = settings.coinbase_address(100).unwrap().to_string();
+        let second = settings.coinbase_address(101).unwrap().to_string();
+
+        assert_eq!(first, "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc");
+        assert_eq!(second, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx");
+        assert_eq!(settings.coinbase_address(102).unwrap().to_string(), first);
+    }
+
+    #[test]
+    fn pool_coinbase_address_wrong_chain_fails() {
+        assert_error_contains(
+            pool_settings_error(
+                "para pool --chain signet --coinbase-address bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            ),
+            "coinbase address `bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq` is not valid for signet",
+        );
+    }
+
     #[test]
     fn pool_override_address_and_port() {
         let options = parse_pool_options("para pool --address 127.0.0.1 --port 9999");
@@ -861,15 +1449,47 @@ mod tests {
 
     #[test]
     fn pool_override_version_mask() {
-        let options = parse_pool_options("para pool --version-mask 00fff000");
+        let options = parse_pool_options("para pool --version-mask 00ffe000");
         let settings = Settings::from_pool_options(options).unwrap();
 
         assert_eq!(
             settings.version_mask,
-            Version::from_str("00fff000").unwrap()
+            Version::from_str("00ffe000").unwrap()
+        );
+    }
+
+    #[test]
+    fn pool_version_mask_outside_bip320_fails() {
+        assert_error_contains(
+            pool_settings_error("para pool --version-mask 20000000"),
+            "version_mask (20000000) must only set bits within the BIP320 mask (1fffe000)",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --version-mask 1fffe001"),
+            "version_mask (1fffe001) must only set bits within the BIP320 mask",
         );
     }
 
+    #[test]
+    fn pool_start_diff_not_numeric_fails() {
+        let err = Arguments::try_parse_from("para pool --start-diff nope".split_whitespace())
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            err.contains("invalid value 'nope' for '--start-diff <START_DIFF>'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn pool_start_diff_si_prefixed() {
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --start-diff 2K")).unwrap();
+        assert_eq!(settings.start_diff, Difficulty::from(2000));
+    }
+
     #[test]
     fn pool_credentials_userpass_when_both_provided() {
         let options = parse_pool_options(
@@ -1048,6 +1668,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pool_vardiff_target_spm() {
+        let options = parse_pool_options("para pool --vardiff-target-spm 6");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.vardiff_period, Duration::from_secs(10));
+
+        let options = parse_pool_options("para pool --vardiff-target-spm 120");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.vardiff_period, Duration::from_millis(500));
+
+        assert_error_contains(
+            pool_settings_error("para pool --vardiff-target-spm 0"),
+            "vardiff_target_spm must be greater than 0",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --vardiff-target-spm inf"),
+            "vardiff_target_spm must be finite",
+        );
+
+        assert!(
+            Arguments::try_parse_from(
+                "para pool --vardiff-target-spm 6 --vardiff-period 10".split_whitespace()
+            )
+            .is_err()
+        );
+    }
+
     #[test]
     fn pool_vardiff_window() {
         let options = parse_pool_options("para pool --vardiff-window 60");
@@ -1063,6 +1711,54 @@ mod tests {
         assert_eq!(settings.vardiff_window, Duration::from_secs(300));
     }
 
+    #[test]
+    fn pool_max_batch_size() {
+        let options = parse_pool_options("para pool --max-batch-size 4");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.max_batch_size(), 4);
+
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.max_batch_size(), 16);
+    }
+
+    #[test]
+    fn pool_json_style() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.json_style(), JsonStyle::Compact);
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --json-style spaced"))
+                .unwrap();
+        assert_eq!(settings.json_style(), JsonStyle::Spaced);
+
+        assert!(
+            Arguments::try_parse_from("para pool --json-style pretty".split_whitespace()).is_err()
+        );
+    }
+
+    #[test]
+    fn pool_announce_difficulty() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert!(!settings.announce_difficulty());
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --announce-difficulty"))
+                .unwrap();
+        assert!(settings.announce_difficulty());
+    }
+
+    #[test]
+    fn pool_legacy_version_mask() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert!(!settings.legacy_version_mask());
+
+        let settings =
+            Settings::from_pool_options(parse_pool_options("para pool --legacy-version-mask"))
+                .unwrap();
+        assert!(settings.legacy_version_mask());
+    }
+
     #[test]
     fn pool_vardiff_window_rejects_invalid_duration() {
         #[track_caller]
@@ -1088,6 +1784,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn max_update_interval() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.max_update_interval(), Duration::from_secs(10));
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --update-interval 5 --max-update-interval 60",
+        ))
+        .unwrap();
+        assert_eq!(settings.update_interval(), Duration::from_secs(5));
+        assert_eq!(settings.max_update_interval(), Duration::from_secs(60));
+
+        assert_error_contains(
+            pool_settings_error("para pool --update-interval 30 --max-update-interval 10"),
+            "max_update_interval (10s) must be >= update_interval (30s)",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --update-interval 120"),
+            "update_interval (120s) must be < stale_template_threshold (120s)",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --max-update-interval 120"),
+            "max_update_interval (120s) must be < stale_template_threshold (120s)",
+        );
+
+        assert_error_contains(
+            pool_settings_error("para pool --max-update-interval 90 --stale-template-threshold 60"),
+            "max_update_interval (90s) must be < stale_template_threshold (60s)",
+        );
+    }
+
+    #[test]
+    fn pool_fee_refresh_threshold() {
+        let settings = Settings::from_pool_options(parse_pool_options("para pool")).unwrap();
+        assert_eq!(settings.fee_refresh_threshold(), None);
+
+        let settings = Settings::from_pool_options(parse_pool_options(
+            "para pool --fee-refresh-threshold 0.5",
+        ))
+        .unwrap();
+        assert_eq!(settings.fee_refresh_threshold(), Some(0.5));
+
+        assert!(
+            Arguments::try_parse_from("para pool --fee-refresh-threshold 0".split_whitespace())
+                .is_err()
+        );
+    }
+
     #[test]
     fn duration_zero_fails() {
         #[track_caller]
@@ -1103,6 +1849,10 @@ mod tests {
             pool_settings_error("para pool --bitcoind-timeout 0"),
             "bitcoind_timeout must be greater than 0",
         );
+        case(
+            pool_settings_error("para pool --stale-template-threshold 0"),
+            "stale_template_threshold must be greater than 0",
+        );
         case(
             proxy_settings_error("--timeout 0"),
             "timeout must be greater than 0",
@@ -1149,6 +1899,82 @@ mod tests {
         assert!(err.to_string().contains("enonce1_size (1) must be >="));
     }
 
+    #[test]
+    fn pool_share_tolerance() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.share_tolerance(), 0.0);
+
+        let options = parse_pool_options("para pool --share-tolerance 0.001");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.share_tolerance(), 0.001);
+
+        let options = parse_pool_options("para pool --share-tolerance 0.1");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.share_tolerance(), 0.1);
+
+        assert!(
+            Arguments::try_parse_from("para pool --share-tolerance 0.11".split_whitespace())
+                .is_err()
+        );
+        assert!(
+            Arguments::try_parse_from("para pool --share-tolerance 1".split_whitespace()).is_err()
+        );
+        assert!(
+            Arguments::try_parse_from("para pool --share-tolerance -0.1".split_whitespace())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn pool_near_miss_factor() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.near_miss_factor(), None);
+
+        let options = parse_pool_options("para pool --near-miss-factor 10");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.near_miss_factor(), Some(10.0));
+
+        assert!(
+            Arguments::try_parse_from("para pool --near-miss-factor 0.5".split_whitespace())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn pool_worker_offline_grace() {
+        let options = parse_pool_options("para pool --worker-offline-grace 300");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.worker_offline_grace(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn pool_maintenance() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.maintenance(), None);
+
+        let options = parse_pool_options("para pool --maintenance discard");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.maintenance(), Some(MaintenanceMode::Discard));
+    }
+
+    #[test]
+    fn pool_instance_id() {
+        let options = parse_pool_options("para pool");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.instance_id(), None);
+
+        let options = parse_pool_options("para pool --instance-id 255");
+        let settings = Settings::from_pool_options(options).unwrap();
+        assert_eq!(settings.instance_id(), Some(255));
+
+        assert!(
+            Arguments::try_parse_from("para pool --instance-id 256".split_whitespace()).is_err()
+        );
+    }
+
     #[test]
     fn pool_enonce1_size_too_large() {
         let options = parse_pool_options("para pool --enonce1-size 9");
@@ -1503,6 +2329,8 @@ mod tests {
             assert_eq!(pool.start_diff, settings.start_diff);
             assert_eq!(pool.vardiff_period, settings.vardiff_period);
             assert_eq!(pool.vardiff_window, settings.vardiff_window);
+            assert_eq!(pool.max_batch_size, settings.max_batch_size);
+            assert_eq!(pool.json_style, settings.json_style);
             assert_eq!(pool.acme_cache, settings.acme_cache);
             assert_eq!(pool.chain, settings.chain);
             assert_eq!(pool.bitcoin_rpc_port, settings.bitcoin_rpc_port);
@@ -1533,6 +2361,11 @@ mod tests {
             settings_default.vardiff_window,
             pool_settings.vardiff_window
         );
+        assert_eq!(
+            settings_default.max_batch_size,
+            pool_settings.max_batch_size
+        );
+        assert_eq!(settings_default.json_style, pool_settings.json_style);
         assert_eq!(settings_default.version_mask, pool_settings.version_mask);
         assert_eq!(
             settings_default.zmq_block_notifications,
@@ -1548,6 +2381,34 @@ mod tests {
             settings_default.update_interval,
             pool_settings.update_interval
         );
+        assert_eq!(
+            settings_default.fee_refresh_threshold,
+            pool_settings.fee_refresh_threshold
+        );
+        assert_eq!(
+            settings_default.stale_template_threshold,
+            pool_settings.stale_template_threshold
+        );
+        assert_eq!(
+            settings_default.coinbase_value_tolerance,
+            pool_settings.coinbase_value_tolerance
+        );
+        assert_eq!(
+            settings_default.worker_offline_grace,
+            pool_settings.worker_offline_grace
+        );
+        assert_eq!(
+            settings_default.max_clock_skew,
+            pool_settings.max_clock_skew
+        );
+        assert_eq!(
+            settings_default.password_policy,
+            pool_settings.password_policy
+        );
+        assert_eq!(
+            settings_default.share_log_policy,
+            pool_settings.share_log_policy
+        );
         assert_eq!(settings_default.acme_cache, pool_settings.acme_cache);
         assert_eq!(settings_default.timeout, pool_settings.timeout);
     }
@@ -1634,6 +2495,24 @@ mod tests {
         assert_eq!(settings.http_admin_token(), Some("admin"));
     }
 
+    #[test]
+    fn redacted_hides_secrets() {
+        let settings = Settings::from_router_options(parse_router_options(
+            "para router --descriptor secret-descriptor --bitcoin-rpc-username user \
+                --bitcoin-rpc-password secret-password --http-api-token secret-api \
+                --http-admin-token secret-admin",
+        ))
+        .unwrap()
+        .redacted();
+
+        let printed = format!("{settings:?}");
+
+        assert!(!printed.contains("secret"), "{printed}");
+        assert_eq!(settings.http_admin_token(), Some("<redacted>"));
+        assert_eq!(settings.bitcoin_rpc_username, Some("user".into()));
+        assert_eq!(settings.http_api_token, Some("<redacted>".into()));
+    }
+
     #[test]
     fn router_override_address_and_port() {
         let options =
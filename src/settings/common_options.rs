@@ -11,7 +11,7 @@ pub(crate) struct CommonOptions {
 
     #[arg(
         long,
-        default_value_t = 42069,
+        default_value_t = DEFAULT_STRATUM_PORT,
         help = "Listen for stratum messages on port <PORT>."
     )]
     pub(crate) port: u16,
@@ -31,6 +31,14 @@ pub(crate) struct CommonOptions {
     #[arg(long, help = "Maximum difficulty for vardiff.")]
     pub(crate) max_diff: Option<Difficulty>,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Accept shares for jobs sent before the last difficulty change at the lower difficulty (lenient), or reject them (strict)."
+    )]
+    pub(crate) difficulty_transition: DifficultyTransition,
+
     #[arg(
         long,
         default_value_t = 3.33,
@@ -38,6 +46,13 @@ pub(crate) struct CommonOptions {
     )]
     pub(crate) vardiff_period: f64,
 
+    #[arg(
+        long,
+        conflicts_with = "vardiff_period",
+        help = "Target <VARDIFF_TARGET_SPM> share submissions per minute per worker instead of setting --vardiff-period."
+    )]
+    pub(crate) vardiff_target_spm: Option<f64>,
+
     #[arg(
         long,
         default_value_t = 300.0,
@@ -45,6 +60,39 @@ pub(crate) struct CommonOptions {
     )]
     pub(crate) vardiff_window: f64,
 
+    #[arg(
+        long,
+        default_value_t = 16,
+        help = "Accept JSON-RPC batches of up to <MAX_BATCH_SIZE> stratum messages. 0 rejects all batches."
+    )]
+    pub(crate) max_batch_size: usize,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Write stratum JSON without whitespace (compact) or with a space after every `,` and `:` (spaced), for miners picky about either. Messages always end in a single newline."
+    )]
+    pub(crate) json_style: JsonStyle,
+
+    #[arg(
+        long,
+        help = "Follow every mining.set_difficulty with a client.show_message stating the new difficulty, for firmware that only displays messages."
+    )]
+    pub(crate) announce_difficulty: bool,
+
+    #[arg(
+        long,
+        help = "Answer mining.configure with the pool's whole version mask rather than the BIP310 negotiated intersection with the mask the miner requested, for firmware written against ckpool's reply, such as stock Antminer S9 bmminer builds, that rolls whatever bits the pool allows instead of the bits it asked for."
+    )]
+    pub(crate) legacy_version_mask: bool,
+
+    #[arg(
+        long,
+        help = "Link found blocks to <EXPLORER_URL>, with {hash} and {height} replaced by the block's hash and height. Defaults to mempool.space, or no link on regtest."
+    )]
+    pub(crate) explorer_url: Option<ExplorerUrl>,
+
     #[arg(long, help = "Request ACME TLS certificate for <ACME_DOMAIN>.")]
     pub(crate) acme_domain: Vec<String>,
 
@@ -64,9 +112,15 @@ pub(crate) struct CommonOptions {
     #[arg(long, help = "Use <STORE_PATH> as database file.")]
     pub(crate) store_path: Option<PathBuf>,
 
-    #[arg(long, help = "Require <HTTP_API_TOKEN> for HTTP API access.")]
+    #[arg(
+        long,
+        help = "Require <HTTP_API_TOKEN>, as bearer token or basic auth password, for HTTP API access."
+    )]
     pub(crate) http_api_token: Option<String>,
 
-    #[arg(long, help = "Require <HTTP_ADMIN_TOKEN> for HTTP admin access.")]
+    #[arg(
+        long,
+        help = "Require <HTTP_ADMIN_TOKEN>, as bearer token or basic auth password, for HTTP admin access."
+    )]
     pub(crate) http_admin_token: Option<String>,
 }
@@ -18,10 +18,23 @@ pub(crate) struct PoolOptions {
     )]
     pub(crate) update_interval: u64,
 
+    #[arg(
+        long,
+        help = "Back off template updates up to every <MAX_UPDATE_INTERVAL> seconds while templates are unchanged."
+    )]
+    pub(crate) max_update_interval: Option<u64>,
+
+    #[arg(
+        long,
+        value_parser = validate_fee_refresh_threshold,
+        help = "Poll the mempool and refresh the block template early once new fees could raise the coinbase value by more than <FEE_REFRESH_THRESHOLD> percent."
+    )]
+    pub(crate) fee_refresh_threshold: Option<f64>,
+
     #[arg(
         long,
         default_value_t,
-        help = "Use version rolling with <VERSION_MASK>."
+        help = "Use version rolling with <VERSION_MASK>, which must lie within the BIP320 mask 1fffe000."
     )]
     pub(crate) version_mask: Version,
 
@@ -38,6 +51,12 @@ pub(crate) struct PoolOptions {
     #[arg(long, default_value_t = MAX_ENONCE_SIZE, help = "Set enonce2 size in bytes (2-8).")]
     pub(crate) enonce2_size: usize,
 
+    #[arg(
+        long,
+        help = "Prefix every enonce1 with <INSTANCE_ID>, so pool instances behind one load balancer hand out disjoint enonce1s."
+    )]
+    pub(crate) instance_id: Option<u8>,
+
     #[arg(
         long,
         default_value_t = 60,
@@ -45,6 +64,66 @@ pub(crate) struct PoolOptions {
     )]
     pub(crate) bitcoind_timeout: u64,
 
+    #[arg(
+        long,
+        default_value_t = 120,
+        help = "Alert when the block template has not been refreshed for <STALE_TEMPLATE_THRESHOLD> seconds."
+    )]
+    pub(crate) stale_template_threshold: u64,
+
+    #[arg(
+        long,
+        help = "The <CHANNEL> at ntfy.sh to use for stale template alerts."
+    )]
+    pub(crate) alerts_ntfy_channel: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Refuse to submit blocks whose coinbase value is off by more than <COINBASE_VALUE_TOLERANCE> sats."
+    )]
+    pub(crate) coinbase_value_tolerance: u64,
+
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        value_parser = validate_share_tolerance,
+        help = "Accept shares up to a fraction of <SHARE_TOLERANCE>, at most 0.1, below the pool difficulty, forgiving rounding differences in miner difficulty math at the cost of crediting slightly less work than was asked for."
+    )]
+    pub(crate) share_tolerance: f64,
+
+    #[arg(
+        long,
+        value_parser = validate_near_miss_factor,
+        help = "Log and record shares within a factor of <NEAR_MISS_FACTOR> of the network difficulty as near misses."
+    )]
+    pub(crate) near_miss_factor: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Only record accepted shares of at least <SHARE_LOG_MIN_DIFF> pool difficulty to the events file. Rejected shares and block solves are always recorded. Cannot be used with --database-url."
+    )]
+    pub(crate) share_log_min_diff: Option<Difficulty>,
+
+    #[arg(
+        long,
+        help = "Record one in <SHARE_LOG_SAMPLE> accepted shares below --share-log-min-diff, or of all accepted shares without it, instead of none. Cannot be used with --database-url."
+    )]
+    pub(crate) share_log_sample: Option<NonZeroU32>,
+
+    #[arg(
+        long,
+        help = "Start in maintenance mode, answering shares with <MAINTENANCE> without counting them."
+    )]
+    pub(crate) maintenance: Option<MaintenanceMode>,
+
+    #[arg(
+        long,
+        default_value_t = 60,
+        help = "Report workers as active until they have been silent for <WORKER_OFFLINE_GRACE> seconds, or longer for workers whose difficulty spaces their shares further apart."
+    )]
+    pub(crate) worker_offline_grace: u64,
+
     #[arg(long, help = "Disable bouncer.")]
     pub(crate) disable_bouncer: bool,
 
@@ -61,6 +140,85 @@ pub(crate) struct PoolOptions {
         help = "Write events to JSON or CSV <EVENTS_FILE>."
     )]
     pub(crate) events_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Only authorize payout addresses listed in <ADDRESS_ALLOWLIST>, one per line. Reloaded on SIGHUP."
+    )]
+    pub(crate) address_allowlist: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Let miners authorize as <ACCOUNT>.<WORKER> and pay each account a unique address derived from <PAYOUT_XPUB>."
+    )]
+    pub(crate) payout_xpub: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 100_000,
+        requires = "payout_xpub",
+        help = "Assign payout addresses to at most <MAX_PAYOUT_ACCOUNTS> accounts."
+    )]
+    pub(crate) max_payout_accounts: u32,
+
+    #[arg(
+        long,
+        help = "Reject worker names longer than <WORKER_NAME_MAX_LENGTH> characters."
+    )]
+    pub(crate) worker_name_max_length: Option<usize>,
+
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Only allow ASCII letters, digits and the characters in <WORKER_NAME_CHARSET> in worker names."
+    )]
+    pub(crate) worker_name_charset: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Accept any password in mining.authorize (optional), reject a missing or empty one (required), or reject one without a difficulty hint like `d=1024` (difficulty-hint). Under required or difficulty-hint, a hint is taken as a suggested difficulty."
+    )]
+    pub(crate) password_policy: PasswordPolicy,
+
+    #[arg(
+        long,
+        help = "Pay block rewards to <COINBASE_ADDRESS> instead of the miner's address, rotating by block height through all given addresses."
+    )]
+    pub(crate) coinbase_address: Vec<Address<NetworkUnchecked>>,
+
+    #[arg(
+        long,
+        conflicts_with = "coinbase_address",
+        help = "Pay block rewards to the addresses in <COINBASE_ADDRESS_FILE>, one per line, rotating by block height. Reloaded on SIGHUP without disconnecting miners."
+    )]
+    pub(crate) coinbase_address_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also request block templates with softfork rule <GBT_RULE>, in addition to segwit (and signet on signet)."
+    )]
+    pub(crate) gbt_rule: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Write a JSON snapshot of connected workers, their stats and found blocks to <SHUTDOWN_SNAPSHOT> on shutdown."
+    )]
+    pub(crate) shutdown_snapshot: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Hand miners a token they can present with mining.resume to get their enonce1 and difficulty back after reconnecting within <RESUME_WINDOW> seconds."
+    )]
+    pub(crate) resume_window: Option<u64>,
+
+    #[arg(
+        long,
+        default_value_t = 7200,
+        help = "Reject shares whose ntime is more than <MAX_CLOCK_SKEW> seconds from the pool's clock."
+    )]
+    pub(crate) max_clock_skew: u64,
 }
 
 fn validate_events_file(s: &str) -> Result<PathBuf> {
@@ -73,6 +231,33 @@ fn validate_events_file(s: &str) -> Result<PathBuf> {
     Ok(path)
 }
 
+fn validate_share_tolerance(s: &str) -> Result<f64> {
+    let tolerance = s.parse::<f64>()?;
+    ensure!(
+        (0.0..=0.1).contains(&tolerance),
+        "Share tolerance must be between 0 and 0.1"
+    );
+    Ok(tolerance)
+}
+
+fn validate_near_miss_factor(s: &str) -> Result<f64> {
+    let factor = s.parse::<f64>()?;
+    ensure!(
+        factor.is_finite() && factor >= 1.0,
+        "Near miss factor must be at least 1"
+    );
+    Ok(factor)
+}
+
+fn validate_fee_refresh_threshold(s: &str) -> Result<f64> {
+    let threshold = s.parse::<f64>()?;
+    ensure!(
+        threshold.is_finite() && threshold > 0.0,
+        "Fee refresh threshold must be greater than 0"
+    );
+    Ok(threshold)
+}
+
 fn validate_database_url(s: &str) -> anyhow::Result<String> {
     ensure!(
         s.starts_with("postgres://") || s.starts_with("postgresql://"),
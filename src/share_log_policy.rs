@@ -0,0 +1,172 @@
+use {super::*, crate::event_sink::ShareEvent};
+
+/// Which accepted shares reach the events file. Storing every low
+/// difficulty share is expensive on a large pool, so shares below
+/// `min_diff` can be dropped, or kept one in `sample`. Rejected shares and
+/// shares that solve a block are always recorded, and the default records
+/// everything. Payouts are credited from the database's shares, so settings
+/// refuse any other policy alongside a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct ShareLogPolicy {
+    min_diff: Option<Difficulty>,
+    sample: Option<NonZeroU32>,
+}
+
+impl ShareLogPolicy {
+    pub(crate) fn new(min_diff: Option<Difficulty>, sample: Option<NonZeroU32>) -> Self {
+        Self { min_diff, sample }
+    }
+
+    pub(crate) fn sampler(self) -> ShareSampler {
+        ShareSampler {
+            policy: self,
+            skipped: 0,
+        }
+    }
+}
+
+/// A connection's running application of a [`ShareLogPolicy`], counting
+/// the shares sampling has passed over.
+#[derive(Debug, Clone)]
+pub(crate) struct ShareSampler {
+    policy: ShareLogPolicy,
+    skipped: u32,
+}
+
+impl ShareSampler {
+    pub(crate) fn records(&mut self, share: &ShareEvent, block_worthy: bool) -> bool {
+        if !share.outcome.is_accepted() || block_worthy {
+            return true;
+        }
+
+        if let Some(min_diff) = self.policy.min_diff
+            && Difficulty::from(share.pool_diff) >= min_diff
+        {
+            return true;
+        }
+
+        match self.policy.sample {
+            Some(sample) => {
+                let record = self.skipped == 0;
+                self.skipped = (self.skipped + 1) % sample.get();
+                record
+            }
+            None => self.policy.min_diff.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::event_sink::{AcceptedShare, RejectedShare},
+    };
+
+    fn accepted(pool_diff: f64) -> ShareEvent {
+        AcceptedShare {
+            address: "bc1test".into(),
+            workername: "rig1".into(),
+            pool_diff,
+            share_diff: pool_diff,
+            blockheight: 800000,
+        }
+        .into()
+    }
+
+    fn rejected(pool_diff: f64) -> ShareEvent {
+        RejectedShare {
+            address: "bc1test".into(),
+            workername: "rig1".into(),
+            pool_diff,
+            share_diff: 0.0,
+            blockheight: 800000,
+            reason: "Stale".into(),
+        }
+        .into()
+    }
+
+    fn recorded(policy: ShareLogPolicy, shares: &[(ShareEvent, bool)]) -> Vec<bool> {
+        let mut sampler = policy.sampler();
+
+        shares
+            .iter()
+            .map(|(share, block_worthy)| sampler.records(share, *block_worthy))
+            .collect()
+    }
+
+    #[test]
+    fn default_records_everything() {
+        assert_eq!(
+            recorded(
+                ShareLogPolicy::default(),
+                &[(accepted(0.001), false), (rejected(0.001), false)]
+            ),
+            [true, true]
+        );
+    }
+
+    #[test]
+    fn sub_threshold_shares_dropped() {
+        let policy = ShareLogPolicy::new(Some(Difficulty::from(1000)), None);
+
+        assert_eq!(
+            recorded(
+                policy,
+                &[
+                    (accepted(999.0), false),
+                    (accepted(1000.0), false),
+                    (accepted(5000.0), false),
+                ]
+            ),
+            [false, true, true]
+        );
+    }
+
+    #[test]
+    fn sub_threshold_shares_sampled() {
+        let policy = ShareLogPolicy::new(Some(Difficulty::from(1000)), NonZeroU32::new(3));
+
+        assert_eq!(
+            recorded(
+                policy,
+                &[
+                    (accepted(1.0), false),
+                    (accepted(1.0), false),
+                    (accepted(1000.0), false),
+                    (accepted(1.0), false),
+                    (accepted(1.0), false),
+                    (accepted(1.0), false),
+                ]
+            ),
+            [true, false, true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn sample_without_threshold_samples_every_share() {
+        let policy = ShareLogPolicy::new(None, NonZeroU32::new(2));
+
+        assert_eq!(
+            recorded(
+                policy,
+                &[
+                    (accepted(5000.0), false),
+                    (accepted(5000.0), false),
+                    (accepted(5000.0), false),
+                ]
+            ),
+            [true, false, true]
+        );
+    }
+
+    #[test]
+    fn rejected_and_block_worthy_shares_always_recorded() {
+        let policy = ShareLogPolicy::new(Some(Difficulty::from(1000)), None);
+
+        assert_eq!(
+            recorded(policy, &[(rejected(1.0), false), (accepted(1.0), true)]),
+            [true, true]
+        );
+    }
+}
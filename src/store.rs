@@ -2,7 +2,8 @@ use {
     super::*,
     bdk_wallet::{ChangeSet, chain::Merge},
     redb::{
-        Database, Durability, ReadableDatabase, ReadableTable, TableDefinition, WriteTransaction,
+        Database, Durability, ReadableDatabase, ReadableTable, ReadableTableMetadata,
+        TableDefinition, WriteTransaction,
     },
 };
 
@@ -19,6 +20,9 @@ const ORDERS: TableDefinition<u32, &[u8]> = TableDefinition::new("ORDERS");
 const WALLET: TableDefinition<u32, &[u8]> = TableDefinition::new("WALLET");
 const USERS: TableDefinition<&str, &[u8]> = TableDefinition::new("USERS");
 const BLOCKS: TableDefinition<u64, &[u8]> = TableDefinition::new("BLOCKS");
+const FOUND_BLOCKS: TableDefinition<u64, &[u8]> = TableDefinition::new("FOUND_BLOCKS");
+const HISTORY: TableDefinition<u64, &[u8]> = TableDefinition::new("HISTORY");
+const ACCOUNTS: TableDefinition<&str, u32> = TableDefinition::new("ACCOUNTS");
 
 #[derive(Serialize, Deserialize)]
 struct Metadata {
@@ -102,6 +106,9 @@ impl Store {
             transaction.open_table(WALLET)?;
             transaction.open_table(USERS)?;
             transaction.open_table(BLOCKS)?;
+            transaction.open_table(FOUND_BLOCKS)?;
+            transaction.open_table(HISTORY)?;
+            transaction.open_table(ACCOUNTS)?;
         }
 
         transaction.commit()?;
@@ -188,6 +195,48 @@ impl Store {
             .transpose()
             .map(|blocks| blocks.unwrap_or_default())
     }
+
+    pub(crate) fn read_found_blocks(&self) -> Result<Vec<entry::FoundBlockEntry>> {
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(FOUND_BLOCKS)?;
+
+        table
+            .get(BLOCKS_KEY)?
+            .map(|value| ciborium::from_reader(value.value()).context("decode found blocks"))
+            .transpose()
+            .map(|blocks| blocks.unwrap_or_default())
+    }
+
+    /// The derivation index recorded for `account`, or the one it would be
+    /// given next if it is new, or `None` if it is new and `max_accounts` are
+    /// already recorded. Records nothing.
+    pub(crate) fn peek_account_index(
+        &self,
+        account: &str,
+        max_accounts: u32,
+    ) -> Result<Option<u32>> {
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(ACCOUNTS)?;
+
+        if let Some(index) = table.get(account)? {
+            return Ok(Some(index.value()));
+        }
+
+        Ok(u32::try_from(table.len()?)
+            .ok()
+            .filter(|&index| index < max_accounts))
+    }
+
+    pub(crate) fn read_history(&self) -> Result<VecDeque<entry::HistorySampleEntry>> {
+        let transaction = self.db.begin_read()?;
+        let table = transaction.open_table(HISTORY)?;
+
+        table
+            .get(BLOCKS_KEY)?
+            .map(|value| ciborium::from_reader(value.value()).context("decode history"))
+            .transpose()
+            .map(|history| history.unwrap_or_default())
+    }
 }
 
 pub(crate) struct WriteTxn {
@@ -265,6 +314,48 @@ impl WriteTxn {
         Ok(())
     }
 
+    pub(crate) fn write_found_blocks(&self, blocks: &[entry::FoundBlockEntry]) -> Result {
+        let mut table = self.inner.open_table(FOUND_BLOCKS)?;
+        let mut bytes = Vec::new();
+
+        ciborium::into_writer(&blocks, &mut bytes).context("encode found blocks")?;
+        table.insert(BLOCKS_KEY, bytes.as_slice())?;
+
+        Ok(())
+    }
+
+    pub(crate) fn write_history(&self, history: &VecDeque<entry::HistorySampleEntry>) -> Result {
+        let mut table = self.inner.open_table(HISTORY)?;
+        let mut bytes = Vec::new();
+
+        ciborium::into_writer(history, &mut bytes).context("encode history")?;
+        table.insert(BLOCKS_KEY, bytes.as_slice())?;
+
+        Ok(())
+    }
+
+    /// The derivation index recorded for `account`, recording the next
+    /// unused one if the account is new, or `None` if it is new and
+    /// `max_accounts` are already recorded.
+    pub(crate) fn account_index(&self, account: &str, max_accounts: u32) -> Result<Option<u32>> {
+        let mut table = self.inner.open_table(ACCOUNTS)?;
+
+        if let Some(index) = table.get(account)? {
+            return Ok(Some(index.value()));
+        }
+
+        let Some(index) = u32::try_from(table.len()?)
+            .ok()
+            .filter(|&index| index < max_accounts)
+        else {
+            return Ok(None);
+        };
+
+        table.insert(account, index)?;
+
+        Ok(Some(index))
+    }
+
     pub(crate) fn commit(self) -> Result {
         Ok(self.inner.commit()?)
     }
@@ -349,6 +440,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn account_indices_are_stable() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.redb");
+
+        {
+            let store = Store::open(&path, Chain::Regtest).unwrap();
+            let txn = store.begin().unwrap();
+            assert_eq!(txn.account_index("alice", 10).unwrap(), Some(0));
+            assert_eq!(txn.account_index("bob", 10).unwrap(), Some(1));
+            assert_eq!(txn.account_index("alice", 10).unwrap(), Some(0));
+            txn.commit().unwrap();
+        }
+
+        let store = Store::open(&path, Chain::Regtest).unwrap();
+        assert_eq!(store.peek_account_index("bob", 10).unwrap(), Some(1));
+        assert_eq!(store.peek_account_index("carol", 10).unwrap(), Some(2));
+        assert_eq!(store.peek_account_index("dave", 10).unwrap(), Some(2));
+
+        let txn = store.begin().unwrap();
+        assert_eq!(txn.account_index("bob", 10).unwrap(), Some(1));
+        assert_eq!(txn.account_index("carol", 10).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn account_indices_stop_at_max_accounts() {
+        let (_directory, store) = temporary_store(Chain::Regtest);
+
+        let txn = store.begin().unwrap();
+        assert_eq!(txn.account_index("alice", 2).unwrap(), Some(0));
+        assert_eq!(txn.account_index("bob", 2).unwrap(), Some(1));
+        assert_eq!(txn.account_index("carol", 2).unwrap(), None);
+        assert_eq!(txn.account_index("alice", 2).unwrap(), Some(0));
+        txn.commit().unwrap();
+
+        assert_eq!(store.peek_account_index("bob", 2).unwrap(), Some(1));
+        assert_eq!(store.peek_account_index("carol", 2).unwrap(), None);
+        assert_eq!(store.peek_account_index("carol", 3).unwrap(), Some(2));
+    }
+
     #[test]
     fn empty_read_returns_default() {
         let (_directory, store) = temporary_store(Chain::Regtest);
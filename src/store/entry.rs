@@ -28,6 +28,10 @@ pub(crate) struct StatsEntry {
     pub(crate) rejected_work: HashWork,
     pub(crate) last_share_secs: Option<f64>,
     pub(crate) best_share: Option<Difficulty>,
+    #[serde(default)]
+    pub(crate) version_rolled_shares: u64,
+    #[serde(default)]
+    pub(crate) version_rolled_bits: [u64; 32],
     pub(crate) dsps_1m: DecayingAverageEntry,
     pub(crate) dsps_5m: DecayingAverageEntry,
     pub(crate) dsps_15m: DecayingAverageEntry,
@@ -60,6 +64,22 @@ pub(crate) struct UserEntry {
     pub(crate) workers: Vec<WorkerEntry>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FoundBlockEntry {
+    pub(crate) height: i32,
+    pub(crate) hash: BlockHash,
+    pub(crate) found_at_secs: u64,
+    pub(crate) finder: String,
+    pub(crate) reward_sat: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct HistorySampleEntry {
+    pub(crate) at_secs: u64,
+    pub(crate) hashrate: HashRate,
+    pub(crate) sps: f64,
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::epoch::instant_to_epoch_secs};
@@ -1,14 +1,47 @@
 use {
     super::*,
-    crate::event_sink::{BlockFoundEvent, Event, ShareEvent},
+    crate::event_sink::{AcceptedShare, BlockFoundEvent, Event, NearMissEvent, ShareEvent},
+    api::{DisconnectReason, MaintenanceMode},
     bouncer::{Bouncer, Consequence},
-    state::{Authorization, Identity, State, Subscription},
+    metatron::{ResumeTicket, connection::Connection},
+    serde_json::value::RawValue,
+    state::{Authorization, Identity, State, Subscription, Workers},
+    store::entry::FoundBlockEntry,
     upstream::UpstreamSubmit,
 };
 
 mod bouncer;
 pub(crate) mod state;
 
+/// How long a single write may wait for the miner to drain its socket before
+/// the connection is dropped as a slow consumer.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A JSON-RPC batch being worked through. Responses are collected and written
+/// back as one array once every request has been handled. Notifications sent
+/// in the meantime, such as `mining.notify` after an authorize, follow it.
+#[derive(Default)]
+struct Batch {
+    requests: VecDeque<Request>,
+    responses: Vec<Message>,
+    notifications: Vec<Message>,
+}
+
+/// A request read off the connection, ready to be handled.
+enum Request {
+    Message(Message),
+    /// A `mining.authorize` as `<ACCOUNT>.<WORKER>`, which only becomes an
+    /// [`Authorize`] once the account's payout address is looked up.
+    Account(AccountAuthorize),
+}
+
+struct AccountAuthorize {
+    id: Id,
+    account: String,
+    workername: String,
+    password: Option<String>,
+}
+
 enum Acquisition {
     Acquired(Extranonce),
     Reroute,
@@ -17,6 +50,7 @@ enum Acquisition {
 
 pub(crate) struct Stratifier<W: Workbase> {
     state: State,
+    workers: Workers,
     socket_addr: SocketAddr,
     settings: Arc<Settings>,
     allocator: Arc<EnonceAllocator>,
@@ -25,13 +59,20 @@ pub(crate) struct Stratifier<W: Workbase> {
     reader: FramedRead<OwnedReadHalf, LinesCodec>,
     writer: FramedWrite<OwnedWriteHalf, LinesCodec>,
     inbox: VecDeque<Message>,
+    batch: Option<Batch>,
     workbase_rx: watch::Receiver<Arc<W>>,
     cancel: CancellationToken,
     jobs: Jobs<W>,
     vardiff: Vardiff,
     bouncer: Bouncer,
     event_tx: Option<mpsc::Sender<Event>>,
+    share_sampler: ShareSampler,
     order: Option<Arc<Order>>,
+    allowlist: Option<Arc<Allowlist>>,
+    connection: Arc<Connection>,
+    disconnect_reason: Option<DisconnectReason>,
+    write_timeout: Duration,
+    resume_token: Option<String>,
 }
 
 impl<W: Workbase> Stratifier<W> {
@@ -50,6 +91,7 @@ impl<W: Workbase> Stratifier<W> {
         event_tx: Option<mpsc::Sender<Event>>,
         start_diff: Difficulty,
         order: Option<Arc<Order>>,
+        allowlist: Option<Arc<Allowlist>>,
     ) -> Self {
         let vardiff = Vardiff::new(
             start_diff,
@@ -57,12 +99,18 @@ impl<W: Workbase> Stratifier<W> {
             settings.vardiff_window(),
             settings.min_diff(),
             settings.max_diff(),
-        );
+        )
+        .with_jitter(&mut rand::rng());
 
         let bouncer = Bouncer::new(settings.disable_bouncer());
 
+        let share_sampler = settings.share_log_policy().sampler();
+
+        let connection = metatron.register_connection(socket_addr, start_diff);
+
         Self {
             state: State::new(),
+            workers: Workers::default(),
             socket_addr,
             settings,
             allocator,
@@ -71,13 +119,20 @@ impl<W: Workbase> Stratifier<W> {
             reader,
             writer,
             inbox,
+            batch: None,
             workbase_rx,
             cancel,
             jobs: Jobs::new(),
             vardiff,
             bouncer,
             event_tx,
+            share_sampler,
             order,
+            allowlist,
+            connection,
+            disconnect_reason: None,
+            write_timeout: WRITE_TIMEOUT,
+            resume_token: None,
         }
     }
 
@@ -87,6 +142,14 @@ impl<W: Workbase> Stratifier<W> {
         let mut idle_check = ticker(self.bouncer.check_interval());
 
         loop {
+            self.flush_batch().await?;
+
+            self.connection.update(
+                self.state.identity().as_ref(),
+                self.state.version_mask(),
+                self.vardiff.current_diff(),
+            );
+
             tokio::select! {
                 _ = cancel.cancelled() => {
                     info!("Session cancelled, sending client.reconnect to {}", self.socket_addr);
@@ -97,6 +160,7 @@ impl<W: Workbase> Stratifier<W> {
                         Err(_) => warn!("Timed out sending client.reconnect to {}", self.socket_addr),
                     }
 
+                    self.disconnect(DisconnectReason::Shutdown);
                     break;
                 }
                 _ = idle_check.tick() => {
@@ -106,22 +170,33 @@ impl<W: Workbase> Stratifier<W> {
                             self.socket_addr,
                             self.bouncer.last_interaction_since().as_secs()
                         );
+                        self.disconnect(DisconnectReason::Idle);
                         break
                     }
                 }
-                message = self.read_message() => {
-                    let Some(message) = message? else {
+                request = self.read_message() => {
+                    let Some(request) = request? else {
+                        self.disconnect(DisconnectReason::ClientEof);
                         break;
                     };
 
-                    let message = match message {
-                        Message::Notification {
+                    let message = match request {
+                        Request::Message(Message::Notification {
                             method: Method::SuggestDifficulty(suggest),
-                        } => {
+                        }) => {
                             self.suggest_difficulty(None, suggest.difficulty()).await?;
                             continue;
                         }
-                        message => message,
+                        Request::Message(message) => message,
+                        Request::Account(request) => {
+                            let consequence = self.authorize_account(request).await?;
+
+                            if self.handle_protocol_consequence(consequence).await {
+                                break;
+                            }
+
+                            continue;
+                        }
                     };
 
                     let Message::Request { id, method } = message else {
@@ -142,27 +217,15 @@ impl<W: Workbase> Stratifier<W> {
                                 break;
                             }
                         }
-                        Method::Authorize(authorize) => {
-                            let Some(subscription) = self.state.subscribed() else {
-                                self.send_error(
-                                    id.clone(),
-                                    StratumError::MethodNotAllowed,
-                                    Some(serde_json::json!({
-                                        "method": "mining.authorize",
-                                        "current_state": self.state.to_string()
-                                    })),
-                                )
-                                .await?;
-
-                                let consequence = self.bouncer.reject();
-                                if self.handle_protocol_consequence(consequence).await {
-                                    break;
-                                }
+                        Method::Resume(resume) => {
+                            let consequence = self.resume(id, resume).await?;
 
-                                continue;
-                            };
-
-                            let consequence = self.authorize(id, authorize, subscription).await?;
+                            if self.handle_protocol_consequence(consequence).await {
+                                break;
+                            }
+                        }
+                        Method::Authorize(authorize) => {
+                            let consequence = self.handle_authorize(id, authorize, None).await?;
 
                             if self.handle_protocol_consequence(consequence).await {
                                 break;
@@ -186,6 +249,19 @@ impl<W: Workbase> Stratifier<W> {
 
                                     self.state = State::Working(session.clone());
 
+                                    debug!(
+                                        "Extranonce layout for {}: enonce1={} enonce1_size={} enonce2_size={} version_mask={} start_diff={}",
+                                        self.socket_addr,
+                                        session.enonce1(),
+                                        session.enonce1().len(),
+                                        self.allocator.enonce2_size(),
+                                        session
+                                            .version_mask()
+                                            .map(|mask| mask.to_string())
+                                            .unwrap_or_else(|| "none".into()),
+                                        self.vardiff.current_diff(),
+                                    );
+
                                     session
                                 },
                                 State::Working(session) => session.clone(),
@@ -206,6 +282,12 @@ impl<W: Workbase> Stratifier<W> {
                                 }
                             };
 
+                            let session = if self.workers.contains(&submit.username) {
+                                self.worker_session(&submit.username, &session)
+                            } else {
+                                session
+                            };
+
                             let consequence = self
                                 .submit(id, submit, session.clone())
                                 .await?;
@@ -231,6 +313,7 @@ impl<W: Workbase> Stratifier<W> {
                             warn!("Failed to send client.reconnect to {}: {err}", self.socket_addr);
                         }
 
+                        self.disconnect(DisconnectReason::UpstreamLost);
                         break;
                     }
 
@@ -249,6 +332,20 @@ impl<W: Workbase> Stratifier<W> {
         Ok(())
     }
 
+    /// Notes why the connection is about to close. The first reason sticks,
+    /// so a drop that follows a more specific one does not overwrite it.
+    fn disconnect(&mut self, reason: DisconnectReason) {
+        self.disconnect_reason.get_or_insert(reason);
+    }
+
+    /// The configured coinbase rotation address for `height`, falling back
+    /// to the miner's own address.
+    fn payout_address(&self, height: i32, miner: &Address) -> Address {
+        self.settings
+            .coinbase_address(height)
+            .unwrap_or_else(|| miner.clone())
+    }
+
     async fn handle_submit_consequence(
         &mut self,
         consequence: Consequence,
@@ -269,11 +366,12 @@ impl<W: Workbase> Stratifier<W> {
                 );
 
                 let workbase = self.workbase_rx.borrow().clone();
+                let payout = self.payout_address(workbase.height(), address);
 
                 match workbase.create_job(
                     enonce1,
                     self.allocator.enonce2_size(),
-                    Some(address),
+                    Some(&payout),
                     self.jobs.next_id(),
                     self.state.version_mask(),
                 ) {
@@ -326,6 +424,7 @@ impl<W: Workbase> Stratifier<W> {
                         .map(|d| d.as_secs())
                         .unwrap_or(0)
                 );
+                self.disconnect(DisconnectReason::Banned);
                 true
             }
         }
@@ -374,6 +473,7 @@ impl<W: Workbase> Stratifier<W> {
                         .map(|d| d.as_secs())
                         .unwrap_or(0)
                 );
+                self.disconnect(DisconnectReason::Banned);
                 true
             }
         }
@@ -394,19 +494,18 @@ impl<W: Workbase> Stratifier<W> {
                 self.vardiff
                     .record_diff_change_job_id(self.jobs.peek_next_id());
 
-                self.send(Message::Notification {
-                    method: Method::SetDifficulty(SetDifficulty(new_diff)),
-                })
-                .await?;
+                self.send_difficulty(new_diff).await?;
             }
         }
 
+        let payout = self.payout_address(workbase.height(), identity.address());
+
         let new_job = Arc::new(
             workbase
                 .create_job(
                     identity.enonce1(),
                     self.allocator.enonce2_size(),
-                    Some(identity.address()),
+                    Some(&payout),
                     self.jobs.next_id(),
                     self.state.version_mask(),
                 )
@@ -429,7 +528,7 @@ impl<W: Workbase> Stratifier<W> {
     }
 
     async fn configure(&mut self, id: Id, configure: Configure) -> Result {
-        if configure.version_rolling_mask.is_none() {
+        let Some(requested_mask) = configure.version_rolling_mask else {
             warn!("Unsupported extension {:?}", configure);
 
             let message = Message::Response {
@@ -446,9 +545,9 @@ impl<W: Workbase> Stratifier<W> {
 
             self.send(message).await?;
             return Ok(());
-        }
+        };
 
-        let version_mask = if let Some(ref upstream) = self.upstream {
+        let available_mask = if let Some(ref upstream) = self.upstream {
             match upstream.version_mask() {
                 Some(mask) => {
                     debug!(
@@ -478,6 +577,33 @@ impl<W: Workbase> Stratifier<W> {
             self.settings.version_mask()
         };
 
+        // BIP310 answers with the bits both sides may roll, and submits are
+        // checked against the same mask. Older firmware ignores the bits it
+        // requested and rolls whatever the answer allows, so those miners
+        // are given the whole available mask.
+        let version_mask = if self.settings.legacy_version_mask() {
+            available_mask
+        } else {
+            available_mask & requested_mask
+        };
+
+        if version_mask == Version::from(0) {
+            debug!(
+                "Version rolling disabled for {} (requested mask {requested_mask} shares no bits with {available_mask})",
+                self.socket_addr
+            );
+
+            let message = Message::Response {
+                id,
+                result: Some(json!({"version-rolling": false})),
+                error: None,
+                reject_reason: None,
+            };
+
+            self.send(message).await?;
+            return Ok(());
+        }
+
         if !self.state.configure(version_mask) {
             self.send_error(
                 id,
@@ -559,11 +685,13 @@ impl<W: Workbase> Stratifier<W> {
             Acquisition::Acquired(enonce1) => enonce1,
             Acquisition::Reroute => {
                 warn!("Upstream saturated, reconnecting {}", self.socket_addr);
+                self.disconnect(DisconnectReason::Rerouted);
                 self.send_reconnect().await?;
                 return Ok(Consequence::Drop);
             }
             Acquisition::Exhausted => {
                 warn!("Pool full, rejecting {}", self.socket_addr);
+                self.disconnect(DisconnectReason::PoolFull);
                 self.send_error(id, StratumError::PoolFull, None).await?;
                 return Ok(Consequence::Drop);
             }
@@ -571,18 +699,27 @@ impl<W: Workbase> Stratifier<W> {
 
         let enonce2_size = self.allocator.enonce2_size();
 
+        self.connection.set_user_agent(&subscribe.user_agent);
         self.state.subscribe(enonce1.clone(), subscribe.user_agent);
 
         self.bouncer.accept();
 
-        let subscriptions = vec![
-            (
-                "mining.set_difficulty".to_string(),
-                SUBSCRIPTION_ID.to_string(),
-            ),
-            ("mining.notify".to_string(), SUBSCRIPTION_ID.to_string()),
+        // The subscription id doubles as the session id. Miners that echo it
+        // back in their next mining.subscribe get the same enonce1 while the
+        // session is still held for resumption.
+        let session_id = enonce1.to_string();
+
+        let mut subscriptions = vec![
+            ("mining.set_difficulty".to_string(), session_id.clone()),
+            ("mining.notify".to_string(), session_id),
         ];
 
+        if self.settings.resume_window().is_some() {
+            let token = hex::encode(rand::random::<[u8; 16]>());
+            subscriptions.push(("mining.resume".to_string(), token.clone()));
+            self.resume_token = Some(token);
+        }
+
         let result = SubscribeResponse {
             subscriptions,
             enonce1: enonce1.clone(),
@@ -600,32 +737,313 @@ impl<W: Workbase> Stratifier<W> {
         Ok(Consequence::None)
     }
 
-    async fn authorize(
-        &mut self,
-        id: Id,
-        authorize: Authorize,
-        subscription: Subscription,
-    ) -> Result<Consequence> {
-        let address = match authorize
-            .username
-            .parse_with_network(self.settings.chain().network())
+    /// Subscribes with the enonce1, difficulty and version mask the token's
+    /// session had when it dropped. Expired or unknown tokens get a fresh
+    /// subscription, as a plain `mining.subscribe` would.
+    async fn resume(&mut self, id: Id, resume: Resume) -> Result<Consequence> {
+        if self.settings.resume_window().is_none() || !self.state.can_subscribe() {
+            self.send_error(
+                id,
+                StratumError::MethodNotAllowed,
+                Some(serde_json::json!({
+                    "method": "mining.resume",
+                    "current_state": self.state.to_string()
+                })),
+            )
+            .await?;
+
+            return Ok(self.bouncer.reject());
+        }
+
+        let ticket = self
+            .metatron
+            .take_resume_ticket(&resume.token, Instant::now());
+
+        if ticket.is_none() {
+            debug!(
+                "Unknown or expired resume token from {}, subscribing afresh",
+                self.socket_addr
+            );
+        }
+
+        if let Some(version_mask) = ticket.as_ref().and_then(|ticket| ticket.version_mask) {
+            self.state.configure(version_mask);
+        }
+
+        let consequence = self
+            .subscribe(
+                id,
+                Subscribe {
+                    user_agent: resume.user_agent,
+                    enonce1: ticket.as_ref().map(|ticket| ticket.enonce1.clone()),
+                },
+            )
+            .await?;
+
+        if let Some(ticket) = ticket
+            && self.state.enonce1() == Some(&ticket.enonce1)
         {
-            Ok(parsed) => parsed,
+            info!(
+                "Resumed {} with enonce1 {} at difficulty {}",
+                self.socket_addr, ticket.enonce1, ticket.difficulty
+            );
+
+            self.vardiff.resume(ticket.difficulty);
+        }
+
+        Ok(consequence)
+    }
+
+    /// Checks `username` against the worker name policy, the chain's network
+    /// and the allowlist. Returns its payout address, or `None` once the miner
+    /// has been told why it may not authorize.
+    async fn check_username(&mut self, id: &Id, username: &Username) -> Result<Option<Address>> {
+        if let Err(err) = self.settings.worker_name_policy().check(username) {
+            debug!(
+                "Rejected authorize for {username} from {}: {err}",
+                self.socket_addr
+            );
+
+            self.send_error(
+                id.clone(),
+                StratumError::Unauthorized,
+                Some(json!({
+                    "message": err.to_string(),
+                    "username": username.as_str(),
+                })),
+            )
+            .await?;
+
+            return Ok(None);
+        }
+
+        match username.parse_with_network(self.settings.chain().network()) {
+            Ok(address)
+                if self
+                    .allowlist
+                    .as_ref()
+                    .is_some_and(|allowlist| !allowlist.allows(&address)) =>
+            {
+                debug!(
+                    "Rejected authorize for {username} from {}: address not on allowlist",
+                    self.socket_addr
+                );
+
+                self.send_error(
+                    id.clone(),
+                    StratumError::Unauthorized,
+                    Some(json!({
+                        "message": "address is not on this pool's allowlist",
+                        "username": username.as_str(),
+                    })),
+                )
+                .await?;
+
+                Ok(None)
+            }
+            Ok(address) => Ok(Some(address)),
             Err(e) => {
                 self.send_error(
-                    id,
+                    id.clone(),
                     StratumError::Unauthorized,
                     Some(json!({
                         "message": e.to_string(),
-                        "username": authorize.username.as_str(),
+                        "username": username.as_str(),
                     })),
                 )
                 .await?;
 
+                Ok(None)
+            }
+        }
+    }
+
+    async fn reject_password(&mut self, id: Id, username: &Username, err: Error) -> Result {
+        debug!(
+            "Rejected authorize for {username} from {}: {err}",
+            self.socket_addr
+        );
+
+        self.send_error(
+            id,
+            StratumError::Unauthorized,
+            Some(json!({
+                "message": err.to_string(),
+                "username": username.as_str(),
+            })),
+        )
+        .await
+    }
+
+    /// Assigns the derivation index an account username was rewritten with,
+    /// once its authorize has passed every policy, so rejected attempts don't
+    /// use up indices. Returns false, after telling the miner, if another
+    /// connection was assigned that index first or no indices are left.
+    async fn claim_account(
+        &mut self,
+        id: &Id,
+        username: &Username,
+        account: Option<(String, u32)>,
+    ) -> Result<bool> {
+        let Some((account, index)) = account else {
+            return Ok(true);
+        };
+
+        let claimed = {
+            let metatron = self.metatron.clone();
+            let account = account.clone();
+            let max_accounts = self.settings.max_payout_accounts();
+
+            task::spawn_blocking(move || metatron.account_index(&account, max_accounts))
+                .await
+                .context("account index task failed")??
+        };
+
+        if claimed == Some(index) {
+            return Ok(true);
+        }
+
+        debug!(
+            "Rejected authorize for {username} from {}: account {account} was not assigned index {index}",
+            self.socket_addr
+        );
+
+        let message = if claimed.is_some() {
+            "account was assigned another payout address, authorize again"
+        } else {
+            "no payout addresses left for new accounts"
+        };
+
+        self.send_error(
+            id.clone(),
+            StratumError::Unauthorized,
+            Some(json!({
+                "message": message,
+                "username": username.as_str(),
+            })),
+        )
+        .await?;
+
+        Ok(false)
+    }
+
+    /// Routes an authorize to [`Self::authorize`] for the connection's first
+    /// worker and to [`Self::authorize_worker`] for any after it. `account` is
+    /// the account id and index the username was rewritten with, if any.
+    async fn handle_authorize(
+        &mut self,
+        id: Id,
+        authorize: Authorize,
+        account: Option<(String, u32)>,
+    ) -> Result<Consequence> {
+        if let Some(identity) = self.state.identity() {
+            return self
+                .authorize_worker(id, authorize, identity, account)
+                .await;
+        }
+
+        let Some(subscription) = self.state.subscribed() else {
+            self.send_error(
+                id,
+                StratumError::MethodNotAllowed,
+                Some(json!({
+                    "method": "mining.authorize",
+                    "current_state": self.state.to_string()
+                })),
+            )
+            .await?;
+
+            return Ok(self.bouncer.reject());
+        };
+
+        self.authorize(id, authorize, subscription, account).await
+    }
+
+    /// With a payout xpub configured, miners may authorize as
+    /// `<ACCOUNT>.<WORKER>`. The account id is replaced with the address the
+    /// account's next or already assigned index derives, and the request is
+    /// handled as an ordinary authorize.
+    async fn authorize_account(&mut self, request: AccountAuthorize) -> Result<Consequence> {
+        let AccountAuthorize {
+            id,
+            account,
+            workername,
+            password,
+        } = request;
+
+        let settings = self.settings.clone();
+
+        let payout_xpub = settings
+            .payout_xpub()
+            .context("account authorize without a payout xpub")?;
+
+        let Some(index) = self
+            .metatron
+            .peek_account_index(&account, settings.max_payout_accounts())?
+        else {
+            debug!(
+                "Rejected authorize for account {account} from {}: no payout addresses left",
+                self.socket_addr
+            );
+
+            self.send_error(
+                id,
+                StratumError::Unauthorized,
+                Some(json!({
+                    "message": "no payout addresses left for new accounts",
+                    "account": account,
+                })),
+            )
+            .await?;
+
+            return Ok(self.bouncer.reject());
+        };
+
+        let address = payout_xpub.derive(index)?;
+
+        debug!(
+            "Account {account} from {} pays to {address} at index {index}",
+            self.socket_addr
+        );
+
+        let username = format!("{address}.{workername}")
+            .parse::<Username>()
+            .context("invalid account username")?;
+
+        self.handle_authorize(id, Authorize { username, password }, Some((account, index)))
+            .await
+    }
+
+    async fn authorize(
+        &mut self,
+        id: Id,
+        authorize: Authorize,
+        subscription: Subscription,
+        account: Option<(String, u32)>,
+    ) -> Result<Consequence> {
+        let Some(address) = self.check_username(&id, &authorize.username).await? else {
+            return Ok(self.bouncer.reject());
+        };
+
+        let hint = match self
+            .settings
+            .password_policy()
+            .check(authorize.password.as_deref())
+        {
+            Ok(hint) => hint,
+            Err(err) => {
+                self.reject_password(id, &authorize.username, err).await?;
                 return Ok(self.bouncer.reject());
             }
         };
 
+        if !self
+            .claim_account(&id, &authorize.username, account)
+            .await?
+        {
+            return Ok(self.bouncer.reject());
+        }
+
         let workername = authorize.username.workername().to_string();
 
         let auth = Arc::new(Authorization {
@@ -651,13 +1069,14 @@ impl<W: Workbase> Stratifier<W> {
         }
 
         let workbase = self.workbase_rx.borrow().clone();
+        let payout = self.payout_address(workbase.height(), &address);
 
         let job = Arc::new(
             workbase
                 .create_job(
                     &subscription.enonce1,
                     self.allocator.enonce2_size(),
-                    Some(&address),
+                    Some(&payout),
                     self.jobs.next_id(),
                     self.state.version_mask(),
                 )
@@ -675,6 +1094,11 @@ impl<W: Workbase> Stratifier<W> {
         self.bouncer.authorize();
         self.bouncer.accept();
 
+        if let Some(hint) = hint {
+            debug!("Difficulty hint {hint} from {}", self.socket_addr);
+            self.vardiff.suggest(hint);
+        }
+
         let current_diff = self.vardiff.current_diff();
 
         debug!(
@@ -682,10 +1106,7 @@ impl<W: Workbase> Stratifier<W> {
             self.socket_addr
         );
 
-        self.send(Message::Notification {
-            method: Method::SetDifficulty(SetDifficulty(current_diff)),
-        })
-        .await?;
+        self.send_difficulty(current_diff).await?;
 
         debug!("Sending mining.notify to {}", self.socket_addr);
 
@@ -699,32 +1120,132 @@ impl<W: Workbase> Stratifier<W> {
         Ok(Consequence::None)
     }
 
-    async fn suggest_difficulty(&mut self, id: Option<Id>, diff: Difficulty) -> Result {
-        debug!("Suggested difficulty {diff} from {}", self.socket_addr);
-
-        let changed = self.vardiff.suggest(diff);
+    async fn authorize_worker(
+        &mut self,
+        id: Id,
+        authorize: Authorize,
+        identity: Identity,
+        account: Option<(String, u32)>,
+    ) -> Result<Consequence> {
+        let Some(address) = self.check_username(&id, &authorize.username).await? else {
+            return Ok(self.bouncer.reject());
+        };
 
-        if let Some(id) = id {
-            self.send(Message::Response {
-                id,
-                result: Some(json!(true)),
-                error: None,
-                reject_reason: None,
-            })
-            .await?;
-        }
+        let hint = match self
+            .settings
+            .password_policy()
+            .check(authorize.password.as_deref())
+        {
+            Ok(hint) => hint,
+            Err(err) => {
+                self.reject_password(id, &authorize.username, err).await?;
+                return Ok(self.bouncer.reject());
+            }
+        };
 
-        if !changed || self.state.identity().is_none() {
-            return Ok(());
+        if !self
+            .claim_account(&id, &authorize.username, account)
+            .await?
+        {
+            return Ok(self.bouncer.reject());
         }
 
-        self.vardiff
-            .record_diff_change_job_id(self.jobs.peek_next_id());
+        if !self
+            .workers
+            .authorize(&identity, authorize.username.clone(), &address)
+        {
+            warn!(
+                "Rejected authorize for {} from {}: connection is authorized for {}",
+                authorize.username,
+                self.socket_addr,
+                identity.address()
+            );
 
-        self.send(Message::Notification {
-            method: Method::SetDifficulty(SetDifficulty(self.vardiff.current_diff())),
+            self.send_error(
+                id,
+                StratumError::Unauthorized,
+                Some(json!({
+                    "message": "address does not match the address authorized on this connection",
+                    "authorized": identity.address().to_string(),
+                    "username": authorize.username.as_str(),
+                })),
+            )
+            .await?;
+
+            return Ok(self.bouncer.reject());
+        }
+
+        debug!(
+            "Authorized worker {} on {} ({} additional workers)",
+            authorize.username,
+            self.socket_addr,
+            self.workers.len()
+        );
+
+        self.send(Message::Response {
+            id,
+            result: Some(json!(true)),
+            error: None,
+            reject_reason: None,
         })
-        .await
+        .await?;
+
+        self.bouncer.accept();
+
+        if let Some(hint) = hint {
+            self.suggest_difficulty(None, hint).await?;
+        }
+
+        Ok(Consequence::None)
+    }
+
+    fn worker_session(&mut self, username: &Username, primary: &Session) -> Arc<Session> {
+        if let Some(session) = self.workers.session(username) {
+            return session;
+        }
+
+        let auth = Arc::new(Authorization {
+            enonce1: primary.enonce1().clone(),
+            username: username.clone(),
+            address: primary.address().clone(),
+            workername: username.workername().to_string(),
+            version_mask: primary.version_mask(),
+        });
+
+        let session = self.metatron.new_session(auth, self.allocator.order_id());
+
+        if let Some(order) = &self.order {
+            order.add_session(session.clone(), self.cancel.clone(), self.socket_addr);
+        }
+
+        self.workers.insert_session(session.clone());
+
+        session
+    }
+
+    async fn suggest_difficulty(&mut self, id: Option<Id>, diff: Difficulty) -> Result {
+        debug!("Suggested difficulty {diff} from {}", self.socket_addr);
+
+        let changed = self.vardiff.suggest(diff);
+
+        if let Some(id) = id {
+            self.send(Message::Response {
+                id,
+                result: Some(json!(true)),
+                error: None,
+                reject_reason: None,
+            })
+            .await?;
+        }
+
+        if !changed || self.state.identity().is_none() {
+            return Ok(());
+        }
+
+        self.vardiff
+            .record_diff_change_job_id(self.jobs.peek_next_id());
+
+        self.send_difficulty(self.vardiff.current_diff()).await
     }
 
     async fn submit(
@@ -758,7 +1279,11 @@ impl<W: Workbase> Stratifier<W> {
                 StratumError::WorkerMismatch
             ));
 
-            session.record_rejected(self.vardiff.pool_diff(submit.job_id));
+            self.record_rejected(
+                &session,
+                self.vardiff.pool_diff(submit.job_id),
+                StratumError::WorkerMismatch,
+            );
 
             return Ok(self.bouncer.reject());
         }
@@ -779,14 +1304,18 @@ impl<W: Workbase> Stratifier<W> {
                 StratumError::Stale
             ));
 
-            session.record_rejected(self.vardiff.pool_diff(submit.job_id));
+            self.record_rejected(
+                &session,
+                self.vardiff.pool_diff(submit.job_id),
+                StratumError::Stale,
+            );
 
             return Ok(self.bouncer.reject());
         };
 
         let pool_diff = self.vardiff.pool_diff(submit.job_id);
 
-        let expected_extranonce2_size = self.allocator.enonce2_size();
+        let expected_extranonce2_size = job.enonce2_size();
 
         if submit.enonce2.len() != expected_extranonce2_size {
             warn!(
@@ -814,7 +1343,7 @@ impl<W: Workbase> Stratifier<W> {
                 StratumError::InvalidNonce2Length
             ));
 
-            session.record_rejected(pool_diff);
+            self.record_rejected(&session, pool_diff, StratumError::InvalidNonce2Length);
 
             return Ok(self.bouncer.reject());
         }
@@ -848,7 +1377,41 @@ impl<W: Workbase> Stratifier<W> {
                 StratumError::NtimeOutOfRange
             ));
 
-            session.record_rejected(pool_diff);
+            self.record_rejected(&session, pool_diff, StratumError::NtimeOutOfRange);
+
+            return Ok(self.bouncer.reject());
+        }
+
+        if let Some(skew) = clock_skew(
+            submit.ntime,
+            SystemTime::now(),
+            self.settings.max_clock_skew(),
+        ) {
+            warn!(
+                "Rejected ntime {skew}s from pool time from {} ({})",
+                session.username(),
+                self.socket_addr,
+            );
+
+            self.send_error(
+                id,
+                StratumError::ClockSkew,
+                Some(json!({
+                    "submit_ntime": submit_ntime,
+                    "skew": skew,
+                    "max_skew": self.settings.max_clock_skew().as_secs(),
+                })),
+            )
+            .await?;
+
+            self.send_event(rejection_event!(
+                session.address().to_string(),
+                session.workername().to_string(),
+                job.workbase.height(),
+                StratumError::ClockSkew
+            ));
+
+            self.record_rejected(&session, pool_diff, StratumError::ClockSkew);
 
             return Ok(self.bouncer.reject());
         }
@@ -875,7 +1438,7 @@ impl<W: Workbase> Stratifier<W> {
                         StratumError::InvalidVersionMask
                     ));
 
-                    session.record_rejected(pool_diff);
+                    self.record_rejected(&session, pool_diff, StratumError::InvalidVersionMask);
 
                     return Ok(self.bouncer.reject());
                 };
@@ -908,7 +1471,7 @@ impl<W: Workbase> Stratifier<W> {
                         StratumError::InvalidVersionMask
                     ));
 
-                    session.record_rejected(pool_diff);
+                    self.record_rejected(&session, pool_diff, StratumError::InvalidVersionMask);
 
                     return Ok(self.bouncer.reject());
                 }
@@ -929,23 +1492,9 @@ impl<W: Workbase> Stratifier<W> {
             _ => job.version(),
         };
 
-        let bits = job.nbits().to_compact();
-
-        let header = Header {
-            version: version.into(),
-            prev_blockhash: job.prevhash().into(),
-            merkle_root: stratum::merkle_root(
-                &job.coinb1,
-                &job.coinb2,
-                &job.enonce1,
-                &submit.enonce2,
-                job.merkle_branches(),
-            )?
-            .into(),
-            time: submit.ntime.into(),
-            bits,
-            nonce: submit.nonce.into(),
-        };
+        let coinbase = job.coinbase(&submit.enonce2);
+
+        let header = job.header(&coinbase, &submit, version);
 
         let hash = header.block_hash();
 
@@ -965,58 +1514,75 @@ impl<W: Workbase> Stratifier<W> {
                 StratumError::Duplicate
             ));
 
-            session.record_rejected(pool_diff);
+            self.record_rejected(&session, pool_diff, StratumError::Duplicate);
 
             return Ok(self.bouncer.reject());
         }
 
-        if let Ok(blockhash) = header.validate_pow(Target::from_compact(bits)) {
-            info!("Block with hash {blockhash} meets network difficulty");
-
-            self.metatron.record_block(blockhash);
-
-            match job.workbase.build_block(&job, &submit, header) {
-                Ok(block) => {
-                    info!("Submitting potential block solve");
-
-                    let block_hex = encode::serialize_hex(&block);
-                    let bitcoin_client = self.settings.bitcoin_rpc_client().await?;
+        let block_worthy = job.nbits().is_met_by(hash);
 
-                    let success = match bitcoin_client
-                        .call_raw::<String>("submitblock", &[json!(block_hex)])
-                        .await
-                    {
-                        Ok(msg) => {
-                            info!("submitblock returned: {msg}");
-                            false
-                        }
-                        Err(e) if e.to_string().contains("Empty data received") => true,
-                        Err(e) => {
-                            error!("Failed to submit block: {e}");
-                            false
-                        }
-                    };
+        if block_worthy {
+            info!(
+                "Block with hash {hash} meets network target {}",
+                target_as_block_hash(job.nbits().to_target())
+            );
 
-                    if success {
-                        info!("SUCCESSFULLY mined block {}", block.block_hash());
-
-                        self.send_event(Event::BlockFound(BlockFoundEvent {
-                            timestamp: None,
-                            blockheight: job.workbase.height(),
-                            blockhash: blockhash.to_string(),
-                            address: session.address().to_string(),
-                            workername: session.workername().to_string(),
-                            diff: Difficulty::from(job.nbits()).as_f64(),
-                            coinbase_value: job.workbase.coinbase_value(),
-                        }));
-                    }
-                }
+            self.metatron.record_block(FoundBlockEntry {
+                height: job.workbase.height(),
+                hash,
+                found_at_secs: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                finder: session.username().to_string(),
+                reward_sat: job
+                    .workbase
+                    .coinbase_value()
+                    .and_then(|value| value.try_into().ok()),
+            });
+
+            match job.workbase.build_block(coinbase, header) {
+                Ok(block) => self.submit_block(&job, &session, block).await?,
                 Err(err) => {
                     debug!("not building block in proxy mode: {err}");
                 }
             }
         }
 
+        // A block solve is never thrown away over which difficulty applied.
+        if !block_worthy
+            && self.settings.difficulty_transition() == DifficultyTransition::Strict
+            && self.vardiff.predates_diff_change(submit.job_id)
+        {
+            debug!(
+                "Rejected share from {} for job_id={} sent before the last difficulty change to {}",
+                session.username(),
+                submit.job_id,
+                self.vardiff.current_diff(),
+            );
+
+            self.send_error(
+                id,
+                StratumError::StaleDifficulty,
+                Some(json!({
+                    "job_id": submit.job_id,
+                    "difficulty": self.vardiff.current_diff(),
+                })),
+            )
+            .await?;
+
+            self.send_event(rejection_event!(
+                session.address().to_string(),
+                session.workername().to_string(),
+                job.workbase.height(),
+                StratumError::StaleDifficulty
+            ));
+
+            self.record_rejected(&session, pool_diff, StratumError::StaleDifficulty);
+
+            return Ok(self.bouncer.reject());
+        }
+
         if pool_diff != self.vardiff.current_diff() {
             debug!(
                 "Using stale pool_diff={} (current={}) for job_id={} from {}",
@@ -1027,14 +1593,17 @@ impl<W: Workbase> Stratifier<W> {
             );
         }
 
-        if !pool_diff.to_target().is_met_by(hash) {
-            let share_diff = Difficulty::from(hash);
+        let share_diff = Difficulty::of_hash(hash);
 
+        // A share that solves a block counts even when the pool target is
+        // above the network target, as it can be on test networks.
+        if !block_worthy && !pool_diff.is_nearly_met_by(hash, self.settings.share_tolerance()) {
             debug!(
-                "Rejected share above pool target from {}: share_diff={} pool_diff={}",
+                "Rejected share above pool target from {}: share_diff={} pool_diff={} target={}",
                 session.username(),
                 share_diff,
                 pool_diff,
+                target_as_block_hash(pool_diff.to_target()),
             );
 
             self.send_error(id, StratumError::AboveTarget, None).await?;
@@ -1043,21 +1612,50 @@ impl<W: Workbase> Stratifier<W> {
                 session.address().to_string(),
                 session.workername().to_string(),
                 pool_diff.as_f64(),
-                share_diff.as_f64(),
+                share_diff,
                 job.workbase.height(),
                 StratumError::AboveTarget
             ));
 
-            session.record_rejected(pool_diff);
+            self.record_rejected(&session, pool_diff, StratumError::AboveTarget);
 
             return Ok(self.bouncer.reject());
         }
 
-        let share_diff = Difficulty::from(hash);
+        // Maintenance only stops shares from counting. Invalid shares are
+        // still rejected as usual and block solves above were submitted.
+        match self.metatron.maintenance() {
+            Some(MaintenanceMode::Reject) => {
+                self.send_error(id, StratumError::Maintenance, None).await?;
+                self.metatron.record_reject(StratumError::Maintenance);
+                return Ok(Consequence::None);
+            }
+            Some(MaintenanceMode::Discard) => {
+                self.send(Message::Response {
+                    id,
+                    result: Some(json!(true)),
+                    error: None,
+                    reject_reason: None,
+                })
+                .await?;
+                return Ok(Consequence::None);
+            }
+            None => {}
+        }
+
+        let share_difficulty = Difficulty::from(share_diff);
 
-        session.record_accepted(pool_diff, share_diff);
+        if let Some(version_bits) = submit
+            .version_bits
+            .filter(|version_bits| *version_bits != Version::from(0))
+        {
+            session.record_version_rolled(version_bits);
+        }
 
-        self.submit_to_upstream(&job, &submit, share_diff, session.enonce1())
+        session.record_accepted(pool_diff, share_difficulty);
+        self.metatron.record_accepted_difficulty(pool_diff);
+
+        self.submit_to_upstream(&job, &submit, share_difficulty, session.enonce1())
             .await;
 
         self.send(Message::Response {
@@ -1068,16 +1666,45 @@ impl<W: Workbase> Stratifier<W> {
         })
         .await?;
 
-        self.send_event(Event::Share(ShareEvent {
-            timestamp: None,
+        let share = ShareEvent::from(AcceptedShare {
             address: session.address().to_string(),
             workername: session.workername().to_string(),
             pool_diff: pool_diff.as_f64(),
-            share_diff: share_diff.as_f64(),
-            result: true,
-            blockheight: Some(job.workbase.height()),
-            reject_reason: None,
-        }));
+            share_diff,
+            blockheight: job.workbase.height(),
+        });
+
+        if self.share_sampler.records(&share, block_worthy) {
+            self.send_event(Event::Share(share));
+        } else {
+            self.metatron.announce_share(&share);
+        }
+
+        let network_diff = Difficulty::from(job.nbits());
+
+        if self.metatron.record_interval_share(
+            job.workbase.height(),
+            share_difficulty,
+            network_diff,
+            self.settings.near_miss_factor(),
+        ) {
+            info!(
+                "Near miss from {}: share_diff={} network_diff={} hash={hash}",
+                session.username(),
+                share_diff,
+                network_diff,
+            );
+
+            self.send_event(Event::NearMiss(NearMissEvent {
+                timestamp: None,
+                blockheight: job.workbase.height(),
+                hash: hash.to_string(),
+                address: session.address().to_string(),
+                workername: session.workername().to_string(),
+                share_diff,
+                network_diff: network_diff.as_f64(),
+            }));
+        }
 
         self.bouncer.accept();
 
@@ -1091,8 +1718,6 @@ impl<W: Workbase> Stratifier<W> {
 
         let upstream_diff = self.upstream.as_ref().map(|upstream| upstream.difficulty());
 
-        let network_diff = Difficulty::from(job.nbits());
-
         if let Some(new_diff) = self
             .vardiff
             .record_share(pool_diff, network_diff, upstream_diff)
@@ -1109,15 +1734,64 @@ impl<W: Workbase> Stratifier<W> {
             self.vardiff
                 .record_diff_change_job_id(self.jobs.peek_next_id());
 
-            self.send(Message::Notification {
-                method: Method::SetDifficulty(SetDifficulty(new_diff)),
-            })
-            .await?;
+            self.send_difficulty(new_diff).await?;
         }
 
         Ok(Consequence::None)
     }
 
+    /// Submits a block solve to bitcoind, unless its coinbase pays out more
+    /// than the template allows.
+    async fn submit_block(&self, job: &Job<W>, session: &Session, block: Block) -> Result {
+        let hash = block.block_hash();
+
+        if let Err(err) = job.workbase.check_coinbase_value(
+            &block,
+            self.settings.chain(),
+            self.settings.coinbase_value_tolerance(),
+        ) {
+            error!("NOT submitting block {hash}, coinbase value check failed: {err}");
+            return Ok(());
+        }
+
+        info!("Submitting potential block solve");
+
+        let block_hex = encode::serialize_hex(&block);
+        let bitcoin_client = self.settings.bitcoin_rpc_client().await?;
+
+        let success = match bitcoin_client
+            .call_raw::<String>("submitblock", &[json!(block_hex)])
+            .await
+        {
+            Ok(msg) => {
+                info!("submitblock returned: {msg}");
+                false
+            }
+            Err(e) if e.to_string().contains("Empty data received") => true,
+            Err(e) => {
+                error!("Failed to submit block: {e}");
+                false
+            }
+        };
+
+        if success {
+            info!("SUCCESSFULLY mined block {hash}");
+
+            self.send_event(Event::BlockFound(BlockFoundEvent {
+                timestamp: None,
+                blockheight: job.workbase.height(),
+                blockhash: hash.to_string(),
+                address: session.address().to_string(),
+                workername: session.workername().to_string(),
+                diff: Difficulty::from(job.nbits()).as_f64(),
+                coinbase_value: job.workbase.coinbase_value(),
+                coinbase_address: job.payout_address.as_ref().map(ToString::to_string),
+            }));
+        }
+
+        Ok(())
+    }
+
     async fn submit_to_upstream(
         &self,
         job: &Job<W>,
@@ -1151,90 +1825,2013 @@ impl<W: Workbase> Stratifier<W> {
         upstream.submit_share(upstream_submit).await;
     }
 
-    async fn read_message(&mut self) -> Result<Option<Message>> {
-        if let Some(message) = self.inbox.pop_front() {
-            return Ok(Some(message));
-        }
-
-        match self.reader.next().await {
-            Some(Ok(line)) => {
-                let message = serde_json::from_str::<Message>(&line).map_err(|e| {
-                    anyhow!(
-                        "invalid stratum message from {}: {e}; line={line:?}",
-                        self.socket_addr
-                    )
-                })?;
-                Ok(Some(message))
+    async fn read_message(&mut self) -> Result<Option<Request>> {
+        loop {
+            if let Some(message) = self
+                .batch
+                .as_mut()
+                .and_then(|batch| batch.requests.pop_front())
+            {
+                return Ok(Some(message));
             }
-            Some(Err(e)) => Err(anyhow!("read error from {}: {e}", self.socket_addr)),
-            None => {
-                debug!("Client {} disconnected", self.socket_addr);
-                Ok(None)
+
+            if let Some(message) = self.inbox.pop_front() {
+                return Ok(Some(Request::Message(message)));
             }
-        }
-    }
 
-    async fn send(&mut self, message: Message) -> Result<()> {
-        let frame = serde_json::to_string(&message)?;
-        self.writer.send(frame).await?;
-        Ok(())
-    }
+            let line = match self.reader.next().await {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    if matches!(e, LinesCodecError::MaxLineLengthExceeded) {
+                        self.disconnect(DisconnectReason::OversizedMessage);
+                    }
 
-    async fn send_reconnect(&mut self) -> Result<()> {
-        self.send(Message::Notification {
-            method: Method::Reconnect(Reconnect::default()),
-        })
-        .await
-    }
+                    return Err(anyhow!("read error from {}: {e}", self.socket_addr));
+                }
+                None => {
+                    debug!("Client {} disconnected", self.socket_addr);
+                    return Ok(None);
+                }
+            };
 
-    async fn send_error(
-        &mut self,
-        id: Id,
-        error: StratumError,
-        traceback: Option<serde_json::Value>,
-    ) -> Result {
-        self.send(Message::Response {
-            id,
-            result: None,
-            error: Some(error.into_response(traceback)),
-            reject_reason: None,
-        })
-        .await
-    }
+            if !line.trim_start().starts_with('[') {
+                let err = match serde_json::from_str::<Message>(&line) {
+                    Ok(message) => return Ok(Some(Request::Message(message))),
+                    Err(err) => err,
+                };
 
-    fn send_event(&self, event: Event) {
-        if let Some(tx) = &self.event_tx
-            && let Err(e) = tx.try_send(event)
-        {
-            warn!("Failed to send event: {e}");
-        }
-    }
-}
+                if let Some(request) = self.account_request(&line) {
+                    return Ok(Some(Request::Account(request)));
+                }
 
-impl<W: Workbase> Drop for Stratifier<W> {
-    fn drop(&mut self) {
-        if let Some(order) = &self.order {
-            order.release_placement(&self.socket_addr);
-        }
+                let Some(response) = malformed_request(&line, &err) else {
+                    self.disconnect(DisconnectReason::InvalidMessage);
+                    bail!(
+                        "invalid stratum message from {}: {err}; line={line:?}",
+                        self.socket_addr
+                    );
+                };
 
-        if let Some(session) = self.state.working() {
-            info!(
-                "Retiring session for {} with workername {} and enonce1 {}",
-                self.socket_addr,
-                session.workername(),
-                session.enonce1()
-            );
+                warn!("Malformed request from {}: {err}", self.socket_addr);
 
-            if let Some(order) = &self.order {
-                order.remove_session(session.id());
+                self.send(response).await?;
+
+                continue;
             }
 
-            self.metatron
-                .retire_session(session, self.allocator.clone());
-        } else if let Some(enonce1) = self.state.enonce1() {
-            self.allocator.release_enonce1(enonce1);
-        }
+            let requests = serde_json::from_str::<Vec<Box<RawValue>>>(&line).map_err(|e| {
+                self.disconnect(DisconnectReason::InvalidMessage);
+                anyhow!(
+                    "invalid stratum batch from {}: {e}; line={line:?}",
+                    self.socket_addr
+                )
+            })?;
+
+            let max = self.settings.max_batch_size();
+
+            if requests.is_empty() || requests.len() > max {
+                warn!(
+                    "Rejected batch of {} messages from {} (max {max})",
+                    requests.len(),
+                    self.socket_addr
+                );
+
+                self.send_error(
+                    Id::Null,
+                    StratumError::InvalidArraySize,
+                    Some(json!({
+                        "message": "batch size out of range",
+                        "size": requests.len(),
+                        "max": max,
+                    })),
+                )
+                .await?;
+
+                continue;
+            }
+
+            debug!(
+                "Batch of {} messages from {}",
+                requests.len(),
+                self.socket_addr
+            );
+
+            let mut batch = Batch::default();
+
+            for request in requests {
+                let err = match serde_json::from_str::<Message>(request.get()) {
+                    Ok(message) => {
+                        batch.requests.push_back(Request::Message(message));
+                        continue;
+                    }
+                    Err(err) => err,
+                };
+
+                if let Some(account) = self.account_request(request.get()) {
+                    batch.requests.push_back(Request::Account(account));
+                    continue;
+                }
+
+                let Some(response) = malformed_request(request.get(), &err) else {
+                    self.disconnect(DisconnectReason::InvalidMessage);
+                    bail!(
+                        "invalid stratum batch from {}: {err}; line={line:?}",
+                        self.socket_addr
+                    );
+                };
+
+                warn!(
+                    "Malformed request in batch from {}: {err}",
+                    self.socket_addr
+                );
+
+                batch.responses.push(response);
+            }
+
+            self.batch = Some(batch);
+
+            // Nothing left to handle if every request was malformed.
+            self.flush_batch().await?;
+        }
+    }
+
+    /// Account ids are not addresses, so with a payout xpub configured an
+    /// authorize as `<ACCOUNT>.<WORKER>` fails to parse as a [`Message`] and is
+    /// picked out here instead, to be handled by [`Self::authorize_account`].
+    fn account_request(&self, raw: &str) -> Option<AccountAuthorize> {
+        self.settings.payout_xpub()?;
+
+        let value = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+
+        if value.get("method").and_then(|method| method.as_str()) != Some("mining.authorize") {
+            return None;
+        }
+
+        let id = serde_json::from_value::<Id>(value.get("id")?.clone()).ok()?;
+
+        let (account, workername) =
+            value["params"][0]
+                .as_str()?
+                .split_once('.')
+                .filter(|(account, workername)| {
+                    PayoutXpub::is_account(account) && !workername.is_empty()
+                })?;
+
+        Some(AccountAuthorize {
+            id,
+            account: account.into(),
+            workername: workername.into(),
+            password: value["params"][1].as_str().map(str::to_string),
+        })
+    }
+
+    /// Writes out a batch once all of its requests have been handled. A batch
+    /// made up only of notifications gets no response array.
+    async fn flush_batch(&mut self) -> Result {
+        let Some(batch) = self.batch.take_if(|batch| batch.requests.is_empty()) else {
+            return Ok(());
+        };
+
+        if !batch.responses.is_empty() {
+            self.write(self.settings.json_style().to_json(&batch.responses)?)
+                .await?;
+        }
+
+        for message in batch.notifications {
+            self.send(message).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send(&mut self, message: Message) -> Result<()> {
+        if let Some(batch) = &mut self.batch {
+            match message {
+                Message::Response { .. } => batch.responses.push(message),
+                _ => batch.notifications.push(message),
+            }
+
+            return Ok(());
+        }
+
+        let frame = self.settings.json_style().to_json(&message)?;
+        self.write(frame).await
+    }
+
+    /// Writes one frame. The socket's send buffer is the only queue, so a
+    /// miner that stops reading eventually blocks the write, and is dropped
+    /// once it has blocked for `write_timeout` rather than stalling the task.
+    async fn write(&mut self, frame: String) -> Result {
+        match timeout(self.write_timeout, self.writer.send(frame)).await {
+            Ok(result) => Ok(result?),
+            Err(_) => {
+                self.disconnect(DisconnectReason::SlowConsumer);
+                bail!(
+                    "Timed out after {}s writing to {}",
+                    self.write_timeout.as_secs_f64(),
+                    self.socket_addr
+                );
+            }
+        }
+    }
+
+    fn record_rejected(&self, session: &Session, pool_diff: Difficulty, reason: StratumError) {
+        session.record_rejected(pool_diff);
+        self.metatron.record_reject(reason);
+    }
+
+    /// Tells the miner its new difficulty. Jobs from the next one on are
+    /// judged at it, so this must go out before that job's `mining.notify`,
+    /// which holds as long as every difficulty change is sent right away.
+    async fn send_difficulty(&mut self, difficulty: Difficulty) -> Result {
+        self.send(Message::Notification {
+            method: Method::SetDifficulty(SetDifficulty(difficulty)),
+        })
+        .await?;
+
+        if self.settings.announce_difficulty() {
+            self.send(Message::Notification {
+                method: Method::ShowMessage(ShowMessage(format!("Difficulty set to {difficulty}"))),
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_reconnect(&mut self) -> Result<()> {
+        // The connection is going away, so unanswered batch requests are
+        // dropped rather than held back behind the reconnect.
+        self.batch = None;
+
+        self.send(Message::Notification {
+            method: Method::Reconnect(Reconnect::default()),
+        })
+        .await
+    }
+
+    async fn send_error(
+        &mut self,
+        id: Id,
+        error: StratumError,
+        traceback: Option<serde_json::Value>,
+    ) -> Result {
+        self.send(Message::Response {
+            id,
+            result: None,
+            error: Some(error.into_response(traceback)),
+            reject_reason: None,
+        })
+        .await
+    }
+
+    fn send_event(&self, event: Event) {
+        if let Event::Share(share) = &event {
+            self.metatron.announce_share(share);
+        }
+
+        if let Some(tx) = &self.event_tx
+            && let Err(e) = tx.try_send(event)
+        {
+            warn!("Failed to send event: {e}");
+        }
+    }
+}
+
+/// Seconds `ntime` is ahead of (positive) or behind (negative) `now`, if
+/// that is more than `tolerance`. A miner with a badly set clock would
+/// otherwise only find out when the network rejects its block.
+fn clock_skew(ntime: Ntime, now: SystemTime, tolerance: Duration) -> Option<i64> {
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+
+    let skew = i64::from(ntime.0) - now;
+
+    (skew.unsigned_abs() > tolerance.as_secs()).then_some(skew)
+}
+
+/// The error response for a request whose params failed to parse, so the
+/// miner hears about it instead of being disconnected. `None` if `raw` is not
+/// recognisable as a request at all.
+fn malformed_request(raw: &str, err: &serde_json::Error) -> Option<Message> {
+    let value = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+
+    let id = serde_json::from_value::<Id>(value.get("id")?.clone())
+        .ok()
+        .filter(|id| *id != Id::Null)?;
+
+    let method = value.get("method")?.as_str()?;
+
+    Some(Message::Response {
+        id,
+        result: None,
+        error: Some(StratumError::InvalidParams.into_response(Some(json!({
+            "method": method,
+            "message": err.to_string(),
+        })))),
+        reject_reason: None,
+    })
+}
+
+/// Releases everything the connection holds in the metatron and allocator.
+/// Runs on clean exit as well as on panic unwind and task abort, so session
+/// counts cannot leak.
+impl<W: Workbase> Drop for Stratifier<W> {
+    fn drop(&mut self) {
+        let reason = self.disconnect_reason.unwrap_or(DisconnectReason::Error);
+
+        self.connection.update(
+            self.state.identity().as_ref(),
+            self.state.version_mask(),
+            self.vardiff.current_diff(),
+        );
+
+        debug!("Disconnected {}: {reason}", self.socket_addr);
+
+        self.metatron
+            .record_disconnect(&self.connection, reason, Instant::now());
+        self.metatron.remove_connection(&self.connection);
+
+        if let Some(order) = &self.order {
+            order.release_placement(&self.socket_addr);
+        }
+
+        for session in self.workers.drain_sessions() {
+            if let Some(order) = &self.order {
+                order.remove_session(session.id());
+            }
+
+            self.metatron.close_session(&session);
+        }
+
+        if let Some(session) = self.state.working() {
+            info!(
+                "Retiring session for {} with workername {} and enonce1 {}",
+                self.socket_addr,
+                session.workername(),
+                session.enonce1()
+            );
+
+            if let Some(order) = &self.order {
+                order.remove_session(session.id());
+            }
+
+            if let Some(token) = self.resume_token.take()
+                && let Some(window) = self.settings.resume_window()
+            {
+                self.metatron.issue_resume_ticket(
+                    token,
+                    ResumeTicket {
+                        enonce1: session.enonce1().clone(),
+                        difficulty: self.vardiff.current_diff(),
+                        version_mask: self.state.version_mask(),
+                        expires: Instant::now() + window,
+                    },
+                );
+            }
+
+            self.metatron
+                .retire_session(session, self.allocator.clone());
+        } else if let Some(enonce1) = self.state.enonce1() {
+            self.allocator.release_enonce1(enonce1);
+        }
 
         debug!("Shutting down stratifier for {}", self.socket_addr,);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::event_sink::ShareOutcome, tokio::net::TcpStream};
+
+    fn test_auth(enonce1: &str, workername: &str) -> Arc<Authorization> {
+        let username = format!("tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.{workername}")
+            .parse::<Username>()
+            .unwrap();
+
+        Arc::new(Authorization {
+            enonce1: enonce1.parse().unwrap(),
+            address: username.address().clone().assume_checked(),
+            workername: workername.into(),
+            username,
+            version_mask: None,
+        })
+    }
+
+    struct Connection {
+        stratifier: Stratifier<BlockTemplate>,
+        client: TcpStream,
+        workbase_tx: watch::Sender<Arc<BlockTemplate>>,
+    }
+
+    fn test_metatron() -> (Arc<Metatron>, tempfile::TempDir) {
+        let (metatron, directory) = Metatron::test();
+        (Arc::new(metatron), directory)
+    }
+
+    fn test_allocator() -> Arc<EnonceAllocator> {
+        Arc::new(EnonceAllocator::new(
+            Extranonces::Pool(PoolExtranonces::new(4, 8).unwrap()),
+            0,
+        ))
+    }
+
+    async fn connection(metatron: &Arc<Metatron>, settings: Settings) -> Connection {
+        connection_with_allocator(metatron, settings, test_allocator()).await
+    }
+
+    async fn connection_with_allocator(
+        metatron: &Arc<Metatron>,
+        settings: Settings,
+        allocator: Arc<EnonceAllocator>,
+    ) -> Connection {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, socket_addr) = listener.accept().await.unwrap();
+        let (read_half, write_half) = stream.into_split();
+
+        let (workbase_tx, workbase_rx) = watch::channel(Arc::new(BlockTemplate::default()));
+
+        let start_diff = settings.start_diff();
+
+        let stratifier = Stratifier::new(
+            socket_addr,
+            Arc::new(settings),
+            allocator,
+            metatron.clone(),
+            None,
+            FramedRead::new(read_half, LinesCodec::new_with_max_length(MAX_MESSAGE_SIZE)),
+            FramedWrite::new(write_half, LinesCodec::new()),
+            VecDeque::new(),
+            workbase_rx,
+            CancellationToken::new(),
+            None,
+            start_diff,
+            None,
+            None,
+        );
+
+        Connection {
+            stratifier,
+            client,
+            workbase_tx,
+        }
+    }
+
+    async fn working_connection(metatron: &Arc<Metatron>) -> Connection {
+        let mut connection = connection(metatron, Settings::default()).await;
+
+        connection.stratifier.state =
+            State::Working(metatron.new_session(test_auth("deadbeef", "foo"), 0));
+        connection
+            .stratifier
+            .workers
+            .insert_session(metatron.new_session(test_auth("deadbeef", "bar"), 0));
+
+        connection
+    }
+
+    struct Client {
+        metatron: Arc<Metatron>,
+        lines: FramedRead<OwnedReadHalf, LinesCodec>,
+        write_half: OwnedWriteHalf,
+        workbase_tx: watch::Sender<Arc<BlockTemplate>>,
+        handle: tokio::task::JoinHandle<Result>,
+        _directory: Option<tempfile::TempDir>,
+    }
+
+    impl Client {
+        async fn next_line(&mut self) -> serde_json::Value {
+            let line = timeout(Duration::from_secs(5), self.lines.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// The next line that is not a notification.
+        async fn next_response(&mut self) -> serde_json::Value {
+            loop {
+                let line = self.next_line().await;
+
+                if line.get("method").is_none() {
+                    return line;
+                }
+            }
+        }
+
+        async fn start_work(&mut self) -> WorkJob {
+            self.start_work_at(BlockTemplate::default().bits).await
+        }
+
+        /// Sends a template with network target `bits`, timestamped now so
+        /// its shares pass the clock skew check, then subscribes and
+        /// authorizes, returning the job the pool sends.
+        async fn start_work_at(&mut self, bits: Nbits) -> WorkJob {
+            self.workbase_tx
+                .send(Arc::new(BlockTemplate {
+                    bits,
+                    current_time: Ntime::from(
+                        SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs() as u32,
+                    ),
+                    ..BlockTemplate::default()
+                }))
+                .unwrap();
+
+            self.write_line(
+                &json!([
+                    {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                    {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+                ])
+                .to_string(),
+            )
+            .await;
+
+            loop {
+                let line = self.next_line().await;
+
+                if line["method"] == "mining.notify" {
+                    return WorkJob {
+                        job_id: line["params"][0].as_str().unwrap().into(),
+                        ntime: line["params"][7].as_str().unwrap().into(),
+                    };
+                }
+            }
+        }
+
+        async fn write_line(&mut self, line: &str) {
+            use tokio::io::AsyncWriteExt;
+
+            self.write_half
+                .write_all(format!("{line}\n").as_bytes())
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Pool arguments under which every share on a job from
+    /// [`Client::start_work`] meets the pool target.
+    const WORKING_POOL: &str = "para pool --start-diff 0.0000000001";
+
+    /// A job the client was sent, for submitting shares against.
+    struct WorkJob {
+        job_id: String,
+        ntime: String,
+    }
+
+    impl WorkJob {
+        fn submit(&self, id: u64, nonce: u32) -> String {
+            json!({
+                "id": id,
+                "method": "mining.submit",
+                "params": [
+                    "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo",
+                    self.job_id,
+                    "0000000000000000",
+                    self.ntime,
+                    format!("{nonce:08x}"),
+                ],
+            })
+            .to_string()
+        }
+    }
+
+    /// Serves a connection to a pool of its own in the background.
+    async fn spawn_client(settings: Settings) -> Client {
+        let (metatron, directory) = test_metatron();
+
+        Client {
+            _directory: Some(directory),
+            ..spawn_client_on(&metatron, test_allocator(), settings).await
+        }
+    }
+
+    /// Serves a connection to `metatron` in the background, for tests that
+    /// run several connections against the same pool.
+    async fn spawn_client_on(
+        metatron: &Arc<Metatron>,
+        allocator: Arc<EnonceAllocator>,
+        settings: Settings,
+    ) -> Client {
+        let Connection {
+            mut stratifier,
+            client,
+            workbase_tx,
+        } = connection_with_allocator(metatron, settings, allocator).await;
+
+        let handle = tokio::spawn(async move { stratifier.serve().await });
+
+        let (read_half, write_half) = client.into_split();
+
+        Client {
+            metatron: metatron.clone(),
+            lines: FramedRead::new(read_half, LinesCodec::new()),
+            write_half,
+            workbase_tx,
+            handle,
+            _directory: None,
+        }
+    }
+
+    async fn send_line(settings: Settings, line: &str) -> Client {
+        let mut client = spawn_client(settings).await;
+        client.write_line(line).await;
+        client
+    }
+
+    #[tokio::test]
+    async fn batch_gets_batched_response() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            r#"[{"id":1,"method":"mining.subscribe","params":["test/1.0"]},{"id":2,"method":"mining.authorize","params":["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo","x"]}]"#,
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert!(responses[0]["error"].is_null());
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["result"], true);
+
+        assert_eq!(client.next_line().await["method"], "mining.set_difficulty");
+        assert_eq!(client.next_line().await["method"], "mining.notify");
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn spaced_json_style_is_written_to_miners() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --json-style spaced"),
+            r#"[{"id":1,"method":"mining.configure","params":[[],{}]}]"#,
+        )
+        .await;
+
+        let line = timeout(Duration::from_secs(5), client.lines.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert!(line.starts_with(r#"[{"id": 1, "result": "#), "{line}");
+        assert!(!line.contains(['\n', '\r']), "{line}");
+
+        client.handle.abort();
+    }
+
+    async fn configured_version_mask(args: &str, requested: &str) -> serde_json::Value {
+        let mut client = send_line(
+            Settings::from_pool_args(args),
+            &json!({
+                "id": 1,
+                "method": "mining.configure",
+                "params": [["version-rolling"], {"version-rolling.mask": requested}],
+            })
+            .to_string(),
+        )
+        .await;
+
+        let response = client.next_line().await;
+
+        client.handle.abort();
+
+        assert_eq!(response["result"]["version-rolling"], true);
+
+        response["result"]["version-rolling.mask"].clone()
+    }
+
+    #[tokio::test]
+    async fn configure_answers_negotiated_version_mask() {
+        assert_eq!(
+            configured_version_mask("para pool", "00fff000").await,
+            "00ffe000"
+        );
+        assert_eq!(
+            configured_version_mask("para pool", "ffffffff").await,
+            "1fffe000"
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_without_common_mask_bits_disables_version_rolling() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &json!({
+                "id": 1,
+                "method": "mining.configure",
+                "params": [["version-rolling"], {"version-rolling.mask": "00001fff"}],
+            })
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(
+            client.next_line().await["result"],
+            json!({"version-rolling": false})
+        );
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn legacy_version_mask_answers_pool_mask() {
+        assert_eq!(
+            configured_version_mask("para pool --legacy-version-mask", "00fff000").await,
+            "1fffe000"
+        );
+    }
+
+    #[tokio::test]
+    async fn retarget_set_difficulty_precedes_next_notify() {
+        let mut client =
+            spawn_client(Settings::from_pool_args("para pool --announce-difficulty")).await;
+
+        client
+            .write_line(r#"{"id":1,"method":"mining.subscribe","params":["test/1.0"]}"#)
+            .await;
+        client
+            .write_line(
+                r#"{"id":2,"method":"mining.authorize","params":["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo","x"]}"#,
+            )
+            .await;
+
+        let mut methods = Vec::new();
+
+        for _ in 0..5 {
+            let line = client.next_line().await;
+            methods.push(line["method"].as_str().unwrap_or("response").to_string());
+
+            if line["method"] == "client.show_message" {
+                assert_eq!(line["params"][0], "Difficulty set to 1");
+            }
+        }
+
+        client
+            .write_line(r#"{"id":3,"method":"mining.suggest_difficulty","params":[1000]}"#)
+            .await;
+
+        for _ in 0..3 {
+            let line = client.next_line().await;
+            methods.push(line["method"].as_str().unwrap_or("response").to_string());
+
+            if line["method"] == "mining.set_difficulty" {
+                assert_eq!(line["params"][0], 1000);
+            }
+        }
+
+        client.workbase_tx.send_replace(Arc::new(BlockTemplate {
+            height: 1,
+            ..Default::default()
+        }));
+
+        methods.push(client.next_line().await["method"].as_str().unwrap().into());
+
+        assert_eq!(
+            methods,
+            [
+                "response",
+                "response",
+                "mining.set_difficulty",
+                "client.show_message",
+                "mining.notify",
+                "response",
+                "mining.set_difficulty",
+                "client.show_message",
+                "mining.notify",
+            ]
+        );
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn reloaded_coinbase_address_pays_subsequent_jobs() {
+        const OLD: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        const NEW: &str = "3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX";
+
+        let script = |address: &str| {
+            hex::encode(
+                address
+                    .parse::<Address<NetworkUnchecked>>()
+                    .unwrap()
+                    .assume_checked()
+                    .script_pubkey()
+                    .as_bytes(),
+            )
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coinbase.txt");
+        fs::write(&path, format!("{OLD}\n")).unwrap();
+
+        let settings = Settings::from_pool_args(&format!(
+            "para pool --coinbase-address-file {}",
+            path.display()
+        ));
+
+        let coinbase_addresses = settings.coinbase_addresses().clone();
+
+        let mut client = spawn_client(settings).await;
+
+        client
+            .write_line(r#"{"id":1,"method":"mining.subscribe","params":["test/1.0"]}"#)
+            .await;
+        client
+            .write_line(
+                r#"{"id":2,"method":"mining.authorize","params":["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo","x"]}"#,
+            )
+            .await;
+
+        let next_notify = async |client: &mut Client| loop {
+            let line = client.next_line().await;
+
+            if line["method"] == "mining.notify" {
+                break line["params"][3].as_str().unwrap().to_string();
+            }
+        };
+
+        let coinb2 = next_notify(&mut client).await;
+        assert!(coinb2.contains(&script(OLD)));
+
+        fs::write(&path, format!("{NEW}\n")).unwrap();
+        assert!(coinbase_addresses.reload().unwrap().is_some());
+
+        client.workbase_tx.send_replace(Arc::new(BlockTemplate {
+            height: 1,
+            ..Default::default()
+        }));
+
+        let coinb2 = next_notify(&mut client).await;
+        assert!(coinb2.contains(&script(NEW)));
+        assert!(!coinb2.contains(&script(OLD)));
+
+        assert!(!client.handle.is_finished());
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn worker_name_policy_rejects_authorize() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --worker-name-max-length 5 --worker-name-charset -"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.toolong", "x"]},
+                {"id": 3, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.rig_1", "x"]},
+                {"id": 4, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.rig-1", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses.as_array().unwrap().len(), 4);
+
+        assert_eq!(responses[1]["error"][0], StratumError::Unauthorized as i32);
+        assert!(
+            responses[1]["error"][2]["message"]
+                .as_str()
+                .unwrap()
+                .contains("at most 5 allowed")
+        );
+
+        assert_eq!(responses[2]["error"][0], StratumError::Unauthorized as i32);
+        assert!(
+            responses[2]["error"][2]["message"]
+                .as_str()
+                .unwrap()
+                .contains("disallowed character `_`")
+        );
+
+        assert_eq!(responses[3]["result"], true);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn required_password_rejects_missing_password() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --password-policy required"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo"]},
+                {"id": 3, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", ""]},
+                {"id": 4, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+
+        for response in &responses.as_array().unwrap()[1..3] {
+            assert_eq!(response["error"][0], StratumError::Unauthorized as i32);
+            assert_eq!(response["error"][2]["message"], "password required");
+        }
+
+        assert_eq!(responses[3]["result"], true);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn optional_password_accepts_missing_password() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses[1]["result"], true);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn password_difficulty_hint_sets_difficulty() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --password-policy difficulty-hint --start-diff 1"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+                {"id": 3, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x,d=1024"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+
+        assert_eq!(responses[1]["error"][0], StratumError::Unauthorized as i32);
+        assert_eq!(
+            responses[1]["error"][2]["message"],
+            "password must include a difficulty hint like `d=1024`"
+        );
+
+        assert_eq!(responses[2]["result"], true);
+
+        let set_difficulty = client.next_line().await;
+        assert_eq!(set_difficulty["method"], "mining.set_difficulty");
+        assert_eq!(set_difficulty["params"][0], 1024);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn password_difficulty_hint_applies_to_additional_workers() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --password-policy required --start-diff 1"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses[1]["result"], true);
+
+        let set_difficulty = client.next_line().await;
+        assert_eq!(set_difficulty["method"], "mining.set_difficulty");
+        assert_eq!(set_difficulty["params"][0], 1);
+
+        let notify = client.next_line().await;
+        assert_eq!(notify["method"], "mining.notify");
+
+        client
+            .write_line(
+                &json!({"id": 3, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.bar", "x,d=1024"]})
+                    .to_string(),
+            )
+            .await;
+
+        assert_eq!(client.next_response().await["result"], true);
+
+        let set_difficulty = client.next_line().await;
+        assert_eq!(set_difficulty["method"], "mining.set_difficulty");
+        assert_eq!(set_difficulty["params"][0], 1024);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn payout_xpub_accounts_get_distinct_stable_addresses() {
+        let xpub = crate::payout_xpub::test_xpub(bitcoin::NetworkKind::Test);
+        let payout = PayoutXpub::new(xpub, Chain::Regtest).unwrap();
+        let args = format!("para pool --chain regtest --payout-xpub {xpub}");
+
+        let (metatron, _directory) = test_metatron();
+
+        let mut addresses = Vec::new();
+
+        for username in ["alice.rig1", "bob.rig1", "alice.rig2"] {
+            let mut client =
+                spawn_client_on(&metatron, test_allocator(), Settings::from_pool_args(&args)).await;
+
+            client
+                .write_line(
+                    &json!({"id": 1, "method": "mining.subscribe", "params": []}).to_string(),
+                )
+                .await;
+            assert_eq!(client.next_response().await["id"], 1);
+
+            client
+                .write_line(
+                    &json!({"id": 2, "method": "mining.authorize", "params": [username, "x"]})
+                        .to_string(),
+                )
+                .await;
+            assert_eq!(client.next_response().await["result"], true, "{username}");
+
+            // A further round trip ensures the connection details are updated.
+            client
+                .write_line(
+                    &json!({"id": 3, "method": "mining.subscribe", "params": []}).to_string(),
+                )
+                .await;
+            assert_eq!(client.next_response().await["id"], 3);
+
+            addresses.push(metatron.connections()[0].details().address.unwrap());
+
+            client.handle.abort();
+            assert!(client.handle.await.unwrap_err().is_cancelled());
+        }
+
+        let alice = payout.derive(0).unwrap();
+        let bob = payout.derive(1).unwrap();
+
+        assert_ne!(alice, bob);
+        assert_eq!(addresses, [alice.clone(), bob, alice]);
+
+        assert_eq!(metatron.account_index("alice", u32::MAX).unwrap(), Some(0));
+        assert_eq!(metatron.account_index("bob", u32::MAX).unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn rejected_account_authorize_assigns_no_index() {
+        let xpub = crate::payout_xpub::test_xpub(bitcoin::NetworkKind::Test);
+
+        let mut client = send_line(
+            Settings::from_pool_args(&format!(
+                "para pool --chain regtest --payout-xpub {xpub} --worker-name-max-length 5"
+            )),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["alice.toolong", "x"]},
+                {"id": 3, "method": "mining.authorize", "params": ["bob.rig1", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses[1]["error"][0], StratumError::Unauthorized as i32);
+        assert_eq!(responses[2]["result"], true);
+
+        assert_eq!(
+            client.metatron.peek_account_index("bob", u32::MAX).unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            client
+                .metatron
+                .peek_account_index("alice", u32::MAX)
+                .unwrap(),
+            Some(1)
+        );
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn max_payout_accounts_rejects_new_accounts() {
+        let xpub = crate::payout_xpub::test_xpub(bitcoin::NetworkKind::Test);
+
+        let mut client = send_line(
+            Settings::from_pool_args(&format!(
+                "para pool --chain regtest --payout-xpub {xpub} --max-payout-accounts 1"
+            )),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["alice.rig1", "x"]},
+                {"id": 3, "method": "mining.authorize", "params": ["bob.rig1", "x"]},
+                {"id": 4, "method": "mining.authorize", "params": ["alice.rig2", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses[1]["result"], true);
+        assert_eq!(responses[2]["error"][0], StratumError::Unauthorized as i32);
+        assert_eq!(responses[3]["result"], true);
+
+        assert_eq!(
+            client.metatron.peek_account_index("alice", 1).unwrap(),
+            Some(0)
+        );
+        assert_eq!(client.metatron.peek_account_index("bob", 1).unwrap(), None);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn maintenance_mode_stops_counting_shares() {
+        let mut client = spawn_client(Settings::from_pool_args(WORKING_POOL)).await;
+        let job = client.start_work().await;
+
+        client
+            .metatron
+            .set_maintenance(Some(MaintenanceMode::Reject));
+        client.write_line(&job.submit(3, 1)).await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["id"], 3);
+        assert_eq!(response["error"][0], StratumError::Maintenance as i32);
+        assert_eq!(
+            client.metatron.rejects(),
+            [(StratumError::Maintenance, 1)].into()
+        );
+
+        client
+            .metatron
+            .set_maintenance(Some(MaintenanceMode::Discard));
+        client.write_line(&job.submit(4, 2)).await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["id"], 4);
+        assert_eq!(response["result"], true);
+
+        let stats = client.metatron.snapshot();
+        assert_eq!(stats.accepted_shares, 0);
+        assert_eq!(stats.rejected_shares, 0);
+
+        client.write_line(&job.submit(5, 2)).await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["id"], 5);
+        assert_eq!(response["error"][0], StratumError::Duplicate as i32);
+
+        client.metatron.set_maintenance(None);
+        client.write_line(&job.submit(6, 3)).await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["id"], 6);
+        assert_eq!(response["result"], true);
+
+        assert_eq!(client.metatron.snapshot().accepted_shares, 1);
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn maintenance_mode_still_records_block_solves() {
+        let mut client = spawn_client(Settings::from_pool_args(WORKING_POOL)).await;
+        let job = client
+            .start_work_at(Nbits::from(CompactTarget::from_consensus(0x2100ffff)))
+            .await;
+
+        client
+            .metatron
+            .set_maintenance(Some(MaintenanceMode::Reject));
+        client.write_line(&job.submit(3, 1)).await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["error"][0], StratumError::Maintenance as i32);
+        assert_eq!(client.metatron.block_count(), 1);
+
+        client.handle.abort();
+    }
+
+    async fn submit_after_difficulty_change(args: &str, bits: Nbits) -> serde_json::Value {
+        let mut client = spawn_client(Settings::from_pool_args(args)).await;
+        let job = client.start_work_at(bits).await;
+
+        client
+            .write_line(
+                &json!({"id": 3, "method": "mining.suggest_difficulty", "params": [0.001]})
+                    .to_string(),
+            )
+            .await;
+        client.write_line(&job.submit(4, 1)).await;
+
+        let response = loop {
+            let response = client.next_response().await;
+
+            if response["id"] == 4 {
+                break response;
+            }
+        };
+
+        client.handle.abort();
+
+        response
+    }
+
+    #[tokio::test]
+    async fn strict_difficulty_transition_rejects_old_job_share() {
+        let response = submit_after_difficulty_change(
+            &format!("{WORKING_POOL} --difficulty-transition strict"),
+            BlockTemplate::default().bits,
+        )
+        .await;
+
+        assert_eq!(response["error"][0], StratumError::StaleDifficulty as i32);
+    }
+
+    #[tokio::test]
+    async fn strict_difficulty_transition_keeps_old_job_block_solve() {
+        let response = submit_after_difficulty_change(
+            &format!("{WORKING_POOL} --difficulty-transition strict"),
+            Nbits::from(CompactTarget::from_consensus(0x2100ffff)),
+        )
+        .await;
+
+        assert_eq!(response["result"], true);
+    }
+
+    #[tokio::test]
+    async fn rejected_submits_counted_by_reason() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+                {"id": 3, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "ff", "0000000000000000", "00000000", "00000000"]},
+                {"id": 4, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "fe", "0000000000000000", "00000000", "00000000"]},
+                {"id": 5, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.bar", "ff", "0000000000000000", "00000000", "00000000"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses.as_array().unwrap().len(), 5);
+
+        assert_eq!(
+            client.metatron.rejects(),
+            [(StratumError::WorkerMismatch, 1), (StratumError::Stale, 2)].into()
+        );
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn submit_far_from_pool_clock_rejected() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+                {"id": 3, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "0", "0000000000000000", "00000000", "00000000"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses[2]["error"][0], StratumError::ClockSkew as i32);
+
+        assert_eq!(
+            client.metatron.rejects(),
+            [(StratumError::ClockSkew, 1)].into()
+        );
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn rejected_submits_reach_share_feed() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &json!([
+                {"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]},
+                {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+            ])
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(client.next_line().await.as_array().unwrap().len(), 2);
+
+        let mut shares = client.metatron.subscribe_shares();
+
+        client
+            .write_line(
+                &json!({"id": 3, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "ff", "0000000000000000", "00000000", "00000000"]})
+                    .to_string(),
+            )
+            .await;
+
+        assert_eq!(client.next_response().await["id"], 3);
+
+        let share = timeout(Duration::from_secs(5), shares.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(share.address, "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq");
+        assert_eq!(share.workername, "foo");
+        assert_eq!(
+            share.outcome,
+            ShareOutcome::Rejected(StratumError::Stale.to_string())
+        );
+        assert!(share.timestamp.is_some());
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn subscription_id_resumes_session() {
+        let (metatron, _directory) = test_metatron();
+
+        let allocator = test_allocator();
+
+        // Sessions are only held for resumption once they have submitted.
+        let subscribe = |params: serde_json::Value| {
+            let metatron = metatron.clone();
+            let allocator = allocator.clone();
+
+            async move {
+                let mut client =
+                    spawn_client_on(&metatron, allocator, Settings::from_pool_args("para pool"))
+                        .await;
+
+                client
+                    .write_line(
+                        &json!([
+                            {"id": 1, "method": "mining.subscribe", "params": params},
+                            {"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]},
+                            {"id": 3, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "ff", "0000000000000000", "00000000", "00000000"]},
+                        ])
+                        .to_string(),
+                    )
+                    .await;
+
+                let result = client.next_line().await[0]["result"].clone();
+
+                client.handle.abort();
+                let _ = (&mut client.handle).await;
+
+                result
+            }
+        };
+
+        let first = subscribe(json!(["test/1.0"])).await;
+        let session_id = first[1].as_str().unwrap();
+
+        assert_eq!(
+            first[0],
+            json!([
+                ["mining.set_difficulty", session_id],
+                ["mining.notify", session_id],
+            ])
+        );
+
+        let resumed = subscribe(json!(["test/1.0", session_id])).await;
+        assert_eq!(resumed[1], session_id);
+
+        let fresh = subscribe(json!(["test/1.0"])).await;
+        assert_ne!(fresh[1], session_id);
+
+        let unknown = subscribe(json!(["test/1.0", "ffffffff"])).await;
+        assert_ne!(unknown[1], "ffffffff");
+    }
+
+    /// Runs one connection through `opening`, authorize and a submit, so its
+    /// session is held once it drops. Returns the subscribe result and the
+    /// difficulty sent after authorize.
+    async fn resumable_session(
+        metatron: &Arc<Metatron>,
+        allocator: &Arc<EnonceAllocator>,
+        opening: Vec<serde_json::Value>,
+    ) -> (serde_json::Value, serde_json::Value) {
+        let mut client = spawn_client_on(
+            metatron,
+            allocator.clone(),
+            Settings::from_pool_args("para pool --resume-window 60"),
+        )
+        .await;
+
+        let mut batch = opening;
+        batch.push(json!({"id": 2, "method": "mining.authorize", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "x"]}));
+        batch.push(json!({"id": 3, "method": "mining.submit", "params": ["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo", "ff", "0000000000000000", "00000000", "00000000"]}));
+
+        client.write_line(&json!(batch).to_string()).await;
+
+        let result = client.next_line().await[0]["result"].clone();
+
+        let set_difficulty = client.next_line().await;
+        assert_eq!(set_difficulty["method"], "mining.set_difficulty");
+
+        client.handle.abort();
+        let _ = (&mut client.handle).await;
+
+        (result, set_difficulty["params"][0].clone())
+    }
+
+    fn resume_token(result: &serde_json::Value) -> String {
+        result[0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|subscription| subscription[0] == "mining.resume")
+            .unwrap()[1]
+            .as_str()
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn resume_token_restores_enonce1_and_difficulty() {
+        let (metatron, _directory) = test_metatron();
+
+        let allocator = test_allocator();
+
+        let (first, difficulty) = resumable_session(
+            &metatron,
+            &allocator,
+            vec![
+                json!({"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]}),
+                json!({"id": 4, "method": "mining.suggest_difficulty", "params": [1000]}),
+            ],
+        )
+        .await;
+
+        assert_eq!(difficulty, json!(1000));
+
+        let (resumed, difficulty) = resumable_session(
+            &metatron,
+            &allocator,
+            vec![json!({"id": 1, "method": "mining.resume", "params": ["test/1.0", resume_token(&first)]})],
+        )
+        .await;
+
+        assert_eq!(resumed[1], first[1]);
+        assert_eq!(difficulty, json!(1000));
+        assert_ne!(
+            resume_token(&resumed),
+            resume_token(&first),
+            "each session gets a new token"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_resume_token_gets_fresh_session() {
+        let (metatron, _directory) = test_metatron();
+
+        let allocator = test_allocator();
+
+        let (first, _) = resumable_session(
+            &metatron,
+            &allocator,
+            vec![
+                json!({"id": 1, "method": "mining.subscribe", "params": ["test/1.0"]}),
+                json!({"id": 4, "method": "mining.suggest_difficulty", "params": [1000]}),
+            ],
+        )
+        .await;
+
+        let token = resume_token(&first);
+
+        let ticket = metatron.take_resume_ticket(&token, Instant::now()).unwrap();
+
+        metatron.issue_resume_ticket(
+            token.clone(),
+            ResumeTicket {
+                expires: Instant::now(),
+                ..ticket
+            },
+        );
+
+        let (fresh, difficulty) = resumable_session(
+            &metatron,
+            &allocator,
+            vec![json!({"id": 1, "method": "mining.resume", "params": ["test/1.0", token]})],
+        )
+        .await;
+
+        assert_ne!(fresh[1], first[1]);
+        assert_ne!(difficulty, json!(1000));
+    }
+
+    #[tokio::test]
+    async fn unknown_resume_token_gets_fresh_session() {
+        let (metatron, _directory) = test_metatron();
+
+        let allocator = test_allocator();
+
+        let (fresh, _) = resumable_session(
+            &metatron,
+            &allocator,
+            vec![json!({"id": 1, "method": "mining.resume", "params": ["test/1.0", "00112233445566778899aabbccddeeff"]})],
+        )
+        .await;
+
+        assert!(fresh[1].is_string());
+        assert_eq!(resume_token(&fresh).len(), 32);
+    }
+
+    #[tokio::test]
+    async fn resume_rejected_without_resume_window() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            r#"{"id":1,"method":"mining.resume","params":["test/1.0","00112233445566778899aabbccddeeff"]}"#,
+        )
+        .await;
+
+        let response = client.next_response().await;
+        assert_eq!(response["id"], 1);
+        assert!(!response["error"].is_null());
+
+        client.handle.abort();
+    }
+
+    #[test]
+    fn clock_skew_within_tolerance() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let tolerance = Duration::from_secs(7200);
+
+        assert_eq!(clock_skew(Ntime::from(1_700_000_000), now, tolerance), None);
+        assert_eq!(clock_skew(Ntime::from(1_700_007_200), now, tolerance), None);
+        assert_eq!(clock_skew(Ntime::from(1_699_992_800), now, tolerance), None);
+    }
+
+    #[test]
+    fn clock_skew_far_future() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let tolerance = Duration::from_secs(7200);
+
+        assert_eq!(
+            clock_skew(Ntime::from(1_700_007_201), now, tolerance),
+            Some(7201)
+        );
+        assert_eq!(
+            clock_skew(Ntime::from(1_800_000_000), now, tolerance),
+            Some(100_000_000)
+        );
+    }
+
+    #[test]
+    fn clock_skew_far_past() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let tolerance = Duration::from_secs(7200);
+
+        assert_eq!(
+            clock_skew(Ntime::from(1_699_992_799), now, tolerance),
+            Some(-7201)
+        );
+        assert_eq!(
+            clock_skew(Ntime::from(0), now, tolerance),
+            Some(-1_700_000_000)
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn working_connection_logs_extranonce_layout() {
+        let logs = CapturedLogs::default();
+
+        let writer = logs.clone();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_max_level(tracing::Level::DEBUG)
+                .with_ansi(false)
+                .with_writer(move || writer.clone())
+                .finish(),
+        );
+
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            r#"[{"id":1,"method":"mining.configure","params":[["version-rolling"],{"version-rolling.mask":"1fffe000"}]},{"id":2,"method":"mining.subscribe","params":["test/1.0"]},{"id":3,"method":"mining.authorize","params":["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo","x"]},{"id":4,"method":"mining.submit","params":["bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.foo","ff","0000000000000000","00000000","00000000"]}]"#,
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        assert_eq!(responses.as_array().unwrap().len(), 4);
+
+        let enonce1 = responses[1]["result"][1].as_str().unwrap().to_string();
+
+        client.handle.abort();
+
+        let logs = String::from_utf8(logs.0.lock().clone()).unwrap();
+
+        let line = logs
+            .lines()
+            .find(|line| line.contains("Extranonce layout"))
+            .unwrap_or_else(|| panic!("no extranonce layout line in:\n{logs}"));
+
+        assert!(line.contains("DEBUG"), "{line}");
+        assert!(line.contains(&format!("enonce1={enonce1} ")), "{line}");
+        assert!(line.contains("enonce1_size=4 "), "{line}");
+        assert!(line.contains("enonce2_size=8 "), "{line}");
+        assert!(line.contains("version_mask=1fffe000 "), "{line}");
+        assert!(line.ends_with("start_diff=1"), "{line}");
+    }
+
+    #[tokio::test]
+    async fn connection_registered_until_dropped() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            r#"{"id":1,"method":"mining.subscribe","params":["bitaxe/2.12.0"]}"#,
+        )
+        .await;
+
+        assert_eq!(client.next_line().await["id"], 1);
+
+        let connections = client.metatron.connections();
+        assert_eq!(connections.len(), 1);
+
+        let details = connections[0].details();
+        assert_eq!(details.user_agent.as_deref(), Some("bitaxe/2.12.0"));
+        assert_eq!(details.address, None);
+        assert_eq!(details.difficulty, Difficulty::default());
+
+        client.handle.abort();
+        assert!(client.handle.await.unwrap_err().is_cancelled());
+
+        assert!(client.metatron.connections().is_empty());
+    }
+
+    #[tokio::test]
+    async fn oversized_batch_rejected() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool --max-batch-size 1"),
+            r#"[{"id":1,"method":"mining.subscribe","params":[]},{"id":2,"method":"mining.subscribe","params":[]}]"#,
+        )
+        .await;
+
+        let response = client.next_line().await;
+        assert!(response["id"].is_null());
+        assert!(response["result"].is_null());
+        assert_eq!(response["error"][0], StratumError::InvalidArraySize as i32);
+        assert_eq!(response["error"][2]["size"], 2);
+        assert_eq!(response["error"][2]["max"], 1);
+
+        assert!(!client.handle.is_finished());
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn aborted_connection_closes_its_sessions() {
+        let (metatron, _directory) = test_metatron();
+
+        let mut connections = Vec::new();
+        for _ in 0..3 {
+            connections.push(working_connection(&metatron).await);
+        }
+
+        assert_eq!(metatron.total_sessions(), 6);
+
+        let handles = connections
+            .into_iter()
+            .map(|connection| {
+                tokio::spawn(async move {
+                    let Connection {
+                        mut stratifier,
+                        client: _client,
+                        workbase_tx: _workbase_tx,
+                    } = connection;
+
+                    stratifier.serve().await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.abort();
+            assert!(handle.await.unwrap_err().is_cancelled());
+        }
+
+        assert_eq!(metatron.total_sessions(), 0);
+    }
+
+    #[tokio::test]
+    async fn panicked_connection_closes_its_sessions() {
+        let (metatron, _directory) = test_metatron();
+
+        let connection = working_connection(&metatron).await;
+
+        assert_eq!(metatron.total_sessions(), 2);
+
+        let handle = tokio::spawn(async move {
+            let _connection = connection;
+            panic!("connection task panicked");
+        });
+
+        assert!(handle.await.unwrap_err().is_panic());
+
+        assert_eq!(metatron.total_sessions(), 0);
+    }
+
+    async fn serve_until_closed(
+        metatron: &Metatron,
+        mut stratifier: Stratifier<BlockTemplate>,
+    ) -> DisconnectReason {
+        let _ = timeout(Duration::from_secs(5), stratifier.serve())
+            .await
+            .unwrap();
+
+        drop(stratifier);
+
+        let reasons = metatron.disconnects().reasons;
+        assert_eq!(reasons.values().sum::<u64>(), 1, "{reasons:?}");
+        *reasons.keys().next().unwrap()
+    }
+
+    async fn serve_line_until_closed(line: &str) -> DisconnectReason {
+        use tokio::io::AsyncWriteExt;
+
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            stratifier,
+            mut client,
+            workbase_tx: _workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        client
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .unwrap();
+
+        serve_until_closed(&metatron, stratifier).await
+    }
+
+    #[tokio::test]
+    async fn client_eof_records_disconnect_reason() {
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            stratifier,
+            client,
+            workbase_tx: _workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        drop(client);
+
+        assert_eq!(
+            serve_until_closed(&metatron, stratifier).await,
+            DisconnectReason::ClientEof
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_records_disconnect_reason() {
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            mut stratifier,
+            client: _client,
+            workbase_tx: _workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        stratifier.bouncer = Bouncer::with_idle_timeout(Duration::from_millis(10));
+
+        assert_eq!(
+            serve_until_closed(&metatron, stratifier).await,
+            DisconnectReason::Idle
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_records_disconnect_reason() {
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            stratifier,
+            client: _client,
+            workbase_tx: _workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        stratifier.cancel.cancel();
+
+        assert_eq!(
+            serve_until_closed(&metatron, stratifier).await,
+            DisconnectReason::Shutdown
+        );
+    }
+
+    #[tokio::test]
+    async fn upstream_loss_records_disconnect_reason() {
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            stratifier,
+            client: _client,
+            workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        drop(workbase_tx);
+
+        assert_eq!(
+            serve_until_closed(&metatron, stratifier).await,
+            DisconnectReason::UpstreamLost
+        );
+    }
+
+    #[tokio::test]
+    async fn non_reading_client_records_slow_consumer_disconnect() {
+        use tokio::io::AsyncWriteExt;
+
+        let (metatron, _directory) = test_metatron();
+
+        let Connection {
+            mut stratifier,
+            client,
+            workbase_tx: _workbase_tx,
+        } = connection(&metatron, Settings::default()).await;
+
+        stratifier.write_timeout = Duration::from_millis(100);
+
+        let (_read_half, mut write_half) = client.into_split();
+
+        // Long ids make every response large, so the socket fills quickly.
+        let requests = format!(
+            r#"{{"id":"{}","method":"mining.suggest_difficulty","params":[1000]}}"#,
+            "x".repeat(1000)
+        ) + "\n";
+        let requests = requests.repeat(100);
+
+        let flood = tokio::spawn(async move {
+            while write_half.write_all(requests.as_bytes()).await.is_ok() {}
+        });
+
+        assert_eq!(
+            serve_until_closed(&metatron, stratifier).await,
+            DisconnectReason::SlowConsumer
+        );
+
+        flood.abort();
+    }
+
+    #[tokio::test]
+    async fn invalid_message_records_disconnect_reason() {
+        assert_eq!(
+            serve_line_until_closed("not json").await,
+            DisconnectReason::InvalidMessage
+        );
+
+        assert_eq!(
+            serve_line_until_closed("[not json]").await,
+            DisconnectReason::InvalidMessage
+        );
+
+        assert_eq!(
+            serve_line_until_closed(
+                r#"{"id":null,"method":"mining.suggest_difficulty","params":"nope"}"#
+            )
+            .await,
+            DisconnectReason::InvalidMessage
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_params_get_error_response() {
+        let methods = [
+            "mining.configure",
+            "mining.subscribe",
+            "mining.authorize",
+            "mining.submit",
+            "mining.suggest_difficulty",
+        ];
+
+        let mut lines = methods
+            .iter()
+            .enumerate()
+            .map(|(id, method)| json!({"id": id, "method": method, "params": "nope"}).to_string())
+            .collect::<Vec<String>>();
+
+        lines.push(r#"{"id":99,"method":"mining.subscribe","params":["test/1.0"]}"#.into());
+
+        let mut client = send_line(Settings::from_pool_args("para pool"), &lines.join("\n")).await;
+
+        for (id, method) in methods.iter().enumerate() {
+            let response = client.next_line().await;
+            assert_eq!(response["id"], id);
+            assert_eq!(response["result"], serde_json::Value::Null);
+            assert_eq!(response["error"][0], StratumError::InvalidParams as i32);
+            assert_eq!(response["error"][2]["method"], *method);
+        }
+
+        let response = client.next_line().await;
+        assert_eq!(response["id"], 99);
+        assert!(response["result"].is_array());
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn malformed_params_in_batch_get_error_response() {
+        let mut client = send_line(
+            Settings::from_pool_args("para pool"),
+            &[
+                r#"[{"id":1,"method":"mining.authorize","params":"nope"},{"id":2,"method":"mining.subscribe","params":["test/1.0"]}]"#,
+                r#"[{"id":3,"method":"mining.submit","params":[1]}]"#,
+            ]
+            .join("\n"),
+        )
+        .await;
+
+        let responses = client.next_line().await;
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["error"][0], StratumError::InvalidParams as i32);
+        assert_eq!(responses[1]["id"], 2);
+        assert!(responses[1]["result"].is_array());
+
+        let responses = client.next_line().await;
+        assert_eq!(responses.as_array().unwrap().len(), 1);
+        assert_eq!(responses[0]["id"], 3);
+        assert_eq!(responses[0]["error"][0], StratumError::InvalidParams as i32);
+
+        assert!(!client.handle.is_finished());
+
+        client.handle.abort();
+    }
+
+    #[tokio::test]
+    async fn oversized_message_records_disconnect_reason() {
+        assert_eq!(
+            serve_line_until_closed(&"x".repeat(MAX_MESSAGE_SIZE + 1)).await,
+            DisconnectReason::OversizedMessage
+        );
+    }
+
+    #[tokio::test]
+    async fn ban_records_disconnect_reason_for_worker() {
+        let (metatron, _directory) = test_metatron();
+
+        let mut connection = working_connection(&metatron).await;
+
+        assert!(
+            connection
+                .stratifier
+                .handle_protocol_consequence(Consequence::Drop)
+                .await
+        );
+
+        drop(connection);
+
+        let disconnects = metatron.disconnects();
+
+        assert_eq!(disconnects.reasons, [(DisconnectReason::Banned, 1)].into());
+        assert_eq!(disconnects.workers.len(), 1);
+        assert_eq!(disconnects.workers[0].worker_name, "foo");
+        assert_eq!(
+            disconnects.workers[0].reasons,
+            [(DisconnectReason::Banned, 1)].into()
+        );
+    }
+
+    #[tokio::test]
+    async fn first_disconnect_reason_sticks() {
+        let (metatron, _directory) = test_metatron();
+
+        let mut connection = connection(&metatron, Settings::default()).await;
+
+        connection.stratifier.disconnect(DisconnectReason::PoolFull);
+
+        assert!(
+            connection
+                .stratifier
+                .handle_protocol_consequence(Consequence::Drop)
+                .await
+        );
+
+        drop(connection);
+
+        assert_eq!(
+            metatron.disconnects().reasons,
+            [(DisconnectReason::PoolFull, 1)].into()
+        );
+    }
+}
@@ -132,6 +132,16 @@ impl Bouncer {
         Consequence::None
     }
 
+    #[cfg(test)]
+    pub(crate) fn with_idle_timeout(idle_timeout: Duration) -> Self {
+        Self {
+            auth_timeout: idle_timeout,
+            idle_timeout,
+            check_interval: idle_timeout,
+            ..Self::new(false)
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn is_authorized(&self) -> bool {
         self.authorized
@@ -26,6 +26,63 @@ impl Identity {
             Identity::Working(session) => session.address(),
         }
     }
+
+    pub(crate) fn username(&self) -> &Username {
+        match self {
+            Identity::Authorized(auth) => &auth.username,
+            Identity::Working(session) => session.username(),
+        }
+    }
+}
+
+/// Worker names authorized on a connection after the first one. They share
+/// the connection's enonce1 and address, and each gets its own session once
+/// it submits.
+#[derive(Default)]
+pub(crate) struct Workers {
+    authorized: HashSet<Username>,
+    sessions: HashMap<Username, Arc<Session>>,
+}
+
+impl Workers {
+    /// Registers `username` as an additional worker. Returns false if
+    /// `address` differs from the one already authorized on the connection.
+    pub(crate) fn authorize(
+        &mut self,
+        identity: &Identity,
+        username: Username,
+        address: &Address,
+    ) -> bool {
+        if identity.address() != address {
+            return false;
+        }
+
+        if identity.username() != &username {
+            self.authorized.insert(username);
+        }
+
+        true
+    }
+
+    pub(crate) fn contains(&self, username: &Username) -> bool {
+        self.authorized.contains(username)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.authorized.len()
+    }
+
+    pub(crate) fn session(&self, username: &Username) -> Option<Arc<Session>> {
+        self.sessions.get(username).cloned()
+    }
+
+    pub(crate) fn insert_session(&mut self, session: Arc<Session>) {
+        self.sessions.insert(session.username().clone(), session);
+    }
+
+    pub(crate) fn drain_sessions(&mut self) -> impl Iterator<Item = Arc<Session>> + '_ {
+        self.sessions.drain().map(|(_, session)| session)
+    }
 }
 
 pub(crate) struct Authorization {
@@ -296,6 +353,78 @@ mod tests {
         assert_eq!(identity.address(), &test_address());
     }
 
+    fn test_username(workername: &str) -> Username {
+        format!("tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.{workername}")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn workers_authorize_multiple_names_for_same_address() {
+        let identity = Identity::Authorized(test_authorization());
+        let mut workers = Workers::default();
+
+        assert!(workers.authorize(&identity, test_username("foo"), &test_address()));
+        assert!(workers.authorize(&identity, test_username("baz"), &test_address()));
+        assert!(workers.authorize(&identity, test_username("foo"), &test_address()));
+
+        assert_eq!(workers.len(), 2);
+        assert!(workers.contains(&test_username("foo")));
+        assert!(workers.contains(&test_username("baz")));
+    }
+
+    #[test]
+    fn workers_reauthorizing_primary_is_not_tracked() {
+        let identity = Identity::Authorized(test_authorization());
+        let mut workers = Workers::default();
+
+        assert!(workers.authorize(&identity, test_username("bar"), &test_address()));
+
+        assert_eq!(workers.len(), 0);
+        assert!(!workers.contains(&test_username("bar")));
+    }
+
+    #[test]
+    fn workers_reject_address_mismatch() {
+        let identity = Identity::Authorized(test_authorization());
+        let mut workers = Workers::default();
+
+        let other: Username = "tb1qft5p2uhsdcdc3l2ua4ap5qqfg4pjaqlp250x7us7a8qqhrxrxfsqaqh7jw.foo"
+            .parse()
+            .unwrap();
+
+        let other_address = other.parse_with_network(Network::Testnet4).unwrap();
+
+        assert!(!workers.authorize(&identity, other.clone(), &other_address));
+
+        assert_eq!(workers.len(), 0);
+        assert!(!workers.contains(&other));
+    }
+
+    #[test]
+    fn workers_keep_one_session_per_name() {
+        let mut workers = Workers::default();
+
+        assert!(workers.session(&test_username("foo")).is_none());
+
+        let session = Arc::new(Session::new(
+            SessionId::new(0, 1),
+            test_enonce1(),
+            test_address(),
+            "foo".into(),
+            test_username("foo"),
+            None,
+        ));
+        workers.insert_session(session.clone());
+
+        assert_eq!(
+            workers.session(&test_username("foo")).unwrap().id(),
+            session.id()
+        );
+        assert_eq!(workers.drain_sessions().count(), 1);
+        assert!(workers.session(&test_username("foo")).is_none());
+    }
+
     #[test]
     fn display_shows_correct_state_names() {
         let mut state = State::new();
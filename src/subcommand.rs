@@ -1,27 +1,53 @@
 use super::*;
 
+pub mod config;
+pub mod import_ckpool_log;
 pub mod miner;
 pub mod ping;
 pub mod pool;
 pub mod proxy;
 pub mod router;
+pub mod selftest;
 pub mod server;
 pub mod sync;
 pub mod template;
 pub mod wallet;
 
+/// Port for stratum endpoints given without one.
+#[derive(Clone, Copy, Debug, Args)]
+pub(crate) struct StratumPortOptions {
+    #[arg(
+        long,
+        default_value_t = DEFAULT_STRATUM_PORT,
+        help = "Dial <STRATUM_PORT> when the endpoint has no port."
+    )]
+    stratum_port: u16,
+}
+
+impl StratumPortOptions {
+    pub(crate) fn port(self) -> u16 {
+        self.stratum_port
+    }
+}
+
 #[derive(Debug, Parser)]
 pub(crate) enum Subcommand {
+    #[command(about = "Inspect settings")]
+    Config(config::ConfigCommand),
+    #[command(about = "Import a ckpool share log")]
+    ImportCkpoolLog(import_ckpool_log::ImportCkpoolLog),
     #[command(about = "Run a toy miner")]
     Miner(miner::Miner),
     #[command(about = "Measure Stratum message ping")]
     Ping(ping::Ping),
     #[command(about = "Run a toy solo pool")]
-    Pool(pool::Pool),
+    Pool(Box<pool::Pool>),
     #[command(about = "Run a toy stratum proxy")]
     Proxy(proxy::Proxy),
     #[command(about = "Run a toy hashrate router")]
     Router(router::RouterCommand),
+    #[command(about = "Mine one share end-to-end against a stratum endpoint")]
+    Selftest(selftest::Selftest),
     #[command(about = "Run API server")]
     Server(server::Server),
     #[command(about = "Sync shares via HTTP")]
@@ -39,11 +65,14 @@ impl Subcommand {
         logs: Arc<logs::Logs>,
     ) -> Result {
         match self {
+            Self::Config(config) => config.run(),
+            Self::ImportCkpoolLog(import) => import.run().await,
             Self::Miner(miner) => miner.run(cancel_token).await,
             Self::Ping(ping) => ping.run(cancel_token).await,
             Self::Pool(pool) => pool.run(cancel_token, logs).await,
             Self::Proxy(proxy) => proxy.run(cancel_token, logs).await,
             Self::Router(router) => router.run(cancel_token, logs).await,
+            Self::Selftest(selftest) => selftest.run(cancel_token).await,
             Self::Server(server) => {
                 let handle = Handle::new();
 
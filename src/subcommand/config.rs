@@ -0,0 +1,79 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct ConfigCommand {
+    #[command(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Subcommand {
+    #[command(about = "Resolve and validate settings without starting any services")]
+    Check(Check),
+}
+
+#[derive(Debug, Parser)]
+struct Check {
+    #[command(subcommand)]
+    target: Target,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Target {
+    #[command(about = "Check pool settings")]
+    Pool(Box<PoolOptions>),
+    #[command(about = "Check proxy settings")]
+    Proxy(Box<ProxyOptions>),
+    #[command(about = "Check router settings")]
+    Router(Box<RouterOptions>),
+}
+
+impl ConfigCommand {
+    pub(crate) fn run(self) -> Result {
+        match self.subcommand {
+            Subcommand::Check(check) => check.run(),
+        }
+    }
+}
+
+impl Check {
+    fn run(self) -> Result {
+        let (name, settings) = match self.target {
+            Target::Pool(options) => ("pool", Settings::from_pool_options(*options)),
+            Target::Proxy(options) => ("proxy", Settings::from_proxy_options(*options)),
+            Target::Router(options) => ("router", Settings::from_router_options(*options)),
+        };
+
+        let settings = settings.with_context(|| format!("invalid {name} settings"))?;
+
+        println!("{:#?}", settings.redacted());
+        println!("{name} settings are valid");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let arguments = Arguments::try_parse_from(
+            "para config check pool --chain regtest --vardiff-window 60".split_whitespace(),
+        )
+        .unwrap();
+
+        let crate::subcommand::Subcommand::Config(ConfigCommand {
+            subcommand:
+                Subcommand::Check(Check {
+                    target: Target::Pool(options),
+                }),
+        }) = arguments.subcommand
+        else {
+            panic!("unexpected subcommand");
+        };
+
+        assert_eq!(options.common.vardiff_window, 60.0);
+    }
+}
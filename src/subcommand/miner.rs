@@ -1,8 +1,17 @@
-use {super::*, controller::Controller, hasher::Hasher, metrics::Metrics, stratum::client::Client};
+use {
+    super::*,
+    controller::Controller,
+    hasher::Search,
+    metrics::{Metrics, Totals},
+    stratum::client::{Client, Proxy},
+};
 
 mod controller;
-mod hasher;
-mod metrics;
+pub(crate) mod hasher;
+pub(crate) mod metrics;
+
+/// How often lifetime totals are written to the state file while mining.
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 pub enum Mode {
@@ -15,6 +24,8 @@ pub enum Mode {
 pub(crate) struct Miner {
     #[arg(help = "Stratum <HOST:PORT>.")]
     stratum_endpoint: String,
+    #[command(flatten)]
+    stratum_port: StratumPortOptions,
     #[arg(long, help = "Stratum <USERNAME>.")]
     username: Username,
     #[arg(long, help = "Stratum <PASSWORD>.")]
@@ -32,6 +43,16 @@ pub(crate) struct Miner {
     throttle: Option<HashRate>,
     #[arg(long, help = "Disable version rolling.")]
     disable_version_rolling: bool,
+    #[arg(
+        long,
+        help = "Keep lifetime hash and share totals in <STATE_FILE> across restarts."
+    )]
+    state_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Dial the pool through <PROXY>, e.g. socks5://127.0.0.1:9050 or http://proxy:3128."
+    )]
+    proxy: Option<Proxy>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,13 +73,25 @@ impl Miner {
             self.stratum_endpoint, self.username
         );
 
-        let client = Client::new(
-            ensure_port(&self.stratum_endpoint),
-            self.username.clone(),
-            self.password.clone(),
-            USER_AGENT.into(),
-            Duration::from_secs(10),
-        );
+        // The proxy resolves the pool's host name, which may not resolve
+        // locally at all, e.g. a Tor onion address.
+        let stratum_endpoint = match &self.proxy {
+            Some(proxy) => {
+                info!("Connecting through proxy {proxy}");
+                ensure_port(&self.stratum_endpoint, self.stratum_port.port())
+            }
+            None => {
+                resolve_stratum_endpoint(&self.stratum_endpoint, self.stratum_port.port()).await?
+            }
+        };
+
+        let client = Client::builder()
+            .with_address(stratum_endpoint)
+            .with_username(self.username.clone())
+            .with_password(self.password.clone())
+            .with_user_agent(USER_AGENT)
+            .with_proxy(self.proxy.clone())
+            .build()?;
 
         let mut system = System::new();
         system.refresh_cpu_all();
@@ -73,8 +106,39 @@ impl Miner {
         info!("Available CPU cores: {}", available_cpu_cores);
         info!("CPU cores to use: {}", cpu_cores);
 
-        let shares = Controller::run(
+        let metrics = Arc::new(match &self.state_file {
+            Some(path) => {
+                let totals = Totals::load(path)?;
+                info!(
+                    "Resuming from {} lifetime hashes and {} shares",
+                    totals.hashes, totals.shares
+                );
+                Metrics::resume(totals)
+            }
+            None => Metrics::new(),
+        });
+
+        if let Some(path) = self.state_file.clone() {
+            let metrics = metrics.clone();
+            let cancel_token = cancel_token.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = cancel_token.cancelled() => break,
+                        _ = sleep(STATE_SAVE_INTERVAL) => {}
+                    }
+
+                    if let Err(err) = metrics.lifetime().save(&path) {
+                        warn!("{err:#}");
+                    }
+                }
+            });
+        }
+
+        let result = Controller::run(
             client,
+            metrics.clone(),
             self.username.clone(),
             cpu_cores,
             self.throttle,
@@ -82,7 +146,23 @@ impl Miner {
             self.disable_version_rolling,
             cancel_token,
         )
-        .await?;
+        .await;
+
+        let lifetime = metrics.lifetime();
+
+        info!(
+            "Session totals: {} hashes, {} shares; lifetime totals: {} hashes, {} shares",
+            metrics.total_hashes(),
+            metrics.total_shares(),
+            lifetime.hashes,
+            lifetime.shares
+        );
+
+        if let Some(path) = &self.state_file {
+            lifetime.save(path)?;
+        }
+
+        let shares = result?;
 
         println!("{}", serde_json::to_string_pretty(&shares)?);
 
@@ -124,6 +204,30 @@ mod tests {
         );
 
         assert_eq!(miner.cpu_cores, Some(8));
+        assert_eq!(miner.state_file, None);
+        assert_eq!(miner.proxy, None);
+    }
+
+    #[test]
+    fn parse_args_with_state_file() {
+        let miner = parse_miner_args(
+            "para miner parasite.wtf:42069 \
+                --username bc1q8jx6g9ujlqmdx3jnt3ap6ll2fdwqjdkdgs959m.worker1 \
+                --state-file /tmp/miner.json",
+        );
+
+        assert_eq!(miner.state_file, Some(PathBuf::from("/tmp/miner.json")));
+    }
+
+    #[test]
+    fn parse_args_with_proxy() {
+        let miner = parse_miner_args(
+            "para miner parasite.wtf:42069 \
+                --username bc1q8jx6g9ujlqmdx3jnt3ap6ll2fdwqjdkdgs959m.worker1 \
+                --proxy socks5://127.0.0.1:9050",
+        );
+
+        assert_eq!(miner.proxy, Some(Proxy::Socks5("127.0.0.1:9050".into())));
     }
 
     #[test]
@@ -9,7 +9,8 @@ pub(crate) struct Controller {
     client: Client,
     cpu_cores: usize,
     enonce1: Extranonce,
-    enonce2: Arc<Mutex<Extranonce>>,
+    enonce2: Arc<Mutex<Option<Extranonce>>>,
+    enonce2_size: usize,
     hasher_cancel: Option<CancellationToken>,
     hashers: TaskTracker,
     hasher_handles: Vec<JoinHandle<()>>,
@@ -28,8 +29,10 @@ pub(crate) struct Controller {
 }
 
 impl Controller {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn run(
         client: Client,
+        metrics: Arc<Metrics>,
         username: Username,
         cpu_cores: usize,
         throttle: Option<HashRate>,
@@ -48,11 +51,12 @@ impl Controller {
             client,
             cpu_cores,
             enonce1: Extranonce::zeros(0),
-            enonce2: Arc::new(Mutex::new(Extranonce::zeros(0))),
+            enonce2: Arc::new(Mutex::new(None)),
+            enonce2_size: 0,
             hasher_cancel: None,
             hashers: TaskTracker::new(),
             hasher_handles: Vec::new(),
-            metrics: Arc::new(Metrics::new()),
+            metrics,
             notify_rx,
             notify_tx,
             mode,
@@ -180,7 +184,8 @@ impl Controller {
         );
 
         self.enonce1 = subscribe.enonce1;
-        self.enonce2 = Arc::new(Mutex::new(Extranonce::zeros(subscribe.enonce2_size)));
+        self.enonce2 = Arc::new(Mutex::new(Some(Extranonce::zeros(subscribe.enonce2_size))));
+        self.enonce2_size = subscribe.enonce2_size;
 
         Ok(events)
     }
@@ -202,9 +207,15 @@ impl Controller {
                         Ok(stratum::client::Event::Notify(notify)) => {
                             self.handle_notify(notify).await?;
                         }
+                        Ok(stratum::client::Event::NewBlock(prevhash)) => {
+                            info!("New block from stratum server: prevhash={prevhash}");
+                        }
                         Ok(stratum::client::Event::SetDifficulty(difficulty)) => {
                             self.handle_set_difficulty(difficulty);
                         }
+                        Ok(stratum::client::Event::Message(message)) => {
+                            info!("Message from stratum server: {message}");
+                        }
                         Ok(stratum::client::Event::Reconnect(_)) => {
                             info!("Received client.reconnect from server");
                             self.cancel_hashers();
@@ -302,54 +313,33 @@ impl Controller {
                             break;
                         }
 
-                        let enonce2 = {
-                            let mut guard = enonce2.lock();
-                            let enonce2 = guard.clone();
-                            guard.increment_wrapping();
-                            enonce2
-                        };
-
-                        let merkle_root = stratum::merkle_root(
-                            &notify.coinb1,
-                            &notify.coinb2,
-                            &enonce1,
-                            &enonce2,
-                            &notify.merkle_branches,
-                        )
-                        .expect("merkle root should calculate");
-
-                        let header = Header {
-                            version: notify.version.into(),
-                            prev_blockhash: notify.prevhash.clone().into(),
-                            merkle_root: merkle_root.into(),
-                            time: notify.ntime.into(),
-                            bits: notify.nbits.into(),
-                            nonce: 0,
-                        };
-
-                        let pool_target = { pool_difficulty.lock().to_target() };
-
-                        let mut hasher = Hasher {
-                            version: notify.version,
-                            header,
-                            pool_target,
+                        let search = Search {
+                            notify: notify.clone(),
+                            enonce1: enonce1.clone(),
                             enonce2: enonce2.clone(),
-                            job_id: notify.job_id,
+                            pool_target: pool_difficulty.lock().to_target(),
                             version_mask,
+                            start_nonce: 0,
                         };
 
                         let cancel_clone = cancel.clone();
                         let metrics_clone = metrics.clone();
 
                         let result = task::spawn_blocking(move || {
-                            hasher.hash(cancel_clone, metrics_clone, throttle)
+                            search.run(cancel_clone, metrics_clone, throttle)
                         })
                         .await;
 
                         match result {
-                            Ok(Ok(share)) => {
+                            Ok(Ok(Some(share))) => {
                                 let _ = share_tx.send(share).await;
                             }
+                            Ok(Ok(None)) => {
+                                info!(
+                                    "Extranonce2 space exhausted on core {core_id}, waiting for a new job"
+                                );
+                                break;
+                            }
                             Ok(Err(err)) => {
                                 warn!("Hasher failed on core {core_id}: {err}");
                                 if cancel.is_cancelled() {
@@ -369,6 +359,12 @@ impl Controller {
     async fn handle_notify(&mut self, notify: Notify) -> Result {
         info!("New job: job_id={}", notify.job_id,);
 
+        // A new job means a new coinbase, so a used up extranonce2 space
+        // starts over.
+        self.enonce2
+            .lock()
+            .get_or_insert_with(|| Extranonce::zeros(self.enonce2_size));
+
         let cancel = if notify.clean_jobs {
             self.cancel_hashers()
         } else {
@@ -8,6 +8,10 @@ pub(crate) enum HasherError {
     NonceSpaceExhausted { nonce: u32 },
 }
 
+/// A share found by a hasher: the job it is for, the solved header, the
+/// extranonce2 used, and any rolled version bits.
+pub(crate) type Solution = (JobId, Header, Extranonce, Option<Version>);
+
 #[derive(Debug)]
 pub(crate) struct Hasher {
     pub(crate) enonce2: Extranonce,
@@ -24,7 +28,7 @@ impl Hasher {
         cancel: CancellationToken,
         metrics: Arc<Metrics>,
         throttle: f64,
-    ) -> Result<(JobId, Header, Extranonce, Option<Version>), HasherError> {
+    ) -> Result<Solution, HasherError> {
         const BATCH: u64 = 10_000;
 
         let mut rng = rand::rng();
@@ -89,10 +93,180 @@ impl Hasher {
     }
 }
 
+/// Hands out the next unused extranonce2, or `None` once the space is used
+/// up for the current job.
+pub(crate) fn next_enonce2(enonce2: &Mutex<Option<Extranonce>>) -> Option<Extranonce> {
+    let mut guard = enonce2.lock();
+    let current = guard.take()?;
+
+    let mut next = current.clone();
+    if next.increment() {
+        *guard = Some(next);
+    }
+
+    Some(current)
+}
+
+/// One hasher's search of a job for a share. Each time the nonce space runs
+/// out it rolls to the next extranonce2 and keeps searching the same job.
+pub(crate) struct Search {
+    pub(crate) notify: Notify,
+    pub(crate) enonce1: Extranonce,
+    pub(crate) enonce2: Arc<Mutex<Option<Extranonce>>>,
+    pub(crate) pool_target: Target,
+    pub(crate) version_mask: Option<Version>,
+    pub(crate) start_nonce: u32,
+}
+
+impl Search {
+    /// Returns `None` once every extranonce2 has been searched, in which case
+    /// the hasher should idle until the next job.
+    pub(crate) fn run(
+        self,
+        cancel: CancellationToken,
+        metrics: Arc<Metrics>,
+        throttle: f64,
+    ) -> Result<Option<Solution>, HasherError> {
+        while let Some(enonce2) = next_enonce2(&self.enonce2) {
+            let merkle_root = stratum::merkle_root(
+                &self.notify.coinb1,
+                &self.notify.coinb2,
+                &self.enonce1,
+                &enonce2,
+                &self.notify.merkle_branches,
+            )
+            .expect("merkle root should calculate");
+
+            let mut hasher = Hasher {
+                version: self.notify.version,
+                header: Header {
+                    version: self.notify.version.into(),
+                    prev_blockhash: self.notify.prevhash.clone().into(),
+                    merkle_root: merkle_root.into(),
+                    time: self.notify.ntime.into(),
+                    bits: self.notify.nbits.into(),
+                    nonce: self.start_nonce,
+                },
+                pool_target: self.pool_target,
+                enonce2,
+                job_id: self.notify.job_id,
+                version_mask: self.version_mask,
+            };
+
+            match hasher.hash(cancel.clone(), metrics.clone(), throttle) {
+                Err(HasherError::NonceSpaceExhausted { .. }) => {
+                    debug!(
+                        "Nonce space exhausted with extranonce2 {}, rolling",
+                        hasher.enonce2
+                    );
+                }
+                result => return result.map(Some),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, bitcoin::TxMerkleNode};
 
+    fn notify() -> Notify {
+        Notify {
+            job_id: "bf".parse().unwrap(),
+            prevhash: "4d16b6f85af6e2198f44ae2a6de67f78487ae5611b77c6c0440b921e00000000"
+                .parse()
+                .unwrap(),
+            coinb1: "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff20020862062f503253482f04b8864e5008".into(),
+            coinb2: "072f736c7573682f000000000100f2052a010000001976a914d23fcdf86f7e756a64a7a9688ef9903327048ed988ac00000000".into(),
+            merkle_branches: Vec::new(),
+            version: Version(block::Version::TWO),
+            nbits: "1c2ac4af".parse().unwrap(),
+            ntime: "504e86b9".parse().unwrap(),
+            clean_jobs: true,
+        }
+    }
+
+    fn search(enonce1: &str, enonce2: &str, pool_target: Target) -> Search {
+        Search {
+            notify: notify(),
+            enonce1: enonce1.parse().unwrap(),
+            enonce2: Arc::new(Mutex::new(Some(enonce2.parse().unwrap()))),
+            pool_target,
+            version_mask: None,
+            start_nonce: u32::MAX,
+        }
+    }
+
+    /// The hash of the only header a search starting at the last nonce tries
+    /// for `enonce2`.
+    fn last_nonce_hash(enonce1: &str, enonce2: &str) -> BlockHash {
+        let result = search(enonce1, enonce2, Target::from_be_bytes([0xff; 32]))
+            .run(CancellationToken::new(), Arc::new(Metrics::new()), f64::MAX)
+            .unwrap()
+            .unwrap();
+
+        result.1.block_hash()
+    }
+
+    fn as_target(hash: BlockHash) -> Target {
+        Target::from_le_bytes(hash.to_byte_array())
+    }
+
+    #[test]
+    fn next_enonce2_stops_at_end_of_space() {
+        let enonce2 = Mutex::new(Some("fe".parse().unwrap()));
+
+        assert_eq!(next_enonce2(&enonce2).unwrap().to_hex(), "fe");
+        assert_eq!(next_enonce2(&enonce2).unwrap().to_hex(), "ff");
+        assert_eq!(next_enonce2(&enonce2), None);
+        assert_eq!(next_enonce2(&enonce2), None);
+    }
+
+    #[test]
+    fn search_rolls_enonce2_on_nonce_exhaustion() {
+        // Find an enonce1 for which the header with extranonce2 01 hashes
+        // lower than with 00, so a target between them forces a roll.
+        let (enonce1, first, second) = (0..=u8::MAX)
+            .map(|byte| format!("{byte:02x}"))
+            .find_map(|enonce1| {
+                let first = last_nonce_hash(&enonce1, "00");
+                let second = last_nonce_hash(&enonce1, "01");
+                (as_target(first) > as_target(second)).then_some((enonce1, first, second))
+            })
+            .unwrap();
+
+        let target = as_target(second);
+        assert!(!target.is_met_by(first));
+
+        let search = search(&enonce1, "00", target);
+        let enonce2 = search.enonce2.clone();
+
+        let (_, header, share_enonce2, _) = search
+            .run(CancellationToken::new(), Arc::new(Metrics::new()), f64::MAX)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(share_enonce2.to_hex(), "01");
+        assert_eq!(header.nonce, u32::MAX);
+        assert_eq!(header.block_hash(), second);
+        assert_eq!(enonce2.lock().as_ref().unwrap().to_hex(), "02");
+    }
+
+    #[test]
+    fn search_idles_once_enonce2_space_is_exhausted() {
+        let search = search("00", "fd", Target::from_be_bytes([0; 32]));
+        let enonce2 = search.enonce2.clone();
+
+        let result = search
+            .run(CancellationToken::new(), Arc::new(Metrics::new()), f64::MAX)
+            .unwrap();
+
+        assert_eq!(result, None);
+        assert_eq!(*enonce2.lock(), None);
+    }
+
     fn shift(leading_zeros: u8) -> Target {
         assert!(leading_zeros <= 32, "leading_zeros too high");
 
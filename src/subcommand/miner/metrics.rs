@@ -1,11 +1,46 @@
 use {super::*, parking_lot::Mutex};
 
+/// Cumulative counts kept in the miner's state file, so totals survive
+/// restarts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Totals {
+    pub(crate) hashes: u64,
+    pub(crate) shares: u64,
+}
+
+impl Totals {
+    /// Totals saved at `path`, or zero if nothing was saved there yet.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("invalid state file {}", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("failed to read state file {}", path.display()))
+            }
+        }
+    }
+
+    /// Writes to a temporary file first, so a crash mid-write leaves the
+    /// previous totals in place.
+    pub(crate) fn save(&self, path: &Path) -> Result {
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, serde_json::to_string(self)?)
+            .with_context(|| format!("failed to write state file {}", tmp.display()))?;
+
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to write state file {}", path.display()))
+    }
+}
+
 struct HashRateCounter {
     last_total: u64,
     smoothed: DecayingAverage,
 }
 
 pub(crate) struct Metrics {
+    previous: Option<Totals>,
     hashes: AtomicU64,
     shares: AtomicU64,
     started: Instant,
@@ -16,6 +51,7 @@ pub(crate) struct Metrics {
 impl Metrics {
     pub(crate) fn new() -> Self {
         Self {
+            previous: None,
             hashes: AtomicU64::new(0),
             shares: AtomicU64::new(0),
             started: Instant::now(),
@@ -27,6 +63,15 @@ impl Metrics {
         }
     }
 
+    /// Metrics for a session that continues from the lifetime totals of
+    /// earlier ones. Session totals still start at zero.
+    pub(crate) fn resume(previous: Totals) -> Self {
+        Self {
+            previous: Some(previous),
+            ..Self::new()
+        }
+    }
+
     pub(crate) fn add_hashes(&self, hashes: u64) {
         self.hashes.fetch_add(hashes, Ordering::Relaxed);
     }
@@ -44,6 +89,16 @@ impl Metrics {
         self.shares.load(Ordering::Relaxed)
     }
 
+    /// Totals across this and every earlier session.
+    pub(crate) fn lifetime(&self) -> Totals {
+        let previous = self.previous.unwrap_or_default();
+
+        Totals {
+            hashes: previous.hashes.saturating_add(self.total_hashes()),
+            shares: previous.shares.saturating_add(self.total_shares()),
+        }
+    }
+
     pub(crate) fn uptime(&self) -> Duration {
         self.started.elapsed()
     }
@@ -65,13 +120,19 @@ impl Metrics {
 
 impl StatusLine for Metrics {
     fn status_line(&self) -> String {
-        format!(
+        let mut line = format!(
             "hashrate={:.2}  shares={} ({:.2}/s)  uptime={}s",
             self.hashrate(),
             self.total_shares(),
             self.sps(),
             self.uptime().as_secs()
-        )
+        );
+
+        if self.previous.is_some() {
+            line.push_str(&format!("  lifetime_shares={}", self.lifetime().shares));
+        }
+
+        line
     }
 }
 
@@ -165,6 +226,74 @@ mod tests {
         assert!(line.contains("uptime="), "missing uptime: {line}");
     }
 
+    #[test]
+    fn lifetime_without_state_matches_session() {
+        let metrics = Metrics::new();
+        metrics.add_hashes(1000);
+        metrics.add_share();
+
+        assert_eq!(
+            metrics.lifetime(),
+            Totals {
+                hashes: 1000,
+                shares: 1
+            }
+        );
+        assert!(!metrics.status_line().contains("lifetime"));
+    }
+
+    #[test]
+    fn restart_with_state_file_carries_totals_forward() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("miner.json");
+
+        let first = Metrics::resume(Totals::load(&path).unwrap());
+        first.add_hashes(1000);
+        first.add_share();
+        first.lifetime().save(&path).unwrap();
+
+        let second = Metrics::resume(Totals::load(&path).unwrap());
+        assert_eq!(second.total_hashes(), 0);
+        assert_eq!(second.total_shares(), 0);
+
+        second.add_hashes(500);
+        second.add_share();
+        second.add_share();
+
+        assert_eq!(second.total_hashes(), 500);
+        assert_eq!(second.total_shares(), 2);
+        assert_eq!(
+            second.lifetime(),
+            Totals {
+                hashes: 1500,
+                shares: 3
+            }
+        );
+        assert!(
+            second.status_line().contains("lifetime_shares=3"),
+            "{}",
+            second.status_line()
+        );
+
+        second.lifetime().save(&path).unwrap();
+        assert_eq!(Totals::load(&path).unwrap().hashes, 1500);
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn invalid_state_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("miner.json");
+        fs::write(&path, "not json").unwrap();
+
+        assert!(
+            Totals::load(&path)
+                .unwrap_err()
+                .to_string()
+                .starts_with("invalid state file")
+        );
+    }
+
     #[test]
     fn status_line_format_is_stable() {
         let metrics = Metrics::new();
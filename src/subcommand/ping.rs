@@ -4,6 +4,8 @@ use super::*;
 pub(crate) struct Ping {
     #[arg(help = "Stratum <HOST:PORT>.")]
     stratum_endpoint: String,
+    #[command(flatten)]
+    stratum_port: StratumPortOptions,
     #[arg(long, help = "Stop after <COUNT> replies.")]
     count: Option<u64>,
     #[arg(long, default_value = "5", help = "Fail after <TIMEOUT> seconds.")]
@@ -12,21 +14,55 @@ pub(crate) struct Ping {
     username: Option<Username>,
     #[arg(long, help = "Stratum <PASSWORD>.")]
     password: Option<String>,
+    #[arg(long, help = "Print a JSON summary instead of per-ping lines.")]
+    json: bool,
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = validate_max_loss,
+        help = "Exit nonzero when more than <MAX_LOSS> of pings fail, as a fraction between 0 and 1."
+    )]
+    max_loss: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct PingAttempt {
+    pub(crate) seq: u64,
+    pub(crate) success: bool,
+    pub(crate) latency_ms: Option<f64>,
+    pub(crate) size: Option<usize>,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct PingSummary {
+    pub(crate) endpoint: String,
+    pub(crate) sent: u64,
+    pub(crate) received: u64,
+    pub(crate) loss: f64,
+    pub(crate) min_ms: f64,
+    pub(crate) avg_ms: f64,
+    pub(crate) max_ms: f64,
+    pub(crate) success: bool,
+    pub(crate) attempts: Vec<PingAttempt>,
 }
 
 impl Ping {
     pub(crate) async fn run(&self, cancel_token: CancellationToken) -> Result {
-        let endpoint = ensure_port(&self.stratum_endpoint);
+        let endpoint =
+            resolve_stratum_endpoint(&self.stratum_endpoint, self.stratum_port.port()).await?;
 
         let ping_type = PingType::new(self.username.clone(), self.password.as_deref());
 
-        println!("{} {}", ping_type, endpoint);
+        if !self.json {
+            println!("{} {}", ping_type, endpoint);
+        }
 
         let stats = Arc::new(PingStats::new());
         let sequence = AtomicU64::new(0);
 
         let mut reply_count = 0;
-        let mut success = false;
+        let mut attempts = Vec::new();
 
         let mut interval = ticker(Duration::from_secs(1));
         interval.tick().await;
@@ -40,18 +76,38 @@ impl Ping {
 
                     tokio::select! {
                         _ = cancel_token.cancelled() => {
-                            println!("Ping cancelled for seq={seq}");
+                            if !self.json {
+                                println!("Ping cancelled for seq={seq}");
+                            }
                             break;
                         }
                         result = self.ping_once(&endpoint, &ping_type) => {
                             match result {
                                 Ok((duration, size)) => {
-                                    success = true;
                                     stats.record_success(duration);
-                                    println!("Response from {endpoint}: seq={seq} size={size} time={:.3}ms", duration.as_secs_f64() * 1000.0);
+                                    let latency_ms = duration.as_secs_f64() * 1000.0;
+                                    if !self.json {
+                                        println!("Response from {endpoint}: seq={seq} size={size} time={latency_ms:.3}ms");
+                                    }
+                                    attempts.push(PingAttempt {
+                                        seq,
+                                        success: true,
+                                        latency_ms: Some(latency_ms),
+                                        size: Some(size),
+                                        error: None,
+                                    });
                                 }
                                 Err(e) => {
-                                    println!("Request timeout for seq={seq} ({e})");
+                                    if !self.json {
+                                        println!("Request timeout for seq={seq} ({e})");
+                                    }
+                                    attempts.push(PingAttempt {
+                                        seq,
+                                        success: false,
+                                        latency_ms: None,
+                                        size: None,
+                                        error: Some(e.to_string()),
+                                    });
                                 }
                             }
                         }
@@ -65,13 +121,40 @@ impl Ping {
             }
         }
 
-        print_final_stats(&self.stratum_endpoint, &stats);
+        let (sent, received, loss_percent, min_ms, avg_ms, max_ms) = stats.get_stats();
+        let loss = loss_percent / 100.0;
 
-        if success {
+        let result = if received == 0 {
+            Err(anyhow!("Ping timed out"))
+        } else if loss > self.max_loss {
+            Err(anyhow!(
+                "{:.1}% of pings failed, more than the allowed {:.1}%",
+                loss_percent,
+                self.max_loss * 100.0
+            ))
+        } else {
             Ok(())
+        };
+
+        if self.json {
+            let summary = PingSummary {
+                endpoint: self.stratum_endpoint.clone(),
+                sent,
+                received,
+                loss,
+                min_ms,
+                avg_ms,
+                max_ms,
+                success: result.is_ok(),
+                attempts,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&summary)?);
         } else {
-            Err(anyhow!("Ping timed out"))
+            print_final_stats(&self.stratum_endpoint, &stats);
         }
+
+        result
     }
 
     async fn ping_once(&self, endpoint: &str, ping_type: &PingType) -> Result<(Duration, usize)> {
@@ -136,6 +219,15 @@ impl Ping {
     }
 }
 
+fn validate_max_loss(s: &str) -> Result<f64> {
+    let max_loss = s.parse::<f64>()?;
+    ensure!(
+        (0.0..=1.0).contains(&max_loss),
+        "Max loss must be between 0 and 1"
+    );
+    Ok(max_loss)
+}
+
 #[derive(Debug, Clone)]
 enum PingType {
     Subscribe,
@@ -271,3 +363,48 @@ fn print_final_stats(stratum_endpoint: &str, stats: &PingStats) {
     println!("\n--- {stratum_endpoint} ping statistics ---");
     print!("{stats}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let arguments = Arguments::try_parse_from(
+            "para ping 127.0.0.1:42069 --json --max-loss 0.25".split_whitespace(),
+        )
+        .unwrap();
+
+        let Subcommand::Ping(ping) = arguments.subcommand else {
+            panic!("unexpected subcommand");
+        };
+
+        assert!(ping.json);
+        assert_eq!(ping.max_loss, 0.25);
+
+        assert!(
+            Arguments::try_parse_from(
+                "para ping 127.0.0.1:42069 --max-loss 1.5".split_whitespace()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn stratum_port_overrides_default() {
+        #[track_caller]
+        fn case(args: &str, port: u16) {
+            let Subcommand::Ping(ping) = Arguments::try_parse_from(args.split_whitespace())
+                .unwrap()
+                .subcommand
+            else {
+                panic!("unexpected subcommand");
+            };
+
+            assert_eq!(ping.stratum_port.port(), port);
+        }
+
+        case("para ping foo", 42069);
+        case("para ping foo --stratum-port 3333", 3333);
+    }
+}
@@ -24,22 +24,34 @@ impl Pool {
 
         let bitcoin_client = Arc::new(settings.bitcoin_rpc_client().await?);
 
+        let store = Arc::new(Store::open(
+            &settings.store_path("pool.redb")?,
+            settings.chain(),
+        )?);
+
+        let metatron = Arc::new(Metatron::open(store)?);
+        metatron.set_maintenance(settings.maintenance());
+        metatron.set_worker_offline_grace(settings.worker_offline_grace());
+        metatron.set_share_cadence(settings.vardiff_period());
+        metatron.spawn(cancel_token.clone(), &tasks);
+
         let workbase_rx = spawn_generator(
             bitcoin_client.clone(),
             settings.clone(),
+            metatron.clone(),
             cancel_token.clone(),
             &tasks,
         )
         .await
         .context("failed to subscribe to ZMQ block notifications")?;
 
-        let store = Arc::new(Store::open(
-            &settings.store_path("pool.redb")?,
-            settings.chain(),
-        )?);
-
-        let metatron = Arc::new(Metatron::open(store)?);
-        metatron.spawn(cancel_token.clone(), &tasks);
+        spawn_template_watchdog(
+            &settings,
+            workbase_rx.clone(),
+            metatron.clone(),
+            cancel_token.clone(),
+            &tasks,
+        );
 
         http_server::spawn(
             &settings,
@@ -47,9 +59,12 @@ impl Pool {
                 metatron.clone(),
                 bitcoin_client,
                 settings.chain(),
+                settings.explorer_url(),
                 logs,
                 settings.http_api_token(),
                 settings.http_admin_token(),
+                cancel_token.clone(),
+                workbase_rx.clone(),
             ),
             cancel_token.clone(),
             &tasks,
@@ -84,13 +99,55 @@ impl Pool {
             spawn_throbber(metatron.clone(), cancel_token.clone(), &tasks);
         }
 
-        let extranonces = Extranonces::Pool(
+        let mut extranonces =
             PoolExtranonces::new(settings.enonce1_size(), settings.enonce2_size())
-                .context("invalid extranonce configuration")?,
-        );
+                .context("invalid extranonce configuration")?;
+
+        if let Some(instance_id) = settings.instance_id() {
+            extranonces = extranonces
+                .with_instance_id(instance_id)
+                .context("invalid extranonce configuration")?;
+        }
+
+        let extranonces = Extranonces::Pool(extranonces);
 
         let allocator = Arc::new(EnonceAllocator::new(extranonces, 0));
 
+        let allowlist = match settings.address_allowlist() {
+            Some(path) => {
+                let allowlist = Arc::new(Allowlist::load(
+                    path.to_path_buf(),
+                    settings.chain().network(),
+                )?);
+
+                info!(
+                    "Loaded allowlist {} with {} addresses",
+                    path.display(),
+                    allowlist.len()
+                );
+
+                allowlist
+                    .clone()
+                    .spawn_reloader(cancel_token.clone(), &tasks)?;
+
+                Some(allowlist)
+            }
+            None => None,
+        };
+
+        if let Some(path) = settings.coinbase_addresses().path() {
+            info!(
+                "Loaded coinbase address file {} with {} addresses",
+                path.display(),
+                settings.coinbase_addresses().len()
+            );
+
+            settings
+                .coinbase_addresses()
+                .clone()
+                .spawn_reloader(cancel_token.clone(), &tasks)?;
+        }
+
         let persist_metatron = metatron.clone();
         let persist_cancel = cancel_token.clone();
         let persist_interval = settings.tick_interval();
@@ -150,6 +207,7 @@ impl Pool {
             let metatron = metatron.clone();
             let disconnect_token = cancel_token.child_token();
             let event_tx = event_tx.clone();
+            let allowlist = allowlist.clone();
 
             tasks.spawn(async move {
                 let _ = stream.set_nodelay(true);
@@ -170,6 +228,7 @@ impl Pool {
                     event_tx,
                     start_diff,
                     None,
+                    allowlist.clone(),
                 );
 
                 if let Err(err) = stratifier.serve().await {
@@ -178,6 +237,13 @@ impl Pool {
             });
         }
 
+        if let Some(path) = settings.shutdown_snapshot() {
+            match api::PoolSnapshot::from_metatron(&metatron, Instant::now()).write(path) {
+                Ok(()) => info!("Wrote shutdown snapshot to {}", path.display()),
+                Err(err) => warn!("Failed to write shutdown snapshot: {err}"),
+            }
+        }
+
         info!("Waiting for {} tasks to complete...", tasks.len());
 
         tasks.close();
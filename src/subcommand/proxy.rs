@@ -44,6 +44,7 @@ impl Proxy {
         )?);
 
         let metatron = Arc::new(Metatron::open(store)?);
+        metatron.set_share_cadence(settings.vardiff_period());
         metatron.spawn(cancel_token.clone(), &tasks);
 
         let router = Arc::new(Router::new(
@@ -67,6 +68,7 @@ impl Proxy {
                 router.clone(),
                 bitcoin_client.clone(),
                 settings.chain(),
+                settings.explorer_url(),
                 logs,
                 settings.http_api_token(),
                 settings.http_admin_token(),
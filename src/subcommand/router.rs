@@ -32,6 +32,7 @@ impl RouterCommand {
         )?);
 
         let metatron = Arc::new(Metatron::open(store)?);
+        metatron.set_share_cadence(settings.vardiff_period());
 
         let wallet = Arc::new(Wallet::open(settings.clone(), metatron.store().clone())?);
 
@@ -62,6 +63,7 @@ impl RouterCommand {
                 router.clone(),
                 bitcoin_client.clone(),
                 settings.chain(),
+                settings.explorer_url(),
                 logs,
                 settings.http_api_token(),
                 settings.http_admin_token(),
@@ -0,0 +1,190 @@
+use {
+    super::*,
+    miner::{hasher::Hasher, metrics::Metrics},
+    stratum::client::{Client, Event, EventReceiver},
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Selftest {
+    #[arg(help = "Stratum <HOST:PORT>.")]
+    stratum_endpoint: String,
+    #[command(flatten)]
+    stratum_port: StratumPortOptions,
+    #[arg(long, help = "Stratum <USERNAME>.")]
+    username: Username,
+    #[arg(long, help = "Stratum <PASSWORD>.")]
+    password: Option<String>,
+    #[arg(long, default_value = "60", help = "Fail after <TIMEOUT> seconds.")]
+    timeout: u64,
+}
+
+impl Selftest {
+    pub(crate) async fn run(&self, cancel_token: CancellationToken) -> Result {
+        let endpoint =
+            resolve_stratum_endpoint(&self.stratum_endpoint, self.stratum_port.port()).await?;
+
+        println!("Selftest {endpoint} as {}", self.username);
+
+        let client = Client::new(
+            endpoint,
+            self.username.clone(),
+            self.password.clone(),
+            USER_AGENT.into(),
+            Duration::from_secs(self.timeout),
+        );
+
+        let start = Instant::now();
+
+        let result = tokio::select! {
+            _ = cancel_token.cancelled() => Err(anyhow!("cancelled")),
+            result = timeout(Duration::from_secs(self.timeout), self.mine_one_share(&client)) => {
+                result.unwrap_or_else(|_| Err(anyhow!("timed out after {}s", self.timeout)))
+            }
+        };
+
+        client.disconnect().await;
+
+        match result {
+            Ok(()) => {
+                println!("PASS in {:.3}ms", millis(start.elapsed()));
+                Ok(())
+            }
+            Err(err) => {
+                println!("FAIL: {err}");
+                Err(anyhow!("selftest failed"))
+            }
+        }
+    }
+
+    async fn mine_one_share(&self, client: &Client) -> Result {
+        let mut events = step("connect", async { Ok(client.connect().await?) }).await?;
+
+        let version_mask = step("configure", async {
+            let (response, _, _) = client
+                .configure(
+                    vec!["version-rolling".to_string()],
+                    Some(Version::from_str("ffffffff")?),
+                )
+                .await?;
+
+            Ok(response
+                .version_rolling
+                .then_some(response.version_rolling_mask)
+                .flatten())
+        })
+        .await?;
+
+        let subscribe = step("subscribe", async { Ok(client.subscribe().await?.0) }).await?;
+
+        step("authorize", async { Ok(client.authorize().await?) }).await?;
+
+        let (notify, difficulty) = step("notify", wait_for_job(&mut events)).await?;
+
+        let (job_id, header, enonce2, version_bits) = step("hash", async {
+            let enonce2 = Extranonce::random(subscribe.enonce2_size);
+
+            let merkle_root = stratum::merkle_root(
+                &notify.coinb1,
+                &notify.coinb2,
+                &subscribe.enonce1,
+                &enonce2,
+                &notify.merkle_branches,
+            )?;
+
+            let mut hasher = Hasher {
+                version: notify.version,
+                header: Header {
+                    version: notify.version.into(),
+                    prev_blockhash: notify.prevhash.clone().into(),
+                    merkle_root: merkle_root.into(),
+                    time: notify.ntime.into(),
+                    bits: notify.nbits.into(),
+                    nonce: 0,
+                },
+                pool_target: difficulty.to_target(),
+                enonce2,
+                job_id: notify.job_id,
+                version_mask,
+            };
+
+            let cancel = CancellationToken::new();
+            let _guard = cancel.clone().drop_guard();
+
+            Ok(task::spawn_blocking(move || {
+                hasher.hash(cancel, Arc::new(Metrics::new()), f64::MAX)
+            })
+            .await??)
+        })
+        .await?;
+
+        step("submit", async {
+            Ok(client
+                .submit(
+                    job_id,
+                    enonce2,
+                    header.time.into(),
+                    header.nonce.into(),
+                    version_bits,
+                )
+                .await?)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Waits for the first job, along with the difficulty the pool set for it.
+async fn wait_for_job(events: &mut EventReceiver) -> Result<(Notify, Difficulty)> {
+    let mut difficulty = Difficulty::default();
+
+    loop {
+        match events.recv().await? {
+            Event::SetDifficulty(set) => difficulty = set,
+            Event::Notify(notify) => return Ok((notify, difficulty)),
+            Event::Disconnected => bail!("disconnected before first job"),
+            _ => {}
+        }
+    }
+}
+
+async fn step<T>(name: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+    let start = Instant::now();
+
+    let result = future.await;
+
+    match &result {
+        Ok(_) => println!("{name:<10} ok   {:>10.3}ms", millis(start.elapsed())),
+        Err(err) => println!("{name:<10} FAIL {:>10.3}ms: {err}", millis(start.elapsed())),
+    }
+
+    result.with_context(|| format!("{name} failed"))
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let arguments = Arguments::try_parse_from(
+            "para selftest 127.0.0.1:42069 \
+                --username bc1q8jx6g9ujlqmdx3jnt3ap6ll2fdwqjdkdgs959m.worker1 \
+                --timeout 5"
+                .split_whitespace(),
+        )
+        .unwrap();
+
+        let Subcommand::Selftest(selftest) = arguments.subcommand else {
+            panic!("unexpected subcommand");
+        };
+
+        assert_eq!(selftest.stratum_endpoint, "127.0.0.1:42069");
+        assert_eq!(selftest.timeout, 5);
+        assert_eq!(selftest.password, None);
+    }
+}
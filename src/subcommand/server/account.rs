@@ -8,6 +8,8 @@ pub struct Account {
     pub ln_address: Option<String>,
     pub past_ln_addresses: Vec<String>,
     pub total_diff: i64,
+    /// Excluded accounts still accrue shares but never receive payouts.
+    pub exclude_from_payout: bool,
     pub last_updated: Option<String>,
     /// Used as a general purpose sparse data storage for aspects of an account that are
     /// not critical to primary operations (mining pool).
@@ -37,11 +39,16 @@ pub struct AccountResponse {
 pub(crate) fn account_router(database: Database) -> axum::Router {
     axum::Router::new()
         .route("/account/{address}", get(account_lookup))
+        .route("/api/accounts", get(accounts_by_ln_address))
         .route("/account/update", post(account_update))
         .route(
             "/account/metadata",
             post(account_metadata_update).layer(DefaultBodyLimit::max(1024)),
         )
+        .route(
+            "/account/metadata/rebuild",
+            post(rebuild_account_metadata).layer(from_extractor::<AdminAuth>()),
+        )
         .layer(from_extractor::<ApiAuth>())
         .layer(Extension(database))
 }
@@ -65,7 +72,7 @@ pub(crate) async fn account_lookup(
     Path(address): Path<String>,
 ) -> ServerResult<Response> {
     database
-        .get_account(&address)
+        .get_account_by_address(&address)
         .await?
         .ok_or_not_found(|| "Account")
         .map(|mut account| {
@@ -75,6 +82,32 @@ pub(crate) async fn account_lookup(
         .map(IntoResponse::into_response)
 }
 
+/// List all accounts sharing a lightning address
+#[utoipa::path(
+    get,
+    path = "/api/accounts",
+    security(("api_token" = [])),
+    params(
+        ("ln" = String, Query, description = "Lightning address")
+    ),
+    responses(
+        (status = 200, description = "Accounts using the lightning address", body = Vec<Account>),
+        (status = 400, description = "Missing lightning address"),
+    ),
+    tag = "account"
+)]
+pub(crate) async fn accounts_by_ln_address(
+    Extension(database): Extension<Database>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ServerResult<Response> {
+    let ln_address = params
+        .get("ln")
+        .filter(|ln_address| !ln_address.is_empty())
+        .ok_or_else(|| ServerError::BadRequest("missing `ln` query parameter".into()))?;
+
+    Ok(Json(database.get_accounts_by_ln_address(ln_address).await?).into_response())
+}
+
 /// Update account lightning address
 ///
 /// BIP322 is used for signing when supported by underlying address.
@@ -171,6 +204,30 @@ pub(crate) async fn account_metadata_update(
         .map(IntoResponse::into_response)
 }
 
+/// Recompute block_count and highest_blockheight metadata from synced shares
+#[utoipa::path(
+    post,
+    path = "/account/metadata/rebuild",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Metadata rebuilt"),
+    ),
+    tag = "account"
+)]
+pub(crate) async fn rebuild_account_metadata(
+    Extension(database): Extension<Database>,
+) -> ServerResult<Response> {
+    let rows_affected = database.rebuild_account_metadata().await?;
+
+    info!("Rebuilt block metadata for {rows_affected} accounts");
+
+    Ok(Json(json!({
+        "status": "OK",
+        "rows_affected": rows_affected,
+    }))
+    .into_response())
+}
+
 pub fn verify_signature(address: &str, message: &str, signature: &String) -> bool {
     match verify_simple_encoded(address, message, signature) {
         Ok(_) => true,
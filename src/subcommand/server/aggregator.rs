@@ -32,6 +32,7 @@ impl Aggregator {
             .route("/aggregator/pool/pool.status", get(pool_status))
             .route("/aggregator/users/{address}", get(user_status))
             .route("/aggregator/users", get(users))
+            .route("/aggregator/blocks", get(blocks))
             .layer(from_extractor::<ApiAuth>())
             .layer(Extension(cache))
             .layer(Extension(client))
@@ -103,6 +104,27 @@ pub(crate) async fn users(Extension(cache): Extension<Arc<Cache>>) -> ServerResu
     Ok(Json(cache.users().await?.ok_or_not_found(|| "Users")?).into_response())
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AggregatedBlocks {
+    pub block_count: u64,
+    pub blocks: Vec<api::FoundBlock>,
+}
+
+/// List distinct found blocks across all aggregator nodes
+#[utoipa::path(
+    get,
+    path = "/aggregator/blocks",
+    security(("api_token" = [])),
+    responses(
+        (status = 200, description = "Found blocks deduplicated by hash", body = AggregatedBlocks),
+        (status = 404, description = "Blocks not available"),
+    ),
+    tag = "aggregator"
+)]
+pub(crate) async fn blocks(Extension(cache): Extension<Arc<Cache>>) -> ServerResult<Response> {
+    Ok(Json(cache.blocks().await?.ok_or_not_found(|| "Blocks")?).into_response())
+}
+
 /// Get minimum blockheight across all aggregator nodes
 #[utoipa::path(
     get,
@@ -1,5 +1,5 @@
 use {
-    super::*,
+    super::{aggregator::AggregatedBlocks, *},
     backon::{ExponentialBuilder, Retryable},
     tokio::sync::Mutex,
 };
@@ -114,6 +114,7 @@ pub(super) struct Cache {
     pool_status: Mutex<Cached<ckpool::Status>>,
     user_statuses: DashMap<String, Arc<Mutex<Cached<ckpool::User>>>>,
     users: Mutex<Cached<Vec<String>>>,
+    blocks: Mutex<Cached<AggregatedBlocks>>,
 }
 
 impl Cache {
@@ -129,6 +130,7 @@ impl Cache {
             pool_status: Mutex::new(Cached::init(config.ttl())),
             user_statuses: DashMap::new(),
             users: Mutex::new(Cached::init(config.ttl())),
+            blocks: Mutex::new(Cached::init(config.ttl())),
         }
     }
 
@@ -173,7 +175,12 @@ impl Cache {
         }
 
         // workaround for stale data in ckpool instances
-        if let (Some(status), Some(database)) = (aggregated.as_mut(), self.database.as_ref()) {
+        if let (Some(status), Some(database)) = (
+            aggregated.as_mut(),
+            self.database
+                .as_ref()
+                .filter(|database| database.is_connected()),
+        ) {
             match database.get_current_round_bestshare().await {
                 Ok(Some(best)) => status.shares.bestshare = best as u64,
                 Ok(None) => status.shares.bestshare = 0,
@@ -237,7 +244,12 @@ impl Cache {
         }
 
         // workaround for stale ckpool stats
-        if let (Some(user), Some(database)) = (aggregated.as_mut(), self.database.as_ref()) {
+        if let (Some(user), Some(database)) = (
+            aggregated.as_mut(),
+            self.database
+                .as_ref()
+                .filter(|database| database.is_connected()),
+        ) {
             match database.get_current_round_bestshare_by_user(&address).await {
                 Ok(best) => user.bestshare = best.unwrap_or(0.0),
                 Err(err) => {
@@ -294,4 +306,118 @@ impl Cache {
 
         Ok(aggregated)
     }
+
+    pub(super) async fn blocks(&self) -> Result<Option<AggregatedBlocks>> {
+        let mut cached = self.blocks.lock().await;
+        if cached.is_fresh(self.config.ttl()) {
+            return Ok(cached.value());
+        }
+
+        let fetches: FuturesUnordered<_> = self
+            .config
+            .nodes()
+            .into_iter()
+            .map(|base| {
+                fetch_for(self.client.clone(), base, "/api/blocks".into(), |s| {
+                    serde_json::from_str::<Vec<api::FoundBlock>>(s).map_err(Into::into)
+                })
+            })
+            .collect();
+
+        let lists = fetches.fold(Vec::new(), |mut acc, (base, res)| async move {
+            match res {
+                Ok(list) => acc.push(list),
+                Err(err) => {
+                    let host = base.host_str().unwrap_or("unknown");
+                    warn!("Failed to fetch blocks from {host} with: {err}");
+                }
+            }
+            acc
+        });
+
+        // `/api/blocks` only lists each node's most recent blocks, so the
+        // total comes from every hash each node has found, counted once.
+        let hashes: FuturesUnordered<_> = self
+            .config
+            .nodes()
+            .into_iter()
+            .map(|base| {
+                fetch_for(
+                    self.client.clone(),
+                    base,
+                    "/api/blocks/hashes".into(),
+                    |s| serde_json::from_str::<Vec<BlockHash>>(s).map_err(Into::into),
+                )
+            })
+            .collect();
+
+        let hashes = hashes.fold(Vec::new(), |mut acc, (base, res)| async move {
+            match res {
+                Ok(list) => acc.push(list),
+                Err(err) => {
+                    let host = base.host_str().unwrap_or("unknown");
+                    warn!("Failed to fetch block hashes from {host} with: {err}");
+                }
+            }
+            acc
+        });
+
+        let (lists, hashes) = tokio::join!(lists, hashes);
+
+        let aggregated = if lists.is_empty() {
+            error!("Failed to aggregate blocks");
+            None
+        } else {
+            let blocks = merge_blocks(lists);
+
+            Some(AggregatedBlocks {
+                block_count: count_distinct_blocks(
+                    hashes
+                        .into_iter()
+                        .chain([blocks.iter().map(|block| block.hash).collect()]),
+                ),
+                blocks,
+            })
+        };
+
+        *cached = Cached::new(aggregated.clone());
+
+        Ok(aggregated)
+    }
+}
+
+/// Counts the distinct block hashes across every node's list, so a block
+/// reported by several nodes counts once.
+pub(super) fn count_distinct_blocks(lists: impl IntoIterator<Item = Vec<BlockHash>>) -> u64 {
+    lists
+        .into_iter()
+        .flatten()
+        .collect::<HashSet<BlockHash>>()
+        .len() as u64
+}
+
+/// Nodes that share a payout address all report the blocks they found
+/// together, so blocks are merged by hash rather than counted per node.
+/// Returns the distinct blocks, newest first.
+pub(super) fn merge_blocks(
+    lists: impl IntoIterator<Item = Vec<api::FoundBlock>>,
+) -> Vec<api::FoundBlock> {
+    let mut blocks = HashMap::<BlockHash, api::FoundBlock>::new();
+
+    for block in lists.into_iter().flatten() {
+        blocks
+            .entry(block.hash)
+            .and_modify(|existing| {
+                if block.found_at < existing.found_at {
+                    *existing = block.clone();
+                }
+            })
+            .or_insert(block);
+    }
+
+    let mut blocks = blocks.into_values().collect::<Vec<api::FoundBlock>>();
+
+    blocks.sort_by(|a, b| b.height.cmp(&a.height).then(a.hash.cmp(&b.hash)));
+
+    blocks
 }
@@ -16,6 +16,43 @@ pub struct TeraShare {
     pub tera_shares: i64,
 }
 
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Contributor {
+    pub username: String,
+    pub total_diff: f64,
+    pub shares: i64,
+    pub best_diff: f64,
+}
+
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct RemoteShare {
+    pub id: i64,
+    pub origin: String,
+    pub blockheight: Option<i32>,
+    pub workinfoid: Option<i64>,
+    pub clientid: Option<i64>,
+    pub enonce1: Option<String>,
+    pub nonce2: Option<String>,
+    pub nonce: Option<String>,
+    pub ntime: Option<String>,
+    pub diff: Option<f64>,
+    pub sdiff: Option<f64>,
+    pub hash: Option<String>,
+    pub result: Option<bool>,
+    pub reject_reason: Option<String>,
+    pub error: Option<String>,
+    pub errn: Option<i32>,
+    pub createdate: Option<String>,
+    pub createby: Option<String>,
+    pub createcode: Option<String>,
+    pub createinet: Option<String>,
+    pub workername: Option<String>,
+    pub username: Option<String>,
+    pub lnurl: Option<String>,
+    pub address: Option<String>,
+    pub agent: Option<String>,
+}
+
 #[derive(sqlx::FromRow, Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
 pub(crate) struct Split {
     pub(crate) worker_name: String,
@@ -55,6 +92,27 @@ pub struct UpdatePayoutStatusRequest {
     pub failure_reason: Option<String>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct RequeuePayoutsRequest {
+    pub payout_ids: Vec<i64>,
+}
+
+/// Selects the payouts to cancel by account, given either by id or by
+/// username, optionally limited to payouts ending within a block range.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, ToSchema)]
+pub struct CancelPayoutsRequest {
+    pub account_id: Option<i64>,
+    pub username: Option<String>,
+    pub start_height: Option<i32>,
+    pub end_height: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct ExcludeFromPayoutRequest {
+    pub btc_address: String,
+    pub exclude_from_payout: bool,
+}
+
 #[derive(sqlx::FromRow, Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
 pub struct HistoricalPayout {
     pub id: i64,
@@ -66,27 +124,154 @@ pub struct HistoricalPayout {
     pub updated_at: String,
 }
 
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct PayoutStatus {
+    pub id: i64,
+    pub username: String,
+    pub amount_sats: i64,
+    pub blockheight_start: i32,
+    pub blockheight_end: i32,
+    pub status: String,
+    pub failure_reason: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub processed_at: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AccountRow {
+    username: String,
+    lnurl: Option<String>,
+    past_lnurls: sqlx::types::Json<Vec<String>>,
+    total_diff: i64,
+    exclude_from_payout: bool,
+    last_updated: Option<String>,
+    metadata: Option<serde_json::Value>,
+}
+
+impl From<AccountRow> for Account {
+    fn from(row: AccountRow) -> Self {
+        Self {
+            btc_address: row.username,
+            ln_address: row.lnurl,
+            past_ln_addresses: row.past_lnurls.0,
+            total_diff: row.total_diff,
+            exclude_from_payout: row.exclude_from_payout,
+            last_updated: row.last_updated,
+            metadata: row.metadata,
+        }
+    }
+}
+
+const MIN_CONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct Database {
     pub(crate) pool: Pool<Postgres>,
+    read_pool: Pool<Postgres>,
+    connected: Arc<AtomicBool>,
 }
 
 impl Database {
+    // The pool is shared across every DB-backed route (sync ingestion,
+    // rounds, payouts, account migration) plus the aggregator bestshare
+    // overlays, so 5 connections starves under concurrent load. Postgres
+    // defaults to max_connections = 100, leaving ample headroom here.
+    fn pool_options() -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(20)
+            .acquire_timeout(Duration::from_secs(5))
+    }
+
     pub async fn new(database_url: String) -> Result<Self> {
+        let pool = Self::pool_options()
+            .connect(&database_url)
+            .await
+            .with_context(|| format!("failed to connect to database at `{database_url}`"))?;
+
         Ok(Self {
-            // The pool is shared across every DB-backed route (sync ingestion,
-            // rounds, payouts, account migration) plus the aggregator bestshare
-            // overlays, so 5 connections starves under concurrent load. Postgres
-            // defaults to max_connections = 100, leaving ample headroom here.
-            pool: PgPoolOptions::new()
-                .max_connections(20)
-                .acquire_timeout(Duration::from_secs(5))
-                .connect(&database_url)
-                .await
-                .with_context(|| format!("failed to connect to database at `{database_url}`"))?,
+            read_pool: pool.clone(),
+            pool,
+            connected: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    /// Builds the pool without connecting, so the server can come up while
+    /// Postgres is unreachable. It stays unavailable until `connect` succeeds.
+    pub(crate) fn new_lazy(database_url: &str) -> Result<Self> {
+        let pool = Self::pool_options()
+            .connect_lazy(database_url)
+            .with_context(|| format!("invalid database url `{database_url}`"))?;
+
+        Ok(Self {
+            read_pool: pool.clone(),
+            pool,
+            connected: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Serves the read-only dashboard queries from the replica at
+    /// `database_url`, so they don't contend with sync writes. Payout queries
+    /// decide what gets paid and, like anything that reads its own writes,
+    /// stay on the primary, since the replica may lag.
+    pub(crate) fn with_read_replica(self, database_url: &str) -> Result<Self> {
+        Ok(Self {
+            read_pool: Self::pool_options()
+                .connect_lazy(database_url)
+                .with_context(|| format!("invalid read database url `{database_url}`"))?,
+            ..self
         })
     }
 
+    pub(crate) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Retries with exponential backoff until a connection is established.
+    pub(crate) async fn connect(&self) {
+        let mut backoff = MIN_CONNECT_BACKOFF;
+
+        loop {
+            match self.pool.acquire().await {
+                Ok(_) => {
+                    self.connected.store(true, Ordering::Relaxed);
+                    return;
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to connect to PostgreSQL, retrying in {}s: {err}",
+                        backoff.as_secs()
+                    );
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Periodically checks the connection, marking the database unavailable
+    /// while Postgres is unreachable and available again once it comes back.
+    pub(crate) async fn monitor(&self) {
+        loop {
+            sleep(CONNECTION_CHECK_INTERVAL).await;
+
+            match sqlx::query("SELECT 1").execute(&self.pool).await {
+                Ok(_) => {
+                    if !self.connected.swap(true, Ordering::Relaxed) {
+                        info!("Reconnected to PostgreSQL");
+                    }
+                }
+                Err(err) => {
+                    if self.connected.swap(false, Ordering::Relaxed) {
+                        warn!("Lost connection to PostgreSQL: {err}");
+                    }
+                }
+            }
+        }
+    }
+
     pub(crate) async fn get_split(&self) -> Result<Vec<Split>> {
         sqlx::query_as::<_, Split>(
             "
@@ -139,6 +324,34 @@ impl Database {
         .map_err(|err| anyhow!(err))
     }
 
+    /// Heights of blocks in `[start, end]` whose rewards have not been
+    /// processed yet, lowest first.
+    pub(crate) async fn get_open_blockheights(
+        &self,
+        start_blockheight: i32,
+        end_blockheight: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<i32>> {
+        sqlx::query_scalar::<_, i32>(
+            "
+            SELECT blockheight
+            FROM blocks
+            WHERE blockheight BETWEEN $1 AND $2
+                AND NOT COALESCE(rewards_processed, FALSE)
+            ORDER BY blockheight
+            LIMIT $3 OFFSET $4
+            ",
+        )
+        .bind(start_blockheight)
+        .bind(end_blockheight)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))
+    }
+
     pub(crate) async fn get_payouts(
         &self,
         blockheight: i32,
@@ -214,6 +427,7 @@ impl Database {
                 WHERE s.blockheight >= $1
                     AND s.blockheight < $2
                     AND s.username != ALL($3)
+                    AND s.username NOT IN (SELECT username FROM accounts WHERE exclude_from_payout)
                     AND s.reject_reason IS NULL
                 GROUP BY s.lnurl, s.username, s.workername
             ),
@@ -242,6 +456,40 @@ impl Database {
         .map_err(|err| anyhow!(err))
     }
 
+    /// Maps a `[start, end)` window of unix times to the `[start, end)` share
+    /// height range paying out the blocks found in it, i.e. from just past
+    /// the block before the first one up to and including the last one.
+    /// Returns `None` if no block was found in the window.
+    pub(crate) async fn get_height_range_for_times(
+        &self,
+        start_secs: i64,
+        end_secs: i64,
+    ) -> Result<Option<(i32, i32)>> {
+        sqlx::query_as::<_, (i32, i32)>(
+            "
+            WITH found AS (
+                SELECT MIN(blockheight) AS first, MAX(blockheight) AS last
+                FROM blocks
+                WHERE time_found >= to_timestamp($1)
+                    AND time_found < to_timestamp($2)
+            )
+            SELECT
+                COALESCE(
+                    (SELECT MAX(b.blockheight) + 1 FROM blocks b WHERE b.blockheight < found.first),
+                    0
+                ) AS start_height,
+                found.last + 1 AS end_height
+            FROM found
+            WHERE found.first IS NOT NULL
+            ",
+        )
+        .bind(start_secs as f64)
+        .bind(end_secs as f64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))
+    }
+
     pub(crate) async fn get_user_payout_range(
         &self,
         start_blockheight: i32,
@@ -271,6 +519,7 @@ impl Database {
             WHERE s.blockheight >= $1
                 AND s.blockheight < $2
                 AND s.username != ALL($4)
+                AND s.username NOT IN (SELECT username FROM accounts WHERE exclude_from_payout)
                 AND s.reject_reason IS NULL
             GROUP BY s.lnurl, s.username, s.workername
         ),
@@ -288,6 +537,7 @@ impl Database {
             WHERE s.blockheight >= $1
                 AND s.blockheight < $2
                 AND s.username = $3
+                AND s.username NOT IN (SELECT username FROM accounts WHERE exclude_from_payout)
                 AND s.reject_reason IS NULL
             GROUP BY s.lnurl, s.username, s.workername
         )
@@ -314,23 +564,14 @@ impl Database {
     }
 
     pub async fn get_account(&self, username: &str) -> Result<Option<Account>> {
-        #[derive(sqlx::FromRow)]
-        pub struct AccountRaw {
-            pub username: String,
-            pub lnurl: Option<String>,
-            pub past_lnurls: sqlx::types::Json<Vec<String>>,
-            pub total_diff: i64,
-            pub last_updated: Option<String>,
-            pub metadata: Option<serde_json::Value>,
-        }
-
-        let Some(raw) = sqlx::query_as::<_, AccountRaw>(
+        Ok(sqlx::query_as::<_, AccountRow>(
             "
             SELECT
                 a.username,
                 a.lnurl,
                 a.past_lnurls,
                 a.total_diff,
+                a.exclude_from_payout,
                 a.lnurl_updated_at::text as last_updated,
                 m.data as metadata
             FROM accounts a
@@ -341,18 +582,45 @@ impl Database {
         .bind(username)
         .fetch_optional(&self.pool)
         .await?
-        else {
-            return Ok(None);
-        };
+        .map(Account::from))
+    }
 
-        Ok(Some(Account {
-            btc_address: raw.username,
-            ln_address: raw.lnurl,
-            past_ln_addresses: raw.past_lnurls.0,
-            total_diff: raw.total_diff,
-            last_updated: raw.last_updated,
-            metadata: raw.metadata,
-        }))
+    /// Like `get_account`, but first normalizes `address` to its canonical
+    /// string form, so e.g. an uppercase bech32 address resolves to the same
+    /// account as its lowercase form.
+    pub async fn get_account_by_address(&self, address: &str) -> Result<Option<Account>> {
+        let normalized = address
+            .parse::<Address<NetworkUnchecked>>()
+            .map(|address| address.assume_checked().to_string())
+            .unwrap_or_else(|_| address.to_string());
+
+        self.get_account(&normalized).await
+    }
+
+    pub async fn get_accounts_by_ln_address(&self, ln_address: &str) -> Result<Vec<Account>> {
+        Ok(sqlx::query_as::<_, AccountRow>(
+            "
+            SELECT
+                a.username,
+                a.lnurl,
+                a.past_lnurls,
+                a.total_diff,
+                a.exclude_from_payout,
+                a.lnurl_updated_at::text as last_updated,
+                m.data as metadata
+            FROM accounts a
+            LEFT JOIN account_metadata m ON a.id = m.account_id
+            WHERE a.lnurl = $1
+            ORDER BY a.username
+            ",
+        )
+        .bind(ln_address)
+        .fetch_all(&self.read_pool)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .into_iter()
+        .map(Account::from)
+        .collect())
     }
 
     pub async fn update_account_lnurl(
@@ -451,6 +719,95 @@ impl Database {
         self.get_account(username).await
     }
 
+    /// Recomputes `block_count` and `highest_blockheight` in every account's
+    /// metadata from its accepted shares in `remote_shares`, leaving other
+    /// metadata keys alone. Accounts without accepted shares lose both keys.
+    pub async fn rebuild_account_metadata(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await.map_err(|err| anyhow!(err))?;
+
+        let rows_affected = sqlx::query(
+            "
+            WITH counts AS (
+                SELECT username,
+                       COUNT(DISTINCT blockheight) AS block_count,
+                       MAX(blockheight) AS highest_blockheight
+                FROM remote_shares
+                WHERE result = TRUE
+                    AND blockheight IS NOT NULL
+                    AND username IS NOT NULL
+                GROUP BY username
+            )
+            INSERT INTO account_metadata (account_id, data, created_at, updated_at)
+            SELECT a.id,
+                   jsonb_build_object(
+                       'block_count', c.block_count,
+                       'highest_blockheight', c.highest_blockheight
+                   ),
+                   NOW(), NOW()
+            FROM counts c
+            JOIN accounts a ON a.username = c.username
+            ON CONFLICT (account_id) DO UPDATE
+            SET data = account_metadata.data || EXCLUDED.data,
+                updated_at = NOW()
+            ",
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        let cleared = sqlx::query(
+            "
+            UPDATE account_metadata am
+            SET data = am.data - 'block_count' - 'highest_blockheight',
+                updated_at = NOW()
+            FROM accounts a
+            WHERE a.id = am.account_id
+                AND am.data ? 'block_count'
+                AND NOT EXISTS (
+                    SELECT 1 FROM remote_shares rs
+                    WHERE rs.username = a.username
+                        AND rs.result = TRUE
+                        AND rs.blockheight IS NOT NULL
+                )
+            ",
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        tx.commit().await.map_err(|err| anyhow!(err))?;
+
+        Ok(rows_affected + cleared)
+    }
+
+    pub async fn set_account_exclude_from_payout(
+        &self,
+        username: &str,
+        exclude_from_payout: bool,
+    ) -> Result<Option<Account>> {
+        let rows_affected = sqlx::query(
+            "
+            UPDATE accounts
+            SET exclude_from_payout = $1, updated_at = NOW()
+            WHERE username = $2
+            ",
+        )
+        .bind(exclude_from_payout)
+        .bind(username)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+
+        self.get_account(username).await
+    }
+
     pub async fn migrate_accounts(&self) -> Result<u64> {
         let result = sqlx::query_scalar::<_, i64>("SELECT refresh_accounts()")
             .fetch_one(&self.pool)
@@ -460,6 +817,24 @@ impl Database {
         Ok(result as u64)
     }
 
+    pub async fn get_remote_share(&self, origin: &str, id: i64) -> Result<Option<RemoteShare>> {
+        sqlx::query_as::<_, RemoteShare>(
+            "
+            SELECT
+                id, origin, blockheight, workinfoid, clientid, enonce1, nonce2, nonce, ntime,
+                diff, sdiff, hash, result, reject_reason, error, errn, createdate, createby,
+                createcode, createinet, workername, username, lnurl, address, agent
+            FROM remote_shares
+            WHERE origin = $1 AND id = $2
+            ",
+        )
+        .bind(origin)
+        .bind(id)
+        .fetch_optional(&self.read_pool)
+        .await
+        .map_err(|err| anyhow!(err))
+    }
+
     pub async fn get_highestdiff(&self, blockheight: i32) -> Result<Option<HighestDiff>> {
         sqlx::query_as::<_, HighestDiff>(
             "
@@ -575,6 +950,50 @@ impl Database {
         .map_err(|err| anyhow!(err))
     }
 
+    /// Accounts with the most accepted difficulty in shares at the last
+    /// `window` heights, counting back from the highest height any share was
+    /// submitted at, best first.
+    pub async fn top_contributors(
+        &self,
+        window: i32,
+        limit: i64,
+        excluded_usernames: Vec<String>,
+    ) -> Result<Vec<Contributor>> {
+        let exclusion_list = if excluded_usernames.is_empty() {
+            vec!["".to_string()]
+        } else {
+            excluded_usernames
+        };
+
+        sqlx::query_as::<_, Contributor>(
+            "
+            WITH tip AS (
+                SELECT MAX(blockheight) AS height
+                FROM remote_shares
+            )
+            SELECT
+                rs.username,
+                SUM(rs.diff) AS total_diff,
+                COUNT(*) AS shares,
+                COALESCE(MAX(rs.sdiff), 0) AS best_diff
+            FROM remote_shares rs, tip
+            WHERE rs.blockheight > tip.height - $1
+                AND rs.username IS NOT NULL
+                AND rs.username != ALL($3)
+                AND rs.reject_reason IS NULL
+            GROUP BY rs.username
+            ORDER BY total_diff DESC, username
+            LIMIT $2
+            ",
+        )
+        .bind(window)
+        .bind(limit)
+        .bind(&exclusion_list)
+        .fetch_all(&self.read_pool)
+        .await
+        .map_err(|err| anyhow!(err))
+    }
+
     pub async fn get_pending_payouts(&self) -> Result<Vec<PendingPayout>> {
         #[derive(sqlx::FromRow)]
         struct PayoutRow {
@@ -697,6 +1116,41 @@ impl Database {
         Ok(rows)
     }
 
+    /// Every payout, whatever its status, for blocks found between
+    /// `start_height` and `end_height` inclusive.
+    pub async fn get_payout_statuses_range(
+        &self,
+        start_height: i32,
+        end_height: i32,
+    ) -> Result<Vec<PayoutStatus>> {
+        let rows = sqlx::query_as::<_, PayoutStatus>(
+            "
+            SELECT
+                p.id as id,
+                a.username as username,
+                p.amount as amount_sats,
+                p.blockheight_start as blockheight_start,
+                p.blockheight_end as blockheight_end,
+                p.status as status,
+                p.failure_reason as failure_reason,
+                p.created_at::text as created_at,
+                p.updated_at::text as updated_at,
+                p.processed_at::text as processed_at
+            FROM payouts p
+            JOIN accounts a ON p.account_id = a.id
+            WHERE p.blockheight_end BETWEEN $1 AND $2
+            ORDER BY p.blockheight_end, p.id
+            ",
+        )
+        .bind(start_height)
+        .bind(end_height)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))?;
+
+        Ok(rows)
+    }
+
     pub async fn get_simulated_payouts(
         &self,
         total_reward: i64,
@@ -725,6 +1179,7 @@ impl Database {
                 FROM accounts a
                 LEFT JOIN payouts p ON p.account_id = a.id
                     AND p.status != 'cancelled'
+                WHERE NOT a.exclude_from_payout
                 GROUP BY a.id, a.username, a.lnurl, a.total_diff
             ),
             payable_accounts AS (
@@ -786,11 +1241,16 @@ impl Database {
         Ok(result)
     }
 
+    /// Updates the status of the given payouts. Each `failure` counts as an
+    /// attempt, and a payout that has failed `max_retries` times is moved to
+    /// `dead_letter` instead, which keeps it out of the pending list until an
+    /// operator re-queues it. Dead-lettered payouts are left untouched.
     pub async fn update_payout_status(
         &self,
         payout_ids: &[i64],
         status: &str,
         failure_reason: Option<&str>,
+        max_retries: i16,
     ) -> Result<u64> {
         if payout_ids.is_empty() {
             return Ok(0);
@@ -804,15 +1264,50 @@ impl Database {
         let rows_affected = sqlx::query(
             "
             UPDATE payouts
-            SET status = $1,
+            SET status = CASE
+                    WHEN $1 = 'failure' AND attempts + 1 >= $4 THEN 'dead_letter'
+                    ELSE $1
+                END,
+                attempts = CASE
+                    WHEN $1 = 'failure' THEN attempts + 1
+                    ELSE attempts
+                END,
                 failure_reason = $2,
                 updated_at = NOW()
             WHERE id = ANY($3)
+                AND status != 'dead_letter'
             ",
         )
         .bind(status)
         .bind(failure_reason)
         .bind(payout_ids)
+        .bind(max_retries)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
+    /// Moves dead-lettered payouts back to `pending` with a fresh retry count.
+    pub async fn requeue_payouts(&self, payout_ids: &[i64]) -> Result<u64> {
+        if payout_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let rows_affected = sqlx::query(
+            "
+            UPDATE payouts
+            SET status = 'pending',
+                attempts = 0,
+                failure_reason = NULL,
+                updated_at = NOW()
+            WHERE id = ANY($1)
+                AND status = 'dead_letter'
+            ",
+        )
+        .bind(payout_ids)
         .execute(&self.pool)
         .await
         .map_err(|err| anyhow!(err))?
@@ -821,6 +1316,69 @@ impl Database {
         Ok(rows_affected)
     }
 
+    /// Cancels an account's unpaid payouts, those `pending`, `failure` or
+    /// `dead_letter`, whose `blockheight_end` falls within the given range.
+    /// Payouts that succeeded or are being processed are never touched.
+    /// Cancelled payouts don't count as paid, so their difficulty is paid out
+    /// again with the next block.
+    pub async fn cancel_payouts(&self, request: &CancelPayoutsRequest) -> Result<u64> {
+        let rows_affected = sqlx::query(
+            "
+            UPDATE payouts p
+            SET status = 'cancelled',
+                updated_at = NOW()
+            FROM accounts a
+            WHERE a.id = p.account_id
+                AND ($1::BIGINT IS NULL OR a.id = $1)
+                AND ($2::TEXT IS NULL OR a.username = $2)
+                AND ($3::INTEGER IS NULL OR p.blockheight_end >= $3)
+                AND ($4::INTEGER IS NULL OR p.blockheight_end <= $4)
+                AND p.status IN ('pending', 'failure', 'dead_letter')
+            ",
+        )
+        .bind(request.account_id)
+        .bind(&request.username)
+        .bind(request.start_height)
+        .bind(request.end_height)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
+    /// Claims the block found alert for `blockhash`. Returns false if it was
+    /// already claimed, e.g. by an earlier batch carrying the same block.
+    pub(crate) async fn claim_block_notification(&self, blockhash: &str) -> Result<bool> {
+        let rows_affected = sqlx::query(
+            "
+            INSERT INTO block_notifications (blockhash)
+            VALUES ($1)
+            ON CONFLICT (blockhash) DO NOTHING
+            ",
+        )
+        .bind(blockhash)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!(err))?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Drops the claim on `blockhash` after the alert could not be sent, so a
+    /// later batch carrying the same block tries again.
+    pub(crate) async fn release_block_notification(&self, blockhash: &str) -> Result {
+        sqlx::query("DELETE FROM block_notifications WHERE blockhash = $1")
+            .bind(blockhash)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| anyhow!(err))?;
+
+        Ok(())
+    }
+
     pub(crate) async fn get_rounds(&self) -> Result<Vec<Round>> {
         sqlx::query_as::<_, Round>(
             "
@@ -0,0 +1,121 @@
+use {
+    super::*,
+    notifications::{NotificationHandler, NotificationType},
+    sysinfo::Disk,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tracks free space on the disk holding the log dir so sync batches can be
+/// turned away before the disk fills up.
+pub(crate) struct DiskGuard {
+    path: PathBuf,
+    min_free_percent: f64,
+    low: AtomicBool,
+}
+
+impl DiskGuard {
+    pub(crate) fn new(path: PathBuf, min_free_percent: f64) -> Self {
+        Self {
+            path,
+            min_free_percent,
+            low: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.min_free_percent > 0.0
+    }
+
+    pub(crate) fn is_low(&self) -> bool {
+        self.low.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn min_free_percent(&self) -> f64 {
+        self.min_free_percent
+    }
+
+    /// Records the current free space. Returns true if this enters or clears
+    /// the low-space condition.
+    pub(crate) fn update(&self, free_percent: f64) -> bool {
+        let low = free_percent < self.min_free_percent;
+        self.low.swap(low, Ordering::Relaxed) != low
+    }
+
+    /// Reads free space from the OS and records it, returning the free
+    /// percentage if this changed the low-space condition.
+    pub(crate) fn refresh(&self) -> Option<f64> {
+        let path = fs::canonicalize(&self.path)
+            .or_else(|_| std::env::current_dir())
+            .ok()?;
+
+        let disks =
+            Disks::new_with_refreshed_list_specifics(DiskRefreshKind::nothing().with_storage());
+
+        let disk = containing_disk(&disks, &path)?;
+
+        if disk.total_space() == 0 {
+            return None;
+        }
+
+        let free_percent = 100.0 * disk.available_space() as f64 / disk.total_space() as f64;
+
+        self.update(free_percent).then_some(free_percent)
+    }
+
+    /// Checks once before returning so batches are gated from the start, then
+    /// keeps checking in the background.
+    pub(crate) fn spawn(self: Arc<Self>, alerts_ntfy_channel: Option<String>) {
+        if let Some(free_percent) = self.refresh() {
+            self.report(free_percent, alerts_ntfy_channel.clone());
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = ticker(CHECK_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                let guard = self.clone();
+                if let Ok(Some(free_percent)) = task::spawn_blocking(move || guard.refresh()).await
+                {
+                    self.report(free_percent, alerts_ntfy_channel.clone());
+                }
+            }
+        });
+    }
+
+    fn report(&self, free_percent: f64, alerts_ntfy_channel: Option<String>) {
+        if !self.is_low() {
+            info!("Disk space recovered to {free_percent:.1}% free, accepting sync batches");
+            return;
+        }
+
+        let message = format!(
+            "disk holding {} is down to {free_percent:.1}% free (minimum {}%), rejecting sync batches",
+            self.path.display(),
+            self.min_free_percent
+        );
+
+        warn!("{message}");
+
+        if let Some(channel) = alerts_ntfy_channel {
+            tokio::spawn(async move {
+                if let Err(err) = NotificationHandler::new(channel)
+                    .send(NotificationType::SystemWarning { message })
+                    .await
+                {
+                    warn!("Failed to send low disk space alert: {err}");
+                }
+            });
+        }
+    }
+}
+
+/// The disk with the longest mount point containing `path`.
+pub(crate) fn containing_disk<'a>(disks: &'a Disks, path: &std::path::Path) -> Option<&'a Disk> {
+    disks
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+}
@@ -1,4 +1,4 @@
-use super::*;
+use {super::*, sysinfo::Disk};
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct NodeStatus {
@@ -15,4 +15,43 @@ pub struct NodeStatus {
     pub best_share: Option<u64>,
     pub accepted: Option<u64>,
     pub rejected: Option<u64>,
+    #[serde(default)]
+    pub disks: Vec<DiskUsage>,
+    #[serde(default)]
+    pub swap_usage_percent: f64,
+    #[serde(default)]
+    pub load_average: LoadAverage,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct DiskUsage {
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub usage_percent: f64,
+}
+
+impl From<&Disk> for DiskUsage {
+    fn from(disk: &Disk) -> Self {
+        let total_bytes = disk.total_space();
+        let available_bytes = disk.available_space();
+
+        Self {
+            mount_point: disk.mount_point().display().to_string(),
+            total_bytes,
+            available_bytes,
+            usage_percent: if total_bytes > 0 {
+                100.0 * total_bytes.saturating_sub(available_bytes) as f64 / total_bytes as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
 }
@@ -7,13 +7,14 @@ pub enum NotificationType {
         hash: String,
         value: i64,
         miner: String,
+        explorer_url: Option<String>,
         test: bool,
     },
-    #[allow(dead_code)]
-    SystemWarning { message: String },
+    SystemWarning {
+        message: String,
+    },
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum NotificationPriority {
     Max = 5,
@@ -41,7 +42,7 @@ impl NotificationHandler {
         }
     }
 
-    pub fn _with_custom_server(server_url: String, channel: String) -> Self {
+    pub fn with_custom_server(server_url: String, channel: String) -> Self {
         Self {
             ntfy_url: server_url,
             channel,
@@ -106,6 +107,34 @@ impl NotificationHandler {
         }
     }
 
+    /// Sends `notification`, retrying up to `retries` times with the delay
+    /// doubling after each failed attempt.
+    pub async fn send_with_retries(
+        &self,
+        notification: NotificationType,
+        retries: u32,
+        retry_delay: Duration,
+    ) -> Result<()> {
+        let mut attempt = 0;
+        let mut delay = retry_delay;
+
+        loop {
+            match self.send(notification.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < retries => {
+                    attempt += 1;
+                    warn!(
+                        "Notification attempt {attempt} of {} failed, retrying in {delay:?}: {err}",
+                        retries + 1,
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
     pub fn format_notification(
         &self,
         notification: NotificationType,
@@ -116,20 +145,25 @@ impl NotificationHandler {
                 hash,
                 value,
                 miner,
+                explorer_url,
                 test,
             } => {
                 let btc_value = value as f64 / 100_000_000.0;
                 let prefix = if test { "[TEST] " } else { "" };
+                let mut message = format!(
+                    "{}Block Height: {}\nHash: {}\nValue: {:.8} BTC\nMiner: {}",
+                    prefix,
+                    height,
+                    &hash[..16], // might be backwards? can't remember, need better test case
+                    btc_value,
+                    miner
+                );
+                if let Some(explorer_url) = explorer_url {
+                    message.push_str(&format!("\nExplorer: {explorer_url}"));
+                }
                 (
                     format!("{}⛏️ New Block Found! #{}", prefix, height),
-                    format!(
-                        "{}Block Height: {}\nHash: {}\nValue: {:.8} BTC\nMiner: {}",
-                        prefix,
-                        height,
-                        &hash[..16], // might be backwards? can't remember, need better test case
-                        btc_value,
-                        miner
-                    ),
+                    message,
                     NotificationPriority::Max,
                     vec![
                         "pick".to_string(),
@@ -204,15 +238,11 @@ impl NotificationHandler {
 const PAYOUTS_ATTACHMENT_MAX_BYTES: usize = 1_500_000;
 
 pub async fn notify_payouts_attachment<T: serde::Serialize>(
-    alerts_ntfy_channel: Option<String>,
+    handler: &NotificationHandler,
     height: i32,
     payouts: &T,
     test: bool,
 ) {
-    let Some(channel) = alerts_ntfy_channel else {
-        return;
-    };
-
     let body = match serde_json::to_vec_pretty(payouts) {
         Ok(b) if b.len() <= PAYOUTS_ATTACHMENT_MAX_BYTES => b,
         Ok(b) => {
@@ -230,7 +260,6 @@ pub async fn notify_payouts_attachment<T: serde::Serialize>(
     };
 
     let prefix = if test { "[TEST] " } else { "" };
-    let handler = NotificationHandler::new(channel);
     if let Err(e) = handler
         .send_attachment(
             format!("payouts-{height}.json"),
@@ -244,28 +273,3 @@ pub async fn notify_payouts_attachment<T: serde::Serialize>(
         info!("Skipping payouts attachment: {e}");
     }
 }
-
-pub async fn notify_block_found(
-    alerts_ntfy_channel: Option<String>,
-    height: i32,
-    hash: String,
-    value: i64,
-    miner: String,
-    test: bool,
-) -> Result<()> {
-    if let Some(alerts_ntfy_channel) = alerts_ntfy_channel {
-        let handler = NotificationHandler::new(alerts_ntfy_channel.clone());
-
-        handler
-            .send(NotificationType::BlockFound {
-                height,
-                hash,
-                value,
-                miner,
-                test,
-            })
-            .await
-    } else {
-        Err(anyhow!("No alerts ntfy channel to notify"))
-    }
-}
@@ -2,12 +2,17 @@ use {
     super::*,
     crate::subcommand::server::{
         database::{
-            FailedPayout, HistoricalPayout, Payout, PendingPayout, Split, UpdatePayoutStatusRequest,
+            CancelPayoutsRequest, ExcludeFromPayoutRequest, FailedPayout, HistoricalPayout, Payout,
+            PayoutStatus, PendingPayout, RequeuePayoutsRequest, Split, UpdatePayoutStatusRequest,
         },
         templates::simulate_payouts::SimulatePayoutsHtml,
     },
 };
 
+const DEFAULT_OPEN_SPLIT_LIMIT: i64 = 25;
+
+const MAX_OPEN_SPLIT_LIMIT: i64 = 100;
+
 pub(crate) fn payouts_router(config: Arc<ServerConfig>, database: Database) -> axum::Router {
     axum::Router::new()
         .route("/payouts", get(payouts_all))
@@ -16,6 +21,9 @@ pub(crate) fn payouts_router(config: Arc<ServerConfig>, database: Database) -> a
         .route("/payouts/simulate", get(payouts_simulate))
         .route("/payouts/{blockheight}", get(payouts))
         .route("/payouts/update", post(update_payout_status))
+        .route("/payouts/exclude", post(exclude_from_payout))
+        .route("/payouts/requeue", post(requeue_payouts))
+        .route("/payouts/cancel", post(cancel_payouts))
         .route(
             "/payouts/range/{start_height}/{end_height}",
             get(payouts_range),
@@ -24,13 +32,26 @@ pub(crate) fn payouts_router(config: Arc<ServerConfig>, database: Database) -> a
             "/payouts/range/{start_height}/{end_height}/user/{username}",
             get(user_payout_range),
         )
+        .route("/payouts/dates/{start}/{end}", get(payouts_dates))
         .route("/split", get(open_split))
         .route("/split/{blockheight}", get(sat_split))
+        .route("/split/open/range/{start}/{end}", get(open_split_range))
         .layer(Extension(database))
         .layer(from_extractor::<AdminAuth>())
         .layer(Extension(config))
 }
 
+/// Read-only payout routes, open to the API token as well as the admin token.
+pub(crate) fn payout_status_router(database: Database) -> axum::Router {
+    axum::Router::new()
+        .route(
+            "/payouts/range/{start_height}/{end_height}/status",
+            get(payouts_range_status),
+        )
+        .layer(Extension(database))
+        .layer(from_extractor::<ApiAuth>())
+}
+
 /// Get all pending and failed payouts
 #[utoipa::path(
     get,
@@ -85,7 +106,7 @@ pub(crate) async fn payouts_failed(
     path = "/payouts/history",
     security(("admin_token" = [])),
     params(
-        ("status" = Option<String>, Query, description = "Filter to a single status: pending|processing|success|failure|cancelled"),
+        ("status" = Option<String>, Query, description = "Filter to a single status: pending|processing|success|failure|cancelled|dead_letter"),
         ("format" = Option<String>, Query, description = "Response format: 'json' (default) or 'csv'")
     ),
     responses(
@@ -237,20 +258,88 @@ pub(crate) async fn open_split(Extension(database): Extension<Database>) -> Serv
 )]
 pub(crate) async fn sat_split(
     Path(blockheight): Path<u32>,
+    Extension(config): Extension<Arc<ServerConfig>>,
     Extension(database): Extension<Database>,
 ) -> ServerResult<Response> {
-    if blockheight == 0 {
+    let Some(split) = block_sat_split(&config, &database, blockheight.try_into().unwrap()).await?
+    else {
         return Err(ServerError::NotFound("block not mined by parasite".into()));
+    };
+
+    Ok(Json(split).into_response())
+}
+
+/// Get sat splits for every block in a range whose rewards are unprocessed
+#[utoipa::path(
+    get,
+    path = "/split/open/range/{start}/{end}",
+    security(("admin_token" = [])),
+    params(
+        ("start" = u32, Path, description = "First block height, inclusive"),
+        ("end" = u32, Path, description = "Last block height, inclusive"),
+        ("limit" = Option<i64>, Query, description = "Most blocks to return, at most 100 (default: 25)"),
+        ("offset" = Option<i64>, Query, description = "Open blocks to skip (default: 0)")
+    ),
+    responses(
+        (status = 200, description = "Sat split for each open block, lowest height first", body = Vec<SatSplit>),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn open_split_range(
+    Path((start, end)): Path<(u32, u32)>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(config): Extension<Arc<ServerConfig>>,
+    Extension(database): Extension<Database>,
+) -> ServerResult<Response> {
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_OPEN_SPLIT_LIMIT)
+        .clamp(1, MAX_OPEN_SPLIT_LIMIT);
+
+    let offset = params
+        .get("offset")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or_default()
+        .max(0);
+
+    let blockheights = database
+        .get_open_blockheights(
+            start.try_into().unwrap_or(i32::MAX),
+            end.try_into().unwrap_or(i32::MAX),
+            limit,
+            offset,
+        )
+        .await?;
+
+    let mut splits = Vec::new();
+    for blockheight in blockheights {
+        if let Some(split) = block_sat_split(&config, &database, blockheight).await? {
+            splits.push(split);
+        }
     }
 
-    let Some((blockheight, blockhash, coinbasevalue, _, username)) = database
-        .get_total_coinbase(blockheight.try_into().unwrap())
-        .await?
+    Ok(Json(splits).into_response())
+}
+
+/// The split of the fees in the block at `blockheight` among the lightning
+/// addresses that contributed to it, or `None` if parasite did not find it.
+async fn block_sat_split(
+    config: &ServerConfig,
+    database: &Database,
+    blockheight: i32,
+) -> ServerResult<Option<SatSplit>> {
+    if blockheight == 0 {
+        return Ok(None);
+    }
+
+    let Some((blockheight, blockhash, coinbasevalue, _, username)) =
+        database.get_total_coinbase(blockheight).await?
     else {
-        return Err(ServerError::NotFound("block not mined by parasite".into()));
+        return Ok(None);
     };
 
-    let total_payment_amount = coinbasevalue.saturating_sub(COIN_VALUE.try_into().unwrap());
+    let total_payment_amount = total_payment_amount(config.chain(), blockheight, coinbasevalue);
 
     let payouts = database.get_payouts(blockheight, username).await?;
 
@@ -264,13 +353,25 @@ pub(crate) async fn sat_split(
         }
     }
 
-    Ok(Json(SatSplit {
+    Ok(Some(SatSplit {
         block_height: blockheight,
+        explorer_url: config
+            .explorer_url()
+            .map(|url| url.block(blockheight, &blockhash)),
         block_hash: blockhash,
         total_payment_amount,
         payments,
-    })
-    .into_response())
+    }))
+}
+
+/// Fees collected by the block at `blockheight`, i.e. `coinbasevalue` minus
+/// the subsidy at that height.
+pub(crate) fn total_payment_amount(chain: Chain, blockheight: i32, coinbasevalue: i64) -> i64 {
+    let subsidy = chain.subsidy(blockheight.try_into().unwrap_or_default());
+
+    coinbasevalue
+        .saturating_sub(subsidy.to_sat().try_into().unwrap())
+        .max(0)
 }
 
 /// Get payouts for a range of blocks
@@ -307,6 +408,116 @@ pub(crate) async fn payouts_range(
     .into_response())
 }
 
+/// Get every payout, in any status, for a range of blocks
+#[utoipa::path(
+    get,
+    path = "/payouts/range/{start_height}/{end_height}/status",
+    security(("api_token" = [])),
+    params(
+        ("start_height" = u32, Path, description = "Start block height (inclusive)"),
+        ("end_height" = u32, Path, description = "End block height (inclusive)"),
+    ),
+    responses(
+        (status = 200, description = "Payout statuses for block range", body = Vec<PayoutStatus>),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn payouts_range_status(
+    Path((start_height, end_height)): Path<(u32, u32)>,
+    Extension(database): Extension<Database>,
+) -> ServerResult<Response> {
+    Ok(Json(
+        database
+            .get_payout_statuses_range(
+                start_height.try_into().unwrap(),
+                end_height.try_into().unwrap(),
+            )
+            .await?,
+    )
+    .into_response())
+}
+
+/// Get payouts for the blocks found in a time range
+#[utoipa::path(
+    get,
+    path = "/payouts/dates/{start}/{end}",
+    security(("admin_token" = [])),
+    params(
+        ("start" = String, Path, description = "Start as unix timestamp, RFC 3339 timestamp or YYYY-MM-DD date (inclusive)"),
+        ("end" = String, Path, description = "End as unix timestamp, RFC 3339 timestamp or YYYY-MM-DD date (exclusive, dates include the whole day)"),
+        ("excluded" = Option<String>, Query, description = "Comma-separated list of usernames to exclude")
+    ),
+    responses(
+        (status = 200, description = "Payouts for blocks found in time range", body = Vec<Payout>),
+        (status = 400, description = "Invalid or empty time range"),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn payouts_dates(
+    Path((start, end)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(database): Extension<Database>,
+) -> ServerResult<Response> {
+    let start_secs = parse_time_bound(&start, TimeBound::Start)?;
+    let end_secs = parse_time_bound(&end, TimeBound::End)?;
+
+    if start_secs >= end_secs {
+        return Err(ServerError::BadRequest(format!(
+            "start `{start}` must be before end `{end}`"
+        )));
+    }
+
+    let excluded_usernames = exclusion_list_from_params(params);
+
+    let payouts = match database
+        .get_height_range_for_times(start_secs, end_secs)
+        .await?
+    {
+        Some((start_height, end_height)) => {
+            database
+                .get_payouts_range(start_height, end_height, excluded_usernames)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Json(payouts).into_response())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TimeBound {
+    Start,
+    End,
+}
+
+/// Parses a unix timestamp, an RFC 3339 timestamp or a `YYYY-MM-DD` date
+/// into unix seconds. A date covers its whole day in UTC, so as an end bound
+/// it stands for the following midnight.
+pub(crate) fn parse_time_bound(s: &str, bound: TimeBound) -> ServerResult<i64> {
+    if let Ok(secs) = s.parse::<i64>() {
+        return Ok(secs);
+    }
+
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(time.timestamp());
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| {
+        ServerError::BadRequest(format!(
+            "invalid time `{s}`: expected unix timestamp, RFC 3339 timestamp or YYYY-MM-DD date"
+        ))
+    })?;
+
+    let date = match bound {
+        TimeBound::Start => Some(date),
+        TimeBound::End => date.succ_opt(),
+    };
+
+    date.and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc().timestamp())
+        .ok_or_else(|| ServerError::BadRequest(format!("time `{s}` is out of range")))
+}
+
 /// Get payouts for a specific user in a block range
 #[utoipa::path(
     get,
@@ -355,6 +566,7 @@ pub(crate) async fn user_payout_range(
     tag = "payouts"
 )]
 pub(crate) async fn update_payout_status(
+    Extension(config): Extension<Arc<ServerConfig>>,
     Extension(database): Extension<Database>,
     Json(request): Json<UpdatePayoutStatusRequest>,
 ) -> ServerResult<Response> {
@@ -363,6 +575,7 @@ pub(crate) async fn update_payout_status(
             &request.payout_ids,
             &request.status,
             request.failure_reason.as_deref(),
+            config.payout_max_retries(),
         )
         .await?;
 
@@ -372,3 +585,82 @@ pub(crate) async fn update_payout_status(
     }))
     .into_response())
 }
+
+/// Re-queue dead-lettered payouts for another round of retries
+#[utoipa::path(
+    post,
+    path = "/payouts/requeue",
+    security(("admin_token" = [])),
+    request_body = RequeuePayoutsRequest,
+    responses(
+        (status = 200, description = "Payouts re-queued successfully"),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn requeue_payouts(
+    Extension(database): Extension<Database>,
+    Json(request): Json<RequeuePayoutsRequest>,
+) -> ServerResult<Response> {
+    let rows_affected = database.requeue_payouts(&request.payout_ids).await?;
+
+    Ok(Json(json!({
+        "status": "OK",
+        "rows_affected": rows_affected,
+    }))
+    .into_response())
+}
+
+/// Cancel an account's unpaid payouts, e.g. while a dispute is resolved
+#[utoipa::path(
+    post,
+    path = "/payouts/cancel",
+    security(("admin_token" = [])),
+    request_body = CancelPayoutsRequest,
+    responses(
+        (status = 200, description = "Payouts cancelled successfully"),
+        (status = 400, description = "Neither or both of account id and username given"),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn cancel_payouts(
+    Extension(database): Extension<Database>,
+    Json(request): Json<CancelPayoutsRequest>,
+) -> ServerResult<Response> {
+    if request.account_id.is_some() == request.username.is_some() {
+        return Err(ServerError::BadRequest(
+            "exactly one of `account_id` and `username` is required".into(),
+        ));
+    }
+
+    let rows_affected = database.cancel_payouts(&request).await?;
+
+    Ok(Json(json!({
+        "status": "OK",
+        "rows_affected": rows_affected,
+    }))
+    .into_response())
+}
+
+/// Exclude an account from (or re-include it in) future payouts
+#[utoipa::path(
+    post,
+    path = "/payouts/exclude",
+    security(("admin_token" = [])),
+    request_body = ExcludeFromPayoutRequest,
+    responses(
+        (status = 200, description = "Account updated", body = Account),
+        (status = 404, description = "Account not found"),
+    ),
+    tag = "payouts"
+)]
+pub(crate) async fn exclude_from_payout(
+    Extension(database): Extension<Database>,
+    Json(request): Json<ExcludeFromPayoutRequest>,
+) -> ServerResult<Response> {
+    database
+        .set_account_exclude_from_payout(&request.btc_address, request.exclude_from_payout)
+        .await?
+        .ok_or_not_found(|| "Account")
+        .map(Json)
+        .map(IntoResponse::into_response)
+}
@@ -1,8 +1,18 @@
 use {
     super::*,
-    crate::subcommand::server::database::{HighestDiff, TeraShare},
+    crate::subcommand::server::database::{Contributor, HighestDiff, TeraShare},
 };
 
+/// Heights the leaderboard covers unless asked otherwise, about a day.
+const DEFAULT_LEADERBOARD_WINDOW: i32 = 144;
+
+const DEFAULT_LEADERBOARD_LIMIT: i64 = 10;
+
+const MAX_LEADERBOARD_LIMIT: i64 = 100;
+
+/// Target block spacing, used to turn time windows into heights.
+const BLOCK_SPACING_SECS: i64 = 600;
+
 pub(crate) fn share_difficulty_router(database: Database) -> axum::Router {
     axum::Router::new()
         .route("/highestdiff/{blockheight}", get(highestdiff))
@@ -12,6 +22,7 @@ pub(crate) fn share_difficulty_router(database: Database) -> axum::Router {
         )
         .route("/highestdiff/{blockheight}/all", get(highestdiff_all_users))
         .route("/terashares", get(get_tera_shares))
+        .route("/api/leaderboard", get(leaderboard))
         .layer(from_extractor::<ApiAuth>())
         .layer(Extension(database))
 }
@@ -128,3 +139,80 @@ pub(crate) async fn get_tera_shares(
     )
     .into_response())
 }
+
+/// Parses a leaderboard window into a number of heights: either a height
+/// count like `144`, or a time like `30m`, `24h` or `7d`, taken at one block
+/// per ten minutes.
+pub(crate) fn parse_leaderboard_window(s: &str) -> ServerResult<i32> {
+    let invalid = || {
+        ServerError::BadRequest(format!(
+            "invalid window `{s}`: expected a number of blocks or a time like 24h"
+        ))
+    };
+
+    let (number, unit_secs) = match s.char_indices().last() {
+        Some((i, 'm')) => (&s[..i], Some(60)),
+        Some((i, 'h')) => (&s[..i], Some(60 * 60)),
+        Some((i, 'd')) => (&s[..i], Some(24 * 60 * 60)),
+        _ => (s, None),
+    };
+
+    let number = number.parse::<i64>().map_err(|_| invalid())?;
+
+    let heights = match unit_secs {
+        Some(unit_secs) => number
+            .checked_mul(unit_secs)
+            .and_then(|secs| secs.checked_add(BLOCK_SPACING_SECS - 1))
+            .map(|secs| secs / BLOCK_SPACING_SECS)
+            .ok_or_else(invalid)?,
+        None => number,
+    };
+
+    if heights < 1 {
+        return Err(invalid());
+    }
+
+    heights.try_into().map_err(|_| invalid())
+}
+
+/// Get the accounts with the most accepted difficulty in a recent window
+#[utoipa::path(
+    get,
+    path = "/api/leaderboard",
+    security(("api_token" = [])),
+    params(
+        ("window" = Option<String>, Query, description = "Number of recent blocks, or a time like 24h taken at one block per ten minutes (default: 144)"),
+        ("limit" = Option<i64>, Query, description = "Most accounts to return, at most 100 (default: 10)"),
+        ("excluded" = Option<String>, Query, description = "Comma-separated list of usernames to exclude")
+    ),
+    responses(
+        (status = 200, description = "Top contributors, best first", body = Vec<Contributor>),
+        (status = 400, description = "Invalid window"),
+    ),
+    tag = "sharediff"
+)]
+pub(crate) async fn leaderboard(
+    Extension(database): Extension<Database>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ServerResult<Response> {
+    let window = params
+        .get("window")
+        .map(|window| parse_leaderboard_window(window))
+        .transpose()?
+        .unwrap_or(DEFAULT_LEADERBOARD_WINDOW);
+
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .clamp(1, MAX_LEADERBOARD_LIMIT);
+
+    let excluded_usernames = exclusion_list_from_params(params);
+
+    Ok(Json(
+        database
+            .top_contributors(window, limit, excluded_usernames)
+            .await?,
+    )
+    .into_response())
+}
@@ -0,0 +1,36 @@
+use {super::*, crate::subcommand::server::database::RemoteShare};
+
+pub(crate) fn shares_router(config: Arc<ServerConfig>, database: Database) -> axum::Router {
+    axum::Router::new()
+        .route("/api/shares/{origin}/{id}", get(share))
+        .layer(Extension(database))
+        .layer(from_extractor::<AdminAuth>())
+        .layer(Extension(config))
+}
+
+/// Get a single synced share for auditing
+#[utoipa::path(
+    get,
+    path = "/api/shares/{origin}/{id}",
+    security(("admin_token" = [])),
+    params(
+        ("origin" = String, Path, description = "Hostname of the node that synced the share"),
+        ("id" = i64, Path, description = "Share id on the origin node")
+    ),
+    responses(
+        (status = 200, description = "Share found", body = RemoteShare),
+        (status = 404, description = "Share not found"),
+    ),
+    tag = "shares"
+)]
+pub(crate) async fn share(
+    Path((origin, id)): Path<(String, i64)>,
+    Extension(database): Extension<Database>,
+) -> ServerResult<Response> {
+    database
+        .get_remote_share(&origin, id)
+        .await?
+        .ok_or_not_found(|| "Share")
+        .map(Json)
+        .map(IntoResponse::into_response)
+}
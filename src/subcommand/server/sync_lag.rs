@@ -0,0 +1,154 @@
+use {
+    super::*,
+    notifications::{NotificationHandler, NotificationType},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How far behind one origin's synced shares are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OriginLag {
+    pub origin: String,
+    /// Highest share id received from the origin.
+    pub last_id: i64,
+    /// Unix time of the last batch received from the origin.
+    pub last_seen: u64,
+    /// Seconds since the last batch.
+    pub lag_secs: u64,
+    pub batches: u64,
+    pub shares: u64,
+    /// Shares per second received since the first batch.
+    pub shares_per_second: f64,
+}
+
+struct Progress {
+    last_id: i64,
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    batches: u64,
+    shares: u64,
+    alerted: bool,
+}
+
+/// Tracks the sync batches each origin has delivered, so operators can see
+/// whether every node is keeping up.
+pub(crate) struct SyncLag {
+    origins: Mutex<BTreeMap<String, Progress>>,
+}
+
+impl SyncLag {
+    pub(crate) fn new() -> Self {
+        Self {
+            origins: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Records a stored batch of `shares` shares ending at share `end_id`.
+    pub(crate) fn record(&self, origin: &str, end_id: i64, shares: usize, now: SystemTime) {
+        let mut origins = self.origins.lock();
+
+        let progress = origins.entry(origin.into()).or_insert(Progress {
+            last_id: end_id,
+            first_seen: now,
+            last_seen: now,
+            batches: 0,
+            shares: 0,
+            alerted: false,
+        });
+
+        progress.last_id = progress.last_id.max(end_id);
+        progress.last_seen = now;
+        progress.batches += 1;
+        progress.shares += shares as u64;
+
+        if progress.alerted {
+            info!("Sync from {origin} caught up");
+            progress.alerted = false;
+        }
+    }
+
+    pub(crate) fn report(&self, now: SystemTime) -> Vec<OriginLag> {
+        self.origins
+            .lock()
+            .iter()
+            .map(|(origin, progress)| {
+                let elapsed = now
+                    .duration_since(progress.first_seen)
+                    .unwrap_or_default()
+                    .as_secs_f64();
+
+                OriginLag {
+                    origin: origin.clone(),
+                    last_id: progress.last_id,
+                    last_seen: progress
+                        .last_seen
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    lag_secs: now
+                        .duration_since(progress.last_seen)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    batches: progress.batches,
+                    shares: progress.shares,
+                    shares_per_second: if elapsed > 0.0 {
+                        progress.shares as f64 / elapsed
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Origins that have just fallen more than `threshold` behind. Each is
+    /// returned once until it sends another batch.
+    pub(crate) fn newly_lagging(&self, threshold: Duration, now: SystemTime) -> Vec<OriginLag> {
+        let lagging = self
+            .report(now)
+            .into_iter()
+            .filter(|lag| lag.lag_secs > threshold.as_secs())
+            .collect::<Vec<OriginLag>>();
+
+        let mut origins = self.origins.lock();
+
+        lagging
+            .into_iter()
+            .filter(|lag| {
+                origins
+                    .get_mut(&lag.origin)
+                    .is_some_and(|progress| !std::mem::replace(&mut progress.alerted, true))
+            })
+            .collect()
+    }
+
+    pub(crate) fn spawn(self: Arc<Self>, threshold: Duration, alerts_ntfy_channel: Option<String>) {
+        tokio::spawn(async move {
+            let mut ticker = ticker(CHECK_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                for lag in self.newly_lagging(threshold, SystemTime::now()) {
+                    let message = format!(
+                        "no sync batch from {} for {}s (last share id {})",
+                        lag.origin, lag.lag_secs, lag.last_id
+                    );
+
+                    warn!("{message}");
+
+                    if let Some(channel) = alerts_ntfy_channel.clone() {
+                        tokio::spawn(async move {
+                            if let Err(err) = NotificationHandler::new(channel)
+                                .send(NotificationType::SystemWarning { message })
+                                .await
+                            {
+                                warn!("Failed to send sync lag alert: {err}");
+                            }
+                        });
+                    }
+                }
+            }
+        });
+    }
+}
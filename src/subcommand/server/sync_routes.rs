@@ -1,12 +1,66 @@
-use super::*;
+use {
+    super::*,
+    axum::extract::{FromRequest, Request, rejection::JsonRejection},
+    notifications::{NotificationHandler, NotificationType},
+    sync::{FailedSubBatch, FoundBlockRecord},
+    sync_lag::OriginLag,
+};
+
+/// `Json<ShareBatch>` that reports malformed bodies as a `SyncResponse`
+/// naming the offending field, rather than axum's plain-text rejection.
+pub(crate) struct SyncBatchJson(pub(crate) ShareBatch);
+
+impl<S> FromRequest<S> for SyncBatchJson
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<SyncResponse>);
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<ShareBatch>::from_request(request, state).await {
+            Ok(Json(batch)) => Ok(Self(batch)),
+            Err(rejection) => {
+                let status = match rejection {
+                    JsonRejection::JsonDataError(_) => StatusCode::BAD_REQUEST,
+                    _ => rejection.status(),
+                };
+
+                let message = rejection.body_text();
+
+                warn!("Rejecting malformed sync batch ({status}): {message}");
+
+                Err((
+                    status,
+                    Json(SyncResponse {
+                        batch_id: 0,
+                        received_count: 0,
+                        status: "ERROR".to_string(),
+                        error_message: Some(message),
+                        failed_sub_batch: None,
+                    }),
+                ))
+            }
+        }
+    }
+}
 
-pub(crate) fn sync_router(config: Arc<ServerConfig>, database: Database) -> axum::Router {
+pub(crate) fn sync_router(
+    config: Arc<ServerConfig>,
+    database: Database,
+    disk_guard: Arc<DiskGuard>,
+    sync_lag: Arc<SyncLag>,
+    tasks: TaskTracker,
+) -> axum::Router {
     axum::Router::new()
         .route(
             "/sync/batch",
             post(sync_batch).layer(DefaultBodyLimit::max(50 * MEBIBYTE)),
         )
+        .route("/api/sync/lag", get(sync_lag_report))
         .layer(Extension(database))
+        .layer(Extension(disk_guard))
+        .layer(Extension(sync_lag))
+        .layer(Extension(tasks))
         .layer(from_extractor::<AdminAuth>())
         .layer(Extension(config))
 }
@@ -19,14 +73,20 @@ pub(crate) fn sync_router(config: Arc<ServerConfig>, database: Database) -> axum
     request_body = ShareBatch,
     responses(
         (status = 200, description = "Batch processed", body = SyncResponse),
+        (status = 400, description = "Malformed batch", body = SyncResponse),
+        (status = 413, description = "Batch exceeds body size limit", body = SyncResponse),
+        (status = 503, description = "Insufficient disk space", body = SyncResponse),
     ),
     tag = "sync"
 )]
 pub(crate) async fn sync_batch(
     Extension(database): Extension<Database>,
     Extension(config): Extension<Arc<ServerConfig>>,
-    Json(batch): Json<ShareBatch>,
-) -> Result<Json<SyncResponse>, StatusCode> {
+    Extension(disk_guard): Extension<Arc<DiskGuard>>,
+    Extension(sync_lag): Extension<Arc<SyncLag>>,
+    Extension(tasks): Extension<TaskTracker>,
+    SyncBatchJson(batch): SyncBatchJson,
+) -> Result<Response, StatusCode> {
     info!(
         "Received sync batch {} with {} shares from {}",
         batch.batch_id,
@@ -34,6 +94,25 @@ pub(crate) async fn sync_batch(
         batch.hostname
     );
 
+    if disk_guard.is_low() {
+        warn!(
+            "Rejecting sync batch {} - disk space below {}% free",
+            batch.batch_id,
+            disk_guard.min_free_percent()
+        );
+        let response = SyncResponse {
+            batch_id: batch.batch_id,
+            received_count: 0,
+            status: "UNAVAILABLE".to_string(),
+            error_message: Some(format!(
+                "Insufficient disk space: less than {}% free, try again later",
+                disk_guard.min_free_percent()
+            )),
+            failed_sub_batch: None,
+        };
+        return Ok((StatusCode::SERVICE_UNAVAILABLE, Json(response)).into_response());
+    }
+
     if config.migrate_accounts() && !MIGRATION_DONE.get_or_init(|| false) {
         warn!(
             "Rejecting sync batch {} - migration in progress",
@@ -44,14 +123,23 @@ pub(crate) async fn sync_batch(
             received_count: 0,
             status: "UNAVAILABLE".to_string(),
             error_message: Some("Migration in progress, try again later".to_string()),
+            failed_sub_batch: None,
         };
-        return Ok(Json(response));
+        return Ok(Json(response).into_response());
     }
 
     let mut new_block_height: Option<i32> = None;
 
     if let Some(block) = &batch.block {
-        match database.upsert_block(block).await {
+        match database
+            .upsert_block(
+                block,
+                config.finder_bonus_bps(),
+                config.donation().as_ref(),
+                config.payout_mode(),
+            )
+            .await
+        {
             Ok(was_inserted) => {
                 if was_inserted {
                     new_block_height = Some(block.blockheight);
@@ -69,42 +157,49 @@ pub(crate) async fn sync_batch(
             Err(e) => error!("Warning: Failed to upsert block: {}", e),
         }
 
-        // Sentinel hash prefix marks a test block-find for the notification path.
-        let is_test = block.blockhash.starts_with("deadbeefdeadbeef");
-
-        let notification_result = notifications::notify_block_found(
-            config.alerts_ntfy_channel(),
-            block.blockheight,
-            block.blockhash.clone(),
-            block.coinbasevalue.unwrap_or(0),
-            block
-                .username
-                .clone()
-                .unwrap_or_else(|| "unknown".to_string()),
-            is_test,
-        )
-        .await;
-
-        match notification_result {
-            Ok(_) => info!("Block notification sent successfully"),
-            Err(e) => error!("Failed to send block notification: {}", e),
-        }
-
-        if new_block_height.is_some()
-            && let Ok(pending) = database.get_pending_payouts().await
-        {
-            notifications::notify_payouts_attachment(
-                config.alerts_ntfy_channel(),
-                block.blockheight,
-                &pending,
-                is_test,
-            )
-            .await;
+        // Notifications retry with delays, so they run off the request path
+        // rather than holding up the batch response. Shutdown waits for them
+        // so a claimed alert isn't abandoned mid-send.
+        if let Some(channel) = config.alerts_ntfy_channel() {
+            let database = database.clone();
+            let config = config.clone();
+            let block = block.clone();
+
+            tasks.spawn(async move {
+                let handler = NotificationHandler::with_custom_server(
+                    config.alerts_ntfy_server().to_string(),
+                    channel,
+                );
+
+                // Sentinel hash prefix marks a test block-find for the notification path.
+                let is_test = block.blockhash.starts_with("deadbeefdeadbeef");
+
+                notify_block_found(&database, &config, &handler, &block, is_test).await;
+
+                if new_block_height.is_some()
+                    && let Ok(pending) = database.get_pending_payouts().await
+                {
+                    notifications::notify_payouts_attachment(
+                        &handler,
+                        block.blockheight,
+                        &pending,
+                        is_test,
+                    )
+                    .await;
+                }
+            });
         }
     }
 
-    match process_share_batch(&batch, &database).await {
+    match process_share_batch(&batch, &database, config.share_insert_batch_size()).await {
         Ok(_) => {
+            sync_lag.record(
+                &batch.hostname,
+                batch.end_id,
+                batch.shares.len(),
+                SystemTime::now(),
+            );
+
             if let Some(height) = new_block_height {
                 if let Err(e) = database.snapshot_round_participation(height).await {
                     error!(
@@ -123,24 +218,104 @@ pub(crate) async fn sync_batch(
                 received_count: batch.shares.len(),
                 status: "OK".to_string(),
                 error_message: None,
+                failed_sub_batch: None,
             };
             info!("Successfully processed batch {}", batch.batch_id);
-            Ok(Json(response))
+            Ok(Json(response).into_response())
         }
         Err(e) => {
             let response = SyncResponse {
                 batch_id: batch.batch_id,
                 received_count: 0,
                 status: "ERROR".to_string(),
-                error_message: Some(e.to_string()),
+                error_message: Some(format!("{e:#}")),
+                failed_sub_batch: e.downcast_ref::<FailedSubBatch>().copied(),
             };
-            error!("Failed to process batch {}: {}", batch.batch_id, e);
-            Ok(Json(response))
+            error!("Failed to process batch {}: {:#}", batch.batch_id, e);
+            Ok(Json(response).into_response())
         }
     }
 }
 
-async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<()> {
+/// Per-origin sync progress and lag
+#[utoipa::path(
+    get,
+    path = "/api/sync/lag",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Sync progress for each origin", body = Vec<OriginLag>),
+    ),
+    tag = "sync"
+)]
+pub(crate) async fn sync_lag_report(
+    Extension(sync_lag): Extension<Arc<SyncLag>>,
+) -> Json<Vec<OriginLag>> {
+    Json(sync_lag.report(SystemTime::now()))
+}
+
+/// Alerts on `block` unless an earlier batch already did. The claim is made
+/// before sending and dropped if every attempt fails, so a duplicate batch
+/// can't double-alert but a later one can still retry. If the claim itself
+/// fails the alert is sent anyway, since a duplicate beats a missed block.
+async fn notify_block_found(
+    database: &Database,
+    config: &ServerConfig,
+    handler: &NotificationHandler,
+    block: &FoundBlockRecord,
+    is_test: bool,
+) {
+    let claimed = match database.claim_block_notification(&block.blockhash).await {
+        Ok(true) => true,
+        Ok(false) => {
+            info!(
+                "Block notification for {} already sent, skipping",
+                block.blockhash
+            );
+            return;
+        }
+        Err(e) => {
+            error!("Failed to claim block notification, sending anyway: {}", e);
+            false
+        }
+    };
+
+    let notification_result = handler
+        .send_with_retries(
+            NotificationType::BlockFound {
+                height: block.blockheight,
+                hash: block.blockhash.clone(),
+                value: block.coinbasevalue.unwrap_or(0),
+                miner: block
+                    .username
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                explorer_url: config
+                    .explorer_url()
+                    .map(|url| url.block(block.blockheight, &block.blockhash)),
+                test: is_test,
+            },
+            config.block_notify_retries(),
+            config.block_notify_retry_delay(),
+        )
+        .await;
+
+    match notification_result {
+        Ok(_) => info!("Block notification sent successfully"),
+        Err(e) => {
+            error!("Failed to send block notification: {}", e);
+
+            if claimed && let Err(e) = database.release_block_notification(&block.blockhash).await {
+                error!("Failed to release block notification: {}", e);
+            }
+        }
+    }
+}
+
+pub(crate) async fn process_share_batch(
+    batch: &ShareBatch,
+    database: &Database,
+    sub_batch_size: usize,
+) -> Result<()> {
     info!(
         "Processing {} shares from batch {}",
         batch.shares.len(),
@@ -151,18 +326,19 @@ async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<
         return Ok(());
     }
 
-    const MAX_SHARES_PER_SUBBATCH: usize = 2500;
     let mut tx = database
         .pool
         .begin()
         .await
         .map_err(|e| anyhow!("Failed to start transaction: {e}"))?;
 
-    for (chunk_idx, chunk) in batch.shares.chunks(MAX_SHARES_PER_SUBBATCH).enumerate() {
+    let mut conflicting = BTreeSet::new();
+
+    for (chunk_idx, chunk) in batch.shares.chunks(sub_batch_size).enumerate() {
         info!(
             "Processing sub-batch {}/{} with {} shares",
             chunk_idx + 1,
-            batch.shares.len().div_ceil(MAX_SHARES_PER_SUBBATCH),
+            batch.shares.len().div_ceil(sub_batch_size),
             chunk.len()
         );
 
@@ -187,8 +363,8 @@ async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<
                 .push_bind(share.diff)
                 .push_bind(share.sdiff)
                 .push_bind(&share.hash)
-                .push_bind(share.result)
-                .push_bind(&share.reject_reason)
+                .push_bind(share.outcome.is_accepted())
+                .push_bind(share.outcome.reject_reason())
                 .push_bind(&share.error)
                 .push_bind(share.errn)
                 .push_bind(&share.createdate)
@@ -196,7 +372,7 @@ async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<
                 .push_bind(&share.createcode)
                 .push_bind(&share.createinet)
                 .push_bind(&share.workername)
-                .push_bind(&share.username)
+                .push_bind(share.username.as_deref().map(str::trim))
                 .push_bind(&share.lnurl)
                 .push_bind(&share.address)
                 .push_bind(&share.agent);
@@ -226,21 +402,57 @@ async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<
             username = EXCLUDED.username,
             lnurl = EXCLUDED.lnurl,
             address = EXCLUDED.address,
-            agent = EXCLUDED.agent",
+            agent = EXCLUDED.agent
+            WHERE remote_shares.result IS NOT DISTINCT FROM EXCLUDED.result
+                AND remote_shares.diff IS NOT DISTINCT FROM EXCLUDED.diff
+            RETURNING id",
         );
 
-        let query = query_builder.build();
-        query.execute(&mut *tx).await.map_err(|e| {
-            anyhow!(
-                "Failed to batch insert shares in sub-batch {}: {e}",
-                chunk_idx + 1
-            )
-        })?;
+        // The first write of a share is authoritative for what payouts count.
+        // A re-sync that would flip its result or change its diff leaves the
+        // stored share alone and is left out of account totals below.
+        let written = query_builder
+            .build_query_scalar::<i64>()
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                Error::from(e).context(FailedSubBatch {
+                    index: chunk_idx,
+                    start_id: chunk.iter().map(|share| share.id).min().unwrap_or_default(),
+                    end_id: chunk.iter().map(|share| share.id).max().unwrap_or_default(),
+                })
+            })?;
+
+        let written = written.into_iter().collect::<HashSet<i64>>();
+
+        conflicting.extend(
+            chunk
+                .iter()
+                .map(|share| share.id)
+                .filter(|id| !written.contains(id)),
+        );
+    }
+
+    if !conflicting.is_empty() {
+        warn!(
+            "Ignored {} re-synced shares from {} whose result or diff conflicts with the stored share: ids {}",
+            conflicting.len(),
+            batch.hostname,
+            conflicting
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
     }
 
     let mut account_updates: HashMap<String, AccountUpdate> = HashMap::new();
 
     for share in &batch.shares {
+        if conflicting.contains(&share.id) {
+            continue;
+        }
+
         if let Some(username) = &share.username {
             let username = username.trim();
             if username.is_empty() {
@@ -256,7 +468,7 @@ async fn process_share_batch(batch: &ShareBatch, database: &Database) -> Result<
                     blockheights: HashSet::new(),
                 });
 
-            if share.result == Some(true) {
+            if share.outcome.is_accepted() {
                 if let Some(diff) = share.diff {
                     entry.total_diff += diff;
                 }
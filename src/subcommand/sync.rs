@@ -1,5 +1,10 @@
+pub use crate::event_sink::ShareOutcome;
+
 use {
-    super::*, crate::subcommand::server::database::Database, reqwest::Client, tokio::time::Duration,
+    super::*,
+    crate::{event_sink::ShareOutcomeColumns, subcommand::server::database::Database},
+    reqwest::Client,
+    tokio::time::Duration,
 };
 
 const SYNC_DELAY_MS: u64 = 1000;
@@ -8,6 +13,141 @@ const TARGET_ID_BUFFER: i64 = 0;
 const HTTP_TIMEOUT_MS: u64 = 30000;
 const MAX_RETRIES: u32 = 3;
 
+/// A fixed slice of every block's distributable reward paid to one account
+/// before the proportional split.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Donation {
+    pub(crate) address: String,
+    pub(crate) bps: u16,
+}
+
+impl Donation {
+    pub(crate) fn amount(&self, total_reward: i64) -> i64 {
+        (i128::from(total_reward) * i128::from(self.bps) / 10_000) as i64
+    }
+}
+
+/// How a block's distributable reward is split once any donation is taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PayoutMode {
+    /// Everyone with unpaid difficulty shares in proportion to it.
+    Proportional,
+    /// Accounts holding at least `solo_threshold_bps` of the unpaid
+    /// difficulty are paid solo-style, everyone below shares in a pool. See
+    /// [`hybrid_payouts`].
+    Hybrid { solo_threshold_bps: u16 },
+}
+
+/// An account with unpaid difficulty, as seen by the payout split.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PayableAccount {
+    pub(crate) account_id: i64,
+    pub(crate) username: String,
+    pub(crate) unpaid_diff: i64,
+}
+
+/// One account's share of a block: `amount` sats for `diff_paid` difficulty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PayoutShare {
+    pub(crate) account_id: i64,
+    pub(crate) amount: i64,
+    pub(crate) diff_paid: i64,
+}
+
+/// Splits `total_reward` in hybrid mode. The rules apply in order:
+///
+/// 1. An account is solo if its unpaid difficulty is at least
+///    `solo_threshold_bps` of all unpaid difficulty, and pooled otherwise.
+/// 2. A solo finder takes the whole reward and only their difficulty is
+///    marked paid.
+/// 3. Otherwise a pooled finder, already paid by the on-chain reserve, gets
+///    a zero amount marking their difficulty paid, as in proportional mode.
+/// 4. The other pooled accounts split the reward in proportion to their
+///    unpaid difficulty. The rounding remainder goes to the largest of them.
+/// 5. Solo accounts that did not find the block are left out, and their
+///    difficulty carries over until they find one.
+///
+/// The returned amounts sum to `total_reward` unless no account is left to
+/// split it, in which case the caller reports what went undistributed.
+pub(crate) fn hybrid_payouts(
+    accounts: &[PayableAccount],
+    finder: Option<&str>,
+    total_reward: i64,
+    solo_threshold_bps: u16,
+) -> Vec<PayoutShare> {
+    let total_unpaid = accounts
+        .iter()
+        .map(|account| i128::from(account.unpaid_diff))
+        .sum::<i128>();
+
+    let is_solo = |account: &PayableAccount| {
+        i128::from(account.unpaid_diff) * 10_000 >= total_unpaid * i128::from(solo_threshold_bps)
+    };
+
+    let finder = accounts
+        .iter()
+        .find(|account| Some(account.username.as_str()) == finder);
+
+    if let Some(finder) = finder
+        && is_solo(finder)
+    {
+        return vec![PayoutShare {
+            account_id: finder.account_id,
+            amount: total_reward,
+            diff_paid: finder.unpaid_diff,
+        }];
+    }
+
+    let mut shares = finder
+        .map(|finder| PayoutShare {
+            account_id: finder.account_id,
+            amount: 0,
+            diff_paid: finder.unpaid_diff,
+        })
+        .into_iter()
+        .collect::<Vec<PayoutShare>>();
+
+    let pooled = accounts
+        .iter()
+        .filter(|account| !is_solo(account))
+        .filter(|account| finder.is_none_or(|finder| finder.account_id != account.account_id))
+        .collect::<Vec<&PayableAccount>>();
+
+    let pooled_unpaid = pooled
+        .iter()
+        .map(|account| i128::from(account.unpaid_diff))
+        .sum::<i128>();
+
+    if pooled_unpaid == 0 {
+        return shares;
+    }
+
+    let split = shares.len();
+
+    shares.extend(pooled.iter().map(|account| PayoutShare {
+        account_id: account.account_id,
+        amount: (i128::from(total_reward) * i128::from(account.unpaid_diff) / pooled_unpaid) as i64,
+        diff_paid: account.unpaid_diff,
+    }));
+
+    let remainder = total_reward - shares.iter().map(|share| share.amount).sum::<i64>();
+
+    let absorber = pooled
+        .iter()
+        .max_by_key(|account| (account.unpaid_diff, Reverse(account.account_id)))
+        .expect("pooled accounts are not empty")
+        .account_id;
+
+    if let Some(share) = shares[split..]
+        .iter_mut()
+        .find(|share| share.account_id == absorber)
+    {
+        share.amount += remainder;
+    }
+
+    shares
+}
+
 #[derive(Debug, Parser)]
 pub struct Sync {
     #[arg(
@@ -65,8 +205,10 @@ pub struct Share {
     pub diff: Option<f64>,
     pub sdiff: Option<f64>,
     pub hash: Option<String>,
-    pub result: Option<bool>,
-    pub reject_reason: Option<String>,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    #[schema(value_type = ShareOutcomeColumns)]
+    pub outcome: ShareOutcome,
     pub error: Option<String>,
     pub errn: Option<i32>,
     pub createdate: Option<String>,
@@ -110,6 +252,32 @@ pub struct SyncResponse {
     pub received_count: usize,
     pub status: String,
     pub error_message: Option<String>,
+    /// The sub-batch whose insert rolled the batch back, if any.
+    #[serde(default)]
+    pub failed_sub_batch: Option<FailedSubBatch>,
+}
+
+/// A sub-batch of shares the server could not insert. The whole batch is
+/// one transaction, so nothing from it was stored, but these are the share
+/// ids to look at before resending.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct FailedSubBatch {
+    /// Zero-based position of the sub-batch within the batch.
+    pub index: usize,
+    pub start_id: i64,
+    pub end_id: i64,
+}
+
+impl Display for FailedSubBatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Failed to batch insert shares in sub-batch {} (share ids {}-{})",
+            self.index + 1,
+            self.start_id,
+            self.end_id
+        )
+    }
 }
 
 #[derive(Debug, ToSchema)]
@@ -357,10 +525,19 @@ impl Sync {
             .map_err(|e| anyhow!("Failed to send HTTP request: {}", e))?;
 
         if !response.status().is_success() {
-            return Err(anyhow!(
-                "HTTP request failed with status: {}",
-                response.status()
-            ));
+            let status = response.status();
+            let error_message = response
+                .json::<SyncResponse>()
+                .await
+                .ok()
+                .and_then(|response| response.error_message);
+
+            return Err(match error_message {
+                Some(error_message) => {
+                    anyhow!("HTTP request failed with status: {status}. Error: {error_message}")
+                }
+                None => anyhow!("HTTP request failed with status: {status}"),
+            });
         }
 
         let sync_response: SyncResponse = response
@@ -369,11 +546,20 @@ impl Sync {
             .map_err(|e| anyhow!("Failed to parse sync response: {}", e))?;
 
         if sync_response.status != "OK" {
-            return Err(anyhow!(
-                "Sync failed with status: {}. Error: {:?}",
-                sync_response.status,
-                sync_response.error_message
-            ));
+            return Err(match sync_response.failed_sub_batch {
+                Some(failed) => anyhow!(
+                    "Sync failed with status: {}, share ids {}-{} rejected. Error: {:?}",
+                    sync_response.status,
+                    failed.start_id,
+                    failed.end_id,
+                    sync_response.error_message
+                ),
+                None => anyhow!(
+                    "Sync failed with status: {}. Error: {:?}",
+                    sync_response.status,
+                    sync_response.error_message
+                ),
+            });
         }
 
         if sync_response.batch_id != batch_id {
@@ -500,7 +686,13 @@ impl Database {
         .map_err(|err| anyhow!("Database query failed: {err}"))
     }
 
-    pub(crate) async fn upsert_block(&self, block: &FoundBlockRecord) -> Result<bool> {
+    pub(crate) async fn upsert_block(
+        &self,
+        block: &FoundBlockRecord,
+        finder_bonus_bps: u16,
+        donation: Option<&Donation>,
+        payout_mode: PayoutMode,
+    ) -> Result<bool> {
         let existing_block =
             sqlx::query_scalar::<_, Option<i32>>("SELECT id FROM blocks WHERE blockheight = $1")
                 .bind(block.blockheight)
@@ -546,6 +738,9 @@ impl Database {
                 block.blockheight,
                 coinbasevalue - 100_000_000,
                 block.username.as_deref(),
+                finder_bonus_bps,
+                donation,
+                payout_mode,
             )
             .await?;
         }
@@ -553,11 +748,31 @@ impl Database {
         Ok(was_inserted)
     }
 
+    /// Splits `total_reward` across all accounts with unpaid difficulty.
+    ///
+    /// With `finder_bonus_bps` of 0 the finder is left out of the split and
+    /// only gets a zero-amount row marking their difficulty as paid. Otherwise
+    /// the finder takes a basis-point bonus off the top and joins the
+    /// proportional split of the rest.
+    ///
+    /// The rounding remainder goes to the bonused finder, or else to the
+    /// account with the most unpaid difficulty (lowest id on a tie), so the
+    /// amounts sum to exactly `total_reward`.
+    ///
+    /// A `donation` is taken off `total_reward` before any of the above and
+    /// paid to the donation account, which is created if it does not exist.
+    ///
+    /// In [`PayoutMode::Hybrid`] the rest is split by [`hybrid_payouts`]
+    /// instead, and `finder_bonus_bps` is ignored.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn populate_payouts_for_block(
         &self,
         blockheight: i32,
         total_reward: i64,
         winner_address: Option<&str>,
+        finder_bonus_bps: u16,
+        donation: Option<&Donation>,
+        payout_mode: PayoutMode,
     ) -> Result {
         let prev_blockheight = sqlx::query_scalar::<_, Option<i32>>(
             "SELECT MAX(blockheight) FROM blocks WHERE blockheight < $1",
@@ -568,6 +783,127 @@ impl Database {
         .map_err(|e| anyhow!("Failed to get previous block height: {e}"))?
         .unwrap_or(0);
 
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to begin transaction: {e}"))?;
+
+        let donation_amount = donation.map_or(0, |donation| donation.amount(total_reward));
+
+        if let Some(donation) = donation
+            && donation_amount > 0
+        {
+            sqlx::query(
+                "
+                WITH donation_account AS (
+                    INSERT INTO accounts (username)
+                    VALUES ($1)
+                    ON CONFLICT (username) DO UPDATE SET username = EXCLUDED.username
+                    RETURNING id, lnurl
+                )
+                INSERT INTO payouts (account_id, amount, diff_paid, blockheight_start, blockheight_end, status)
+                SELECT
+                    id,
+                    $2,
+                    0,
+                    $3,
+                    $4,
+                    CASE
+                        WHEN lnurl IS NOT NULL
+                        THEN 'pending'
+                        ELSE 'failure'
+                    END
+                FROM donation_account
+                ",
+            )
+            .bind(&donation.address)
+            .bind(donation_amount)
+            .bind(prev_blockheight)
+            .bind(blockheight)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to insert donation payout: {e}"))?;
+        }
+
+        let total_reward = total_reward - donation_amount;
+
+        if let PayoutMode::Hybrid { solo_threshold_bps } = payout_mode {
+            let accounts = sqlx::query_as::<_, (i64, String, i64)>(
+                "
+                SELECT
+                    a.id,
+                    a.username,
+                    (a.total_diff - COALESCE(SUM(p.diff_paid), 0))::BIGINT as unpaid_diff
+                FROM accounts a
+                LEFT JOIN payouts p ON p.account_id = a.id
+                    AND p.status != 'cancelled'
+                WHERE NOT a.exclude_from_payout
+                GROUP BY a.id, a.username, a.total_diff
+                HAVING a.total_diff - COALESCE(SUM(p.diff_paid), 0) > 0
+                ORDER BY a.id
+                ",
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to load payable accounts: {e}"))?
+            .into_iter()
+            .map(|(account_id, username, unpaid_diff)| PayableAccount {
+                account_id,
+                username,
+                unpaid_diff,
+            })
+            .collect::<Vec<PayableAccount>>();
+
+            let shares =
+                hybrid_payouts(&accounts, winner_address, total_reward, solo_threshold_bps);
+
+            let undistributed = total_reward - shares.iter().map(|share| share.amount).sum::<i64>();
+
+            if undistributed > 0 {
+                warn!(
+                    "No pooled account to split the reward for block {blockheight}, \
+                     leaving {undistributed} sats undistributed"
+                );
+            }
+
+            for share in shares {
+                sqlx::query(
+                    "
+                    INSERT INTO payouts (account_id, amount, diff_paid, blockheight_start, blockheight_end, status)
+                    SELECT
+                        id,
+                        $2,
+                        $3,
+                        $4,
+                        $5,
+                        CASE
+                            WHEN $2 = 0
+                            THEN 'success'
+                            WHEN lnurl IS NOT NULL
+                            THEN 'pending'
+                            ELSE 'failure'
+                        END
+                    FROM accounts
+                    WHERE id = $1
+                    ",
+                )
+                .bind(share.account_id)
+                .bind(share.amount)
+                .bind(share.diff_paid)
+                .bind(prev_blockheight)
+                .bind(blockheight)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| anyhow!("Failed to insert hybrid payout: {e}"))?;
+            }
+
+            return tx
+                .commit()
+                .await
+                .map_err(|e| anyhow!("Failed to commit payouts: {e}"));
+        }
+
         sqlx::query(
             "
             WITH eligible_accounts AS (
@@ -580,6 +916,7 @@ impl Database {
                 FROM accounts a
                 LEFT JOIN payouts p ON p.account_id = a.id
                     AND p.status != 'cancelled'
+                WHERE NOT a.exclude_from_payout
                 GROUP BY a.id, a.username, a.total_diff
             ),
             payable_accounts AS (
@@ -590,12 +927,48 @@ impl Database {
                     total_diff - already_paid_diff as unpaid_diff
                 FROM eligible_accounts
                 WHERE total_diff - already_paid_diff > 0
-                    AND username != COALESCE($4, '')
+                    AND ($5 > 0 OR username != COALESCE($4, ''))
             ),
             total_unpaid AS (
                 SELECT SUM(unpaid_diff) as total_diff
                 FROM payable_accounts
             ),
+            finder_bonus AS (
+                SELECT
+                    CASE
+                        WHEN EXISTS (SELECT 1 FROM payable_accounts WHERE username = $4)
+                        THEN FLOOR($3::NUMERIC * $5 / 10000)::BIGINT
+                        ELSE 0
+                    END as bonus
+            ),
+            proportional_payouts AS (
+                SELECT
+                    pa.account_id,
+                    pa.username,
+                    pa.lnurl,
+                    pa.unpaid_diff,
+                    FLOOR(pa.unpaid_diff::NUMERIC * ($3 - fb.bonus) / tu.total_diff::NUMERIC)::BIGINT as amount
+                FROM payable_accounts pa
+                CROSS JOIN total_unpaid tu
+                CROSS JOIN finder_bonus fb
+                WHERE tu.total_diff > 0
+            ),
+            remainder AS (
+                SELECT $3 - fb.bonus - COALESCE(SUM(pp.amount), 0) as amount
+                FROM finder_bonus fb
+                LEFT JOIN proportional_payouts pp ON TRUE
+                GROUP BY fb.bonus
+            ),
+            remainder_recipient AS (
+                SELECT pp.account_id
+                FROM proportional_payouts pp
+                CROSS JOIN finder_bonus fb
+                ORDER BY
+                    fb.bonus > 0 AND pp.username = $4 DESC,
+                    pp.unpaid_diff DESC,
+                    pp.account_id
+                LIMIT 1
+            ),
             finder_account AS (
                 SELECT
                     a.id as account_id,
@@ -606,28 +979,29 @@ impl Database {
                 LEFT JOIN payouts p ON p.account_id = a.id
                     AND p.status != 'cancelled'
                 WHERE a.username = $4
+                    AND NOT a.exclude_from_payout
                 GROUP BY a.id, a.username, a.total_diff
                 HAVING a.total_diff - COALESCE(SUM(p.diff_paid), 0) > 0
             )
             INSERT INTO payouts (account_id, amount, diff_paid, blockheight_start, blockheight_end, status)
             SELECT
-                pa.account_id,
-                CASE
-                    WHEN tu.total_diff > 0
-                    THEN FLOOR((pa.unpaid_diff::NUMERIC / tu.total_diff::NUMERIC) * $3)::BIGINT
-                    ELSE 0
-                END as amount,
-                pa.unpaid_diff as diff_paid,
+                pp.account_id,
+                pp.amount
+                    + CASE WHEN fb.bonus > 0 AND pp.username = $4 THEN fb.bonus ELSE 0 END
+                    + CASE WHEN pp.account_id = rr.account_id THEN r.amount ELSE 0 END
+                    as amount,
+                pp.unpaid_diff as diff_paid,
                 $2 as blockheight_start,
                 $1 as blockheight_end,
                 CASE
-                    WHEN pa.lnurl IS NOT NULL
+                    WHEN pp.lnurl IS NOT NULL
                     THEN 'pending'
                     ELSE 'failure'
                 END as status
-            FROM payable_accounts pa
-            CROSS JOIN total_unpaid tu
-            WHERE tu.total_diff > 0
+            FROM proportional_payouts pp
+            CROSS JOIN finder_bonus fb
+            CROSS JOIN remainder r
+            CROSS JOIN remainder_recipient rr
             UNION ALL
             SELECT
                 fa.account_id,
@@ -637,16 +1011,22 @@ impl Database {
                 $1 as blockheight_end,
                 'success' as status
             FROM finder_account fa
+            WHERE $5 = 0
             ",
         )
             .bind(blockheight)
             .bind(prev_blockheight)
             .bind(total_reward)
             .bind(winner_address.unwrap_or(""))
-            .execute(&self.pool)
+            .bind(i32::from(finder_bonus_bps))
+            .execute(&mut *tx)
             .await
             .map_err(|e| anyhow!("Failed to populate payouts: {e}"))?;
 
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit payouts: {e}"))?;
+
         Ok(())
     }
 
@@ -7,6 +7,8 @@ use {
 pub struct Template {
     #[arg(help = "Stratum <HOST:PORT>.")]
     stratum_endpoint: String,
+    #[command(flatten)]
+    stratum_port: StratumPortOptions,
     #[arg(long, help = "Stratum <USERNAME>.")]
     pub username: Username,
     #[arg(long, help = "Stratum <PASSWORD>.")]
@@ -55,7 +57,8 @@ impl Template {
             self.stratum_endpoint, self.username
         );
 
-        let endpoint = ensure_port(&self.stratum_endpoint);
+        let endpoint =
+            resolve_stratum_endpoint(&self.stratum_endpoint, self.stratum_port.port()).await?;
 
         let client = Client::new(
             endpoint.clone(),
@@ -130,13 +133,19 @@ impl Template {
         pool_difficulty: Option<Difficulty>,
     ) -> Result<Output> {
         let enonce2 = Extranonce::random(subscription.enonce2_size);
-        let coinbase_bin = hex::decode(format!(
-            "{}{}{}{}",
-            notify.coinb1, subscription.enonce1, enonce2, notify.coinb2
-        ))?;
 
-        let mut cursor = bitcoin::io::Cursor::new(&coinbase_bin);
-        let coinbase_tx = bitcoin::Transaction::consensus_decode_from_finite_reader(&mut cursor)?;
+        let Coinbase {
+            transaction: coinbase_tx,
+            merkle_root,
+        } = Arc::new(notify.clone())
+            .create_job(
+                &subscription.enonce1,
+                subscription.enonce2_size,
+                None,
+                notify.job_id,
+                None,
+            )?
+            .coinbase(&enonce2);
 
         let ascii_tag = Self::extract_coinbase_text(&coinbase_tx);
 
@@ -159,20 +168,12 @@ impl Template {
             .map(|dt| dt.to_rfc3339())
             .unwrap_or_else(|| ntime_unix.to_string());
 
-        let merkle_root = merkle_root(
-            &notify.coinb1,
-            &notify.coinb2,
-            &subscription.enonce1,
-            &enonce2,
-            &notify.merkle_branches,
-        )?;
-
         Ok(Output {
             job_id: notify.job_id,
             prevhash: notify.prevhash.clone(),
             previous_block_hash: BlockHash::from(notify.prevhash.clone()),
             coinbase: CoinbaseInfo {
-                size_bytes: coinbase_bin.len(),
+                size_bytes: coinbase_tx.base_size(),
                 ascii_tag,
                 outputs,
             },
@@ -131,6 +131,10 @@ impl Upstream {
                     );
                     first_notify = Some(notify);
                 }
+                Ok(Event::NewBlock(_)) => {}
+                Ok(Event::Message(message)) => {
+                    info!("Message from upstream: {message}");
+                }
                 Ok(Event::Reconnect(_)) | Ok(Event::Disconnected) => {
                     bail!("Disconnected from upstream before initialization complete");
                 }
@@ -152,10 +156,18 @@ impl Upstream {
 
         let difficulty_clone = difficulty.clone();
         let disconnect = Disconnect(connected.clone());
+        let enonce1 = subscribe.enonce1.clone();
+        let enonce2_size = subscribe.enonce2_size;
+        let metatron_clone = metatron.clone();
 
         tasks.spawn(async move {
             let _disconnect = disconnect;
 
+            // The client reports a new prevhash just before the notify that
+            // carries it, and only the notify's coinbase says what height the
+            // upstream is now mining.
+            let mut new_block: Option<PrevHash> = None;
+
             loop {
                 tokio::select! {
                     biased;
@@ -171,12 +183,30 @@ impl Upstream {
                                     "Received notify: job_id={}, clean_jobs={}",
                                     notify.job_id, notify.clean_jobs
                                 );
+
+                                if let Some(prevhash) = new_block.take() {
+                                    announce_new_block(
+                                        &metatron_clone,
+                                        &notify,
+                                        prevhash,
+                                        &enonce1,
+                                        enonce2_size,
+                                    );
+                                }
+
                                 workbase_tx.send_replace(Arc::new(notify));
                             }
+                            Ok(Event::NewBlock(prevhash)) => {
+                                info!("Upstream moved to new block: prevhash={prevhash}");
+                                new_block = Some(prevhash);
+                            }
                             Ok(Event::SetDifficulty(diff)) => {
                                 info!("Received set_difficulty: {}", diff);
                                 *difficulty_clone.write() = diff;
                             }
+                            Ok(Event::Message(message)) => {
+                                info!("Message from upstream: {message}");
+                            }
                             Ok(Event::Reconnect(_)) | Ok(Event::Disconnected) => {
                                 warn!("Disconnected from upstream");
                                 break;
@@ -363,6 +393,31 @@ impl Upstream {
     }
 }
 
+/// Announces the upstream's move onto `prevhash`, at the height `notify`'s
+/// coinbase commits to.
+fn announce_new_block(
+    metatron: &Metatron,
+    notify: &Notify,
+    prevhash: PrevHash,
+    enonce1: &Extranonce,
+    enonce2_size: usize,
+) {
+    let height = CoinbaseParts::decode(&notify.coinb1, enonce1, enonce2_size, &notify.coinb2)
+        .ok()
+        .and_then(|coinbase| coinbase.height());
+
+    match height {
+        Some(height) => metatron.announce_new_block(NewBlock {
+            height,
+            prevhash: prevhash.into(),
+        }),
+        None => warn!(
+            "Upstream notify {} has no BIP34 height, not announcing new block",
+            notify.job_id
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -394,4 +449,40 @@ mod tests {
             .await
             .expect("disconnected() must resolve once set_connected flips to false");
     }
+
+    #[test]
+    fn new_block_announced_at_coinbase_height() {
+        let (metatron, _dir) = Metatron::test();
+        let mut new_blocks = metatron.subscribe_new_blocks();
+
+        let prevhash = BlockHash::from_byte_array([1; 32]);
+
+        let notify = Notify {
+            job_id: JobId::new(0),
+            prevhash: prevhash.into(),
+            coinb1: format!("0100000001{}ffffffff0c0300350c", "00".repeat(32)),
+            coinb2: "ffffffff0100000000000000000000000000".into(),
+            merkle_branches: Vec::new(),
+            version: Version(block::Version::TWO),
+            nbits: "1d00ffff".parse().unwrap(),
+            ntime: 1231006505.into(),
+            clean_jobs: true,
+        };
+
+        announce_new_block(
+            &metatron,
+            &notify,
+            notify.prevhash.clone(),
+            &"00000000".parse().unwrap(),
+            4,
+        );
+
+        assert_eq!(
+            new_blocks.try_recv().unwrap(),
+            NewBlock {
+                height: 800_000,
+                prevhash,
+            }
+        );
+    }
 }
@@ -40,7 +40,7 @@ impl FromStr for UpstreamTarget {
         ensure!(!username.is_empty(), "empty username in `{s}`");
 
         Ok(Self {
-            endpoint: ensure_port(endpoint),
+            endpoint: ensure_port(endpoint, DEFAULT_STRATUM_PORT),
             username: username.parse::<Username>()?,
             password,
         })
@@ -1,4 +1,4 @@
-use super::*;
+use {super::*, rand::Rng};
 
 /// Minimum window ratio before considering adjustment.
 /// Fraction of expected time (or shares) per window.
@@ -13,12 +13,35 @@ const HYSTERESIS_LOW: f64 = 0.5;
 /// Copied from ckpool.
 const HYSTERESIS_HIGH: f64 = 1.33;
 
+/// Each connection holds off retargeting for a random delay of up to this
+/// fraction of the vardiff period, so connections that start together do not
+/// all retarget at once.
+const JITTER_FRACTION: f64 = 0.25;
+
+/// How to judge shares for jobs sent before the connection's latest
+/// `mining.set_difficulty`. Miners often keep hashing on the old job for a
+/// moment after a retarget, and stratum does not say which difficulty such
+/// shares should meet.
+#[derive(Default, clap::ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum DifficultyTransition {
+    /// Credit them at the lower of the old and new difficulty, so no honest
+    /// work is lost, at the cost of briefly accepting easier shares after an
+    /// increase.
+    #[default]
+    Lenient,
+    /// Reject them like ckpool does, so every accepted share met the
+    /// difficulty the miner was last told, at the cost of rejecting work in
+    /// flight across each retarget.
+    Strict,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Vardiff {
     period: Duration,
     window: Duration,
     min_shares_for_adjustment: u32,
     min_time_for_adjustment: Duration,
+    jitter: Duration,
     dsps: DecayingAverage,
     start_diff: Difficulty,
     current_diff: Difficulty,
@@ -48,6 +71,7 @@ impl Vardiff {
             window,
             min_shares_for_adjustment: (expected_shares_per_window * MIN_WINDOW_RATIO) as u32,
             min_time_for_adjustment: Duration::from_secs_f64(window_secs * MIN_WINDOW_RATIO),
+            jitter: Duration::ZERO,
             dsps: DecayingAverage::new(window),
             start_diff,
             current_diff: start_diff,
@@ -62,6 +86,13 @@ impl Vardiff {
         }
     }
 
+    pub(crate) fn with_jitter(self, rng: &mut impl Rng) -> Self {
+        Self {
+            jitter: self.period.mul_f64(JITTER_FRACTION * rng.random::<f64>()),
+            ..self
+        }
+    }
+
     fn clamp_difficulty(&self, diff: Difficulty, upstream_diff: Option<Difficulty>) -> Difficulty {
         let mut result = diff;
 
@@ -91,12 +122,14 @@ impl Vardiff {
         self.diff_change_job_id = Some(next_job_id);
     }
 
-    pub(crate) fn pool_diff(&self, job_id: JobId) -> Difficulty {
-        let stale = self
-            .diff_change_job_id
-            .is_some_and(|change_id| job_id < change_id);
+    /// Whether `job_id` was sent before the latest difficulty change.
+    pub(crate) fn predates_diff_change(&self, job_id: JobId) -> bool {
+        self.diff_change_job_id
+            .is_some_and(|change_id| job_id < change_id)
+    }
 
-        if stale {
+    pub(crate) fn pool_diff(&self, job_id: JobId) -> Difficulty {
+        if self.predates_diff_change(job_id) {
             self.old_diff.min(self.current_diff)
         } else {
             self.current_diff
@@ -134,6 +167,17 @@ impl Vardiff {
         true
     }
 
+    /// Picks up where a resumed session left off rather than ramping up from
+    /// the start difficulty again.
+    pub(crate) fn resume(&mut self, diff: Difficulty) {
+        let clamped = self.clamp_difficulty(diff, None);
+
+        self.old_diff = clamped;
+        self.current_diff = clamped;
+        self.shares_since_change = 0;
+        self.last_diff_change = Instant::now();
+    }
+
     pub(crate) fn clamp_to_upstream(&mut self, upstream_diff: Difficulty) -> Option<Difficulty> {
         if upstream_diff < self.current_diff {
             self.old_diff = self.current_diff;
@@ -187,8 +231,13 @@ impl Vardiff {
         let first_share = self.first_share?;
         let time_since_first = now.duration_since(first_share);
         let time_since_change = now.duration_since(self.last_diff_change);
+
+        if time_since_change < self.jitter {
+            return None;
+        }
+
         let enough_shares = self.shares_since_change >= self.min_shares_for_adjustment;
-        let enough_time = time_since_change >= self.min_time_for_adjustment;
+        let enough_time = time_since_change >= self.min_time_for_adjustment + self.jitter;
 
         if !enough_shares && !enough_time {
             debug!(
@@ -221,23 +270,28 @@ impl Vardiff {
         let optimal = dsps * self.period.as_secs_f64();
         assert!(optimal > 0.0, "optimal difficulty must be positive");
 
-        let new_diff = Difficulty::from(optimal.min(network_diff.as_f64()));
+        let unclamped = Difficulty::from(optimal.min(network_diff.as_f64()));
 
-        let new_diff = {
-            let clamped = self.clamp_difficulty(new_diff, upstream_diff);
-            if clamped != new_diff {
-                debug!(
-                    "Vardiff clamped {} -> {} (min={:?}, upstream={:?}, max={:?})",
-                    new_diff, clamped, self.min_diff, upstream_diff, self.max_diff
-                );
-            }
-            clamped
-        };
+        let new_diff = self.clamp_difficulty(unclamped, upstream_diff);
+
+        if new_diff != unclamped {
+            debug!(
+                "Vardiff clamped {} -> {} (min={:?}, upstream={:?}, max={:?})",
+                unclamped, new_diff, self.min_diff, upstream_diff, self.max_diff
+            );
+        }
 
         if self.current_diff == new_diff {
             return None;
         }
 
+        if let Some(max) = self.max_diff
+            && new_diff == max
+            && unclamped > max
+        {
+            info!("Vardiff capped at max_diff {max}, share rate called for {unclamped}");
+        }
+
         if new_diff < self.current_diff && self.shares_since_change == 1 {
             debug!(
                 "Guarding against oscillation on difficulty decrease after first share since adjustment"
@@ -263,7 +317,10 @@ impl Vardiff {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        rand::{SeedableRng, rngs::StdRng},
+    };
 
     fn secs(s: u64) -> Duration {
         Duration::from_secs(s)
@@ -341,6 +398,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn raises_difficulty_toward_target_shares_per_minute() {
+        let target_spm = 6.0;
+        let start_diff = Difficulty::from(10);
+        let mut vardiff = Vardiff::new(
+            start_diff,
+            Duration::from_secs_f64(60.0 / target_spm),
+            secs(300),
+            None,
+            None,
+        );
+
+        let base = Instant::now();
+        vardiff.first_share = Some(base);
+        vardiff.last_diff_change = base;
+        vardiff.dsps = DecayingAverage::with_start_time(secs(300), base);
+
+        let mut t = base;
+        for _ in 0..1500 {
+            t += secs(1);
+            vardiff.dsps.record(start_diff.as_f64(), t);
+            vardiff.shares_since_change += 1;
+        }
+
+        let new_diff = vardiff
+            .evaluate_adjustment(Difficulty::from(1_000_000), None, t)
+            .unwrap();
+
+        assert!(new_diff > start_diff);
+
+        let spm_at_new_diff = 60.0 * vardiff.dsps.value_at(t) / new_diff.as_f64();
+
+        assert!(
+            (spm_at_new_diff - target_spm).abs() < 0.5,
+            "expected ~{target_spm} shares per minute at {new_diff}, got {spm_at_new_diff}"
+        );
+    }
+
     #[test]
     fn respects_network_diff_ceiling() {
         let mut vardiff = Vardiff::new(Difficulty::from(10), secs(5), secs(10), None, None);
@@ -453,6 +548,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn share_flood_raises_difficulty_only_to_max_diff() {
+        let max_diff = Difficulty::from(50);
+        let mut vardiff =
+            Vardiff::new(Difficulty::from(1), secs(5), secs(10), None, Some(max_diff));
+
+        let base = Instant::now();
+        vardiff.first_share = Some(base);
+        vardiff.last_diff_change = base;
+        vardiff.dsps = DecayingAverage::with_start_time(secs(10), base);
+
+        let mut t = base;
+        let mut adjustments = Vec::new();
+
+        for _ in 0..10_000 {
+            t += millis(10);
+            vardiff.dsps.record(vardiff.current_diff().as_f64(), t);
+            vardiff.shares_since_change += 1;
+
+            if let Some(new_diff) =
+                vardiff.evaluate_adjustment(Difficulty::from(1_000_000), None, t)
+            {
+                assert!(new_diff <= max_diff, "{new_diff} exceeds {max_diff}");
+                adjustments.push(new_diff);
+            }
+        }
+
+        assert_eq!(adjustments.last(), Some(&max_diff), "{adjustments:?}");
+        assert_eq!(vardiff.current_diff(), max_diff);
+    }
+
     #[test]
     fn clamp_to_upstream_lowers_difficulty() {
         let start_diff = Difficulty::from(100);
@@ -579,6 +705,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn jitter_is_a_fraction_of_period() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let vardiff = Vardiff::new(Difficulty::from(1), secs(4), secs(300), None, None)
+                .with_jitter(&mut rng);
+            assert!(vardiff.jitter < secs(1), "{:?}", vardiff.jitter);
+        }
+
+        let vardiff = Vardiff::new(Difficulty::from(1), secs(4), secs(300), None, None);
+        assert_eq!(vardiff.jitter, Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_spreads_retargets_of_connections_started_together() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let base = Instant::now();
+
+        let first_retarget = |vardiff: Vardiff| {
+            let mut vardiff = vardiff;
+            vardiff.first_share = Some(base);
+            vardiff.last_diff_change = base;
+            vardiff.dsps = DecayingAverage::with_start_time(secs(300), base);
+
+            for i in 1..=5 {
+                vardiff.dsps.record(1000.0, base + secs(i));
+                vardiff.shares_since_change += 1;
+            }
+
+            (0..)
+                .map(|step| base + secs(240) + millis(10 * step))
+                .find(|&t| {
+                    vardiff
+                        .clone()
+                        .evaluate_adjustment(Difficulty::from(1_000_000), None, t)
+                        .is_some()
+                })
+                .unwrap()
+        };
+
+        let connections = [
+            Vardiff::new(Difficulty::from(1), secs(5), secs(300), None, None).with_jitter(&mut rng),
+            Vardiff::new(Difficulty::from(1), secs(5), secs(300), None, None).with_jitter(&mut rng),
+        ];
+
+        let [a, b] = connections.map(first_retarget);
+
+        assert_ne!(a, b);
+
+        for retarget in [a, b] {
+            assert!(retarget >= base + secs(240));
+            assert!(retarget <= base + secs(240) + millis(1250));
+        }
+    }
+
     #[test]
     fn decreases_difficulty_for_slow_shares() {
         let start_diff = Difficulty::from(100);
@@ -0,0 +1,345 @@
+use {
+    super::*,
+    crate::subcommand::server::notifications::{NotificationHandler, NotificationType},
+};
+
+/// Restarts within this window count towards an alert.
+const RAPID_RESTART_WINDOW: Duration = Duration::from_secs(300);
+
+/// Restarts within `RAPID_RESTART_WINDOW` that fire an alert.
+const MAX_RAPID_RESTARTS: usize = 5;
+
+/// A task that ran this long before dying restarts without backoff.
+const RESTART_BACKOFF_RESET: Duration = Duration::from_secs(60);
+
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+pub(crate) struct TemplateWatchdog {
+    threshold: Duration,
+    updated: Instant,
+    stale: bool,
+}
+
+impl TemplateWatchdog {
+    pub(crate) fn new(threshold: Duration, now: Instant) -> Self {
+        Self {
+            threshold,
+            updated: now,
+            stale: false,
+        }
+    }
+
+    /// Records a fresh template. Returns true if this clears a stale condition.
+    pub(crate) fn refresh(&mut self, now: Instant) -> bool {
+        self.updated = now;
+        std::mem::replace(&mut self.stale, false)
+    }
+
+    /// Returns the template age the first time it exceeds the threshold, and
+    /// `None` until a fresh template clears the condition.
+    pub(crate) fn check(&mut self, now: Instant) -> Option<Duration> {
+        let age = now.saturating_duration_since(self.updated);
+
+        if self.stale || age <= self.threshold {
+            return None;
+        }
+
+        self.stale = true;
+
+        Some(age)
+    }
+}
+
+pub(crate) fn spawn_template_watchdog<W: Send + Sync + 'static>(
+    settings: &Settings,
+    mut workbase_rx: watch::Receiver<Arc<W>>,
+    metatron: Arc<Metatron>,
+    cancel: CancellationToken,
+    tasks: &TaskTracker,
+) {
+    info!("Spawning template watchdog task");
+
+    let threshold = settings.stale_template_threshold();
+    let alerts_ntfy_channel = settings.alerts_ntfy_channel().map(String::from);
+
+    tasks.spawn(async move {
+        let mut watchdog = TemplateWatchdog::new(threshold, Instant::now());
+        let mut ticker = ticker(Duration::from_secs(1));
+
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+
+                changed = workbase_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+
+                    if watchdog.refresh(Instant::now()) {
+                        info!("Fresh block template received, no longer building on a stale tip");
+                    }
+                }
+
+                _ = ticker.tick() => {
+                    let Some(age) = watchdog.check(Instant::now()) else {
+                        continue;
+                    };
+
+                    let message = format!(
+                        "block template has not been refreshed for {}s, pool may be building on a stale tip",
+                        age.as_secs()
+                    );
+
+                    warn!("{message}");
+
+                    metatron.record_stale_template();
+
+                    if let Some(channel) = alerts_ntfy_channel.clone() {
+                        tokio::spawn(async move {
+                            if let Err(err) = NotificationHandler::new(channel)
+                                .send(NotificationType::SystemWarning { message })
+                                .await
+                            {
+                                warn!("Failed to send stale template alert: {err}");
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        info!("Shutting down template watchdog");
+    });
+}
+
+/// Counts recent restarts of a supervised task.
+pub(crate) struct RestartTracker {
+    restarts: VecDeque<Instant>,
+    alerted: bool,
+}
+
+impl RestartTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            restarts: VecDeque::new(),
+            alerted: false,
+        }
+    }
+
+    /// Records a restart. Returns the number of recent restarts the first
+    /// time it reaches `MAX_RAPID_RESTARTS`, and `None` until the task
+    /// settles down again.
+    pub(crate) fn record(&mut self, now: Instant) -> Option<usize> {
+        while self
+            .restarts
+            .front()
+            .is_some_and(|restart| now.saturating_duration_since(*restart) > RAPID_RESTART_WINDOW)
+        {
+            self.restarts.pop_front();
+        }
+
+        self.restarts.push_back(now);
+
+        if self.restarts.len() < MAX_RAPID_RESTARTS {
+            self.alerted = false;
+            return None;
+        }
+
+        (!std::mem::replace(&mut self.alerted, true)).then_some(self.restarts.len())
+    }
+}
+
+/// Keeps the ZMQ block notification task running. Whenever the task spawned
+/// by `spawn` exits, fails or panics before `cancel`, it is respawned after
+/// a backoff starting at `backoff`, so the pool is never left without block
+/// notifications.
+pub(crate) fn spawn_zmq_watchdog<F, Fut>(
+    mut spawn: F,
+    backoff: Duration,
+    metatron: Arc<Metatron>,
+    alerts_ntfy_channel: Option<String>,
+    cancel: CancellationToken,
+    tasks: &TaskTracker,
+) where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result> + Send + 'static,
+{
+    info!("Spawning ZMQ watchdog task");
+
+    tasks.spawn(async move {
+        let mut restarts = RestartTracker::new();
+        let mut delay = backoff;
+
+        loop {
+            let started = Instant::now();
+            let mut handle = tokio::spawn(spawn());
+
+            let result = tokio::select! {
+                _ = cancel.cancelled() => {
+                    handle.abort();
+                    break;
+                }
+                result = &mut handle => result,
+            };
+
+            if cancel.is_cancelled() {
+                break;
+            }
+
+            if started.elapsed() > RESTART_BACKOFF_RESET {
+                delay = backoff;
+            }
+
+            match result {
+                Ok(Ok(())) => warn!("ZMQ task exited, restarting in {delay:?}"),
+                Ok(Err(err)) => warn!("ZMQ task failed: {err}, restarting in {delay:?}"),
+                Err(err) => error!("ZMQ task died: {err}, restarting in {delay:?}"),
+            }
+
+            metatron.record_zmq_restart();
+
+            if let Some(count) = restarts.record(Instant::now()) {
+                let message = format!(
+                    "ZMQ task restarted {count} times in {}s, block notifications may be unreliable",
+                    RAPID_RESTART_WINDOW.as_secs()
+                );
+
+                error!("{message}");
+
+                if let Some(channel) = alerts_ntfy_channel.clone() {
+                    tokio::spawn(async move {
+                        if let Err(err) = NotificationHandler::new(channel)
+                            .send(NotificationType::SystemWarning { message })
+                            .await
+                        {
+                            warn!("Failed to send ZMQ restart alert: {err}");
+                        }
+                    });
+                }
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = sleep(delay) => {}
+            }
+
+            delay = (delay * 2).min(MAX_RESTART_BACKOFF);
+        }
+
+        info!("Shutting down ZMQ watchdog");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(120);
+
+    #[test]
+    fn fresh_template_is_not_stale() {
+        let start = Instant::now();
+        let mut watchdog = TemplateWatchdog::new(THRESHOLD, start);
+
+        assert_eq!(watchdog.check(start), None);
+        assert_eq!(watchdog.check(start + THRESHOLD), None);
+    }
+
+    #[test]
+    fn stalled_template_fires_exactly_one_alert() {
+        let start = Instant::now();
+        let mut watchdog = TemplateWatchdog::new(THRESHOLD, start);
+
+        let alerts = (1..=300)
+            .filter_map(|secs| watchdog.check(start + Duration::from_secs(secs)))
+            .collect::<Vec<Duration>>();
+
+        assert_eq!(alerts, vec![Duration::from_secs(121)]);
+    }
+
+    #[test]
+    fn fresh_template_clears_stale_condition() {
+        let start = Instant::now();
+        let mut watchdog = TemplateWatchdog::new(THRESHOLD, start);
+
+        let stalled = start + THRESHOLD + Duration::from_secs(1);
+        assert!(watchdog.check(stalled).is_some());
+        assert!(watchdog.refresh(stalled));
+        assert!(!watchdog.refresh(stalled));
+        assert_eq!(watchdog.check(stalled + THRESHOLD), None);
+
+        let stalled_again = stalled + THRESHOLD + Duration::from_secs(1);
+        assert!(watchdog.check(stalled_again).is_some());
+        assert_eq!(watchdog.check(stalled_again), None);
+    }
+
+    #[test]
+    fn rapid_restarts_fire_exactly_one_alert() {
+        let start = Instant::now();
+        let mut restarts = RestartTracker::new();
+
+        let alerts = (0..10)
+            .filter_map(|secs| restarts.record(start + Duration::from_secs(secs)))
+            .collect::<Vec<usize>>();
+
+        assert_eq!(alerts, vec![MAX_RAPID_RESTARTS]);
+    }
+
+    #[test]
+    fn spread_out_restarts_do_not_alert() {
+        let start = Instant::now();
+        let mut restarts = RestartTracker::new();
+
+        for i in 0..20 {
+            assert_eq!(restarts.record(start + RAPID_RESTART_WINDOW / 2 * i), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn zmq_task_respawned_after_dying() {
+        let (metatron, _dir) = Metatron::test();
+        let metatron = Arc::new(metatron);
+        let cancel = CancellationToken::new();
+        let tasks = TaskTracker::new();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut spawns = 0;
+
+        spawn_zmq_watchdog(
+            move || {
+                spawns += 1;
+                let spawn = spawns;
+                let tx = tx.clone();
+
+                async move {
+                    match spawn {
+                        1 => panic!("ZMQ task crashed"),
+                        2 => bail!("ZMQ connection lost"),
+                        3 => Ok(()),
+                        _ => {
+                            tx.send(spawn).await?;
+                            std::future::pending().await
+                        }
+                    }
+                }
+            },
+            Duration::from_millis(1),
+            metatron.clone(),
+            None,
+            cancel.clone(),
+            &tasks,
+        );
+
+        assert_eq!(
+            timeout(Duration::from_secs(5), rx.recv()).await.unwrap(),
+            Some(4)
+        );
+        assert_eq!(metatron.zmq_restarts(), 3);
+
+        cancel.cancel();
+        tasks.close();
+        timeout(Duration::from_secs(5), tasks.wait()).await.unwrap();
+    }
+}
@@ -22,9 +22,9 @@ pub(crate) trait Workbase: Clone + Send + Sync + 'static {
 
     fn clean_jobs(&self, prev: Option<&Self>) -> bool;
 
-    fn build_block(&self, job: &Job<Self>, submit: &Submit, header: Header) -> Result<Block>
-    where
-        Self: Sized;
+    fn build_block(&self, coinbase: Coinbase, header: Header) -> Result<Block>;
+
+    fn check_coinbase_value(&self, block: &Block, chain: Chain, tolerance: Amount) -> Result;
 }
 
 impl Workbase for BlockTemplate {
@@ -85,12 +85,15 @@ impl Workbase for BlockTemplate {
         .build()
         .context("failed to build coinbase")?;
 
+        let coinbase_parts = CoinbaseParts::decode(&coinb1, enonce1, enonce2_size, &coinb2)?;
+
         Ok(Job {
             job_id,
             upstream_job_id: job_id,
             coinb1,
             coinb2,
-            enonce1: enonce1.clone(),
+            coinbase_parts,
+            payout_address: Some(address.clone()),
             version_mask,
             workbase: self.clone(),
         })
@@ -100,23 +103,18 @@ impl Workbase for BlockTemplate {
         prev.map(|prev| prev.height != self.height).unwrap_or(true)
     }
 
-    fn build_block(&self, job: &Job<Self>, submit: &Submit, header: Header) -> Result<Block> {
-        let coinbase_bin = hex::decode(format!(
-            "{}{}{}{}",
-            job.coinb1, job.enonce1, submit.enonce2, job.coinb2,
-        ))
-        .context("failed to decode coinbase hex")?;
-
-        let mut cursor = bitcoin::io::Cursor::new(&coinbase_bin);
-        let coinbase_tx = Transaction::consensus_decode_from_finite_reader(&mut cursor)
-            .context("failed to decode coinbase transaction")?;
-
-        let txdata = std::iter::once(coinbase_tx)
+    fn build_block(&self, coinbase: Coinbase, header: Header) -> Result<Block> {
+        let txdata = std::iter::once(coinbase.transaction)
             .chain(self.transactions.iter().map(|tx| tx.transaction.clone()))
             .collect();
 
         let block = Block { header, txdata };
 
+        ensure!(
+            block.check_merkle_root(),
+            "block merkle root does not commit to its transactions"
+        );
+
         if self.height > 16 {
             ensure!(
                 block.bip34_block_height().is_ok(),
@@ -126,6 +124,42 @@ impl Workbase for BlockTemplate {
 
         Ok(block)
     }
+
+    fn check_coinbase_value(&self, block: &Block, chain: Chain, tolerance: Amount) -> Result {
+        let fees = self.transactions.iter().map(|tx| tx.fee).sum::<Amount>();
+        let expected = chain.subsidy(self.height) + fees;
+
+        ensure!(
+            abs_diff(self.coinbase_value, expected) <= tolerance,
+            "template coinbasevalue {} does not match subsidy plus fees {} at height {}",
+            self.coinbase_value.to_sat(),
+            expected.to_sat(),
+            self.height,
+        );
+
+        let coinbase = block
+            .coinbase()
+            .ok_or_else(|| anyhow!("block has no coinbase transaction"))?;
+
+        let output_value = coinbase
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>();
+
+        ensure!(
+            abs_diff(output_value, self.coinbase_value) <= tolerance,
+            "coinbase outputs pay {} but template coinbasevalue is {}",
+            output_value.to_sat(),
+            self.coinbase_value.to_sat(),
+        );
+
+        Ok(())
+    }
+}
+
+fn abs_diff(a: Amount, b: Amount) -> Amount {
+    a.max(b) - a.min(b)
 }
 
 impl Workbase for Notify {
@@ -160,7 +194,7 @@ impl Workbase for Notify {
     fn create_job(
         self: &Arc<Self>,
         enonce1: &Extranonce,
-        _enonce2_size: usize,
+        enonce2_size: usize,
         _address: Option<&Address>,
         job_id: JobId,
         version_mask: Option<Version>,
@@ -170,7 +204,13 @@ impl Workbase for Notify {
             upstream_job_id: self.job_id,
             coinb1: self.coinb1.clone(),
             coinb2: self.coinb2.clone(),
-            enonce1: enonce1.clone(),
+            coinbase_parts: CoinbaseParts::decode(
+                &self.coinb1,
+                enonce1,
+                enonce2_size,
+                &self.coinb2,
+            )?,
+            payout_address: None,
             version_mask,
             workbase: self.clone(),
         })
@@ -180,7 +220,247 @@ impl Workbase for Notify {
         self.clean_jobs
     }
 
-    fn build_block(&self, _job: &Job<Self>, _submit: &Submit, _header: Header) -> Result<Block> {
+    fn build_block(&self, _coinbase: Coinbase, _header: Header) -> Result<Block> {
+        bail!("proxy mode does not build blocks")
+    }
+
+    fn check_coinbase_value(&self, _block: &Block, _chain: Chain, _tolerance: Amount) -> Result {
         bail!("proxy mode does not build blocks")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::block_template::TemplateTransaction};
+
+    const HEIGHT: u64 = 200;
+
+    fn template() -> Arc<BlockTemplate> {
+        Arc::new(BlockTemplate {
+            height: HEIGHT,
+            coinbase_value: Chain::Regtest.subsidy(HEIGHT),
+            ..Default::default()
+        })
+    }
+
+    fn block(template: &Arc<BlockTemplate>) -> Block {
+        let address = Address::from_str("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080")
+            .unwrap()
+            .assume_checked();
+
+        let enonce1 = Extranonce::random(ENONCE1_SIZE);
+
+        let job = template
+            .create_job(&enonce1, 8, Some(&address), JobId::new(0), None)
+            .unwrap();
+
+        let submit = Submit {
+            username: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080.worker"
+                .parse()
+                .unwrap(),
+            job_id: job.job_id,
+            enonce2: "0000000000000000".parse().unwrap(),
+            ntime: template.ntime(),
+            nonce: "00000000".parse().unwrap(),
+            version_bits: None,
+        };
+
+        let coinbase = job.coinbase(&submit.enonce2);
+
+        let header = job.header(&coinbase, &submit, job.version());
+
+        template.build_block(coinbase, header).unwrap()
+    }
+
+    #[test]
+    fn coinbase_value_matches_template() {
+        let template = template();
+        let block = block(&template);
+
+        template
+            .check_coinbase_value(&block, Chain::Regtest, Amount::ZERO)
+            .unwrap();
+    }
+
+    #[test]
+    fn tampered_coinbase_value_is_rejected() {
+        let template = template();
+        let mut block = block(&template);
+
+        block.txdata[0].output[0].value += Amount::from_sat(1);
+
+        assert_eq!(
+            template
+                .check_coinbase_value(&block, Chain::Regtest, Amount::ZERO)
+                .unwrap_err()
+                .to_string(),
+            "coinbase outputs pay 2500000001 but template coinbasevalue is 2500000000",
+        );
+
+        template
+            .check_coinbase_value(&block, Chain::Regtest, Amount::from_sat(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn template_coinbase_value_must_match_subsidy_plus_fees() {
+        let template = template();
+        let block = block(&template);
+
+        assert_eq!(
+            template
+                .check_coinbase_value(&block, Chain::Mainnet, Amount::ZERO)
+                .unwrap_err()
+                .to_string(),
+            "template coinbasevalue 2500000000 does not match subsidy plus fees 5000000000 at height 200",
+        );
+    }
+
+    #[test]
+    fn template_fees_count_towards_expected_coinbase_value() {
+        let fee = Amount::from_sat(1_000);
+
+        let transaction = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+
+        let template = Arc::new(BlockTemplate {
+            height: HEIGHT,
+            coinbase_value: Chain::Regtest.subsidy(HEIGHT) + fee,
+            merkle_branches: stratum::merkle_branches(vec![transaction.compute_txid()]),
+            transactions: vec![TemplateTransaction {
+                txid: transaction.compute_txid(),
+                transaction,
+                fee,
+            }],
+            ..Default::default()
+        });
+
+        let block = block(&template);
+
+        template
+            .check_coinbase_value(&block, Chain::Regtest, Amount::ZERO)
+            .unwrap();
+    }
+
+    #[test]
+    fn coinbase_only_template_builds_valid_block() {
+        let template = template();
+        let block = block(&template);
+
+        assert!(template.merkle_branches().is_empty());
+        assert_eq!(block.txdata.len(), 1);
+        assert_eq!(
+            block.header.merkle_root.to_raw_hash(),
+            block.txdata[0].compute_txid().to_raw_hash()
+        );
+        assert!(block.check_merkle_root());
+    }
+
+    #[test]
+    fn mismatched_merkle_root_is_rejected() {
+        let template = template();
+        let block = block(&template);
+
+        let merkle_root = MerkleNode::from(bitcoin::hashes::sha256d::Hash::all_zeros());
+
+        let mut header = block.header;
+        header.merkle_root = merkle_root.into();
+
+        assert_eq!(
+            template
+                .build_block(
+                    Coinbase {
+                        transaction: block.txdata[0].clone(),
+                        merkle_root,
+                    },
+                    header,
+                )
+                .unwrap_err()
+                .to_string(),
+            "block merkle root does not commit to its transactions",
+        );
+    }
+
+    #[test]
+    fn coinbase_merkle_root_commits_to_template_transactions() {
+        let transactions = (0..3)
+            .map(|n| {
+                let transaction = Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::from_consensus(n),
+                    input: Vec::new(),
+                    output: Vec::new(),
+                };
+
+                TemplateTransaction {
+                    txid: transaction.compute_txid(),
+                    transaction,
+                    fee: Amount::ZERO,
+                }
+            })
+            .collect::<Vec<TemplateTransaction>>();
+
+        let template = Arc::new(BlockTemplate {
+            height: HEIGHT,
+            coinbase_value: Chain::Regtest.subsidy(HEIGHT),
+            merkle_branches: stratum::merkle_branches(
+                transactions.iter().map(|tx| tx.txid).collect(),
+            ),
+            transactions,
+            ..Default::default()
+        });
+
+        let block = block(&template);
+
+        assert_eq!(block.txdata.len(), 4);
+        assert!(block.check_merkle_root());
+    }
+
+    #[test]
+    fn job_keeps_payout_address_across_reload() {
+        const OLD: &str = "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc";
+        const NEW: &str = "tb1q6en7qjxgw4ev8xwx94pzdry6a6ky7wlfeqzunz";
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coinbase.txt");
+        fs::write(&path, format!("{OLD}\n")).unwrap();
+
+        let coinbase_addresses = CoinbaseAddresses::load(path.clone(), Network::Signet).unwrap();
+
+        let template = template();
+
+        let job = template
+            .create_job(
+                &Extranonce::random(ENONCE1_SIZE),
+                8,
+                coinbase_addresses.get(template.height()).as_ref(),
+                JobId::new(0),
+                None,
+            )
+            .unwrap();
+
+        fs::write(&path, format!("{NEW}\n")).unwrap();
+        assert!(coinbase_addresses.reload().unwrap().is_some());
+
+        let payout_address = job.payout_address.clone().unwrap();
+        assert_eq!(payout_address.to_string(), OLD);
+        assert_eq!(
+            coinbase_addresses
+                .get(template.height())
+                .unwrap()
+                .to_string(),
+            NEW
+        );
+
+        let coinbase = job.coinbase(&"0000000000000000".parse().unwrap());
+
+        assert_eq!(
+            coinbase.transaction.output[0].script_pubkey,
+            payout_address.script_pubkey()
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+
+/// Conventions worker names must follow to authorize, keeping logs and the
+/// database clean. The default policy allows every name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct WorkerNamePolicy {
+    max_length: Option<usize>,
+    charset: Option<String>,
+}
+
+impl WorkerNamePolicy {
+    /// `charset` lists the characters allowed besides ASCII letters and
+    /// digits.
+    pub(crate) fn new(max_length: Option<usize>, charset: Option<String>) -> Self {
+        Self {
+            max_length,
+            charset,
+        }
+    }
+
+    pub(crate) fn check(&self, username: &Username) -> Result {
+        let workername = username.workername();
+
+        if let Some(max_length) = self.max_length {
+            let length = workername.chars().count();
+            ensure!(
+                length <= max_length,
+                "worker name `{workername}` is {length} characters long, at most {max_length} allowed"
+            );
+        }
+
+        if let Some(charset) = &self.charset
+            && let Some(disallowed) = workername
+                .chars()
+                .find(|c| !c.is_ascii_alphanumeric() && !charset.contains(*c))
+        {
+            bail!("worker name `{workername}` contains disallowed character `{disallowed}`");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn username(workername: &str) -> Username {
+        format!("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq.{workername}")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn default_allows_everything() {
+        let policy = WorkerNamePolicy::default();
+        assert!(policy.check(&username("rig 1/β!")).is_ok());
+        assert!(policy.check(&username(&"x".repeat(1000))).is_ok());
+    }
+
+    #[test]
+    fn conforming_name() {
+        let policy = WorkerNamePolicy::new(Some(8), Some("-_".into()));
+        assert!(policy.check(&username("rig-1_a")).is_ok());
+    }
+
+    #[test]
+    fn over_long_name() {
+        let policy = WorkerNamePolicy::new(Some(8), None);
+        assert!(policy.check(&username("12345678")).is_ok());
+        assert_eq!(
+            policy
+                .check(&username("123456789"))
+                .unwrap_err()
+                .to_string(),
+            "worker name `123456789` is 9 characters long, at most 8 allowed"
+        );
+    }
+
+    #[test]
+    fn disallowed_characters() {
+        let policy = WorkerNamePolicy::new(None, Some("-".into()));
+        assert_eq!(
+            policy.check(&username("rig_1")).unwrap_err().to_string(),
+            "worker name `rig_1` contains disallowed character `_`"
+        );
+        assert!(policy.check(&username("rig-β")).is_err());
+    }
+}
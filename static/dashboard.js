@@ -427,7 +427,10 @@ function renderBitcoinData(data) {
 
   set('btc_height', data.height);
   const link = document.getElementById('btc_height_link');
-  if (link && data.height != null) link.href = `https://mempool.space/block/${data.height}`;
+  if (link) {
+    if (data.explorer_url) link.href = data.explorer_url;
+    else link.removeAttribute('href');
+  }
   copyable('network_difficulty', formatDifficulty(data.network_difficulty), data.network_difficulty);
   set('network_hashrate', data.network_hashrate, formatHashrate);
   set('mempool_txs', data.mempool_txs);
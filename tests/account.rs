@@ -570,3 +570,106 @@ async fn metadata_update_allowlist() {
     assert!(metadata.get("block_count").is_none());
     assert!(metadata.get("highest_blockheight").is_none());
 }
+
+#[tokio::test]
+async fn accounts_by_ln_address_groups_shared_address() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    insert_test_account(
+        db_url.clone(),
+        "user_b",
+        Some("shared@ln.com"),
+        vec![],
+        3000,
+    )
+    .await
+    .unwrap();
+    insert_test_account(
+        db_url.clone(),
+        "user_a",
+        Some("shared@ln.com"),
+        vec![],
+        5000,
+    )
+    .await
+    .unwrap();
+    insert_test_account(
+        db_url.clone(),
+        "user_c",
+        Some("unique@ln.com"),
+        vec![],
+        2000,
+    )
+    .await
+    .unwrap();
+
+    let accounts = server
+        .get_json_async::<Vec<Account>>("/api/accounts?ln=shared@ln.com")
+        .await;
+
+    assert_eq!(
+        accounts
+            .iter()
+            .map(|account| account.btc_address.as_str())
+            .collect::<Vec<&str>>(),
+        ["user_a", "user_b"],
+    );
+    assert_eq!(accounts[0].total_diff, 5000);
+    assert_eq!(accounts[1].total_diff, 3000);
+
+    let accounts = server
+        .get_json_async::<Vec<Account>>("/api/accounts?ln=unique@ln.com")
+        .await;
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].btc_address, "user_c");
+}
+
+#[tokio::test]
+async fn accounts_by_ln_address_empty() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    insert_test_account(
+        db_url.clone(),
+        "user_a",
+        Some("shared@ln.com"),
+        vec![],
+        5000,
+    )
+    .await
+    .unwrap();
+
+    let accounts = server
+        .get_json_async::<Vec<Account>>("/api/accounts?ln=nobody@ln.com")
+        .await;
+
+    assert!(accounts.is_empty());
+
+    let response = server.get_json_async_raw("/api/accounts").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn account_lookup_normalizes_address() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let test_account = TestAccount::new();
+    let btc_address = test_account.native_segwit_address.clone();
+
+    insert_test_account(db_url.clone(), &btc_address, Some("foo@bar.com"), vec![], 0)
+        .await
+        .unwrap();
+
+    let account = server
+        .get_json_async::<Account>(&format!("/account/{}", btc_address.to_uppercase()))
+        .await;
+
+    assert_eq!(account.btc_address, btc_address);
+}
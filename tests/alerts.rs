@@ -98,6 +98,7 @@ fn test_format_block_found_notification() {
             hash: "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054".to_string(),
             value: 625000000,
             miner: "test_pool".to_string(),
+            explorer_url: None,
             test,
         };
 
@@ -110,6 +111,7 @@ fn test_format_block_found_notification() {
         assert!(message.contains("850000"));
         assert!(message.contains("6.25000000 BTC"));
         assert!(message.contains("test_pool"));
+        assert!(!message.contains("Explorer"));
         assert!(matches!(priority, NotificationPriority::Max));
         assert!(tags.contains(&"mining".to_string()));
         assert!(tags.contains(&"bitcoin".to_string()));
@@ -120,6 +122,28 @@ fn test_format_block_found_notification() {
     case(true);
 }
 
+#[test]
+fn test_format_block_found_notification_with_explorer_url() {
+    let handler = NotificationHandler::new("test_channel".to_string());
+    let notification = NotificationType::BlockFound {
+        height: 850000,
+        hash: "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054".to_string(),
+        value: 625000000,
+        miner: "test_pool".to_string(),
+        explorer_url: Some(
+            "https://mempool.space/block/00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054"
+                .to_string(),
+        ),
+        test: false,
+    };
+
+    let (_, message, _, _) = handler.format_notification(notification);
+
+    assert!(message.ends_with(
+        "\nExplorer: https://mempool.space/block/00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054"
+    ));
+}
+
 #[tokio::test]
 #[ignore]
 async fn test_send_block_notification() {
@@ -131,6 +155,7 @@ async fn test_send_block_notification() {
         hash: "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054".to_string(),
         value: 625000000,
         miner: "test_pool".to_string(),
+        explorer_url: None,
         test: false,
     };
 
@@ -163,7 +188,7 @@ async fn test_send_block_notification() {
 
 #[tokio::test]
 async fn test_notification_failure_handling() {
-    let handler = NotificationHandler::_with_custom_server(
+    let handler = NotificationHandler::with_custom_server(
         "http://invalid.ntfy.server.local".to_string(),
         "test_channel".to_string(),
     );
@@ -0,0 +1,58 @@
+use super::*;
+
+fn check(args: &str) -> (Option<i32>, String, String) {
+    let output = CommandBuilder::new(format!("config check {args}"))
+        .capture_stdout(true)
+        .capture_stderr(true)
+        .spawn()
+        .wait_with_output()
+        .unwrap();
+
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn valid_pool_settings() {
+    let (code, stdout, stderr) = check(
+        "pool --chain regtest --bitcoin-rpc-username user --bitcoin-rpc-password hunter2 \
+         --vardiff-window 60",
+    );
+
+    assert_eq!(code, Some(0), "{stderr}");
+    assert!(stdout.contains("pool settings are valid"), "{stdout}");
+    assert!(stdout.contains("chain: Regtest"), "{stdout}");
+    assert!(stdout.contains("vardiff_window: 60s"), "{stdout}");
+    assert!(stdout.contains("<redacted>"), "{stdout}");
+    assert!(!stdout.contains("hunter2"), "{stdout}");
+}
+
+#[test]
+fn invalid_settings_name_the_field() {
+    #[track_caller]
+    fn case(args: &str, message: &str) {
+        let (code, stdout, stderr) = check(args);
+        assert_ne!(code, Some(0), "{stdout}");
+        assert!(stderr.contains(message), "{stderr}");
+        assert!(!stdout.contains("settings are valid"), "{stdout}");
+    }
+
+    case(
+        "pool --vardiff-window 0",
+        "vardiff_window must be greater than 0",
+    );
+    case("pool --version-mask nope", "--version-mask");
+    case("pool --version-mask 00000001", "version_mask");
+    case(
+        "pool --update-interval 10 --max-update-interval 5",
+        "max_update_interval (5s) must be >= update_interval (10s)",
+    );
+    case(
+        "pool --bitcoin-rpc-password hunter2",
+        "no bitcoin RPC username specified",
+    );
+    case("pool --vardif-window 60", "--vardif-window");
+}
@@ -0,0 +1,149 @@
+use {super::*, test_stack::TestStack};
+
+const ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+/// Solves one share on the latest job. On regtest any share at the pool's
+/// start difficulty also meets the network target, so each one is a block.
+async fn find_block(
+    client: &stratum::client::Client,
+    events: &mut stratum::client::EventReceiver,
+    enonce1: &Extranonce,
+    enonce2_size: usize,
+    stale: Option<&stratum::PrevHash>,
+) -> stratum::Notify {
+    let notify = timeout(Duration::from_secs(30), async {
+        loop {
+            let (notify, _) = wait_for_notify(events).await;
+
+            if Some(&notify.prevhash) != stale {
+                break notify;
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for a job on the new tip");
+
+    submit_share(
+        client,
+        &notify,
+        enonce1,
+        enonce2_size,
+        Difficulty::from(0.00001),
+    )
+    .await
+    .unwrap();
+
+    notify
+}
+
+#[tokio::test]
+#[serial(heavy)]
+#[timeout(180000)]
+async fn mined_block_pays_out() {
+    let stack = TestStack::spawn("--start-diff 0.00001", "--finder-bonus-bps 100").await;
+
+    // Shares at the start difficulty round to nothing in `accounts.total_diff`,
+    // so the miner brings difficulty from earlier rounds.
+    insert_test_account(
+        stack.server_database_url(),
+        ADDRESS,
+        Some("miner@lnurl.com"),
+        Vec::new(),
+        1000,
+    )
+    .await
+    .unwrap();
+
+    let height = stack.pool.get_block_height().await;
+
+    let client = stack
+        .pool
+        .stratum_client_for_username(&format!("{ADDRESS}.rig"))
+        .await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let found = find_block(
+        &client,
+        &mut events,
+        &subscribe.enonce1,
+        subscribe.enonce2_size,
+        None,
+    )
+    .await;
+
+    // Sync only sends a block once shares for a later one exist.
+    find_block(
+        &client,
+        &mut events,
+        &subscribe.enonce1,
+        subscribe.enonce2_size,
+        Some(&found.prevhash),
+    )
+    .await;
+
+    stack
+        .pool
+        .wait_for_blocks(2, Duration::from_secs(30))
+        .await
+        .unwrap();
+
+    assert_eq!(stack.pool.get_block_height().await, height + 2);
+
+    let blockheight = i32::try_from(height + 1).unwrap();
+
+    let pool_db = sqlx::PgPool::connect(&stack.pool_database_url())
+        .await
+        .unwrap();
+
+    let (username, coinbasevalue): (String, i64) =
+        sqlx::query_as("SELECT username, coinbasevalue FROM blocks WHERE blockheight = $1")
+            .bind(blockheight)
+            .fetch_one(&pool_db)
+            .await
+            .unwrap();
+
+    assert_eq!(username, ADDRESS);
+
+    let accepted: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM shares WHERE blockheight = $1 AND username = $2 AND result",
+    )
+    .bind(blockheight)
+    .bind(ADDRESS)
+    .fetch_one(&pool_db)
+    .await
+    .unwrap();
+
+    assert_eq!(accepted, 1);
+
+    stack.sync().await;
+
+    let server_db = sqlx::PgPool::connect(&stack.server_database_url())
+        .await
+        .unwrap();
+
+    let payouts: Vec<(String, i64, i64, String)> = sqlx::query_as(
+        "SELECT a.username, p.amount, p.diff_paid, p.status
+           FROM payouts p JOIN accounts a ON a.id = p.account_id
+           WHERE p.blockheight_end = $1",
+    )
+    .bind(blockheight)
+    .fetch_all(&server_db)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        payouts,
+        [(
+            ADDRESS.to_string(),
+            coinbasevalue - 100_000_000,
+            1000,
+            "pending".to_string()
+        )],
+        "the only contributor takes the whole reward"
+    );
+
+    client.disconnect().await;
+}
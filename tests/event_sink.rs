@@ -310,3 +310,64 @@ async fn test_block_found_event() {
         "Difficulty should be positive"
     );
 }
+
+#[ignore]
+#[cfg(target_os = "linux")]
+#[tokio::test]
+#[serial_test::serial(heavy)]
+async fn test_block_found_rotates_coinbase_address() {
+    use sqlx::PgPool;
+
+    let rotation = [
+        "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc",
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+    ];
+
+    let bitcoind = bitcoind();
+    let pg_db = setup_pg_db();
+    let database_url = pg_db.connection_uri();
+    setup_test_schema(database_url.clone()).await.unwrap();
+
+    {
+        let pool = TestPool::spawn_with_args(
+            &bitcoind,
+            format!(
+                "--database-url {database_url} --start-diff 0.00001 --coinbase-address {} --coinbase-address {}",
+                rotation[0], rotation[1]
+            ),
+        );
+
+        pool.mine_block().await;
+        pool.mine_block().await;
+        pool.wait_for_blocks(2, Duration::from_secs(10))
+            .await
+            .expect("Failed to detect blocks in pool status within timeout");
+    }
+
+    let db_pool = PgPool::connect(&database_url).await.unwrap();
+    let blocks: Vec<(i32, String, Option<String>)> = sqlx::query_as(
+        "SELECT blockheight, username, coinbase_address FROM blocks ORDER BY blockheight",
+    )
+    .fetch_all(&db_pool)
+    .await
+    .unwrap();
+
+    assert_eq!(blocks.len(), 2, "Database should have exactly 2 blocks");
+
+    for (blockheight, username, coinbase_address) in &blocks {
+        assert_eq!(
+            coinbase_address.as_deref(),
+            Some(rotation[*blockheight as usize % rotation.len()]),
+        );
+        assert_eq!(
+            username,
+            &signet_username()
+                .address()
+                .clone()
+                .assume_checked()
+                .to_string()
+        );
+    }
+
+    assert_ne!(blocks[0].2, blocks[1].2);
+}
@@ -91,3 +91,101 @@ async fn zmq_reconnects_after_bitcoind_restart() {
         "Pool should still be running after bitcoind restart"
     );
 }
+
+#[tokio::test]
+#[timeout(120000)]
+async fn block_with_segwit_transactions_is_accepted() {
+    let bitcoind = spawn_regtest();
+    let funding_descriptor = generate_descriptor();
+    let address = fund_wallet(&bitcoind, &funding_descriptor).await;
+
+    let txid =
+        send_to_address_without_mining(&bitcoind, &funding_descriptor, &address, 100_000).await;
+
+    let client = bitcoind.client().unwrap();
+    let height = client.call_raw::<u64>("getblockcount", &[]).await.unwrap();
+
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    CommandBuilder::new(format!(
+        "miner --mode block-found --username {address}.rig {}",
+        pool.stratum_endpoint()
+    ))
+    .spawn()
+    .wait()
+    .unwrap();
+
+    let blockhash = timeout(Duration::from_secs(30), async {
+        loop {
+            if client.call_raw::<u64>("getblockcount", &[]).await.unwrap() > height {
+                break client
+                    .call_raw::<String>("getblockhash", &[json!(height + 1)])
+                    .await
+                    .unwrap();
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("Pool block was not accepted by submitblock");
+
+    let block = client
+        .call_raw::<serde_json::Value>("getblock", &[json!(blockhash), json!(2)])
+        .await
+        .unwrap();
+
+    let transactions = block["tx"].as_array().unwrap();
+
+    let segwit = transactions
+        .iter()
+        .find(|tx| tx["txid"] == json!(txid.to_string()))
+        .expect("pool block should include the mempool transaction");
+
+    assert_ne!(
+        segwit["txid"], segwit["hash"],
+        "transaction should carry witness data"
+    );
+}
+
+#[tokio::test]
+#[timeout(120000)]
+async fn coinbase_only_block_is_accepted_on_fresh_node() {
+    let bitcoind = spawn_regtest();
+
+    let client = bitcoind.client().unwrap();
+    let height = client.call_raw::<u64>("getblockcount", &[]).await.unwrap();
+
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    CommandBuilder::new(format!(
+        "miner --mode block-found --username bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080.rig {}",
+        pool.stratum_endpoint()
+    ))
+    .spawn()
+    .wait()
+    .unwrap();
+
+    let blockhash = timeout(Duration::from_secs(30), async {
+        loop {
+            if client.call_raw::<u64>("getblockcount", &[]).await.unwrap() > height {
+                break client
+                    .call_raw::<String>("getblockhash", &[json!(height + 1)])
+                    .await
+                    .unwrap();
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("Coinbase-only block was not accepted by submitblock");
+
+    let block = client
+        .call_raw::<serde_json::Value>("getblock", &[json!(blockhash), json!(1)])
+        .await
+        .unwrap();
+
+    let transactions = block["tx"].as_array().unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(block["merkleroot"], transactions[0]);
+}
@@ -0,0 +1,180 @@
+use {super::*, para::subcommand::import_ckpool_log::ImportSummary};
+
+const ALICE: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+const BOB: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+fn share_line(username: &str, diff: f64, result: bool) -> String {
+    let mut share = json!({
+        "workinfoid": 7204512345678901i64,
+        "clientid": 1,
+        "enonce1": "d3a1b2c4",
+        "nonce2": "0000000000000000",
+        "nonce": "1a2b3c4d",
+        "ntime": "665f1e20",
+        "diff": diff,
+        "sdiff": diff * 2.0,
+        "hash": "00000000000000abcdef",
+        "result": result,
+        "errn": 0,
+        "createdate": "1717509664,123456789",
+        "createby": "code",
+        "createcode": "parse_submit",
+        "createinet": "10.0.0.1:3333",
+        "workername": format!("{username}.rig"),
+        "username": username,
+        "address": "10.0.0.1",
+        "agent": "cgminer/4.12.0",
+    });
+
+    if !result {
+        share["reject-reason"] = json!("Stale");
+    }
+
+    share.to_string()
+}
+
+fn write_share_log(dir: &TempDir, blockheight: u32, lines: &[String]) -> PathBuf {
+    let height_dir = dir.path().join(format!("{blockheight:08x}"));
+    fs::create_dir_all(&height_dir).unwrap();
+
+    let path = height_dir.join("6650d1e200000001.sharelog");
+    fs::write(&path, lines.join("\n")).unwrap();
+    path
+}
+
+async fn account_totals(database_url: &str, username: &str) -> (i64, i64) {
+    let pool = sqlx::PgPool::connect(database_url).await.unwrap();
+
+    let totals = sqlx::query_as(
+        "SELECT a.total_diff, (m.data->>'block_count')::bigint
+         FROM accounts a JOIN account_metadata m ON m.account_id = a.id
+         WHERE a.username = $1",
+    )
+    .bind(username)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    pool.close().await;
+
+    totals
+}
+
+#[tokio::test]
+async fn import_ckpool_log() {
+    let server = TestServer::spawn_with_db().await;
+    let database_url = server.database_url().unwrap();
+    setup_test_schema(database_url.clone()).await.unwrap();
+
+    let logs = TempDir::new().unwrap();
+
+    let first = write_share_log(
+        &logs,
+        850000,
+        &[
+            share_line(ALICE, 1000.0, true),
+            share_line(BOB, 250.0, true),
+            share_line(ALICE, 500.0, true),
+            share_line(BOB, 4000.0, false),
+        ],
+    );
+
+    let second = write_share_log(&logs, 850001, &[share_line(ALICE, 100.0, true)]);
+
+    let summary = CommandBuilder::new(format!(
+        "import-ckpool-log --database-url {database_url} {}",
+        first.display()
+    ))
+    .run_and_deserialize_output::<ImportSummary>();
+
+    assert_eq!(
+        summary,
+        ImportSummary {
+            origin: "6650d1e200000001.sharelog".into(),
+            blockheight: 850000,
+            shares: 4,
+            accepted: 3,
+            accounts: 2,
+            total_diff: 1750.0,
+            dry_run: false,
+        }
+    );
+
+    CommandBuilder::new(format!(
+        "import-ckpool-log --database-url {database_url} --origin node-b {}",
+        second.display()
+    ))
+    .run_and_deserialize_output::<ImportSummary>();
+
+    assert_eq!(account_totals(&database_url, ALICE).await, (1600, 2));
+    assert_eq!(account_totals(&database_url, BOB).await, (250, 1));
+
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+
+    let stored: Vec<(i64, String, i32, bool)> = sqlx::query_as(
+        "SELECT id, origin, blockheight, result FROM remote_shares ORDER BY origin, id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    pool.close().await;
+
+    assert_eq!(
+        stored,
+        vec![
+            (1, "6650d1e200000001.sharelog".into(), 850000, true),
+            (2, "6650d1e200000001.sharelog".into(), 850000, true),
+            (3, "6650d1e200000001.sharelog".into(), 850000, true),
+            (4, "6650d1e200000001.sharelog".into(), 850000, false),
+            (1, "node-b".into(), 850001, true),
+        ]
+    );
+}
+
+#[test]
+fn import_ckpool_log_dry_run() {
+    let logs = TempDir::new().unwrap();
+
+    let path = write_share_log(
+        &logs,
+        850000,
+        &[
+            share_line(ALICE, 1000.0, true),
+            share_line(BOB, 4000.0, false),
+        ],
+    );
+
+    let summary = CommandBuilder::new(format!(
+        "import-ckpool-log --dry-run --database-url postgres://127.0.0.1:1/none {}",
+        path.display()
+    ))
+    .run_and_deserialize_output::<ImportSummary>();
+
+    assert_eq!(summary.shares, 2);
+    assert_eq!(summary.accepted, 1);
+    assert_eq!(summary.accounts, 1);
+    assert_eq!(summary.total_diff, 1000.0);
+    assert!(summary.dry_run);
+}
+
+#[test]
+fn import_ckpool_log_reports_bad_lines() {
+    let logs = TempDir::new().unwrap();
+
+    let path = write_share_log(
+        &logs,
+        850000,
+        &[share_line(ALICE, 1000.0, true), "{\"diff\":1".into()],
+    );
+
+    let output = CommandBuilder::new(format!("import-ckpool-log --dry-run {}", path.display()))
+        .spawn()
+        .wait_with_output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2: "), "{stderr}");
+}
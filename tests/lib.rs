@@ -59,9 +59,13 @@ use {
             miner::Share,
             server::{
                 account::{Account, AccountMetadataUpdate, AccountUpdate},
-                database::{Database, HighestDiff, Payout, PendingPayout},
+                database::{
+                    Contributor, Database, HighestDiff, Payout, PendingPayout, RemoteShare,
+                },
+            },
+            sync::{
+                FailedSubBatch, FoundBlockRecord, ShareBatch, ShareOutcome, Sync, SyncResponse,
             },
-            sync::{FoundBlockRecord, ShareBatch, Sync, SyncResponse},
         },
     },
     pgtemp::{PgTempDB, PgTempDBBuilder},
@@ -98,16 +102,23 @@ mod test_psql;
 #[cfg(target_os = "linux")]
 mod test_router;
 mod test_server;
+#[cfg(target_os = "linux")]
+mod test_stack;
 mod to_args;
 
 #[cfg(target_os = "linux")]
 mod account;
 mod alerts;
+mod config;
+#[cfg(target_os = "linux")]
+mod end_to_end;
 #[cfg(target_os = "linux")]
 mod event_sink;
 #[cfg(target_os = "linux")]
 mod generator;
 #[cfg(target_os = "linux")]
+mod import_ckpool_log;
+#[cfg(target_os = "linux")]
 mod payouts;
 #[cfg(target_os = "linux")]
 mod ping;
@@ -117,6 +128,8 @@ mod pool;
 mod proxy;
 #[cfg(target_os = "linux")]
 mod router;
+#[cfg(target_os = "linux")]
+mod selftest;
 mod server;
 #[cfg(target_os = "linux")]
 mod server_with_db;
@@ -145,6 +158,64 @@ fn spawn_regtest() -> Bitcoind {
     Bitcoind::spawn_no_listen(tempdir, rpc_port, zmq_port, false, Network::Regtest).unwrap()
 }
 
+#[cfg(target_os = "linux")]
+fn generate_descriptor() -> String {
+    CommandBuilder::new("wallet --chain regtest generate")
+        .run_and_deserialize_output::<para::subcommand::wallet::generate::Output>()
+        .descriptor
+}
+
+#[cfg(target_os = "linux")]
+async fn fund_wallet(bitcoind: &Bitcoind, descriptor: &str) -> String {
+    let directory = TempDir::new().unwrap();
+    let data_dir = directory.path().to_str().unwrap();
+
+    let address = CommandBuilder::new(format!(
+        "wallet \
+         --chain regtest \
+         --bitcoin-rpc-port {} \
+         --bitcoin-rpc-username {} \
+         --bitcoin-rpc-password {} \
+         --data-dir {data_dir} \
+         --descriptor {descriptor} \
+         receive",
+        bitcoind.rpc_port, bitcoind.rpc_user, bitcoind.rpc_password,
+    ))
+    .run_and_deserialize_output::<para::subcommand::wallet::receive::Output>()
+    .address
+    .assume_checked()
+    .to_string();
+
+    generate_to_address(bitcoind, 101, &address).await;
+
+    address
+}
+
+#[cfg(target_os = "linux")]
+async fn send_to_address_without_mining(
+    bitcoind: &Bitcoind,
+    funding_descriptor: &str,
+    address: &str,
+    amount: u64,
+) -> bitcoin::Txid {
+    let directory = TempDir::new().unwrap();
+    let data_dir = directory.path().to_str().unwrap();
+
+    CommandBuilder::new(format!(
+        "wallet \
+         --chain regtest \
+         --bitcoin-rpc-port {} \
+         --bitcoin-rpc-username {} \
+         --bitcoin-rpc-password {} \
+         --data-dir {data_dir} \
+         --descriptor {funding_descriptor} \
+         send --fee-rate 1 --address {address} --amount {amount}",
+        bitcoind.rpc_port, bitcoind.rpc_user, bitcoind.rpc_password,
+    ))
+    .run_and_deserialize_output::<para::subcommand::wallet::send::Output>()
+    .txid
+}
+
 #[cfg(target_os = "linux")]
 async fn generate_to_address(bitcoind: &Bitcoind, n: u64, address: &str) {
     bitcoind
@@ -1,4 +1,10 @@
-use super::*;
+use {
+    super::*,
+    para::subcommand::server::database::{
+        ExcludeFromPayoutRequest, RequeuePayoutsRequest, UpdatePayoutStatusRequest,
+    },
+    std::collections::BTreeSet,
+};
 
 #[tokio::test]
 async fn test_block_insertion_creates_payouts() {
@@ -231,7 +237,7 @@ async fn test_payout_distribution_proportional_to_diff() {
     let (username_c, amount_c, diff_c) = &payouts[2];
     assert_eq!(username_c, "user_c");
     assert_eq!(*diff_c, 3000);
-    assert_eq!(*amount_c, 250000000);
+    assert_eq!(*amount_c, 250000001, "Largest account takes the remainder");
 
     pool.close().await;
 }
@@ -302,6 +308,97 @@ async fn test_payout_excludes_cancelled_payouts_from_calculation() {
     pool.close().await;
 }
 
+#[tokio::test]
+async fn test_cancel_payouts_makes_diff_payable_again() {
+    use para::subcommand::server::database::CancelPayoutsRequest;
+
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(
+        db_url.clone(),
+        "user_x",
+        Some("user_x@ln.com"),
+        vec![],
+        2000,
+    )
+    .await
+    .unwrap();
+
+    let account_id: i64 = sqlx::query_scalar("SELECT id FROM accounts WHERE username = 'user_x'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    sqlx::query(
+        "INSERT INTO payouts (account_id, amount, diff_paid, blockheight_start, blockheight_end, status)
+         VALUES ($1, 50000000, 500, 0, 800001, 'success'),
+                ($1, 50000000, 500, 800001, 800002, 'pending')",
+    )
+    .bind(account_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response: serde_json::Value = server
+        .post_json(
+            "/payouts/cancel",
+            &CancelPayoutsRequest {
+                username: Some("user_x".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["rows_affected"], 1);
+
+    let statuses: Vec<String> =
+        sqlx::query_scalar("SELECT status FROM payouts WHERE account_id = $1 ORDER BY id")
+            .bind(account_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(statuses, ["success", "cancelled"]);
+
+    let mut test_block = create_test_block(800003);
+    test_block.coinbasevalue = Some(300000000);
+    test_block.username = Some("user_y".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let diff_paid: i64 = sqlx::query_scalar(
+        "SELECT diff_paid FROM payouts
+         WHERE account_id = $1 AND status = 'pending'",
+    )
+    .bind(account_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        diff_paid, 1500,
+        "Cancelled diff is unpaid again, successful diff stays paid"
+    );
+
+    pool.close().await;
+}
+
 #[tokio::test]
 async fn test_payout_considers_previous_successful_payouts() {
     let server = TestServer::spawn_with_db().await;
@@ -558,15 +655,458 @@ async fn test_block_without_coinbasevalue_no_payouts() {
         .unwrap();
 
     assert_eq!(
-        payout_count, 0,
-        "Should not create payouts when block has no coinbasevalue"
+        payout_count, 0,
+        "Should not create payouts when block has no coinbasevalue"
+    );
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_multiple_users_with_finder_exclusion() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(db_url.clone(), "miner_1", Some("m1@ln.com"), vec![], 1000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "miner_2", Some("m2@ln.com"), vec![], 2000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "miner_3", Some("m3@ln.com"), vec![], 3000)
+        .await
+        .unwrap();
+    insert_test_account(
+        db_url.clone(),
+        "finder",
+        Some("finder@ln.com"),
+        vec![],
+        4000,
+    )
+    .await
+    .unwrap();
+
+    let mut test_block = create_test_block(800011);
+    test_block.coinbasevalue = Some(1000000000);
+    test_block.username = Some("finder".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let finder_payout: (i64, String, i64) = sqlx::query_as(
+        "SELECT p.amount, p.status, p.diff_paid
+         FROM payouts p
+         JOIN accounts a ON p.account_id = a.id
+         WHERE a.username = 'finder'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(finder_payout.0, 0, "Finder should have zero amount");
+    assert_eq!(
+        finder_payout.1, "success",
+        "Finder should have success status"
+    );
+    assert_eq!(
+        finder_payout.2, 4000,
+        "Finder's diff should be marked as paid"
+    );
+
+    let other_payouts: Vec<(String, i64, String, i64)> = sqlx::query_as(
+        "SELECT a.username, p.amount, p.status, p.diff_paid
+         FROM payouts p
+         JOIN accounts a ON p.account_id = a.id
+         WHERE a.username != 'finder'
+         ORDER BY a.username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(other_payouts.len(), 3);
+
+    assert_eq!(other_payouts[0].0, "miner_1");
+    assert_eq!(other_payouts[0].1, 150000000);
+    assert_eq!(other_payouts[0].2, "pending");
+    assert_eq!(other_payouts[0].3, 1000);
+
+    assert_eq!(other_payouts[1].0, "miner_2");
+    assert_eq!(other_payouts[1].1, 300000000);
+    assert_eq!(other_payouts[1].2, "pending");
+    assert_eq!(other_payouts[1].3, 2000);
+
+    assert_eq!(other_payouts[2].0, "miner_3");
+    assert_eq!(other_payouts[2].1, 450000000);
+    assert_eq!(other_payouts[2].2, "pending");
+    assert_eq!(other_payouts[2].3, 3000);
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_excluded_account_gets_no_payout() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(db_url.clone(), "miner_1", Some("m1@ln.com"), vec![], 1000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "pool_fee", Some("fee@ln.com"), vec![], 3000)
+        .await
+        .unwrap();
+
+    let account: Account = server
+        .post_json(
+            "/payouts/exclude",
+            &ExcludeFromPayoutRequest {
+                btc_address: "pool_fee".into(),
+                exclude_from_payout: true,
+            },
+        )
+        .await;
+
+    assert!(account.exclude_from_payout);
+    assert_eq!(account.total_diff, 3000);
+
+    let response = server
+        .post_json_raw(
+            "/payouts/exclude",
+            &ExcludeFromPayoutRequest {
+                btc_address: "unknown".into(),
+                exclude_from_payout: true,
+            },
+        )
+        .await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let mut test_block = create_test_block(800012);
+    test_block.coinbasevalue = Some(600000000);
+    test_block.username = Some("finder".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let payouts: Vec<(String, i64, String)> = sqlx::query_as(
+        "SELECT a.username, p.amount, p.status
+         FROM payouts p
+         JOIN accounts a ON p.account_id = a.id
+         ORDER BY a.username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        payouts,
+        vec![("miner_1".to_string(), 500000000, "pending".to_string())],
+        "Excluded account should get no payout row at all"
+    );
+
+    let total_diff: i64 =
+        sqlx::query_scalar("SELECT total_diff FROM accounts WHERE username = 'pool_fee'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(total_diff, 3000, "Excluded account should keep its shares");
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_finder_bonus_paid_on_top_of_proportional_share() {
+    let server = TestServer::spawn_with_db_args("--finder-bonus-bps 1000").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(db_url.clone(), "miner_1", Some("m1@ln.com"), vec![], 1000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "miner_2", Some("m2@ln.com"), vec![], 2000)
+        .await
+        .unwrap();
+    insert_test_account(
+        db_url.clone(),
+        "finder",
+        Some("finder@ln.com"),
+        vec![],
+        3000,
+    )
+    .await
+    .unwrap();
+
+    let mut test_block = create_test_block(800020);
+    test_block.coinbasevalue = Some(1000000000);
+    test_block.username = Some("finder".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let payouts: Vec<(String, i64, String, i64)> = sqlx::query_as(
+        "SELECT a.username, p.amount, p.status, p.diff_paid
+         FROM payouts p
+         JOIN accounts a ON p.account_id = a.id
+         ORDER BY a.username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    // 10% of the 900000000 distributable goes to the finder up front and the
+    // remaining 810000000 is split 1:2:3.
+    assert_eq!(
+        payouts,
+        vec![
+            (
+                "finder".to_string(),
+                90000000 + 405000000,
+                "pending".to_string(),
+                3000
+            ),
+            (
+                "miner_1".to_string(),
+                135000000,
+                "pending".to_string(),
+                1000
+            ),
+            (
+                "miner_2".to_string(),
+                270000000,
+                "pending".to_string(),
+                2000
+            ),
+        ]
+    );
+
+    let total: i64 = payouts.iter().map(|payout| payout.1).sum();
+    assert_eq!(total, 900000000, "Payouts should sum to the distributable");
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_hybrid_payout_mode() {
+    let server = TestServer::spawn_with_db_args("--hybrid-solo-threshold-bps 4000").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(db_url.clone(), "miner_1", Some("m1@ln.com"), vec![], 1000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "miner_2", None, vec![], 2000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "whale", Some("whale@ln.com"), vec![], 7000)
+        .await
+        .unwrap();
+
+    let payouts = |pool: sqlx::PgPool| async move {
+        sqlx::query_as::<_, (String, i64, String, i64)>(
+            "SELECT a.username, p.amount, p.status, p.diff_paid
+             FROM payouts p
+             JOIN accounts a ON p.account_id = a.id
+             ORDER BY p.id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    let mut test_block = create_test_block(800030);
+    test_block.coinbasevalue = Some(1000000000);
+    test_block.username = Some("miner_1".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    // The whale holds 70% of unpaid difficulty and is solo. The pooled
+    // finder was paid by the on-chain reserve, so it is only marked paid and
+    // the other pooled miner takes the whole distributable reward.
+    assert_eq!(
+        payouts(pool.clone()).await,
+        vec![
+            ("miner_1".to_string(), 0, "success".to_string(), 1000),
+            (
+                "miner_2".to_string(),
+                900000000,
+                "failure".to_string(),
+                2000
+            ),
+        ]
+    );
+
+    let mut test_block = create_test_block(800031);
+    test_block.coinbasevalue = Some(1000000000);
+    test_block.username = Some("whale".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 2,
+        end_id: 2,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    // The whale's difficulty carried over and it finds the next block solo.
+    let payouts = payouts(pool.clone()).await;
+    assert_eq!(
+        payouts[2],
+        ("whale".to_string(), 900000000, "pending".to_string(), 7000)
+    );
+
+    let total: i64 = payouts.iter().map(|payout| payout.1).sum();
+    assert_eq!(total, 1800000000, "Payouts should sum to both rewards");
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_donation_taken_before_proportional_split() {
+    let donation_address = "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc";
+
+    let server = TestServer::spawn_with_db_args(format!(
+        "--donation-bps 1000 --donation-address {donation_address}"
+    ))
+    .await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    insert_test_account(db_url.clone(), "miner_1", Some("m1@ln.com"), vec![], 1000)
+        .await
+        .unwrap();
+    insert_test_account(db_url.clone(), "miner_2", Some("m2@ln.com"), vec![], 2000)
+        .await
+        .unwrap();
+    insert_test_account(
+        db_url.clone(),
+        donation_address,
+        Some("donate@ln.com"),
+        vec![],
+        0,
+    )
+    .await
+    .unwrap();
+
+    let mut test_block = create_test_block(800030);
+    test_block.coinbasevalue = Some(1000000000);
+    test_block.username = Some("finder".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let payouts: Vec<(String, i64, String, i64)> = sqlx::query_as(
+        "SELECT a.username, p.amount, p.status, p.diff_paid
+         FROM payouts p
+         JOIN accounts a ON p.account_id = a.id
+         ORDER BY a.username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    // 1000 bps of the 900000000 distributable is donated, the remaining
+    // 810000000 is split 1:2 between the miners.
+    assert_eq!(
+        payouts,
+        vec![
+            (
+                "miner_1".to_string(),
+                270000000,
+                "pending".to_string(),
+                1000
+            ),
+            (
+                "miner_2".to_string(),
+                540000000,
+                "pending".to_string(),
+                2000
+            ),
+            (
+                donation_address.to_string(),
+                90000000,
+                "pending".to_string(),
+                0
+            ),
+        ]
     );
 
+    let total: i64 = payouts.iter().map(|payout| payout.1).sum();
+    assert_eq!(total, 900000000, "Payouts should sum to the distributable");
+
     pool.close().await;
 }
 
 #[tokio::test]
-async fn test_multiple_users_with_finder_exclusion() {
+async fn test_rounding_remainder_goes_to_largest_account() {
     let server = TestServer::spawn_with_db().await;
     let db_url = server.database_url().unwrap();
     setup_test_schema(db_url.clone()).await.unwrap();
@@ -579,21 +1119,9 @@ async fn test_multiple_users_with_finder_exclusion() {
     insert_test_account(db_url.clone(), "miner_2", Some("m2@ln.com"), vec![], 2000)
         .await
         .unwrap();
-    insert_test_account(db_url.clone(), "miner_3", Some("m3@ln.com"), vec![], 3000)
-        .await
-        .unwrap();
-    insert_test_account(
-        db_url.clone(),
-        "finder",
-        Some("finder@ln.com"),
-        vec![],
-        4000,
-    )
-    .await
-    .unwrap();
 
-    let mut test_block = create_test_block(800011);
-    test_block.coinbasevalue = Some(1000000000);
+    let mut test_block = create_test_block(800040);
+    test_block.coinbasevalue = Some(100000100);
     test_block.username = Some("finder".to_string());
 
     let batch = ShareBatch {
@@ -609,53 +1137,22 @@ async fn test_multiple_users_with_finder_exclusion() {
     let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
     assert_eq!(response.status, "OK");
 
-    let finder_payout: (i64, String, i64) = sqlx::query_as(
-        "SELECT p.amount, p.status, p.diff_paid
-         FROM payouts p
-         JOIN accounts a ON p.account_id = a.id
-         WHERE a.username = 'finder'",
-    )
-    .fetch_one(&pool)
-    .await
-    .unwrap();
-
-    assert_eq!(finder_payout.0, 0, "Finder should have zero amount");
-    assert_eq!(
-        finder_payout.1, "success",
-        "Finder should have success status"
-    );
-    assert_eq!(
-        finder_payout.2, 4000,
-        "Finder's diff should be marked as paid"
-    );
-
-    let other_payouts: Vec<(String, i64, String, i64)> = sqlx::query_as(
-        "SELECT a.username, p.amount, p.status, p.diff_paid
+    let payouts: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT a.username, p.amount
          FROM payouts p
          JOIN accounts a ON p.account_id = a.id
-         WHERE a.username != 'finder'
          ORDER BY a.username",
     )
     .fetch_all(&pool)
     .await
     .unwrap();
 
-    assert_eq!(other_payouts.len(), 3);
-
-    assert_eq!(other_payouts[0].0, "miner_1");
-    assert_eq!(other_payouts[0].1, 150000000);
-    assert_eq!(other_payouts[0].2, "pending");
-    assert_eq!(other_payouts[0].3, 1000);
-
-    assert_eq!(other_payouts[1].0, "miner_2");
-    assert_eq!(other_payouts[1].1, 299999999);
-    assert_eq!(other_payouts[1].2, "pending");
-    assert_eq!(other_payouts[1].3, 2000);
-
-    assert_eq!(other_payouts[2].0, "miner_3");
-    assert_eq!(other_payouts[2].1, 450000000);
-    assert_eq!(other_payouts[2].2, "pending");
-    assert_eq!(other_payouts[2].3, 3000);
+    // 100 sats split 1:2 rounds down to 33 and 66, and the leftover sat goes
+    // to miner_2, which has the most unpaid difficulty.
+    assert_eq!(
+        payouts,
+        vec![("miner_1".to_string(), 33), ("miner_2".to_string(), 67)]
+    );
 
     pool.close().await;
 }
@@ -1034,6 +1531,163 @@ async fn test_update_payout_status_to_failure_with_reason() {
     pool.close().await;
 }
 
+async fn dead_letter_test_payout(server: &TestServer, blockheight: i64) -> i64 {
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    insert_test_account(
+        db_url.clone(),
+        "unpayable",
+        Some("unpayable@ln.com"),
+        vec![],
+        3000,
+    )
+    .await
+    .unwrap();
+
+    let mut test_block = create_test_block(blockheight);
+    test_block.coinbasevalue = Some(400000000);
+    test_block.username = Some("finder".to_string());
+
+    let batch = ShareBatch {
+        block: Some(test_block),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    };
+
+    let _response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let payout_id = sqlx::query_scalar(
+        "SELECT id FROM payouts WHERE blockheight_end = $1 AND status = 'pending' LIMIT 1",
+    )
+    .bind(blockheight as i32)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    pool.close().await;
+
+    payout_id
+}
+
+async fn fail_payout(server: &TestServer, payout_id: i64, reason: &str) -> serde_json::Value {
+    server
+        .post_json(
+            "/payouts/update",
+            &UpdatePayoutStatusRequest {
+                payout_ids: vec![payout_id],
+                status: "failure".to_string(),
+                failure_reason: Some(reason.to_string()),
+            },
+        )
+        .await
+}
+
+#[tokio::test]
+async fn test_nth_payout_failure_dead_letters() {
+    let server = TestServer::spawn_with_db_args("--payout-max-retries 3").await;
+    let payout_id = dead_letter_test_payout(&server, 800030).await;
+    let pool = sqlx::PgPool::connect(&server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    for attempt in 1..=2 {
+        fail_payout(&server, payout_id, &format!("attempt {attempt}")).await;
+
+        let pending: Vec<PendingPayout> = server.get_json_async("/payouts?format=json").await;
+        assert_eq!(
+            pending.len(),
+            1,
+            "Failure {attempt} should still be retried"
+        );
+    }
+
+    let response = fail_payout(&server, payout_id, "no route").await;
+    assert_eq!(response["rows_affected"], 1);
+
+    let (status, attempts, failure_reason): (String, i16, Option<String>) =
+        sqlx::query_as("SELECT status, attempts, failure_reason FROM payouts WHERE id = $1")
+            .bind(payout_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(status, "dead_letter");
+    assert_eq!(attempts, 3);
+    assert_eq!(failure_reason.as_deref(), Some("no route"));
+
+    let pending: Vec<PendingPayout> = server.get_json_async("/payouts?format=json").await;
+    assert!(pending.is_empty(), "Dead-lettered payouts are not retried");
+
+    let response = fail_payout(&server, payout_id, "ignored").await;
+    assert_eq!(
+        response["rows_affected"], 0,
+        "Dead-lettered payouts only change via re-queue"
+    );
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_requeue_resets_dead_letter_retry_count() {
+    let server = TestServer::spawn_with_db_args("--payout-max-retries 1").await;
+    let payout_id = dead_letter_test_payout(&server, 800031).await;
+    let pool = sqlx::PgPool::connect(&server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    fail_payout(&server, payout_id, "no route").await;
+
+    let response: serde_json::Value = server
+        .post_json(
+            "/payouts/requeue",
+            &RequeuePayoutsRequest {
+                payout_ids: vec![payout_id],
+            },
+        )
+        .await;
+
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["rows_affected"], 1);
+
+    let (status, attempts, failure_reason): (String, i16, Option<String>) =
+        sqlx::query_as("SELECT status, attempts, failure_reason FROM payouts WHERE id = $1")
+            .bind(payout_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(status, "pending");
+    assert_eq!(attempts, 0);
+    assert_eq!(failure_reason, None);
+
+    let pending: Vec<PendingPayout> = server.get_json_async("/payouts?format=json").await;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].payout_ids, vec![payout_id]);
+
+    let response: serde_json::Value = server
+        .post_json(
+            "/payouts/requeue",
+            &RequeuePayoutsRequest {
+                payout_ids: vec![payout_id],
+            },
+        )
+        .await;
+
+    assert_eq!(
+        response["rows_affected"], 0,
+        "Only dead-lettered payouts can be re-queued"
+    );
+
+    pool.close().await;
+}
+
 #[tokio::test]
 async fn test_update_multiple_payout_status() {
     let server = TestServer::spawn_with_db().await;
@@ -1157,3 +1811,245 @@ async fn test_get_pending_payouts_empty_block() {
         "Should return empty list for non-existent block"
     );
 }
+
+#[tokio::test]
+async fn test_payouts_range_status_returns_every_status() {
+    use para::subcommand::server::database::PayoutStatus;
+
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    for username in ["alice", "bob"] {
+        insert_test_account(db_url.clone(), username, None, vec![], 1000)
+            .await
+            .unwrap();
+    }
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    for (username, amount, height, status, failure_reason) in [
+        ("alice", 1000, 800050, "success", None),
+        ("bob", 2000, 800050, "failure", Some("no route")),
+        ("alice", 3000, 800051, "pending", None),
+        ("bob", 4000, 800051, "dead_letter", Some("gave up")),
+        ("alice", 5000, 800052, "pending", None),
+    ] {
+        sqlx::query(
+            "
+            INSERT INTO payouts
+                (account_id, amount, diff_paid, blockheight_start, blockheight_end, status, failure_reason)
+            SELECT id, $2, 0, $3, $3, $4, $5 FROM accounts WHERE username = $1
+            ",
+        )
+        .bind(username)
+        .bind(amount as i64)
+        .bind(height)
+        .bind(status)
+        .bind(failure_reason)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let payouts: Vec<PayoutStatus> = server
+        .get_json_async("/payouts/range/800050/800051/status")
+        .await;
+
+    assert_eq!(
+        payouts
+            .iter()
+            .map(|payout| (
+                payout.username.as_str(),
+                payout.amount_sats,
+                payout.blockheight_end,
+                payout.status.as_str(),
+                payout.failure_reason.as_deref(),
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("alice", 1000, 800050, "success", None),
+            ("bob", 2000, 800050, "failure", Some("no route")),
+            ("alice", 3000, 800051, "pending", None),
+            ("bob", 4000, 800051, "dead_letter", Some("gave up")),
+        ]
+    );
+
+    assert!(
+        payouts
+            .iter()
+            .all(|payout| payout.created_at.is_some() && payout.updated_at.is_some())
+    );
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_sat_split_subtracts_subsidy_across_regtest_halving() {
+    let server = TestServer::spawn_with_db_args("--chain regtest").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    for (height, subsidy) in [(149, 5_000_000_000i64), (150, 2_500_000_000)] {
+        sqlx::query(
+            "
+            INSERT INTO blocks (blockheight, blockhash, confirmed, workername, username, diff, coinbasevalue)
+            VALUES ($1, $2, TRUE, 'rig', 'finder', 1.0, $3)
+            ",
+        )
+        .bind(height)
+        .bind(format!("{height:064x}"))
+        .bind(subsidy + 12_345)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    for height in [149, 150] {
+        let split: serde_json::Value = server.get_json_async(format!("/split/{height}")).await;
+        assert_eq!(split["block_height"], height);
+        assert_eq!(split["total_payment_amount"], 12_345);
+        assert_eq!(split["explorer_url"], serde_json::Value::Null);
+    }
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_sat_split_links_block_explorer_for_chain() {
+    let server = TestServer::spawn_with_db_args("--chain signet").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let height = 800000;
+    let hash = format!("{height:064x}");
+
+    sqlx::query(
+        "
+        INSERT INTO blocks (blockheight, blockhash, confirmed, workername, username, diff, coinbasevalue)
+        VALUES ($1, $2, TRUE, 'rig', 'finder', 1.0, 625012345)
+        ",
+    )
+    .bind(height)
+    .bind(&hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let split: serde_json::Value = server.get_json_async(format!("/split/{height}")).await;
+    assert_eq!(
+        split["explorer_url"],
+        format!("https://mempool.space/signet/block/{hash}")
+    );
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_open_split_range_returns_one_split_per_open_block() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    for (height, miners) in [
+        (800099, 0),
+        (800100, 2),
+        (800101, 3),
+        (800102, 1),
+        (800103, 1),
+    ] {
+        insert_test_block(db_url.clone(), height).await.unwrap();
+        insert_test_remote_shares(db_url.clone(), miners, height)
+            .await
+            .unwrap();
+    }
+
+    sqlx::query("UPDATE blocks SET rewards_processed = TRUE WHERE blockheight IN (800099, 800103)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Each block carries 12,345 sats of fees on top of the 6.25 BTC subsidy.
+    sqlx::query("UPDATE blocks SET coinbasevalue = 625012345")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let recipients = |splits: &serde_json::Value| {
+        splits
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|split| {
+                (
+                    split["block_height"].as_i64().unwrap(),
+                    split["payments"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|payment| payment["lightning_address"].as_str().unwrap().to_string())
+                        .collect::<BTreeSet<String>>(),
+                )
+            })
+            .collect::<Vec<(i64, BTreeSet<String>)>>()
+    };
+
+    let lnurls = |count: u32| {
+        (0..count)
+            .map(|i| format!("lnurl{i}@test.gov"))
+            .collect::<BTreeSet<String>>()
+    };
+
+    let splits: serde_json::Value = server
+        .get_json_async("/split/open/range/800090/800110")
+        .await;
+
+    assert_eq!(
+        recipients(&splits),
+        vec![
+            (800100, lnurls(2)),
+            (800101, lnurls(3)),
+            (800102, lnurls(1))
+        ]
+    );
+
+    for split in splits.as_array().unwrap() {
+        assert_eq!(split["total_payment_amount"], 12_345);
+    }
+
+    for split in splits.as_array().unwrap() {
+        let single: serde_json::Value = server
+            .get_json_async(format!("/split/{}", split["block_height"]))
+            .await;
+        assert_eq!(&single, split);
+    }
+
+    let splits: serde_json::Value = server
+        .get_json_async("/split/open/range/800090/800110?limit=2")
+        .await;
+
+    assert_eq!(
+        recipients(&splits),
+        vec![(800100, lnurls(2)), (800101, lnurls(3))]
+    );
+
+    let splits: serde_json::Value = server
+        .get_json_async("/split/open/range/800090/800110?limit=2&offset=2")
+        .await;
+
+    assert_eq!(recipients(&splits), vec![(800102, lnurls(1))]);
+
+    let splits: serde_json::Value = server
+        .get_json_async("/split/open/range/800101/800101")
+        .await;
+
+    assert_eq!(recipients(&splits), vec![(800101, lnurls(3))]);
+
+    pool.close().await;
+}
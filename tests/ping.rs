@@ -79,3 +79,53 @@ fn ping_ckpool() {
         Some(0)
     );
 }
+
+#[test]
+#[timeout(30000)]
+fn ping_json_unreachable_exits_nonzero() {
+    let output = CommandBuilder::new(format!(
+        "ping --count 2 --timeout 1 --json 127.0.0.1:{}",
+        allocate_port()
+    ))
+    .capture_stdout(true)
+    .spawn()
+    .wait_with_output()
+    .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+
+    let summary = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["sent"], 2);
+    assert_eq!(summary["received"], 0);
+    assert_eq!(summary["attempts"].as_array().unwrap().len(), 2);
+    assert_eq!(summary["attempts"][0]["success"], false);
+}
+
+#[test]
+#[timeout(90000)]
+fn ping_json_healthy_pool_exits_zero() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(&bitcoind, "");
+
+    let output = CommandBuilder::new(format!(
+        "ping --count 2 --timeout 10 --json --max-loss 0 {}",
+        pool.stratum_endpoint()
+    ))
+    .capture_stdout(true)
+    .spawn()
+    .wait_with_output()
+    .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+
+    let summary = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["received"], 2);
+    assert_eq!(summary["loss"], 0.0);
+    assert!(summary["attempts"][0]["latency_ms"].as_f64().unwrap() > 0.0);
+}
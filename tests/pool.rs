@@ -1,4 +1,10 @@
-use super::*;
+use {
+    super::*,
+    futures::StreamExt,
+    tokio_tungstenite::tungstenite::{
+        self, client::IntoClientRequest, http::header::AUTHORIZATION,
+    },
+};
 
 fn authorize_raw(endpoint: &str, username: &str) -> Option<stratum::Message> {
     let mut stream = TcpStream::connect(endpoint).unwrap();
@@ -56,13 +62,95 @@ fn mine_to_pool() {
     assert_eq!(output.len(), 1);
 }
 
+/// Minimal no-auth SOCKS5 proxy that tunnels each connection to the address
+/// the client asks for, counting the tunnels it opens.
+fn spawn_socks5_proxy() -> (String, Arc<AtomicUsize>) {
+    use std::io::Read;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let tunnels = Arc::new(AtomicUsize::new(0));
+
+    let counter = tunnels.clone();
+    thread::spawn(move || {
+        for mut client in listener.incoming().map_while(Result::ok) {
+            let mut greeting = [0u8; 3];
+            client.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            client.write_all(&[5, 0]).unwrap();
+
+            let mut request = [0u8; 4];
+            client.read_exact(&mut request).unwrap();
+            assert_eq!(request[..3], [5, 1, 0]);
+
+            let host = match request[3] {
+                1 => {
+                    let mut ip = [0u8; 4];
+                    client.read_exact(&mut ip).unwrap();
+                    std::net::Ipv4Addr::from(ip).to_string()
+                }
+                3 => {
+                    let mut len = [0u8; 1];
+                    client.read_exact(&mut len).unwrap();
+                    let mut host = vec![0u8; len[0].into()];
+                    client.read_exact(&mut host).unwrap();
+                    String::from_utf8(host).unwrap()
+                }
+                atyp => panic!("unexpected address type {atyp}"),
+            };
+
+            let mut port = [0u8; 2];
+            client.read_exact(&mut port).unwrap();
+
+            let upstream = TcpStream::connect((host, u16::from_be_bytes(port))).unwrap();
+            client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+
+            for (mut from, mut to) in [
+                (client.try_clone().unwrap(), upstream.try_clone().unwrap()),
+                (upstream, client),
+            ] {
+                thread::spawn(move || {
+                    let _ = std::io::copy(&mut from, &mut to);
+                    let _ = to.shutdown(std::net::Shutdown::Write);
+                });
+            }
+        }
+    });
+
+    (address, tunnels)
+}
+
+#[test]
+#[timeout(90000)]
+fn mine_to_pool_through_socks5_proxy() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    let (proxy, tunnels) = spawn_socks5_proxy();
+
+    let miner = CommandBuilder::new(format!(
+        "miner --mode share-found --username {} {} --cpu-cores 1 --proxy socks5://{proxy}",
+        signet_username(),
+        pool.stratum_endpoint(),
+    ))
+    .spawn();
+
+    let stdout = miner.wait_with_output().unwrap();
+    let output =
+        serde_json::from_str::<Vec<Share>>(&String::from_utf8_lossy(&stdout.stdout)).unwrap();
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(tunnels.load(Ordering::SeqCst), 1);
+}
+
 #[tokio::test]
 #[timeout(120000)]
 async fn stratum_state_machine() {
     let bitcoind = bitcoind();
     let pool = TestPool::spawn_with_args(
         &bitcoind,
-        "--start-diff 0.00001 --disable-bouncer --update-interval 120",
+        "--start-diff 0.00001 --disable-bouncer --update-interval 120 --stale-template-threshold 300",
     );
 
     // State::Init
@@ -218,8 +306,8 @@ async fn stratum_state_machine() {
             .await
             .unwrap();
 
-        // authorize in Working -> MethodNotAllowed
-        assert_stratum_error(client.authorize().await, StratumError::MethodNotAllowed);
+        // authorize again in Working -> allowed
+        client.authorize().await.unwrap();
 
         // submit in Working -> allowed
         let enonce2 = Extranonce::random(enonce2_size);
@@ -271,6 +359,11 @@ async fn stratum_state_machine() {
         let (subscribe_result, _, _) = client.subscribe().await.unwrap();
         let enonce1 = subscribe_result.enonce1.clone();
 
+        // The subscription id is the session id to resume with
+        for (_, subscription_id) in &subscribe_result.subscriptions {
+            assert_eq!(*subscription_id, enonce1.to_string());
+        }
+
         client.authorize().await.unwrap();
 
         // Must submit a share for session to be stored (requires authorization)
@@ -530,6 +623,36 @@ async fn suggest_difficulty_request_gets_response_and_sets_difficulty() {
     .expect("Timeout waiting for suggested set_difficulty");
 }
 
+#[tokio::test]
+#[timeout(120000)]
+async fn suggest_difficulty_notification_sets_difficulty() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+    client
+        .notify_suggest_difficulty(Difficulty::from(1000))
+        .await
+        .unwrap();
+
+    timeout(Duration::from_secs(10), async {
+        loop {
+            match events.recv().await.unwrap() {
+                stratum::client::Event::SetDifficulty(diff) if diff == Difficulty::from(1000) => {
+                    break;
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for suggested set_difficulty");
+}
+
 #[tokio::test]
 #[timeout(120000)]
 async fn suggest_difficulty_repeat_within_period_is_ignored() {
@@ -814,6 +937,26 @@ async fn new_job_shares_rejected_at_old_diff() {
     assert!(got_above_target);
 }
 
+#[tokio::test]
+#[timeout(90000)]
+async fn bitcoin_status_links_tip_to_configured_explorer() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--explorer-url http://explorer.local/block/{height}",
+    );
+
+    let bitcoin_status = pool.get_bitcoin_status().await.unwrap();
+
+    assert_eq!(
+        bitcoin_status.explorer_url,
+        Some(format!(
+            "http://explorer.local/block/{}",
+            bitcoin_status.height
+        ))
+    );
+}
+
 #[tokio::test]
 #[timeout(90000)]
 async fn share_validation() {
@@ -843,6 +986,7 @@ async fn share_validation() {
 
     let bitcoin_status = pool.get_bitcoin_status().await.unwrap();
     assert!(bitcoin_status.network_difficulty.as_f64() > 0.0);
+    assert_eq!(bitcoin_status.explorer_url, None);
 
     let client = pool.stratum_client().await;
     let mut events = client.connect().await.unwrap();
@@ -1064,6 +1208,19 @@ async fn share_validation() {
     let status = pool.get_status().await.unwrap();
     assert_eq!(status.downstream.stats.accepted_shares, 1);
     assert_eq!(status.downstream.stats.rejected_shares, 9);
+    assert_eq!(
+        status.rejects_by_reason,
+        [
+            (StratumError::InvalidNonce2Length, 2),
+            (StratumError::WorkerMismatch, 1),
+            (StratumError::Stale, 1),
+            (StratumError::NtimeOutOfRange, 2),
+            (StratumError::Duplicate, 1),
+            (StratumError::AboveTarget, 1),
+            (StratumError::InvalidVersionMask, 1),
+        ]
+        .into()
+    );
 
     // Stale after new block (submitted via pre-mined block to bitcoind)
     let old_job_id = notify.job_id;
@@ -1196,7 +1353,10 @@ async fn share_validation() {
 #[timeout(90000)]
 async fn bouncer() {
     let bitcoind = bitcoind();
-    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --update-interval 120");
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--start-diff 0.00001 --update-interval 120 --stale-template-threshold 300",
+    );
 
     let auth_timeout_test = async {
         let client = pool.stratum_client().await;
@@ -1516,7 +1676,7 @@ async fn high_diff_port() {
     let pool = TestPool::spawn_with_args(
         &bitcoind,
         format!(
-            "--start-diff 0.00001 --disable-bouncer --update-interval 120 --high-diff-port {high_diff_port}",
+            "--start-diff 0.00001 --disable-bouncer --update-interval 120 --stale-template-threshold 300 --high-diff-port {high_diff_port}",
         ),
     );
 
@@ -1548,7 +1708,10 @@ async fn high_diff_port() {
 #[timeout(90000)]
 async fn idle_drop_retires_session() {
     let bitcoind = bitcoind();
-    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --update-interval 120");
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--start-diff 0.00001 --update-interval 120 --stale-template-threshold 300",
+    );
 
     let client = pool.stratum_client().await;
     let mut events = client.connect().await.unwrap();
@@ -1583,7 +1746,7 @@ async fn pool_persists_stats_across_restart() {
     let bitcoind = bitcoind();
     let pool = TestPool::spawn_with_args(
         &bitcoind,
-        "--start-diff 0.00001 --disable-bouncer --update-interval 120",
+        "--start-diff 0.00001 --disable-bouncer --update-interval 120 --stale-template-threshold 300",
     );
 
     let username = signet_username();
@@ -1623,7 +1786,7 @@ async fn pool_persists_stats_across_restart() {
 
     let pool = pool.restart(
         &bitcoind,
-        "--start-diff 0.00001 --disable-bouncer --update-interval 120",
+        "--start-diff 0.00001 --disable-bouncer --update-interval 120 --stale-template-threshold 300",
     );
 
     let status = pool.get_status().await.unwrap();
@@ -1655,3 +1818,553 @@ async fn pool_persists_stats_across_restart() {
         "worker stats should survive restart"
     );
 }
+
+#[tokio::test]
+#[timeout(120000)]
+async fn multiple_workers_per_connection() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--start-diff 0.00001 --disable-bouncer --update-interval 120 --stale-template-threshold 300",
+    );
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    let enonce1 = subscribe.enonce1;
+
+    client.authorize().await.unwrap();
+
+    let (notify, difficulty) = wait_for_notify(&mut events).await;
+
+    let second: Username = "tb1qft5p2uhsdcdc3l2ua4ap5qqfg4pjaqlp250x7us7a8qqhrxrxfsqaqh7jw.tock"
+        .parse()
+        .unwrap();
+
+    client
+        .authorize_with_username(second.clone())
+        .await
+        .unwrap();
+
+    assert_stratum_error(
+        client
+            .authorize_with_username(
+                "tb1qkrrl75qekv9ree0g2qt49j8vdynsvlc4kuctrc.tock"
+                    .parse()
+                    .unwrap(),
+            )
+            .await,
+        StratumError::Unauthorized,
+    );
+
+    for username in [signet_username(), second] {
+        let enonce2 = Extranonce::random(subscribe.enonce2_size);
+        let (ntime, nonce) = solve_share(&notify, &enonce1, &enonce2, difficulty);
+        client
+            .submit_with_username(username, notify.job_id, enonce2, ntime, nonce, None)
+            .await
+            .unwrap();
+    }
+
+    let user = pool
+        .get_user(
+            &signet_username()
+                .address()
+                .clone()
+                .assume_checked()
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    let mut workers = user
+        .workers
+        .iter()
+        .map(|worker| (worker.name.as_str(), worker.stats.accepted_shares))
+        .collect::<Vec<(&str, u64)>>();
+    workers.sort();
+
+    assert_eq!(
+        workers,
+        vec![(signet_username().workername(), 1), ("tock", 1)]
+    );
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn admin_shutdown_drains_connections() {
+    let bitcoind = bitcoind();
+    let mut pool =
+        TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --http-admin-token admin");
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    wait_for_notify(&mut events).await;
+
+    let http = reqwest::Client::new();
+    let shutdown_url = format!("{}/admin/shutdown", pool.api_endpoint());
+
+    assert_eq!(
+        http.post(&shutdown_url).send().await.unwrap().status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    let response = http
+        .post(&shutdown_url)
+        .bearer_auth("admin")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.json::<api::Shutdown>().await.unwrap(),
+        api::Shutdown {
+            draining_connections: 1,
+        }
+    );
+
+    timeout(Duration::from_secs(20), async {
+        loop {
+            match events.recv().await {
+                Ok(stratum::client::Event::Disconnected) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    })
+    .await
+    .expect("Connection was not drained");
+
+    timeout(Duration::from_secs(20), async {
+        while pool.try_wait().unwrap().is_none() {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("Pool did not exit");
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn admin_maintenance_toggles_status() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--start-diff 0.00001 --http-admin-token admin --maintenance reject",
+    );
+
+    assert_eq!(
+        pool.get_status().await.unwrap().maintenance,
+        Some(api::MaintenanceMode::Reject)
+    );
+
+    let http = reqwest::Client::new();
+    let maintenance_url = format!("{}/admin/maintenance", pool.api_endpoint());
+
+    assert_eq!(
+        http.post(&maintenance_url)
+            .json(&api::Maintenance { mode: None })
+            .send()
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    let response = http
+        .post(&maintenance_url)
+        .bearer_auth("admin")
+        .json(&api::Maintenance { mode: None })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(pool.get_status().await.unwrap().maintenance, None);
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn shutdown_snapshot_records_connected_miner() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshot = dir.path().join("snapshot.json");
+
+    let bitcoind = bitcoind();
+    let mut pool = TestPool::spawn_with_args(
+        &bitcoind,
+        format!(
+            "--start-diff 0.00001 --http-admin-token admin --shutdown-snapshot {}",
+            snapshot.display()
+        ),
+    );
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let (notify, difficulty) = wait_for_notify(&mut events).await;
+
+    let enonce2 = Extranonce::random(subscribe.enonce2_size);
+    let (ntime, nonce) = solve_share(&notify, &subscribe.enonce1, &enonce2, difficulty);
+    client
+        .submit(notify.job_id, enonce2, ntime, nonce, None)
+        .await
+        .unwrap();
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/admin/shutdown", pool.api_endpoint()))
+        .bearer_auth("admin")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    timeout(Duration::from_secs(20), async {
+        while pool.try_wait().unwrap().is_none() {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("Pool did not exit");
+
+    let snapshot =
+        serde_json::from_str::<api::PoolSnapshot>(&fs::read_to_string(&snapshot).unwrap()).unwrap();
+
+    let username = signet_username();
+
+    assert_eq!(snapshot.connections.len(), 1);
+    assert_eq!(snapshot.downstream.stats.accepted_shares, 1);
+    assert_eq!(snapshot.users.len(), 1);
+    assert_eq!(
+        snapshot.users[0]
+            .address
+            .clone()
+            .assume_checked()
+            .to_string(),
+        username.address().clone().assume_checked().to_string()
+    );
+    assert_eq!(snapshot.users[0].workers.len(), 1);
+    assert_eq!(snapshot.users[0].workers[0].stats.accepted_shares, 1);
+    assert!(snapshot.users[0].workers[0].stats.best_share.is_some());
+    assert!(snapshot.blocks.is_empty());
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn address_allowlist() {
+    let allowed = signet_username();
+    let other = "tb1q6en7qjxgw4ev8xwx94pzdry6a6ky7wlfeqzunz.rig";
+
+    let dir = tempfile::tempdir().unwrap();
+    let allowlist = dir.path().join("allowlist.txt");
+    fs::write(
+        &allowlist,
+        format!("# farm\n{}\n", allowed.address().clone().assume_checked()),
+    )
+    .unwrap();
+
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        format!("--address-allowlist {}", allowlist.display()),
+    );
+
+    let client = pool.stratum_client_for_username(&allowed.to_string()).await;
+    client.connect().await.unwrap();
+    client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let client = pool.stratum_client_for_username(other).await;
+    client.connect().await.unwrap();
+    client.subscribe().await.unwrap();
+
+    let err = client.authorize().await.unwrap_err().to_string();
+    assert!(err.contains("allowlist"), "{err}");
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn status_reports_network_context() {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    let height = pool.get_block_height().await;
+
+    let network = pool.get_status().await.unwrap().network.unwrap();
+    assert_eq!(network.height as u64, height + 1);
+    assert!(network.network_difficulty.as_f64() > 0.0);
+    assert_eq!(network.expected_block_time_secs, None);
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let (notify, difficulty) = wait_for_notify(&mut events).await;
+
+    let enonce2 = Extranonce::random(subscribe.enonce2_size);
+    let (ntime, nonce) = solve_share(&notify, &subscribe.enonce1, &enonce2, difficulty);
+    client
+        .submit(notify.job_id, enonce2, ntime, nonce, None)
+        .await
+        .unwrap();
+
+    let network = pool.get_status().await.unwrap().network.unwrap();
+    assert!(network.expected_block_time_secs.is_some());
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn connections_list_connected_miners() {
+    let bitcoind = bitcoind();
+    let pool =
+        TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --http-admin-token admin");
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    wait_for_notify(&mut events).await;
+
+    let http = reqwest::Client::new();
+    let connections_url = format!("{}/api/connections", pool.api_endpoint());
+
+    assert_eq!(
+        http.get(&connections_url).send().await.unwrap().status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    assert_eq!(
+        http.get(&connections_url)
+            .basic_auth("operator", Some("wrong"))
+            .send()
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    assert_eq!(
+        http.get(&connections_url)
+            .basic_auth("operator", Some("admin"))
+            .send()
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::OK,
+    );
+
+    let connections = http
+        .get(&connections_url)
+        .bearer_auth("admin")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<api::ConnectionDetail>>()
+        .await
+        .unwrap();
+
+    assert_eq!(connections.len(), 1);
+
+    let connection = &connections[0];
+    assert_eq!(connection.user_agent.as_deref(), Some(USER_AGENT));
+    assert_eq!(
+        connection.address.as_ref(),
+        Some(signet_username().address())
+    );
+    assert_eq!(connection.difficulty, Difficulty::from(0.00001));
+    assert!(connection.socket_addr.ip().is_loopback());
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn disconnects_count_reasons_per_worker() {
+    let bitcoind = bitcoind();
+    let pool =
+        TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --http-admin-token admin");
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    wait_for_notify(&mut events).await;
+
+    client.disconnect().await;
+
+    let http = reqwest::Client::new();
+    let disconnects_url = format!("{}/api/disconnects", pool.api_endpoint());
+
+    assert_eq!(
+        http.get(&disconnects_url).send().await.unwrap().status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    let disconnects = timeout(Duration::from_secs(10), async {
+        loop {
+            let disconnects = http
+                .get(&disconnects_url)
+                .bearer_auth("admin")
+                .send()
+                .await
+                .unwrap()
+                .json::<api::Disconnects>()
+                .await
+                .unwrap();
+
+            if !disconnects.reasons.is_empty() {
+                break disconnects;
+            }
+
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+        disconnects.reasons,
+        [(api::DisconnectReason::ClientEof, 1)].into()
+    );
+
+    assert_eq!(disconnects.workers.len(), 1);
+    assert_eq!(&disconnects.workers[0].address, signet_username().address());
+    assert_eq!(
+        disconnects.workers[0].worker_name,
+        signet_username().workername()
+    );
+}
+
+async fn submit_share_across_difficulty_change(args: &str) -> Result<Duration, ClientError> {
+    let bitcoind = bitcoind();
+    let pool = TestPool::spawn_with_args(&bitcoind, args);
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let (notify, difficulty) = wait_for_notify(&mut events).await;
+
+    client
+        .suggest_difficulty(Difficulty::from(0.001))
+        .await
+        .unwrap();
+
+    let enonce2 = Extranonce::random(subscribe.enonce2_size);
+    let (ntime, nonce) = solve_share(&notify, &subscribe.enonce1, &enonce2, difficulty);
+
+    client
+        .submit(notify.job_id, enonce2, ntime, nonce, None)
+        .await
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn lenient_difficulty_transition_accepts_share_at_old_difficulty() {
+    submit_share_across_difficulty_change("--start-diff 0.00001")
+        .await
+        .unwrap();
+
+    submit_share_across_difficulty_change("--start-diff 0.00001 --difficulty-transition lenient")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn strict_difficulty_transition_rejects_share_at_old_difficulty() {
+    assert_stratum_error(
+        submit_share_across_difficulty_change(
+            "--start-diff 0.00001 --difficulty-transition strict",
+        )
+        .await,
+        StratumError::StaleDifficulty,
+    );
+}
+
+#[tokio::test]
+#[timeout(90000)]
+async fn shares_websocket_streams_share_results() {
+    let bitcoind = bitcoind();
+    let pool =
+        TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --http-admin-token admin");
+
+    let url = format!("{}/api/ws/shares", pool.api_endpoint()).replacen("http", "ws", 1);
+
+    assert!(matches!(
+        tokio_tungstenite::connect_async(url.as_str()).await,
+        Err(tungstenite::Error::Http(response)) if response.status() == StatusCode::UNAUTHORIZED
+    ));
+
+    let mut request = url.into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert(AUTHORIZATION, "Bearer admin".parse().unwrap());
+
+    let (mut feed, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    let client = pool.stratum_client().await;
+    let mut events = client.connect().await.unwrap();
+
+    let (subscribe, _, _) = client.subscribe().await.unwrap();
+    client.authorize().await.unwrap();
+
+    let (notify, difficulty) = wait_for_notify(&mut events).await;
+
+    let enonce2 = Extranonce::random(subscribe.enonce2_size);
+    let (ntime, nonce) = solve_share(&notify, &subscribe.enonce1, &enonce2, difficulty);
+
+    client
+        .submit(notify.job_id, enonce2.clone(), ntime, nonce, None)
+        .await
+        .unwrap();
+
+    assert_stratum_error(
+        client
+            .submit(notify.job_id, enonce2, ntime, nonce, None)
+            .await,
+        StratumError::Duplicate,
+    );
+
+    let mut next_share = async || loop {
+        let message = timeout(Duration::from_secs(10), feed.next())
+            .await
+            .expect("no share event")
+            .unwrap()
+            .unwrap();
+
+        if let tungstenite::Message::Text(text) = message {
+            return serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        }
+    };
+
+    let accepted = next_share().await;
+    assert_eq!(accepted["type"], "share");
+    assert_eq!(accepted["result"], true);
+    assert_eq!(accepted["workername"], signet_username().workername());
+    assert_eq!(accepted["pool_diff"], difficulty.as_f64());
+    assert_eq!(accepted["reject_reason"], serde_json::Value::Null);
+    assert!(accepted["timestamp"].is_i64());
+
+    let rejected = next_share().await;
+    assert_eq!(rejected["result"], false);
+    assert_eq!(rejected["workername"], signet_username().workername());
+    assert_eq!(
+        rejected["reject_reason"],
+        StratumError::Duplicate.to_string()
+    );
+}
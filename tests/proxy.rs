@@ -448,7 +448,10 @@ async fn proxy_relays_job_updates_and_new_blocks() {
 #[timeout(120000)]
 async fn reconnects_on_upstream_disconnect() {
     let bitcoind = bitcoind();
-    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001 --update-interval 120");
+    let pool = TestPool::spawn_with_args(
+        &bitcoind,
+        "--start-diff 0.00001 --update-interval 120 --stale-template-threshold 300",
+    );
     let pool_port = pool.pool_port();
 
     let proxy = TestProxy::spawn_with_args(
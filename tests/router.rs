@@ -1,60 +1,5 @@
 use super::*;
 
-fn generate_descriptor() -> String {
-    CommandBuilder::new("wallet --chain regtest generate")
-        .run_and_deserialize_output::<para::subcommand::wallet::generate::Output>()
-        .descriptor
-}
-
-async fn fund_wallet(bitcoind: &Bitcoind, descriptor: &str) -> String {
-    let directory = TempDir::new().unwrap();
-    let data_dir = directory.path().to_str().unwrap();
-
-    let address = CommandBuilder::new(format!(
-        "wallet \
-         --chain regtest \
-         --bitcoin-rpc-port {} \
-         --bitcoin-rpc-username {} \
-         --bitcoin-rpc-password {} \
-         --data-dir {data_dir} \
-         --descriptor {descriptor} \
-         receive",
-        bitcoind.rpc_port, bitcoind.rpc_user, bitcoind.rpc_password,
-    ))
-    .run_and_deserialize_output::<para::subcommand::wallet::receive::Output>()
-    .address
-    .assume_checked()
-    .to_string();
-
-    generate_to_address(bitcoind, 101, &address).await;
-
-    address
-}
-
-async fn send_to_address_without_mining(
-    bitcoind: &Bitcoind,
-    funding_descriptor: &str,
-    address: &str,
-    amount: u64,
-) -> bitcoin::Txid {
-    let directory = TempDir::new().unwrap();
-    let data_dir = directory.path().to_str().unwrap();
-
-    CommandBuilder::new(format!(
-        "wallet \
-         --chain regtest \
-         --bitcoin-rpc-port {} \
-         --bitcoin-rpc-username {} \
-         --bitcoin-rpc-password {} \
-         --data-dir {data_dir} \
-         --descriptor {funding_descriptor} \
-         send --fee-rate 1 --address {address} --amount {amount}",
-        bitcoind.rpc_port, bitcoind.rpc_user, bitcoind.rpc_password,
-    ))
-    .run_and_deserialize_output::<para::subcommand::wallet::send::Output>()
-    .txid
-}
-
 async fn pay_address(bitcoind: &Bitcoind, funding_descriptor: &str, address: &str, amount: u64) {
     send_to_address_without_mining(bitcoind, funding_descriptor, address, amount).await;
 
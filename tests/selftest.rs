@@ -0,0 +1,58 @@
+use super::*;
+
+#[test]
+#[timeout(90000)]
+fn selftest_passes_against_pool() {
+    let bitcoind = spawn_regtest();
+    let pool = TestPool::spawn_with_args(&bitcoind, "--start-diff 0.00001");
+
+    let output = CommandBuilder::new(format!(
+        "selftest --username bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080.selftest {}",
+        pool.stratum_endpoint()
+    ))
+    .capture_stdout(true)
+    .spawn()
+    .wait_with_output()
+    .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+
+    for step in [
+        "connect",
+        "configure",
+        "subscribe",
+        "authorize",
+        "notify",
+        "hash",
+        "submit",
+    ] {
+        assert!(
+            stdout
+                .lines()
+                .any(|line| line.starts_with(step) && line.contains(" ok ")),
+            "{step} did not pass:\n{stdout}"
+        );
+    }
+
+    assert!(stdout.contains("PASS"), "{stdout}");
+}
+
+#[test]
+#[timeout(30000)]
+fn selftest_fails_without_pool() {
+    let output = CommandBuilder::new(format!(
+        "selftest --timeout 5 --username bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080.selftest 127.0.0.1:{}",
+        allocate_port()
+    ))
+    .capture_stdout(true)
+    .spawn()
+    .wait_with_output()
+    .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert!(stdout.contains("connect    FAIL"), "{stdout}");
+}
@@ -454,6 +454,29 @@ fn status_json() {
     assert!(status.disk_usage_percent > 0.0);
 }
 
+#[test]
+fn status_json_includes_per_disk_usage() {
+    let server = TestServer::spawn();
+
+    let status = server.get_json::<serde_json::Value>("/status", None);
+
+    assert!(status["disk_usage_percent"].is_f64());
+    assert!(status["swap_usage_percent"].is_f64());
+    assert!(status["load_average"]["one"].is_f64());
+
+    let disks = status["disks"].as_array().unwrap();
+    assert!(!disks.is_empty());
+
+    for disk in disks {
+        assert!(disk["mount_point"].is_string());
+        assert!(disk["total_bytes"].is_u64());
+        assert!(disk["available_bytes"].is_u64());
+
+        let usage_percent = disk["usage_percent"].as_f64().unwrap();
+        assert!((0.0..=100.0).contains(&usage_percent));
+    }
+}
+
 #[test]
 fn status_with_auth() {
     let server = TestServer::spawn_with_args("--admin-token verysecrettoken");
@@ -603,6 +626,15 @@ fn aggregator_negative_cache_on_users() {
     pretty_assert_eq!(response, typical_user());
 }
 
+#[test]
+fn aggregate_blocks_unavailable_without_node_blocks() {
+    let server = TestServer::spawn();
+
+    let aggregator = TestServer::spawn_with_args(format!("--nodes {}", server.url()));
+
+    aggregator.assert_response_code("/aggregator/blocks", StatusCode::NOT_FOUND);
+}
+
 #[test]
 #[ignore]
 #[serial(heavy)]
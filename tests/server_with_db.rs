@@ -492,6 +492,133 @@ async fn test_payouts_range_url_encoded_exclusions() {
     );
 }
 
+async fn set_block_time_found(
+    database_url: String,
+    blockheight: i64,
+    time_found: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+
+    sqlx::query("UPDATE blocks SET time_found = $2::timestamptz WHERE blockheight = $1")
+        .bind(blockheight as i32)
+        .bind(time_found)
+        .execute(&pool)
+        .await?;
+
+    pool.close().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_payouts_dates() {
+    let server = TestServer::spawn_with_db().await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let miners = [
+        (
+            1100,
+            "1CPDJtMzuSyvnGi8o9ZAtAWPfqHZhjQQhB",
+            "2024-01-01T12:00:00Z",
+        ),
+        (
+            1101,
+            "3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX",
+            "2024-01-02T12:00:00Z",
+        ),
+        (
+            1102,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "2024-01-03T12:00:00Z",
+        ),
+        (
+            1103,
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            "2024-01-04T12:00:00Z",
+        ),
+    ];
+
+    for (block_height, username, time_found) in miners {
+        insert_test_shares_with_users(
+            server.database_url().unwrap(),
+            vec![(username.to_string(), 100.0)],
+            block_height,
+        )
+        .await
+        .unwrap();
+        insert_test_block(server.database_url().unwrap(), block_height)
+            .await
+            .unwrap();
+        set_block_time_found(server.database_url().unwrap(), block_height, time_found)
+            .await
+            .unwrap();
+    }
+
+    let payees = |payouts: Vec<Payout>| {
+        let mut payees = payouts
+            .into_iter()
+            .map(|payout| payout.btcaddress.unwrap())
+            .collect::<Vec<String>>();
+        payees.sort();
+        payees
+    };
+
+    let payouts: Vec<Payout> = server
+        .get_json_async("/payouts/dates/2024-01-02/2024-01-03")
+        .await;
+
+    assert_eq!(
+        payees(payouts),
+        vec![
+            "3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX".to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+        ]
+    );
+
+    // 2024-01-03T00:00:00Z up to 2024-01-10T00:00:00Z, reaching past the last block
+    let payouts: Vec<Payout> = server
+        .get_json_async("/payouts/dates/1704240000/1704844800")
+        .await;
+
+    assert_eq!(
+        payees(payouts),
+        vec![
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+        ]
+    );
+
+    let payouts: Vec<Payout> = server
+        .get_json_async(
+            "/payouts/dates/2024-01-02/2024-01-03?excluded=3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX",
+        )
+        .await;
+
+    assert_eq!(
+        payees(payouts),
+        vec!["bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string()]
+    );
+
+    let payouts: Vec<Payout> = server
+        .get_json_async("/payouts/dates/2025-01-01/2025-01-31")
+        .await;
+
+    assert!(payouts.is_empty());
+
+    let response = server
+        .get_json_async_raw("/payouts/dates/2024-01-03/2024-01-02")
+        .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .get_json_async_raw("/payouts/dates/yesterday/today")
+        .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_invalid() {
     let server = TestServer::spawn_with_db_args("--admin-token verysecrettoken").await;
@@ -539,6 +666,48 @@ async fn test_valid_auth() {
     assert!(res.status().is_success());
 }
 
+#[tokio::test]
+async fn test_share_by_id() {
+    let mut server = TestServer::spawn_with_db_args("--admin-token verysecrettoken").await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    insert_test_remote_shares(server.database_url().unwrap(), 1, 100)
+        .await
+        .unwrap();
+
+    let res = server.get_json_async_raw("/api/shares/test_origin/1").await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    server.admin_token = Some("verysecrettoken".into());
+
+    let res = server.get_json_async_raw("/api/shares/test_origin/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let share: RemoteShare = res.json().await.unwrap();
+
+    assert_eq!(share.id, 1);
+    assert_eq!(share.origin, "test_origin");
+    assert_eq!(share.blockheight, Some(100));
+    assert_eq!(share.diff, Some(1000.0));
+    assert_eq!(share.sdiff, Some(500.0));
+    assert_eq!(share.result, Some(true));
+    assert_eq!(share.reject_reason, None);
+    assert_eq!(share.workername, Some("worker_0".into()));
+    assert_eq!(share.username, Some("user_0".into()));
+
+    let res = server
+        .get_json_async_raw("/api/shares/test_origin/999")
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = server
+        .get_json_async_raw("/api/shares/other_origin/1")
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_highestdiff_basic() {
     let server = TestServer::spawn_with_db().await;
@@ -702,6 +871,86 @@ async fn test_highestdiff_all_users_empty() {
     assert!(highestdiffs.is_empty());
 }
 
+#[tokio::test]
+async fn test_leaderboard() {
+    let server = TestServer::spawn_with_db().await;
+    let database_url = server.database_url().unwrap();
+    setup_test_schema(database_url.clone()).await.unwrap();
+
+    let users = |users: &[(&str, f64)]| {
+        users
+            .iter()
+            .map(|(username, diff)| (username.to_string(), *diff))
+            .collect::<Vec<_>>()
+    };
+
+    insert_test_shares_with_users(
+        database_url.clone(),
+        users(&[("alice", 1000.0), ("bob", 3000.0), ("carol", 2000.0)]),
+        100,
+    )
+    .await
+    .unwrap();
+
+    insert_test_shares_with_users(database_url.clone(), users(&[("alice", 2500.0)]), 99)
+        .await
+        .unwrap();
+
+    insert_test_shares_with_users(database_url, users(&[("dave", 10000.0)]), 50)
+        .await
+        .unwrap();
+
+    #[track_caller]
+    fn ranking(contributors: Vec<Contributor>) -> Vec<(String, f64)> {
+        contributors
+            .into_iter()
+            .map(|contributor| (contributor.username, contributor.total_diff))
+            .collect()
+    }
+
+    assert_eq!(
+        ranking(server.get_json_async("/api/leaderboard?window=2").await),
+        users(&[("alice", 3500.0), ("bob", 3000.0), ("carol", 2000.0)]),
+    );
+
+    assert_eq!(
+        ranking(server.get_json_async("/api/leaderboard?window=1").await),
+        users(&[("bob", 3000.0), ("carol", 2000.0), ("alice", 1000.0)]),
+    );
+
+    assert_eq!(
+        ranking(
+            server
+                .get_json_async("/api/leaderboard?window=2&limit=2")
+                .await
+        ),
+        users(&[("alice", 3500.0), ("bob", 3000.0)]),
+    );
+
+    assert_eq!(
+        ranking(
+            server
+                .get_json_async("/api/leaderboard?window=2&excluded=alice,carol")
+                .await
+        ),
+        users(&[("bob", 3000.0)]),
+    );
+
+    let contributors: Vec<Contributor> = server.get_json_async("/api/leaderboard").await;
+    assert_eq!(contributors.len(), 4);
+    assert_eq!(contributors[0].username, "dave");
+    assert_eq!(contributors[1].shares, 2);
+    assert_eq!(contributors[1].best_diff, 2500.0);
+
+    assert_eq!(
+        server
+            .get_json_async_raw("/api/leaderboard?window=forever")
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
 #[tokio::test]
 async fn aggregator_blockheight_no_nodes() {
     let server = TestServer::spawn_with_db().await;
@@ -1272,3 +1521,283 @@ async fn auth_tiers_with_db() {
     case(&server, "/payouts", "foo", StatusCode::UNAUTHORIZED).await;
     case(&server, "/payouts", "bar", StatusCode::OK).await;
 }
+
+#[tokio::test]
+async fn test_database_routes_unavailable_until_connected() {
+    let (mut server, builder) = TestServer::spawn_with_pending_db();
+
+    let response = server.get_json_async_raw("/payouts/800000").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.text().await.unwrap(), "database unavailable");
+
+    let health: serde_json::Value = server.get_json_async("/healthcheck").await;
+    assert_eq!(health["database_connected"], false);
+
+    server.set_database(builder.start_async().await);
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let start = Instant::now();
+    loop {
+        let health: serde_json::Value = server.get_json_async("/healthcheck").await;
+        if health["database_connected"] == true {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "server never connected to database"
+        );
+        sleep(Duration::from_millis(250)).await;
+    }
+
+    let payouts: Vec<Payout> = server.get_json_async("/payouts/800000").await;
+    assert!(payouts.is_empty());
+}
+
+#[tokio::test]
+async fn test_database_routes_unavailable_after_disconnect() {
+    let mut server = TestServer::spawn_with_db().await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let health: serde_json::Value = server.get_json_async("/healthcheck").await;
+    assert_eq!(health["database_connected"], true);
+
+    server.stop_database();
+
+    let start = Instant::now();
+    loop {
+        let health: serde_json::Value = server.get_json_async("/healthcheck").await;
+        if health["database_connected"] == false {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "server never noticed the database went away"
+        );
+        sleep(Duration::from_millis(250)).await;
+    }
+
+    let response = server.get_json_async_raw("/payouts/800000").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_read_replica() {
+    let replica = pgtemp::PgTempDB::from_builder(TestServer::pg_temp_db_builder(None));
+    setup_test_schema(replica.connection_uri()).await.unwrap();
+
+    let mut server = TestServer::spawn_with_db_args(format!(
+        "--admin-token verysecrettoken --read-database-url {}",
+        replica.connection_uri()
+    ))
+    .await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    server.admin_token = Some("verysecrettoken".into());
+
+    insert_test_remote_shares(replica.connection_uri(), 1, 100)
+        .await
+        .unwrap();
+
+    let res = server.get_json_async_raw("/api/shares/test_origin/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let share: RemoteShare = res.json().await.unwrap();
+    assert_eq!(share.blockheight, Some(100));
+
+    let address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    insert_test_account(server.database_url().unwrap(), address, None, Vec::new(), 0)
+        .await
+        .unwrap();
+
+    let account: Account = server
+        .post_json(
+            "/payouts/exclude",
+            &serde_json::json!({
+                "btc_address": address,
+                "exclude_from_payout": true,
+            }),
+        )
+        .await;
+
+    assert!(account.exclude_from_payout);
+
+    let replica_accounts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts")
+        .fetch_one(
+            &sqlx::PgPool::connect(&replica.connection_uri())
+                .await
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(replica_accounts, 0);
+
+    insert_test_remote_shares(server.database_url().unwrap(), 3, 100)
+        .await
+        .unwrap();
+
+    let res = server.get_json_async_raw("/split").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let split: Vec<serde_json::Value> = res.json().await.unwrap();
+    assert_eq!(split.len(), 3, "payout queries should read the primary");
+}
+
+/// Stands in for ntfy, failing the first `failures` block alerts with a 503.
+/// Returns the server URL, the number of block alerts it received, and the
+/// number it accepted.
+async fn spawn_flaky_ntfy(failures: usize) -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let delivered = Arc::new(AtomicUsize::new(0));
+
+    let router = axum::Router::new().route(
+        "/{channel}",
+        axum::routing::post({
+            let attempts = attempts.clone();
+            let delivered = delivered.clone();
+            move || async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    delivered.fetch_add(1, Ordering::SeqCst);
+                    StatusCode::OK
+                }
+            }
+        })
+        .put(|| async { StatusCode::OK }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    (url, attempts, delivered)
+}
+
+/// Block alerts are sent in the background after the batch response, so
+/// wait for `count` to reach `expected`.
+async fn wait_for_alerts(count: &AtomicUsize, expected: usize) {
+    timeout(Duration::from_secs(10), async {
+        while count.load(Ordering::SeqCst) < expected {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("expected {expected} block alerts"));
+}
+
+async fn wait_for_released_claim(database_url: String, blockhash: &str) {
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let claimed: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM block_notifications WHERE blockhash = $1")
+                    .bind(blockhash)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+
+            if claimed == 0 {
+                break;
+            }
+
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("block alert claim for {blockhash} was not released"));
+}
+
+fn block_batch(block: &FoundBlockRecord) -> ShareBatch {
+    ShareBatch {
+        block: Some(block.clone()),
+        shares: vec![],
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 0,
+        start_id: 1,
+        end_id: 1,
+    }
+}
+
+#[tokio::test]
+async fn test_block_notification_retries_until_delivered() {
+    let (ntfy, attempts, delivered) = spawn_flaky_ntfy(2).await;
+
+    let server = TestServer::spawn_with_db_args(format!(
+        "--alerts-ntfy-channel blocks --alerts-ntfy-server {ntfy} \
+         --block-notify-retries 3 --block-notify-retry-delay-ms 10"
+    ))
+    .await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let response: SyncResponse = server
+        .post_json("/sync/batch", &block_batch(&create_test_block(800000)))
+        .await;
+    assert_eq!(response.status, "OK");
+
+    wait_for_alerts(&delivered, 1).await;
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_block_notification_not_repeated_for_duplicate_block() {
+    let (ntfy, _, delivered) = spawn_flaky_ntfy(0).await;
+
+    let server = TestServer::spawn_with_db_args(format!(
+        "--alerts-ntfy-channel blocks --alerts-ntfy-server {ntfy}"
+    ))
+    .await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let block = create_test_block(800000);
+
+    let response: SyncResponse = server.post_json("/sync/batch", &block_batch(&block)).await;
+    assert_eq!(response.status, "OK");
+    wait_for_alerts(&delivered, 1).await;
+
+    let response: SyncResponse = server.post_json("/sync/batch", &block_batch(&block)).await;
+    assert_eq!(response.status, "OK");
+    sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(delivered.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_block_notification_retried_by_duplicate_after_giving_up() {
+    let (ntfy, attempts, delivered) = spawn_flaky_ntfy(2).await;
+
+    let server = TestServer::spawn_with_db_args(format!(
+        "--alerts-ntfy-channel blocks --alerts-ntfy-server {ntfy} \
+         --block-notify-retries 1 --block-notify-retry-delay-ms 10"
+    ))
+    .await;
+    setup_test_schema(server.database_url().unwrap())
+        .await
+        .unwrap();
+
+    let block = create_test_block(800000);
+
+    let response: SyncResponse = server.post_json("/sync/batch", &block_batch(&block)).await;
+    assert_eq!(response.status, "OK");
+    wait_for_alerts(&attempts, 2).await;
+    wait_for_released_claim(server.database_url().unwrap(), &block.blockhash).await;
+    assert_eq!(delivered.load(Ordering::SeqCst), 0);
+
+    let response: SyncResponse = server.post_json("/sync/batch", &block_batch(&block)).await;
+    assert_eq!(response.status, "OK");
+    wait_for_alerts(&delivered, 1).await;
+}
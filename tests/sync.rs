@@ -1,4 +1,7 @@
-use {super::*, crate::test_psql::create_shares_for_user, tokio_util::sync::CancellationToken};
+use {
+    super::*, crate::test_psql::create_shares_for_user,
+    para::subcommand::server::sync_lag::OriginLag, tokio_util::sync::CancellationToken,
+};
 
 pub(crate) static BATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -148,6 +151,181 @@ async fn test_sync_empty_batch() {
     pool.close().await;
 }
 
+#[tokio::test]
+async fn test_sync_lag_per_origin() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let lag: Vec<OriginLag> = server.get_json_async("/api/sync/lag").await;
+    assert!(lag.is_empty());
+
+    for (hostname, count, end_id) in [
+        ("lag-node-a", 3, 3),
+        ("lag-node-b", 2, 2),
+        ("lag-node-a", 4, 7),
+    ] {
+        let batch = ShareBatch {
+            block: None,
+            shares: create_test_shares(count, 800000),
+            hostname: hostname.into(),
+            batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+            total_shares: count as usize,
+            start_id: end_id - count as i64 + 1,
+            end_id,
+        };
+
+        let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+        assert_eq!(response.status, "OK");
+    }
+
+    let lag: Vec<OriginLag> = server.get_json_async("/api/sync/lag").await;
+
+    assert_eq!(
+        lag.iter()
+            .map(|lag| (lag.origin.as_str(), lag.last_id, lag.batches, lag.shares))
+            .collect::<Vec<_>>(),
+        [("lag-node-a", 7, 2, 7), ("lag-node-b", 2, 1, 2)]
+    );
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    for lag in &lag {
+        assert!(lag.lag_secs < 10, "{lag:?}");
+        assert!(now.abs_diff(lag.last_seen) < 10, "{lag:?}");
+    }
+}
+
+async fn post_raw_sync_batch(server: &TestServer, body: impl Into<reqwest::Body>) -> Response {
+    reqwest::Client::new()
+        .post(server.url().join("/sync/batch").unwrap())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_sync_truncated_batch_is_bad_request() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let body = serde_json::to_string(&ShareBatch {
+        block: None,
+        shares: create_test_shares(2, 800000),
+        hostname: "test-node-truncated".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 2,
+        start_id: 1,
+        end_id: 2,
+    })
+    .unwrap();
+
+    let response = post_raw_sync_batch(&server, body[..body.len() / 2].to_string()).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response: SyncResponse = response.json().await.unwrap();
+
+    assert_eq!(response.status, "ERROR");
+    assert!(response.error_message.is_some());
+}
+
+#[tokio::test]
+async fn test_sync_batch_with_bad_field_names_field() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let mut batch = serde_json::to_value(ShareBatch {
+        block: None,
+        shares: create_test_shares(2, 800000),
+        hostname: "test-node-bad-field".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 2,
+        start_id: 1,
+        end_id: 2,
+    })
+    .unwrap();
+
+    batch["shares"][1]["id"] = serde_json::json!("not a number");
+
+    let response = post_raw_sync_batch(&server, batch.to_string()).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response: SyncResponse = response.json().await.unwrap();
+
+    let error_message = response.error_message.unwrap();
+    assert!(
+        error_message.contains("shares[1].id"),
+        "error message should name the bad field: {error_message}"
+    );
+}
+
+#[tokio::test]
+async fn test_sync_oversized_batch_is_payload_too_large() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let response = post_raw_sync_batch(&server, vec![b' '; 51 << 20]).await;
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let response: SyncResponse = response.json().await.unwrap();
+
+    assert_eq!(response.status, "ERROR");
+}
+
+#[tokio::test]
+async fn test_sync_batch_rejected_when_disk_space_low() {
+    let server = TestServer::spawn_with_db_args("--min-free-disk-percent 100").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let batch = ShareBatch {
+        block: None,
+        shares: create_test_shares(2, 800000),
+        hostname: "test-node-disk-full".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 2,
+        start_id: 1,
+        end_id: 2,
+    };
+
+    let response = server.post_json_raw("/sync/batch", &batch).await;
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response: SyncResponse = response.json().await.unwrap();
+
+    assert_eq!(response.status, "UNAVAILABLE");
+    assert_eq!(response.received_count, 0);
+    assert!(
+        response
+            .error_message
+            .unwrap()
+            .contains("Insufficient disk space")
+    );
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM remote_shares WHERE origin = $1")
+        .bind(&batch.hostname)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 0);
+
+    pool.close().await;
+}
+
 #[tokio::test]
 #[ignore]
 async fn test_sync_batch_block_find_notification_e2e() {
@@ -377,6 +555,186 @@ async fn test_sync_batch_with_block_only() {
     pool.close().await;
 }
 
+#[tokio::test]
+async fn test_sync_batch_larger_than_share_insert_batch_size() {
+    let server = TestServer::spawn_with_db_args("--share-insert-batch-size 10").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let test_shares = create_test_shares(25, 800003);
+
+    let batch = ShareBatch {
+        block: None,
+        shares: test_shares,
+        hostname: "test-node-sub-batches".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 25,
+        start_id: 1,
+        end_id: 25,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+
+    assert_eq!(response.status, "OK");
+    assert_eq!(response.received_count, 25);
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let stored_shares: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM remote_shares WHERE origin = $1")
+            .bind(&batch.hostname)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(stored_shares, 25);
+
+    // A duplicate id among the last shares fails the sub-batch holding them,
+    // which shows the batch was inserted as exactly 10 + 10 + 5 shares.
+    let mut resent_shares = batch.shares.clone();
+    resent_shares[24].id = resent_shares[23].id;
+
+    let response: SyncResponse = server
+        .post_json(
+            "/sync/batch",
+            &ShareBatch {
+                shares: resent_shares,
+                batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+                ..batch
+            },
+        )
+        .await;
+
+    assert_eq!(
+        response.failed_sub_batch,
+        Some(FailedSubBatch {
+            index: 2,
+            start_id: 21,
+            end_id: 24,
+        })
+    );
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_sync_batch_reports_failed_sub_batch() {
+    let server = TestServer::spawn_with_db_args("--share-insert-batch-size 10").await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let mut test_shares = create_test_shares(25, 800003);
+
+    // A share id repeated within one insert can't be upserted twice.
+    test_shares[15].id = test_shares[14].id;
+
+    let batch = ShareBatch {
+        block: None,
+        shares: test_shares,
+        hostname: "test-node-failed-sub-batch".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 25,
+        start_id: 1,
+        end_id: 25,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+
+    assert_eq!(response.status, "ERROR");
+    assert_eq!(response.received_count, 0);
+    assert_eq!(
+        response.failed_sub_batch,
+        Some(FailedSubBatch {
+            index: 1,
+            start_id: 11,
+            end_id: 20,
+        })
+    );
+    assert!(
+        response
+            .error_message
+            .unwrap()
+            .starts_with("Failed to batch insert shares in sub-batch 2 (share ids 11-20): ")
+    );
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let stored_shares: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM remote_shares WHERE origin = $1")
+            .bind(&batch.hostname)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(stored_shares, 0, "the whole batch rolls back");
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_sync_batch_keeps_first_result_of_resynced_share() {
+    let server = TestServer::spawn_with_db().await;
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let hostname = "test-node-resync-conflict".to_string();
+
+    let batch = ShareBatch {
+        block: None,
+        shares: create_test_shares(2, 800003),
+        hostname: hostname.clone(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        total_shares: 2,
+        start_id: 1,
+        end_id: 2,
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &batch).await;
+    assert_eq!(response.status, "OK");
+
+    let mut shares = create_test_shares(2, 800003);
+    shares[0].outcome = ShareOutcome::Rejected("Stale".into());
+    shares[1].diff = Some(1.0);
+
+    let resync = ShareBatch {
+        shares,
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        ..batch
+    };
+
+    let response: SyncResponse = server.post_json("/sync/batch", &resync).await;
+    assert_eq!(response.status, "OK");
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    let stored: Vec<(i64, Option<bool>, Option<f64>)> =
+        sqlx::query_as("SELECT id, result, diff FROM remote_shares WHERE origin = $1 ORDER BY id")
+            .bind(&hostname)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(
+        stored,
+        [(1, Some(true), Some(1000.0)), (2, Some(true), Some(1001.0))]
+    );
+
+    let total_diffs: Vec<i64> = sqlx::query_scalar(
+        "SELECT total_diff FROM accounts WHERE username IN ('user_0', 'user_1') ORDER BY username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        total_diffs,
+        [1000, 1001],
+        "conflicting re-syncs are not credited"
+    );
+
+    pool.close().await;
+}
+
 #[tokio::test]
 #[timeout(90000)]
 #[ignore]
@@ -955,6 +1313,83 @@ async fn test_sync_batch_block_count_preserves_other_metadata() {
     pool.close().await;
 }
 
+#[tokio::test]
+async fn test_rebuild_account_metadata_restores_block_counts() {
+    let mut server = TestServer::spawn_with_db_args("--admin-token admin").await;
+    server.admin_token = Some("admin".into());
+    let db_url = server.database_url().unwrap();
+    setup_test_schema(db_url.clone()).await.unwrap();
+
+    let alice = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+    let bob = "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy";
+
+    let mut shares = create_shares_for_user(alice, &[400000, 400001, 400001, 400003], 1);
+    shares.extend(create_shares_for_user(bob, &[400002], 100));
+    shares.extend(create_shares_for_user(&format!(" {bob} "), &[400004], 200));
+
+    let batch = ShareBatch {
+        block: None,
+        total_shares: shares.len(),
+        shares,
+        hostname: "test-node".to_string(),
+        batch_id: BATCH_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+        start_id: 1,
+        end_id: 100,
+    };
+
+    let _: SyncResponse = server.post_json("/sync/batch", &batch).await;
+
+    let pool = sqlx::PgPool::connect(&db_url).await.unwrap();
+
+    sqlx::query(
+        "UPDATE account_metadata
+         SET data = data || '{\"block_count\": 42, \"highest_blockheight\": 1, \"custom_field\": \"test_value\"}'::jsonb",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let metadata = |username: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_as::<_, (i64, i32, String)>(
+                "SELECT (data->>'block_count')::bigint,
+                        (data->>'highest_blockheight')::int,
+                        data->>'custom_field'
+                 FROM account_metadata am
+                 JOIN accounts a ON a.id = am.account_id
+                 WHERE a.username = $1",
+            )
+            .bind(username)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+
+    assert_eq!(metadata(alice).await, (42, 1, "test_value".into()));
+
+    assert_eq!(
+        reqwest::Client::new()
+            .post(server.url().join("/account/metadata/rebuild").unwrap())
+            .send()
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::UNAUTHORIZED,
+    );
+
+    let response: serde_json::Value = server
+        .post_json("/account/metadata/rebuild", &json!({}))
+        .await;
+    assert_eq!(response["rows_affected"], 2);
+
+    assert_eq!(metadata(alice).await, (3, 400003, "test_value".into()));
+    assert_eq!(metadata(bob).await, (2, 400004, "test_value".into()));
+
+    pool.close().await;
+}
+
 #[tokio::test]
 async fn test_sync_batch_block_count_multiple_users() {
     let server = TestServer::spawn_with_db().await;
@@ -1028,7 +1463,7 @@ async fn test_sync_batch_block_count_ignores_failed_shares() {
     let mut shares =
         create_shares_for_user("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", &[600000, 600001], 1);
 
-    shares[1].result = Some(false);
+    shares[1].outcome = ShareOutcome::Rejected("Stale".into());
 
     let batch = ShareBatch {
         block: None,
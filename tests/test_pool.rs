@@ -39,10 +39,11 @@ impl TestPool {
         let pool_port = port.unwrap_or_else(allocate_port);
         let http_port = allocate_port();
         let zmq_port = bitcoind.zmq_port;
+        let chain = bitcoind.network;
 
         let pool_handle = CommandBuilder::new(format!(
             "pool
-                --chain signet
+                --chain {chain}
                 --address 127.0.0.1
                 --port {pool_port}
                 --http-port {http_port}
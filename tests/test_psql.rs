@@ -1,6 +1,6 @@
 use {
     crate::address,
-    para::subcommand::sync::{FoundBlockRecord, Share},
+    para::subcommand::sync::{FoundBlockRecord, Share, ShareOutcome},
 };
 
 pub(crate) fn create_test_shares(count: u32, blockheight: i64) -> Vec<Share> {
@@ -17,8 +17,7 @@ pub(crate) fn create_test_shares(count: u32, blockheight: i64) -> Vec<Share> {
             diff: Some(1000.0 + i as f64),
             sdiff: Some(500.0 + i as f64),
             hash: Some(format!("hash_{:064x}", i)),
-            result: Some(true),
-            reject_reason: None,
+            outcome: ShareOutcome::Accepted,
             error: None,
             errn: None,
             createdate: Some("2024-01-01 12:00:00".to_string()),
@@ -50,8 +49,7 @@ pub fn create_shares_for_user(username: &str, blockheights: &[i32], base_id: i64
             diff: Some(1000.0),
             sdiff: Some(500.0),
             hash: Some(format!("hash_{:064x}", i)),
-            result: Some(true),
-            reject_reason: None,
+            outcome: ShareOutcome::Accepted,
             error: None,
             errn: None,
             createdate: Some("2024-01-01 12:00:00".to_string()),
@@ -167,7 +165,8 @@ pub(crate) async fn setup_test_schema(db_url: String) -> Result<(), Box<dyn std:
                     diff DOUBLE PRECISION,
                     time_found TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
                     coinbasevalue BIGINT,
-                    rewards_processed BOOLEAN DEFAULT FALSE
+                    rewards_processed BOOLEAN DEFAULT FALSE,
+                    coinbase_address VARCHAR(128)
                 )
                 "#,
     )
@@ -183,6 +182,7 @@ pub(crate) async fn setup_test_schema(db_url: String) -> Result<(), Box<dyn std:
                     lnurl            VARCHAR(255),
                     past_lnurls      JSONB                    DEFAULT '[]'::JSONB,
                     total_diff       BIGINT                   DEFAULT 0,
+                    exclude_from_payout BOOLEAN               NOT NULL DEFAULT FALSE,
                     lnurl_updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
                     created_at       TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
                     updated_at       TIMESTAMP WITH TIME ZONE DEFAULT NOW()
@@ -200,7 +200,7 @@ pub(crate) async fn setup_test_schema(db_url: String) -> Result<(), Box<dyn std:
                     diff_paid         BIGINT         NOT NULL,
                     blockheight_start INTEGER        NOT NULL,
                     blockheight_end   INTEGER        NOT NULL,
-                    status            VARCHAR(20)    NOT NULL  DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'success', 'failure', 'cancelled')),
+                    status            VARCHAR(20)    NOT NULL  DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'success', 'failure', 'cancelled', 'dead_letter')),
                     attempts          SMALLINT       NOT NULL  DEFAULT 0,
                     failure_reason    TEXT,
                     transaction_id    VARCHAR(64),
@@ -307,6 +307,34 @@ pub(crate) async fn setup_test_schema(db_url: String) -> Result<(), Box<dyn std:
         .execute(&pool)
         .await?;
 
+    sqlx::query(
+        r#"
+                CREATE TABLE IF NOT EXISTS block_notifications (
+                    blockhash VARCHAR(64) PRIMARY KEY,
+                    notified_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+                )
+                "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+                CREATE TABLE IF NOT EXISTS near_misses (
+                    id SERIAL PRIMARY KEY,
+                    blockheight INTEGER NOT NULL,
+                    hash VARCHAR(64) NOT NULL,
+                    workername VARCHAR(255),
+                    username VARCHAR(128),
+                    sdiff DOUBLE PRECISION NOT NULL,
+                    network_diff DOUBLE PRECISION NOT NULL,
+                    createdate TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+                )
+                "#,
+    )
+    .execute(&pool)
+    .await?;
+
     sqlx::query(
         r#"
                 CREATE TABLE IF NOT EXISTS round_participation_history (
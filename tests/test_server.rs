@@ -74,26 +74,32 @@ impl TestServer {
     }
 
     #[cfg(target_os = "linux")]
-    pub(crate) async fn spawn_with_db_args(args: impl ToArgs) -> Self {
+    pub(crate) fn pg_temp_db_builder(port: Option<u16>) -> PgTempDBBuilder {
         let psql_binpath = match Command::new("pg_config").arg("--bindir").output() {
             Ok(output) if output.status.success() => String::from_utf8(output.stdout)
                 .ok()
                 .map(|s| PathBuf::from(s.trim())),
             _ => None,
         };
-        let pg_db = PgTempDB::from_builder(PgTempDBBuilder {
+
+        PgTempDBBuilder {
             initdb_args: Default::default(),
             temp_dir_prefix: None,
             db_user: None,
             password: None,
-            port: None,
+            port,
             dbname: None,
             persist_data_dir: false,
             dump_path: None,
             load_path: None,
             server_configs: Default::default(),
             bin_path: psql_binpath,
-        });
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) async fn spawn_with_db_args(args: impl ToArgs) -> Self {
+        let pg_db = PgTempDB::from_builder(Self::pg_temp_db_builder(None));
 
         Self::spawn_with_db_override(args, pg_db).await
     }
@@ -101,7 +107,30 @@ impl TestServer {
     #[cfg(target_os = "linux")]
     pub(crate) async fn spawn_with_db_override(args: impl ToArgs, database: PgTempDB) -> Self {
         let database_url = database.connection_uri();
+        let mut server = Self::spawn_with_db_url(args, &database_url);
+        server.pg_db = Some(database);
+        server
+    }
+
+    /// Spawns a server pointed at a database that has not been started yet,
+    /// returning the builder to start it with.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn spawn_with_pending_db() -> (Self, PgTempDBBuilder) {
+        let port = allocate_port();
+        let builder = Self::pg_temp_db_builder(Some(port));
+
+        let database_url = format!(
+            "postgresql://{}:{}@localhost:{port}/{}",
+            builder.get_user(),
+            builder.get_password(),
+            builder.get_dbname()
+        );
+
+        (Self::spawn_with_db_url([], &database_url), builder)
+    }
 
+    #[cfg(target_os = "linux")]
+    fn spawn_with_db_url(args: impl ToArgs, database_url: &str) -> Self {
         let tempdir = Arc::new(TempDir::new().unwrap());
         let logdir = tempdir.path().join("logs");
         fs::create_dir(&logdir).unwrap();
@@ -140,7 +169,7 @@ impl TestServer {
             child,
             port,
             tempdir,
-            pg_db: Some(database),
+            pg_db: None,
             admin_token: None,
         }
     }
@@ -153,6 +182,16 @@ impl TestServer {
         self.tempdir.path().join("logs")
     }
 
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_database(&mut self, database: PgTempDB) {
+        self.pg_db = Some(database);
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn stop_database(&mut self) {
+        self.pg_db = None;
+    }
+
     #[cfg(target_os = "linux")]
     pub(crate) fn database_url(&self) -> Option<String> {
         self.pg_db.as_ref().map(|db| db.connection_uri())
@@ -0,0 +1,69 @@
+use {
+    super::*, crate::sync::SyncSendTestExt, para::subcommand::sync::Sync,
+    tokio_util::sync::CancellationToken,
+};
+
+/// A regtest node, a pool recording shares and blocks to its own database,
+/// and a server that database syncs to, so a test can follow work from
+/// `mining.subscribe` all the way to the payouts table.
+pub(crate) struct TestStack {
+    pub(crate) pool: TestPool,
+    pub(crate) server: TestServer,
+    _bitcoind: Bitcoind,
+    pool_db: PgTempDB,
+}
+
+impl TestStack {
+    pub(crate) async fn spawn(pool_args: impl ToArgs, server_args: impl ToArgs) -> Self {
+        let bitcoind = spawn_regtest();
+
+        // A node without blocks is in initial block download and will not
+        // hand out templates.
+        generate_to_address(&bitcoind, 1, &bitcoind.op_true_address().to_string()).await;
+
+        let pool_db = PgTempDB::from_builder(TestServer::pg_temp_db_builder(None));
+        setup_test_schema(pool_db.connection_uri()).await.unwrap();
+
+        let server = TestServer::spawn_with_db_args(server_args).await;
+        setup_test_schema(server.database_url().unwrap())
+            .await
+            .unwrap();
+
+        let pool = TestPool::spawn_with_args(
+            &bitcoind,
+            format!(
+                "--database-url {} {}",
+                pool_db.connection_uri(),
+                pool_args.to_args().join(" ")
+            ),
+        );
+
+        Self {
+            pool,
+            server,
+            _bitcoind: bitcoind,
+            pool_db,
+        }
+    }
+
+    pub(crate) fn pool_database_url(&self) -> String {
+        self.pool_db.connection_uri()
+    }
+
+    pub(crate) fn server_database_url(&self) -> String {
+        self.server.database_url().unwrap()
+    }
+
+    /// Sends everything the pool has recorded for completed blocks to the
+    /// server, the way `para sync` does, returning once caught up.
+    pub(crate) async fn sync(&self) {
+        Sync::default()
+            .with_endpoint(self.server.url().to_string())
+            .with_database_url(self.pool_database_url())
+            .with_terminate_when_complete(true)
+            .with_temp_file()
+            .run(CancellationToken::new())
+            .await
+            .unwrap();
+    }
+}